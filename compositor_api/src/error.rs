@@ -49,6 +49,8 @@ where
                 ErrorType::UserError => StatusCode::BAD_REQUEST,
                 ErrorType::ServerError => StatusCode::INTERNAL_SERVER_ERROR,
                 ErrorType::EntityNotFound => StatusCode::NOT_FOUND,
+                ErrorType::Unauthorized => StatusCode::UNAUTHORIZED,
+                ErrorType::Conflict => StatusCode::CONFLICT,
             },
         }
     }
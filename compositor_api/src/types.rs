@@ -33,10 +33,23 @@ pub use component::Tiles;
 pub use component::View;
 pub use component::WebView;
 
+pub use register_input::FragmentedMp4InputStream;
+pub use register_input::HlsInputStream;
 pub use register_input::Mp4;
+pub use register_input::MpegTsInputStream;
+pub use register_input::SrtInputStream;
+pub use register_input::TestSourceInput;
+pub use register_input::WhipInput;
+pub use register_output::HlsOutputStream;
+pub use register_output::Mp4OutputStream;
+pub use register_output::MpegTsOutputStream;
+pub use register_output::RtmpOutputStream;
 pub use register_output::RtpOutputStream;
+pub use register_output::SrtOutputStream;
+pub use register_output::WhepOutputStream;
 
 pub use register_input::DeckLink;
+pub use register_input::RtmpInputStream;
 pub use register_input::RtpInputStream;
 
 pub use renderer::ImageSpec;
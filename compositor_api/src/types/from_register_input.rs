@@ -5,10 +5,13 @@ use compositor_pipeline::{
     pipeline::{
         self, decoder,
         input::{self, rtp::InputAudioStream},
+        rtp::{sdp::SessionDescription, AUDIO_PAYLOAD_TYPE, VIDEO_PAYLOAD_TYPE},
     },
     queue,
 };
+use compositor_render::scene;
 
+use super::from_util::{parse_ip_address, parse_multicast_interface, srt_connection_options};
 use super::register_input::*;
 use super::util::*;
 
@@ -50,6 +53,7 @@ impl TryFrom<InputRtpAudioOptions> for InputAudioStream {
                     options: decoder::AudioDecoderOptions::Opus(decoder::OpusDecoderOptions {
                         forward_error_correction,
                     }),
+                    payload_type: AUDIO_PAYLOAD_TYPE,
                 })
             }
             InputRtpAudioOptions::Aac {
@@ -75,6 +79,7 @@ impl TryFrom<InputRtpAudioOptions> for InputAudioStream {
                         depayloader_mode,
                         asc: Some(asc),
                     }),
+                    payload_type: AUDIO_PAYLOAD_TYPE,
                 })
             }
         }
@@ -92,30 +97,327 @@ impl TryFrom<RtpInputStream> for pipeline::RegisterInputOptions {
             required,
             offset_ms,
             transport_protocol,
+            jitter_buffer_latency_ms,
+            sdp,
+            multicast,
+            srtp,
+        } = value;
+
+        let jitter_buffer_latency_ms = jitter_buffer_latency_ms.unwrap_or(50.0);
+        if !jitter_buffer_latency_ms.is_finite() || jitter_buffer_latency_ms < 0.0 {
+            return Err(TypeError::new(
+                "`jitter_buffer_latency_ms` has to be a non-negative number.",
+            ));
+        }
+
+        let mut multicast = multicast.map(TryFrom::try_from).transpose()?;
+        let mut srtp = srtp.map(TryFrom::try_from).transpose()?;
+        let (rtp_stream, port, transport_protocol) = match sdp {
+            Some(sdp) => {
+                const SDP_WITH_STREAM_SPEC: &str = "`video`, `audio` and `transport_protocol` can not be specified together with `sdp`.";
+                if video.is_some() || audio.is_some() || transport_protocol.is_some() {
+                    return Err(TypeError::new(SDP_WITH_STREAM_SPEC));
+                }
+
+                let sdp = SessionDescription::parse(&sdp)
+                    .map_err(|err| TypeError::new(format!("Invalid SDP. {err}")))?;
+                let rtp_stream = input::rtp::RtpStream::from_sdp(&sdp)
+                    .map_err(|err| TypeError::new(format!("Invalid SDP. {err}")))?;
+                let port = port.unwrap_or(PortOrPortRange::U16(sdp.port)).try_into()?;
+                if multicast.is_none()
+                    && sdp.transport_protocol == pipeline::rtp::TransportProtocol::Udp
+                {
+                    multicast = sdp
+                        .address
+                        .parse()
+                        .ok()
+                        .filter(std::net::IpAddr::is_multicast)
+                        .map(|group| input::rtp::MulticastGroup {
+                            group,
+                            source: None,
+                            interface: None,
+                        });
+                }
+                if srtp.is_none() {
+                    srtp = sdp.srtp;
+                }
+                (rtp_stream, port, sdp.transport_protocol)
+            }
+            None => {
+                const NO_VIDEO_AUDIO_SPEC: &str =
+                    "At least one of `video` and `audio` has to be specified in `register_input` request.";
+                const NO_PORT: &str = "`port` has to be specified if `sdp` is not provided.";
+
+                if video.is_none() && audio.is_none() {
+                    return Err(TypeError::new(NO_VIDEO_AUDIO_SPEC));
+                }
+                let port = port.ok_or_else(|| TypeError::new(NO_PORT))?.try_into()?;
+
+                let rtp_stream = input::rtp::RtpStream {
+                    video: video.as_ref().map(|video| input::rtp::InputVideoStream {
+                        options: decoder::VideoDecoderOptions {
+                            codec: match video {
+                                InputRtpVideoOptions::FfmepgH264 => pipeline::VideoCodec::H264,
+                                InputRtpVideoOptions::FfmpegVp8 => pipeline::VideoCodec::Vp8,
+                                InputRtpVideoOptions::FfmpegVp9 => pipeline::VideoCodec::Vp9,
+                                InputRtpVideoOptions::FfmpegAv1 => pipeline::VideoCodec::Av1,
+                            },
+                        },
+                        payload_type: VIDEO_PAYLOAD_TYPE,
+                    }),
+                    audio: audio.map(TryFrom::try_from).transpose()?,
+                };
+                let transport_protocol = transport_protocol.unwrap_or(TransportProtocol::Udp);
+                (rtp_stream, port, transport_protocol.into())
+            }
+        };
+
+        if multicast.is_some() && transport_protocol != pipeline::rtp::TransportProtocol::Udp {
+            return Err(TypeError::new(
+                "`multicast` can only be used with `transport_protocol=\"udp\"`.",
+            ));
+        }
+
+        let input_options = input::InputOptions::Rtp(input::rtp::RtpReceiverOptions {
+            port,
+            stream: rtp_stream,
+            transport_protocol,
+            jitter_buffer_latency: Duration::from_secs_f64(jitter_buffer_latency_ms / 1000.0),
+            multicast,
+            srtp,
+        });
+
+        let queue_options = queue::QueueInputOptions {
+            required: required.unwrap_or(false),
+            offset: offset_ms.map(|offset_ms| Duration::from_secs_f64(offset_ms / 1000.0)),
+            buffer_duration: None,
+        };
+
+        Ok(pipeline::RegisterInputOptions {
+            input_options,
+            queue_options,
+        })
+    }
+}
+
+impl TryFrom<RtpMulticastGroup> for input::rtp::MulticastGroup {
+    type Error = TypeError;
+
+    fn try_from(value: RtpMulticastGroup) -> Result<Self, Self::Error> {
+        let group = parse_ip_address(&value.group)?;
+        if !group.is_multicast() {
+            return Err(TypeError::new(format!(
+                "\"{group}\" is not a multicast address."
+            )));
+        }
+        let source = value
+            .source
+            .map(|source| parse_ip_address(&source))
+            .transpose()?;
+        match source {
+            Some(_) if group.is_ipv6() => {
+                return Err(TypeError::new(
+                    "Source-specific multicast is supported only for IPv4 groups.",
+                ))
+            }
+            Some(source) if !source.is_ipv4() => {
+                return Err(TypeError::new(
+                    "Multicast source has to be an IPv4 address for IPv4 groups.",
+                ))
+            }
+            _ => {}
+        }
+        let interface = value
+            .interface
+            .map(|interface| parse_multicast_interface(group, &interface))
+            .transpose()?;
+        Ok(Self {
+            group,
+            source,
+            interface,
+        })
+    }
+}
+
+impl TryFrom<RtmpInputStream> for pipeline::RegisterInputOptions {
+    type Error = TypeError;
+
+    fn try_from(value: RtmpInputStream) -> Result<Self, Self::Error> {
+        let RtmpInputStream {
+            port,
+            app,
+            stream_key,
+            video,
+            audio,
+            required,
+            offset_ms,
+        } = value;
+
+        const NO_VIDEO_AUDIO_SPEC: &str =
+            "At least one of `video` and `audio` has to be specified in `register_input` request.";
+
+        if video.is_none() && audio.is_none() {
+            return Err(TypeError::new(NO_VIDEO_AUDIO_SPEC));
+        }
+
+        const EMPTY_STREAM_KEY: &str = "The `stream_key` field can not be empty.";
+        if stream_key.is_empty() {
+            return Err(TypeError::new(EMPTY_STREAM_KEY));
+        }
+
+        let input_options = input::InputOptions::Rtmp(input::rtmp::RtmpReceiverOptions {
+            port: pipeline::Port(port),
+            app: app.unwrap_or_else(|| "live".to_string()).into(),
+            stream_key: stream_key.into(),
+            video: video.is_some(),
+            audio: audio.is_some(),
+        });
+
+        let queue_options = queue::QueueInputOptions {
+            required: required.unwrap_or(false),
+            offset: offset_ms.map(|offset_ms| Duration::from_secs_f64(offset_ms / 1000.0)),
+            buffer_duration: None,
+        };
+
+        Ok(pipeline::RegisterInputOptions {
+            input_options,
+            queue_options,
+        })
+    }
+}
+
+impl TryFrom<WhipInput> for pipeline::RegisterInputOptions {
+    type Error = TypeError;
+
+    fn try_from(value: WhipInput) -> Result<Self, Self::Error> {
+        let WhipInput {
+            video,
+            audio,
+            bearer_token,
+            required,
+            offset_ms,
+            jitter_buffer_latency_ms,
         } = value;
 
         const NO_VIDEO_AUDIO_SPEC: &str =
             "At least one of `video` and `audio` has to be specified in `register_input` request.";
+        const EMPTY_BEARER_TOKEN: &str = "The `bearer_token` field can not be empty.";
 
         if video.is_none() && audio.is_none() {
             return Err(TypeError::new(NO_VIDEO_AUDIO_SPEC));
         }
+        if bearer_token.as_ref().is_some_and(String::is_empty) {
+            return Err(TypeError::new(EMPTY_BEARER_TOKEN));
+        }
 
-        let rtp_stream = input::rtp::RtpStream {
-            video: video.as_ref().map(|video| input::rtp::InputVideoStream {
-                options: match video {
-                    InputRtpVideoOptions::FfmepgH264 => decoder::VideoDecoderOptions {
-                        codec: pipeline::VideoCodec::H264,
-                    },
+        let jitter_buffer_latency_ms = jitter_buffer_latency_ms.unwrap_or(50.0);
+        if !jitter_buffer_latency_ms.is_finite() || jitter_buffer_latency_ms < 0.0 {
+            return Err(TypeError::new(
+                "`jitter_buffer_latency_ms` has to be a non-negative number.",
+            ));
+        }
+
+        let input_options = input::InputOptions::Whip(input::whip::WhipReceiverOptions {
+            video: video.map(|video| decoder::VideoDecoderOptions {
+                codec: match video {
+                    InputWhipVideoOptions::FfmpegH264 => pipeline::VideoCodec::H264,
+                    InputWhipVideoOptions::FfmpegVp8 => pipeline::VideoCodec::Vp8,
+                    InputWhipVideoOptions::FfmpegVp9 => pipeline::VideoCodec::Vp9,
+                    InputWhipVideoOptions::FfmpegAv1 => pipeline::VideoCodec::Av1,
+                },
+            }),
+            audio: audio.map(|audio| match audio {
+                InputWhipAudioOptions::Opus {
+                    forward_error_correction,
+                } => decoder::OpusDecoderOptions {
+                    forward_error_correction: forward_error_correction.unwrap_or(false),
                 },
             }),
-            audio: audio.map(TryFrom::try_from).transpose()?,
+            bearer_token: bearer_token.map(Into::into),
+            jitter_buffer_latency: Duration::from_secs_f64(jitter_buffer_latency_ms / 1000.0),
+        });
+
+        let queue_options = queue::QueueInputOptions {
+            required: required.unwrap_or(false),
+            offset: offset_ms.map(|offset_ms| Duration::from_secs_f64(offset_ms / 1000.0)),
+            buffer_duration: None,
         };
 
-        let input_options = input::InputOptions::Rtp(input::rtp::RtpReceiverOptions {
-            port: port.try_into()?,
-            stream: rtp_stream,
-            transport_protocol: transport_protocol.unwrap_or(TransportProtocol::Udp).into(),
+        Ok(pipeline::RegisterInputOptions {
+            input_options,
+            queue_options,
+        })
+    }
+}
+
+impl TryFrom<HlsInputStream> for pipeline::RegisterInputOptions {
+    type Error = TypeError;
+
+    fn try_from(value: HlsInputStream) -> Result<Self, Self::Error> {
+        let HlsInputStream {
+            url,
+            path,
+            required,
+            offset_ms,
+        } = value;
+
+        const BAD_URL_PATH_SPEC: &str =
+            "Exactly one of `url` or `path` has to be specified in a register request for an HLS input.";
+
+        let url = match (url, path) {
+            (Some(_), Some(_)) | (None, None) => {
+                return Err(TypeError::new(BAD_URL_PATH_SPEC));
+            }
+            (Some(url), None) => url,
+            (None, Some(path)) => path,
+        };
+
+        let queue_options = queue::QueueInputOptions {
+            required: required.unwrap_or(false),
+            offset: offset_ms.map(|offset_ms| Duration::from_secs_f64(offset_ms / 1000.0)),
+            buffer_duration: None,
+        };
+
+        Ok(pipeline::RegisterInputOptions {
+            input_options: input::InputOptions::Hls(input::hls::HlsReceiverOptions {
+                url: url.into(),
+            }),
+            queue_options,
+        })
+    }
+}
+
+impl TryFrom<SrtInputStream> for pipeline::RegisterInputOptions {
+    type Error = TypeError;
+
+    fn try_from(value: SrtInputStream) -> Result<Self, Self::Error> {
+        let SrtInputStream {
+            mode,
+            port,
+            ip,
+            latency_ms,
+            passphrase,
+            stream_id,
+            video,
+            audio,
+            required,
+            offset_ms,
+        } = value;
+
+        const NO_VIDEO_AUDIO_SPEC: &str =
+            "At least one of `video` and `audio` has to be specified in `register_input` request.";
+
+        if video.is_none() && audio.is_none() {
+            return Err(TypeError::new(NO_VIDEO_AUDIO_SPEC));
+        }
+
+        let input_options = input::InputOptions::Srt(input::srt::SrtReceiverOptions {
+            connection: srt_connection_options(mode, port, ip, latency_ms, passphrase, stream_id)?,
+            video: video.is_some(),
+            audio: audio.map(|audio| match audio {
+                InputSrtAudioOptions::Aac => pipeline::AudioCodec::Aac,
+                InputSrtAudioOptions::Opus => pipeline::AudioCodec::Opus,
+            }),
         });
 
         let queue_options = queue::QueueInputOptions {
@@ -131,6 +433,126 @@ impl TryFrom<RtpInputStream> for pipeline::RegisterInputOptions {
     }
 }
 
+impl TryFrom<MpegTsInputStream> for pipeline::RegisterInputOptions {
+    type Error = TypeError;
+
+    fn try_from(value: MpegTsInputStream) -> Result<Self, Self::Error> {
+        let MpegTsInputStream {
+            port,
+            multicast,
+            video,
+            audio,
+            required,
+            offset_ms,
+        } = value;
+
+        const NO_VIDEO_AUDIO_SPEC: &str =
+            "At least one of `video` and `audio` has to be specified in `register_input` request.";
+
+        if video.is_none() && audio.is_none() {
+            return Err(TypeError::new(NO_VIDEO_AUDIO_SPEC));
+        }
+
+        let multicast: Option<input::rtp::MulticastGroup> =
+            multicast.map(TryFrom::try_from).transpose()?;
+        if let Some(input::rtp::MulticastGroup {
+            interface: Some(pipeline::rtp::MulticastInterface::V6(_)),
+            ..
+        }) = multicast
+        {
+            return Err(TypeError::new(
+                "`interface` of an IPv6 multicast group is not supported by MPEG-TS inputs.",
+            ));
+        }
+
+        let input_options = input::InputOptions::MpegTs(input::mpeg_ts::MpegTsReceiverOptions {
+            port: pipeline::Port(port),
+            multicast,
+            video: video.is_some(),
+            audio: audio.map(|audio| match audio {
+                InputMpegTsAudioOptions::Aac => pipeline::AudioCodec::Aac,
+                InputMpegTsAudioOptions::Opus => pipeline::AudioCodec::Opus,
+            }),
+        });
+
+        let queue_options = queue::QueueInputOptions {
+            required: required.unwrap_or(false),
+            offset: offset_ms.map(|offset_ms| Duration::from_secs_f64(offset_ms / 1000.0)),
+            buffer_duration: None,
+        };
+
+        Ok(pipeline::RegisterInputOptions {
+            input_options,
+            queue_options,
+        })
+    }
+}
+
+impl TryFrom<FragmentedMp4InputStream> for pipeline::RegisterInputOptions {
+    type Error = TypeError;
+
+    fn try_from(value: FragmentedMp4InputStream) -> Result<Self, Self::Error> {
+        let FragmentedMp4InputStream {
+            transport_protocol,
+            port,
+            video,
+            audio,
+            required,
+            offset_ms,
+        } = value;
+
+        const NO_VIDEO_AUDIO_SPEC: &str =
+            "At least one of `video` and `audio` has to be specified in `register_input` request.";
+
+        if video.is_none() && audio.is_none() {
+            return Err(TypeError::new(NO_VIDEO_AUDIO_SPEC));
+        }
+
+        let transport = match (
+            transport_protocol.unwrap_or(FragmentedMp4TransportProtocol::Http),
+            port,
+        ) {
+            (FragmentedMp4TransportProtocol::Http, None) => {
+                input::mp4::fragmented_receiver::FragmentedMp4Transport::Http
+            }
+            (FragmentedMp4TransportProtocol::Http, Some(_)) => {
+                return Err(TypeError::new(
+                    "The `port` field can only be specified for `\"tcp_server\"` transport.",
+                ))
+            }
+            (FragmentedMp4TransportProtocol::TcpServer, Some(port)) => {
+                input::mp4::fragmented_receiver::FragmentedMp4Transport::TcpServer {
+                    port: pipeline::Port(port),
+                }
+            }
+            (FragmentedMp4TransportProtocol::TcpServer, None) => {
+                return Err(TypeError::new(
+                    "The `port` field is required for `\"tcp_server\"` transport.",
+                ))
+            }
+        };
+
+        let input_options = input::InputOptions::FragmentedMp4(
+            input::mp4::fragmented_receiver::FragmentedMp4Options {
+                transport,
+                video: video.is_some(),
+                audio: audio.is_some(),
+            },
+        );
+
+        let queue_options = queue::QueueInputOptions {
+            required: required.unwrap_or(false),
+            offset: offset_ms.map(|offset_ms| Duration::from_secs_f64(offset_ms / 1000.0)),
+            buffer_duration: None,
+        };
+
+        Ok(pipeline::RegisterInputOptions {
+            input_options,
+            queue_options,
+        })
+    }
+}
+
 impl TryFrom<Mp4> for pipeline::RegisterInputOptions {
     type Error = TypeError;
 
@@ -138,6 +560,9 @@ impl TryFrom<Mp4> for pipeline::RegisterInputOptions {
         let Mp4 {
             url,
             path,
+            should_loop,
+            start_at_ms,
+            end_at_ms,
             required,
             offset_ms,
         } = value;
@@ -153,6 +578,96 @@ impl TryFrom<Mp4> for pipeline::RegisterInputOptions {
             (None, Some(path)) => input::mp4::Source::File(path.into()),
         };
 
+        const NEGATIVE_POSITION: &str = "`start_at_ms` and `end_at_ms` can not be negative.";
+        const BAD_PLAYBACK_RANGE: &str = "`start_at_ms` has to be smaller than `end_at_ms`.";
+
+        if start_at_ms.is_some_and(|start| start < 0.0) || end_at_ms.is_some_and(|end| end < 0.0) {
+            return Err(TypeError::new(NEGATIVE_POSITION));
+        }
+        if let (Some(start), Some(end)) = (start_at_ms, end_at_ms) {
+            if start >= end {
+                return Err(TypeError::new(BAD_PLAYBACK_RANGE));
+            }
+        }
+
+        let queue_options = queue::QueueInputOptions {
+            required: required.unwrap_or(false),
+            offset: offset_ms.map(|offset_ms| Duration::from_secs_f64(offset_ms / 1000.0)),
+            buffer_duration: None,
+        };
+
+        Ok(pipeline::RegisterInputOptions {
+            input_options: input::InputOptions::Mp4(input::mp4::Mp4Options {
+                source,
+                should_loop: should_loop.unwrap_or(false),
+                start_at: start_at_ms.map(|ms| Duration::from_secs_f64(ms / 1000.0)),
+                end_at: end_at_ms.map(|ms| Duration::from_secs_f64(ms / 1000.0)),
+            }),
+            queue_options,
+        })
+    }
+}
+
+impl TryFrom<TestSourceInput> for pipeline::RegisterInputOptions {
+    type Error = TypeError;
+
+    fn try_from(value: TestSourceInput) -> Result<Self, Self::Error> {
+        let TestSourceInput {
+            resolution,
+            framerate,
+            pattern,
+            color,
+            audio,
+            required,
+            offset_ms,
+        } = value;
+
+        const BAD_RESOLUTION: &str =
+            "Test source resolution has to be non-zero and divisible by 2 in each dimension.";
+        const BAD_FRAMERATE: &str = "Test source framerate has to be greater than zero.";
+        const BAD_FREQUENCY: &str = "`frequency_hz` has to be a positive number.";
+
+        let resolution: compositor_render::Resolution = resolution.into();
+        if resolution.width == 0
+            || resolution.height == 0
+            || resolution.width % 2 != 0
+            || resolution.height % 2 != 0
+        {
+            return Err(TypeError::new(BAD_RESOLUTION));
+        }
+
+        let framerate = match framerate {
+            Some(framerate) => framerate.try_into()?,
+            None => compositor_render::Framerate { num: 30, den: 1 },
+        };
+        if framerate.num == 0 || framerate.den == 0 {
+            return Err(TypeError::new(BAD_FRAMERATE));
+        }
+
+        let pattern = match pattern.unwrap_or(TestSourcePattern::SmpteBars) {
+            TestSourcePattern::SmpteBars => input::test_source::TestPattern::SmpteBars,
+            TestSourcePattern::MovingPattern => input::test_source::TestPattern::MovingPattern,
+            TestSourcePattern::SolidColor => {
+                let color = match color {
+                    Some(color) => color.try_into()?,
+                    None => scene::RGBColor(0, 0, 0),
+                };
+                input::test_source::TestPattern::SolidColor(color)
+            }
+        };
+
+        let audio = match audio {
+            Some(TestSourceAudio::Sine { frequency_hz }) => {
+                let frequency = frequency_hz.unwrap_or(1000.0);
+                if !frequency.is_finite() || frequency <= 0.0 {
+                    return Err(TypeError::new(BAD_FREQUENCY));
+                }
+                Some(input::test_source::TestAudio::Sine { frequency })
+            }
+            Some(TestSourceAudio::Silence) => Some(input::test_source::TestAudio::Silence),
+            None => None,
+        };
+
         let queue_options = queue::QueueInputOptions {
             required: required.unwrap_or(false),
             offset: offset_ms.map(|offset_ms| Duration::from_secs_f64(offset_ms / 1000.0)),
@@ -160,7 +675,12 @@ impl TryFrom<Mp4> for pipeline::RegisterInputOptions {
         };
 
         Ok(pipeline::RegisterInputOptions {
-            input_options: input::InputOptions::Mp4(input::mp4::Mp4Options { source }),
+            input_options: input::InputOptions::TestSource(input::test_source::TestSourceOptions {
+                pattern,
+                resolution,
+                framerate,
+                audio,
+            }),
             queue_options,
         })
     }
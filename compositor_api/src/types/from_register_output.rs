@@ -1,12 +1,17 @@
+use std::{collections::HashMap, time::Duration};
+
 use compositor_pipeline::pipeline::{
     self,
     encoder::{
-        self,
+        self, ffmpeg_av1,
         ffmpeg_h264::{self},
+        ffmpeg_vpx,
     },
     output,
 };
 
+use super::audio::AudioChannels;
+use super::from_util::{parse_ip_address, parse_multicast_interface, srt_connection_options};
 use super::register_output::*;
 use super::util::*;
 use super::*;
@@ -19,6 +24,9 @@ impl TryFrom<RtpOutputStream> for pipeline::RegisterOutputOptions<output::Output
             port,
             ip,
             transport_protocol,
+            multicast_ttl,
+            multicast_interface,
+            srtp,
             video,
             audio,
         } = request;
@@ -29,50 +37,22 @@ impl TryFrom<RtpOutputStream> for pipeline::RegisterOutputOptions<output::Output
             ));
         }
 
-        let (video_options, video_encoder_options) = match video.clone() {
-            Some(v) => {
-                if v.resolution.width % 2 != 0 || v.resolution.height % 2 != 0 {
-                    return Err(TypeError::new(
-                        "Output video width and height has to be divisible by 2",
-                    ));
-                };
-
-                let VideoEncoderOptions::FfmpegH264 {
-                    preset,
-                    ffmpeg_options,
-                } = v.encoder;
-
-                (
-                    Some(pipeline::OutputVideoOptions {
-                        initial: v.initial.try_into()?,
-                        end_condition: v.send_eos_when.unwrap_or_default().try_into()?,
-                    }),
-                    Some(pipeline::encoder::VideoEncoderOptions::H264(
-                        ffmpeg_h264::Options {
-                            preset: preset.into(),
-                            resolution: v.resolution.into(),
-                            raw_options: ffmpeg_options.unwrap_or_default().into_iter().collect(),
-                        },
-                    )),
-                )
-            }
-            None => (None, None),
-        };
+        let (video_options, video_encoder_options) = video
+            .map(|v| output_video_options(v.resolution, v.send_eos_when, v.encoder, v.initial))
+            .transpose()?
+            .unzip();
 
         let (audio_options, audio_encoder_options) = match audio.clone() {
             Some(a) => {
                 let AudioEncoderOptions::Opus { channels, preset } = a.encoder;
 
                 (
-                    Some(pipeline::OutputAudioOptions {
-                        initial: a.initial.try_into()?,
-                        channels: channels.clone().into(),
-                        end_condition: a.send_eos_when.unwrap_or_default().try_into()?,
-                        mixing_strategy: a
-                            .mixing_strategy
-                            .unwrap_or(MixingStrategy::SumClip)
-                            .into(),
-                    }),
+                    Some(output_audio_options(
+                        a.mixing_strategy,
+                        a.send_eos_when,
+                        channels.clone(),
+                        a.initial,
+                    )?),
                     Some(pipeline::encoder::AudioEncoderOptions::Opus(
                         encoder::opus::Options {
                             channels: channels.into(),
@@ -84,8 +64,28 @@ impl TryFrom<RtpOutputStream> for pipeline::RegisterOutputOptions<output::Output
             None => (None, None),
         };
 
-        let connection_options = match transport_protocol.unwrap_or(TransportProtocol::Udp) {
-            TransportProtocol::Udp => {
+        let multicast_group = ip
+            .as_deref()
+            .and_then(|ip| ip.parse::<std::net::IpAddr>().ok())
+            .filter(|ip| ip.is_multicast());
+        let multicast = match multicast_group {
+            Some(group) => output::rtp::MulticastSenderOptions {
+                ttl: multicast_ttl,
+                interface: multicast_interface
+                    .map(|interface| parse_multicast_interface(group, &interface))
+                    .transpose()?,
+            },
+            None if multicast_ttl.is_some() || multicast_interface.is_some() => {
+                return Err(TypeError::new(
+                    "\"multicast_ttl\" and \"multicast_interface\" fields are only allowed if \"ip\" is a multicast address.",
+                ));
+            }
+            None => output::rtp::MulticastSenderOptions::default(),
+        };
+
+        let transport_protocol = transport_protocol.unwrap_or(RtpOutputTransportProtocol::Udp);
+        let connection_options = match transport_protocol {
+            RtpOutputTransportProtocol::Udp => {
                 let pipeline::rtp::RequestedPort::Exact(port) = port.try_into()? else {
                     return Err(TypeError::new(
                         "Port range can not be used with UDP output stream (transport_protocol=\"udp\").",
@@ -99,9 +99,10 @@ impl TryFrom<RtpOutputStream> for pipeline::RegisterOutputOptions<output::Output
                 output::rtp::RtpConnectionOptions::Udp {
                     port: pipeline::Port(port),
                     ip,
+                    multicast,
                 }
             }
-            TransportProtocol::TcpServer => {
+            RtpOutputTransportProtocol::TcpServer => {
                 if ip.is_some() {
                     return Err(TypeError::new(
                         "\"ip\" field is not allowed when registering TCP server connection (transport_protocol=\"tcp_server\").",
@@ -112,13 +113,512 @@ impl TryFrom<RtpOutputStream> for pipeline::RegisterOutputOptions<output::Output
                     port: port.try_into()?,
                 }
             }
+            RtpOutputTransportProtocol::TcpClient => {
+                let pipeline::rtp::RequestedPort::Exact(port) = port.try_into()? else {
+                    return Err(TypeError::new(
+                        "Port range can not be used with TCP client connection (transport_protocol=\"tcp_client\").",
+                    ));
+                };
+                let Some(ip) = ip else {
+                    return Err(TypeError::new(
+                        "\"ip\" field is required when registering TCP client connection (transport_protocol=\"tcp_client\").",
+                    ));
+                };
+                if multicast_group.is_some() {
+                    return Err(TypeError::new(
+                        "Multicast address can not be used with TCP client connection (transport_protocol=\"tcp_client\").",
+                    ));
+                }
+                output::rtp::RtpConnectionOptions::TcpClient {
+                    port: pipeline::Port(port),
+                    ip,
+                }
+            }
         };
 
         let output_options = output::OutputOptions {
             output_protocol: output::OutputProtocolOptions::Rtp(output::rtp::RtpSenderOptions {
                 connection_options,
-                video: video.map(|_| pipeline::VideoCodec::H264),
+                video: video_encoder_options
+                    .as_ref()
+                    .map(|encoder| encoder.codec()),
+                audio: audio.map(|_| pipeline::AudioCodec::Opus),
+                srtp: srtp.map(TryFrom::try_from).transpose()?,
+            }),
+            video: video_encoder_options,
+            audio: audio_encoder_options,
+        };
+
+        Ok(Self {
+            output_options,
+            video: video_options,
+            audio: audio_options,
+        })
+    }
+}
+
+impl TryFrom<RtmpOutputStream> for pipeline::RegisterOutputOptions<output::OutputOptions> {
+    type Error = TypeError;
+
+    fn try_from(request: RtmpOutputStream) -> Result<Self, Self::Error> {
+        let RtmpOutputStream { url, video, audio } = request;
+
+        if video.is_none() && audio.is_none() {
+            return Err(TypeError::new(
+                "At least one of \"video\" and \"audio\" fields have to be specified.",
+            ));
+        }
+        if !url.starts_with("rtmp://") && !url.starts_with("rtmps://") {
+            return Err(TypeError::new(
+                "\"url\" has to start with \"rtmp://\" or \"rtmps://\".",
+            ));
+        }
+
+        let (video_options, video_encoder_options) = video
+            .map(|v| output_video_options(v.resolution, v.send_eos_when, v.encoder, v.initial))
+            .transpose()?
+            .unzip();
+
+        let (audio_options, audio_encoder_options) = match audio.clone() {
+            Some(a) => {
+                let RtmpAudioEncoderOptions::Aac { channels } = a.encoder;
+
+                (
+                    Some(output_audio_options(
+                        a.mixing_strategy,
+                        a.send_eos_when,
+                        channels.clone(),
+                        a.initial,
+                    )?),
+                    Some(pipeline::encoder::AudioEncoderOptions::Aac(
+                        encoder::fdk_aac::Options {
+                            channels: channels.into(),
+                        },
+                    )),
+                )
+            }
+            None => (None, None),
+        };
+
+        let output_options = output::OutputOptions {
+            output_protocol: output::OutputProtocolOptions::Rtmp(output::rtmp::RtmpSenderOptions {
+                url,
+                video: video_encoder_options
+                    .as_ref()
+                    .map(|encoder| encoder.codec()),
+                audio: audio.map(|_| pipeline::AudioCodec::Aac),
+            }),
+            video: video_encoder_options,
+            audio: audio_encoder_options,
+        };
+
+        Ok(Self {
+            output_options,
+            video: video_options,
+            audio: audio_options,
+        })
+    }
+}
+
+impl TryFrom<Mp4OutputStream> for pipeline::RegisterOutputOptions<output::OutputOptions> {
+    type Error = TypeError;
+
+    fn try_from(request: Mp4OutputStream) -> Result<Self, Self::Error> {
+        let Mp4OutputStream {
+            path,
+            fragmented,
+            video,
+            audio,
+        } = request;
+
+        if video.is_none() && audio.is_none() {
+            return Err(TypeError::new(
+                "At least one of \"video\" and \"audio\" fields have to be specified.",
+            ));
+        }
+
+        let (video_options, video_encoder_options) = video
+            .map(|v| output_video_options(v.resolution, v.send_eos_when, v.encoder, v.initial))
+            .transpose()?
+            .unzip();
+
+        let (audio_options, audio_encoder_options, audio_codec) = match audio {
+            Some(a) => {
+                let (channels, encoder_options, codec) = match a.encoder {
+                    Mp4AudioEncoderOptions::Aac { channels } => (
+                        channels.clone(),
+                        pipeline::encoder::AudioEncoderOptions::Aac(encoder::fdk_aac::Options {
+                            channels: channels.into(),
+                        }),
+                        pipeline::AudioCodec::Aac,
+                    ),
+                    Mp4AudioEncoderOptions::Opus { channels, preset } => (
+                        channels.clone(),
+                        pipeline::encoder::AudioEncoderOptions::Opus(encoder::opus::Options {
+                            channels: channels.into(),
+                            preset: preset.unwrap_or(OpusEncoderPreset::Quality).into(),
+                        }),
+                        pipeline::AudioCodec::Opus,
+                    ),
+                };
+
+                (
+                    Some(output_audio_options(
+                        a.mixing_strategy,
+                        a.send_eos_when,
+                        channels,
+                        a.initial,
+                    )?),
+                    Some(encoder_options),
+                    Some(codec),
+                )
+            }
+            None => (None, None, None),
+        };
+
+        let output_options = output::OutputOptions {
+            output_protocol: output::OutputProtocolOptions::Mp4(output::mp4::Mp4WriterOptions {
+                output_path: path.into(),
+                fragmented: fragmented.unwrap_or(false),
+                video: video_encoder_options
+                    .as_ref()
+                    .map(|encoder| encoder.codec()),
+                audio: audio_codec,
+            }),
+            video: video_encoder_options,
+            audio: audio_encoder_options,
+        };
+
+        Ok(Self {
+            output_options,
+            video: video_options,
+            audio: audio_options,
+        })
+    }
+}
+
+impl TryFrom<HlsOutputStream> for pipeline::RegisterOutputOptions<output::OutputOptions> {
+    type Error = TypeError;
+
+    fn try_from(request: HlsOutputStream) -> Result<Self, Self::Error> {
+        let HlsOutputStream {
+            directory,
+            segment_duration_ms,
+            playlist_size,
+            segment_type,
+            video,
+            audio,
+        } = request;
+
+        if video.is_none() && audio.is_none() {
+            return Err(TypeError::new(
+                "At least one of \"video\" and \"audio\" fields have to be specified.",
+            ));
+        }
+
+        let segment_duration_ms = segment_duration_ms.unwrap_or(6000.0);
+        if !segment_duration_ms.is_finite() || segment_duration_ms <= 0.0 {
+            return Err(TypeError::new(
+                "\"segment_duration_ms\" has to be a positive number.",
+            ));
+        }
+        let playlist_size = playlist_size.unwrap_or(5);
+        if playlist_size == 0 {
+            return Err(TypeError::new("\"playlist_size\" has to be at least 1."));
+        }
+
+        let (video_options, video_encoder_options) = video
+            .map(|v| output_video_options(v.resolution, v.send_eos_when, v.encoder, v.initial))
+            .transpose()?
+            .unzip();
+
+        let (audio_options, audio_encoder_options) = match audio.clone() {
+            Some(a) => {
+                let HlsAudioEncoderOptions::Aac { channels } = a.encoder;
+
+                (
+                    Some(output_audio_options(
+                        a.mixing_strategy,
+                        a.send_eos_when,
+                        channels.clone(),
+                        a.initial,
+                    )?),
+                    Some(pipeline::encoder::AudioEncoderOptions::Aac(
+                        encoder::fdk_aac::Options {
+                            channels: channels.into(),
+                        },
+                    )),
+                )
+            }
+            None => (None, None),
+        };
+
+        let segment_type = match segment_type.unwrap_or(HlsSegmentType::MpegTs) {
+            HlsSegmentType::MpegTs => output::hls::HlsSegmentType::MpegTs,
+            HlsSegmentType::Fmp4 => output::hls::HlsSegmentType::Fmp4,
+        };
+
+        let output_options = output::OutputOptions {
+            output_protocol: output::OutputProtocolOptions::Hls(output::hls::HlsWriterOptions {
+                directory: directory.into(),
+                segment_duration: Duration::from_secs_f64(segment_duration_ms / 1000.0),
+                playlist_size: playlist_size as usize,
+                segment_type,
+                video: video_encoder_options
+                    .as_ref()
+                    .map(|encoder| encoder.codec()),
+                audio: audio.map(|_| pipeline::AudioCodec::Aac),
+            }),
+            video: video_encoder_options,
+            audio: audio_encoder_options,
+        };
+
+        Ok(Self {
+            output_options,
+            video: video_options,
+            audio: audio_options,
+        })
+    }
+}
+
+impl TryFrom<SrtOutputStream> for pipeline::RegisterOutputOptions<output::OutputOptions> {
+    type Error = TypeError;
+
+    fn try_from(request: SrtOutputStream) -> Result<Self, Self::Error> {
+        let SrtOutputStream {
+            mode,
+            port,
+            ip,
+            latency_ms,
+            passphrase,
+            stream_id,
+            video,
+            audio,
+        } = request;
+
+        if video.is_none() && audio.is_none() {
+            return Err(TypeError::new(
+                "At least one of \"video\" and \"audio\" fields have to be specified.",
+            ));
+        }
+
+        let (video_options, video_encoder_options) = video
+            .map(|v| output_video_options(v.resolution, v.send_eos_when, v.encoder, v.initial))
+            .transpose()?
+            .unzip();
+
+        let (audio_options, audio_encoder_options, audio_codec) = match audio {
+            Some(a) => {
+                let (channels, encoder_options, codec) = match a.encoder {
+                    SrtAudioEncoderOptions::Aac { channels } => (
+                        channels.clone(),
+                        pipeline::encoder::AudioEncoderOptions::Aac(encoder::fdk_aac::Options {
+                            channels: channels.into(),
+                        }),
+                        pipeline::AudioCodec::Aac,
+                    ),
+                    SrtAudioEncoderOptions::Opus { channels, preset } => (
+                        channels.clone(),
+                        pipeline::encoder::AudioEncoderOptions::Opus(encoder::opus::Options {
+                            channels: channels.into(),
+                            preset: preset.unwrap_or(OpusEncoderPreset::Voip).into(),
+                        }),
+                        pipeline::AudioCodec::Opus,
+                    ),
+                };
+
+                (
+                    Some(output_audio_options(
+                        a.mixing_strategy,
+                        a.send_eos_when,
+                        channels,
+                        a.initial,
+                    )?),
+                    Some(encoder_options),
+                    Some(codec),
+                )
+            }
+            None => (None, None, None),
+        };
+
+        let output_options = output::OutputOptions {
+            output_protocol: output::OutputProtocolOptions::Srt(output::srt::SrtSenderOptions {
+                connection: srt_connection_options(
+                    mode, port, ip, latency_ms, passphrase, stream_id,
+                )?,
+                video: video_encoder_options
+                    .as_ref()
+                    .map(|encoder| encoder.codec()),
+                audio: audio_codec,
+            }),
+            video: video_encoder_options,
+            audio: audio_encoder_options,
+        };
+
+        Ok(Self {
+            output_options,
+            video: video_options,
+            audio: audio_options,
+        })
+    }
+}
+
+impl TryFrom<MpegTsOutputStream> for pipeline::RegisterOutputOptions<output::OutputOptions> {
+    type Error = TypeError;
+
+    fn try_from(request: MpegTsOutputStream) -> Result<Self, Self::Error> {
+        let MpegTsOutputStream {
+            ip,
+            port,
+            multicast_ttl,
+            multicast_interface,
+            video,
+            audio,
+        } = request;
+
+        if video.is_none() && audio.is_none() {
+            return Err(TypeError::new(
+                "At least one of \"video\" and \"audio\" fields have to be specified.",
+            ));
+        }
+
+        let (video_options, video_encoder_options) = video
+            .map(|v| output_video_options(v.resolution, v.send_eos_when, v.encoder, v.initial))
+            .transpose()?
+            .unzip();
+
+        let (audio_options, audio_encoder_options, audio_codec) = match audio {
+            Some(a) => {
+                let (channels, encoder_options, codec) = match a.encoder {
+                    MpegTsAudioEncoderOptions::Aac { channels } => (
+                        channels.clone(),
+                        pipeline::encoder::AudioEncoderOptions::Aac(encoder::fdk_aac::Options {
+                            channels: channels.into(),
+                        }),
+                        pipeline::AudioCodec::Aac,
+                    ),
+                    MpegTsAudioEncoderOptions::Opus { channels, preset } => (
+                        channels.clone(),
+                        pipeline::encoder::AudioEncoderOptions::Opus(encoder::opus::Options {
+                            channels: channels.into(),
+                            preset: preset.unwrap_or(OpusEncoderPreset::Voip).into(),
+                        }),
+                        pipeline::AudioCodec::Opus,
+                    ),
+                };
+
+                (
+                    Some(output_audio_options(
+                        a.mixing_strategy,
+                        a.send_eos_when,
+                        channels,
+                        a.initial,
+                    )?),
+                    Some(encoder_options),
+                    Some(codec),
+                )
+            }
+            None => (None, None, None),
+        };
+
+        let ip_address = parse_ip_address(&ip)?;
+        let multicast = match ip_address.is_multicast() {
+            true => output::rtp::MulticastSenderOptions {
+                ttl: multicast_ttl,
+                interface: multicast_interface
+                    .map(|interface| parse_multicast_interface(ip_address, &interface))
+                    .transpose()?,
+            },
+            false if multicast_ttl.is_some() || multicast_interface.is_some() => {
+                return Err(TypeError::new(
+                    "\"multicast_ttl\" and \"multicast_interface\" fields are only allowed if \"ip\" is a multicast address.",
+                ));
+            }
+            false => output::rtp::MulticastSenderOptions::default(),
+        };
+        if let Some(pipeline::rtp::MulticastInterface::V6(_)) = multicast.interface {
+            return Err(TypeError::new(
+                "\"multicast_interface\" can not be used with IPv6 groups in MPEG-TS outputs.",
+            ));
+        }
+
+        let output_options = output::OutputOptions {
+            output_protocol: output::OutputProtocolOptions::MpegTs(
+                output::mpeg_ts::MpegTsSenderOptions {
+                    ip,
+                    port: pipeline::Port(port),
+                    multicast,
+                    video: video_encoder_options
+                        .as_ref()
+                        .map(|encoder| encoder.codec()),
+                    audio: audio_codec,
+                },
+            ),
+            video: video_encoder_options,
+            audio: audio_encoder_options,
+        };
+
+        Ok(Self {
+            output_options,
+            video: video_options,
+            audio: audio_options,
+        })
+    }
+}
+
+impl TryFrom<WhepOutputStream> for pipeline::RegisterOutputOptions<output::OutputOptions> {
+    type Error = TypeError;
+
+    fn try_from(request: WhepOutputStream) -> Result<Self, Self::Error> {
+        let WhepOutputStream {
+            bearer_token,
+            video,
+            audio,
+        } = request;
+
+        if video.is_none() && audio.is_none() {
+            return Err(TypeError::new(
+                "At least one of \"video\" and \"audio\" fields have to be specified.",
+            ));
+        }
+        if bearer_token.as_ref().is_some_and(String::is_empty) {
+            return Err(TypeError::new("\"bearer_token\" field can not be empty."));
+        }
+
+        let (video_options, video_encoder_options) = video
+            .map(|v| output_video_options(v.resolution, v.send_eos_when, v.encoder, v.initial))
+            .transpose()?
+            .unzip();
+
+        let (audio_options, audio_encoder_options) = match audio.clone() {
+            Some(a) => {
+                let AudioEncoderOptions::Opus { channels, preset } = a.encoder;
+
+                (
+                    Some(output_audio_options(
+                        a.mixing_strategy,
+                        a.send_eos_when,
+                        channels.clone(),
+                        a.initial,
+                    )?),
+                    Some(pipeline::encoder::AudioEncoderOptions::Opus(
+                        encoder::opus::Options {
+                            channels: channels.into(),
+                            preset: preset.unwrap_or(OpusEncoderPreset::Voip).into(),
+                        },
+                    )),
+                )
+            }
+            None => (None, None),
+        };
+
+        let output_options = output::OutputOptions {
+            output_protocol: output::OutputProtocolOptions::Whep(output::whep::WhepSenderOptions {
+                video: video_encoder_options
+                    .as_ref()
+                    .map(|encoder| encoder.codec()),
                 audio: audio.map(|_| pipeline::AudioCodec::Opus),
+                bearer_token: bearer_token.map(Into::into),
             }),
             video: video_encoder_options,
             audio: audio_encoder_options,
@@ -132,6 +632,178 @@ impl TryFrom<RtpOutputStream> for pipeline::RegisterOutputOptions<output::Output
     }
 }
 
+fn output_video_options(
+    resolution: Resolution,
+    send_eos_when: Option<OutputEndCondition>,
+    encoder: VideoEncoderOptions,
+    initial: Video,
+) -> Result<
+    (
+        pipeline::OutputVideoOptions,
+        pipeline::encoder::VideoEncoderOptions,
+    ),
+    TypeError,
+> {
+    if resolution.width % 2 != 0 || resolution.height % 2 != 0 {
+        return Err(TypeError::new(
+            "Output video width and height has to be divisible by 2",
+        ));
+    };
+
+    let encoder_options = match encoder {
+        VideoEncoderOptions::FfmpegH264 {
+            preset,
+            ffmpeg_options,
+        } => pipeline::encoder::VideoEncoderOptions::H264(ffmpeg_h264::Options {
+            preset: preset.into(),
+            resolution: resolution.into(),
+            raw_options: ffmpeg_options.unwrap_or_default().into_iter().collect(),
+        }),
+        VideoEncoderOptions::FfmpegVp8 {
+            bitrate,
+            deadline,
+            cpu_used,
+            keyframe_interval,
+            ffmpeg_options,
+        } => pipeline::encoder::VideoEncoderOptions::Vp8(vpx_encoder_options(
+            resolution,
+            bitrate,
+            deadline,
+            cpu_used,
+            keyframe_interval,
+            ffmpeg_options,
+            16,
+        )?),
+        VideoEncoderOptions::FfmpegVp9 {
+            bitrate,
+            deadline,
+            cpu_used,
+            keyframe_interval,
+            ffmpeg_options,
+        } => pipeline::encoder::VideoEncoderOptions::Vp9(vpx_encoder_options(
+            resolution,
+            bitrate,
+            deadline,
+            cpu_used,
+            keyframe_interval,
+            ffmpeg_options,
+            9,
+        )?),
+        VideoEncoderOptions::FfmpegAv1 {
+            bitrate,
+            cpu_used,
+            keyframe_interval,
+            ffmpeg_options,
+        } => pipeline::encoder::VideoEncoderOptions::Av1(av1_encoder_options(
+            resolution,
+            bitrate,
+            cpu_used,
+            keyframe_interval,
+            ffmpeg_options,
+        )?),
+    };
+
+    Ok((
+        pipeline::OutputVideoOptions {
+            initial: initial.try_into()?,
+            end_condition: send_eos_when.unwrap_or_default().try_into()?,
+        },
+        encoder_options,
+    ))
+}
+
+fn vpx_encoder_options(
+    resolution: Resolution,
+    bitrate: Option<u64>,
+    deadline: Option<VpxEncoderDeadline>,
+    cpu_used: Option<u8>,
+    keyframe_interval: Option<u32>,
+    ffmpeg_options: Option<HashMap<String, String>>,
+    max_cpu_used: u8,
+) -> Result<ffmpeg_vpx::Options, TypeError> {
+    const DEFAULT_BITRATE: u64 = 2_000_000;
+    const DEFAULT_CPU_USED: u8 = 8;
+    const DEFAULT_KEYFRAME_INTERVAL: u32 = 250;
+
+    let bitrate = bitrate.unwrap_or(DEFAULT_BITRATE);
+    if bitrate == 0 {
+        return Err(TypeError::new("\"bitrate\" has to be greater than 0."));
+    }
+    let cpu_used = cpu_used.unwrap_or(DEFAULT_CPU_USED);
+    if cpu_used > max_cpu_used {
+        return Err(TypeError::new(format!(
+            "\"cpu_used\" has to be in range 0-{max_cpu_used}."
+        )));
+    }
+    let keyframe_interval = keyframe_interval.unwrap_or(DEFAULT_KEYFRAME_INTERVAL);
+    if keyframe_interval == 0 {
+        return Err(TypeError::new(
+            "\"keyframe_interval\" has to be greater than 0.",
+        ));
+    }
+
+    Ok(ffmpeg_vpx::Options {
+        resolution: resolution.into(),
+        bitrate,
+        deadline: deadline.unwrap_or(VpxEncoderDeadline::Realtime).into(),
+        cpu_used,
+        keyframe_interval,
+        raw_options: ffmpeg_options.unwrap_or_default().into_iter().collect(),
+    })
+}
+
+fn av1_encoder_options(
+    resolution: Resolution,
+    bitrate: Option<u64>,
+    cpu_used: Option<u8>,
+    keyframe_interval: Option<u32>,
+    ffmpeg_options: Option<HashMap<String, String>>,
+) -> Result<ffmpeg_av1::Options, TypeError> {
+    const DEFAULT_BITRATE: u64 = 1_000_000;
+    const DEFAULT_CPU_USED: u8 = 8;
+    const MAX_CPU_USED: u8 = 8;
+    const DEFAULT_KEYFRAME_INTERVAL: u32 = 250;
+
+    let bitrate = bitrate.unwrap_or(DEFAULT_BITRATE);
+    if bitrate == 0 {
+        return Err(TypeError::new("\"bitrate\" has to be greater than 0."));
+    }
+    let cpu_used = cpu_used.unwrap_or(DEFAULT_CPU_USED);
+    if cpu_used > MAX_CPU_USED {
+        return Err(TypeError::new(format!(
+            "\"cpu_used\" has to be in range 0-{MAX_CPU_USED}."
+        )));
+    }
+    let keyframe_interval = keyframe_interval.unwrap_or(DEFAULT_KEYFRAME_INTERVAL);
+    if keyframe_interval == 0 {
+        return Err(TypeError::new(
+            "\"keyframe_interval\" has to be greater than 0.",
+        ));
+    }
+
+    Ok(ffmpeg_av1::Options {
+        resolution: resolution.into(),
+        bitrate,
+        cpu_used,
+        keyframe_interval,
+        raw_options: ffmpeg_options.unwrap_or_default().into_iter().collect(),
+    })
+}
+
+fn output_audio_options(
+    mixing_strategy: Option<MixingStrategy>,
+    send_eos_when: Option<OutputEndCondition>,
+    channels: AudioChannels,
+    initial: Audio,
+) -> Result<pipeline::OutputAudioOptions, TypeError> {
+    Ok(pipeline::OutputAudioOptions {
+        initial: initial.try_into()?,
+        channels: channels.into(),
+        end_condition: send_eos_when.unwrap_or_default().try_into()?,
+        mixing_strategy: mixing_strategy.unwrap_or(MixingStrategy::SumClip).into(),
+    })
+}
+
 impl TryFrom<OutputEndCondition> for pipeline::PipelineOutputEndCondition {
     type Error = TypeError;
 
@@ -195,6 +867,16 @@ impl From<H264EncoderPreset> for encoder::ffmpeg_h264::EncoderPreset {
     }
 }
 
+impl From<VpxEncoderDeadline> for ffmpeg_vpx::EncoderDeadline {
+    fn from(value: VpxEncoderDeadline) -> Self {
+        match value {
+            VpxEncoderDeadline::Realtime => ffmpeg_vpx::EncoderDeadline::Realtime,
+            VpxEncoderDeadline::Good => ffmpeg_vpx::EncoderDeadline::Good,
+            VpxEncoderDeadline::Best => ffmpeg_vpx::EncoderDeadline::Best,
+        }
+    }
+}
+
 impl From<OpusEncoderPreset> for encoder::AudioEncoderPreset {
     fn from(value: OpusEncoderPreset) -> Self {
         match value {
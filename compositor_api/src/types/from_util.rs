@@ -1,6 +1,6 @@
-use std::time::Duration;
+use std::{net::IpAddr, time::Duration};
 
-use compositor_pipeline::pipeline::rtp;
+use compositor_pipeline::pipeline::{self, rtp, srt};
 use compositor_render::scene;
 
 use super::util::*;
@@ -237,3 +237,102 @@ impl From<TransportProtocol> for rtp::TransportProtocol {
         }
     }
 }
+
+impl TryFrom<SrtpOptions> for rtp::srtp::SrtpOptions {
+    type Error = TypeError;
+
+    fn try_from(value: SrtpOptions) -> Result<Self, Self::Error> {
+        let profile = match value.profile.unwrap_or(SrtpProfile::AesCm128HmacSha1_80) {
+            SrtpProfile::AesCm128HmacSha1_80 => rtp::srtp::SrtpProfile::AesCm128HmacSha1_80,
+        };
+        Ok(rtp::srtp::SrtpOptions::from_inline_key(
+            profile, &value.key,
+        )?)
+    }
+}
+
+pub(super) fn parse_ip_address(ip: &str) -> Result<IpAddr, TypeError> {
+    ip.parse()
+        .map_err(|_| TypeError::new(format!("\"{ip}\" is not a valid IP address.")))
+}
+
+/// IPv4 groups use an address of the interface and IPv6 groups use an interface index.
+pub(super) fn parse_multicast_interface(
+    group: IpAddr,
+    interface: &str,
+) -> Result<rtp::MulticastInterface, TypeError> {
+    match group {
+        IpAddr::V4(_) => match interface.parse() {
+            Ok(IpAddr::V4(address)) => Ok(rtp::MulticastInterface::V4(address)),
+            _ => Err(TypeError::new(format!(
+                "Multicast interface \"{interface}\" has to be an IPv4 address of the interface for IPv4 groups."
+            ))),
+        },
+        IpAddr::V6(_) => match interface.parse() {
+            Ok(index) => Ok(rtp::MulticastInterface::V6(index)),
+            Err(_) => Err(TypeError::new(format!(
+                "Multicast interface \"{interface}\" has to be an interface index for IPv6 groups."
+            ))),
+        },
+    }
+}
+
+/// Connection options shared by SRT inputs and outputs.
+pub(super) fn srt_connection_options(
+    mode: SrtMode,
+    port: u16,
+    ip: Option<String>,
+    latency_ms: Option<f64>,
+    passphrase: Option<String>,
+    stream_id: Option<String>,
+) -> Result<srt::SrtConnectionOptions, TypeError> {
+    let mode = match (mode, ip) {
+        (SrtMode::Listener, None) => srt::SrtMode::Listener {
+            port: pipeline::Port(port),
+        },
+        (SrtMode::Listener, Some(_)) => {
+            return Err(TypeError::new(
+                "\"ip\" field is not allowed in the SRT listener mode (mode=\"listener\").",
+            ));
+        }
+        (SrtMode::Caller, Some(ip)) => srt::SrtMode::Caller {
+            ip: parse_ip_address(&ip)?.to_string().into(),
+            port: pipeline::Port(port),
+        },
+        (SrtMode::Caller, None) => {
+            return Err(TypeError::new(
+                "\"ip\" field is required in the SRT caller mode (mode=\"caller\").",
+            ));
+        }
+    };
+    if stream_id.is_some() && matches!(mode, srt::SrtMode::Listener { .. }) {
+        return Err(TypeError::new(
+            "\"stream_id\" field is only allowed in the SRT caller mode (mode=\"caller\").",
+        ));
+    }
+
+    let latency = match latency_ms {
+        Some(latency_ms) if !(0.0..=60_000.0).contains(&latency_ms) => {
+            return Err(TypeError::new(
+                "\"latency_ms\" has to be a value between 0 and 60000.",
+            ));
+        }
+        Some(latency_ms) => Duration::from_secs_f64(latency_ms / 1000.0),
+        None => srt::DEFAULT_LATENCY,
+    };
+
+    if let Some(passphrase) = &passphrase {
+        if !(10..=79).contains(&passphrase.len()) {
+            return Err(TypeError::new(
+                "SRT \"passphrase\" has to be between 10 and 79 characters long.",
+            ));
+        }
+    }
+
+    Ok(srt::SrtConnectionOptions {
+        mode,
+        latency,
+        passphrase: passphrase.map(Into::into),
+        stream_id: stream_id.map(Into::into),
+    })
+}
@@ -1,7 +1,11 @@
+use std::time::Duration;
+
+use compositor_pipeline::pipeline::{self, rtp, srt};
 use compositor_render::scene;
 
 use crate::types::{
-    util::{RGBAColor, RGBColor},
+    from_util::{parse_multicast_interface, srt_connection_options},
+    util::{RGBAColor, RGBColor, SrtMode},
     TypeError,
 };
 
@@ -56,3 +60,72 @@ fn test_rgba_deserialization() {
         )),
     );
 }
+
+#[test]
+fn test_multicast_interface_parsing() {
+    let ipv4_group = "239.0.0.1".parse().unwrap();
+    let ipv6_group = "ff0e::1".parse().unwrap();
+    assert_eq!(
+        parse_multicast_interface(ipv4_group, "192.168.1.10"),
+        Ok(rtp::MulticastInterface::V4("192.168.1.10".parse().unwrap()))
+    );
+    assert_eq!(
+        parse_multicast_interface(ipv6_group, "3"),
+        Ok(rtp::MulticastInterface::V6(3))
+    );
+    assert!(parse_multicast_interface(ipv4_group, "3").is_err());
+    assert!(parse_multicast_interface(ipv6_group, "192.168.1.10").is_err());
+}
+
+#[test]
+fn test_srt_connection_options() {
+    assert_eq!(
+        srt_connection_options(
+            SrtMode::Caller,
+            9000,
+            Some("127.0.0.1".to_string()),
+            Some(200.0),
+            Some("0123456789".to_string()),
+            Some("live/camera1".to_string()),
+        ),
+        Ok(srt::SrtConnectionOptions {
+            mode: srt::SrtMode::Caller {
+                ip: "127.0.0.1".into(),
+                port: pipeline::Port(9000),
+            },
+            latency: Duration::from_millis(200),
+            passphrase: Some("0123456789".into()),
+            stream_id: Some("live/camera1".into()),
+        })
+    );
+    assert_eq!(
+        srt_connection_options(SrtMode::Listener, 9000, None, None, None, None),
+        Ok(srt::SrtConnectionOptions {
+            mode: srt::SrtMode::Listener {
+                port: pipeline::Port(9000)
+            },
+            latency: srt::DEFAULT_LATENCY,
+            passphrase: None,
+            stream_id: None,
+        })
+    );
+    assert!(srt_connection_options(SrtMode::Caller, 9000, None, None, None, None).is_err());
+    assert!(srt_connection_options(
+        SrtMode::Listener,
+        9000,
+        Some("127.0.0.1".to_string()),
+        None,
+        None,
+        None
+    )
+    .is_err());
+    assert!(srt_connection_options(
+        SrtMode::Listener,
+        9000,
+        None,
+        None,
+        Some("short".to_string()),
+        None
+    )
+    .is_err());
+}
@@ -6,18 +6,209 @@ use serde::{Deserialize, Serialize};
 use super::util::*;
 
 /// Parameters for an input stream from RTP source.
-/// At least one of `video` and `audio` has to be defined.
+/// Streams can be described either with `sdp` or with `port`, `video` and `audio` fields.
+/// In the latter case, at least one of `video` and `audio` has to be defined.
 #[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct RtpInputStream {
     /// UDP port or port range on which the compositor should listen for the stream.
-    pub port: PortOrPortRange,
+    /// Required if `sdp` is not specified. Otherwise, it overrides the port from the SDP.
+    pub port: Option<PortOrPortRange>,
     /// Transport protocol.
     pub transport_protocol: Option<TransportProtocol>,
+    /// Multicast group that the compositor should join to receive the stream. Only valid
+    /// with `transport_protocol="udp"`. If not specified and `sdp` describes a multicast
+    /// stream, the connection address from the SDP is joined.
+    pub multicast: Option<RtpMulticastGroup>,
+    /// Decrypt the stream with SRTP. If not specified and `sdp` contains an `a=crypto`
+    /// attribute, the key from the SDP is used.
+    pub srtp: Option<SrtpOptions>,
     /// Parameters of a video source included in the RTP stream.
     pub video: Option<InputRtpVideoOptions>,
     /// Parameters of an audio source included in the RTP stream.
     pub audio: Option<InputRtpAudioOptions>,
+    /// Session description (SDP) of the stream, e.g. generated with the ffmpeg `-sdp_file`
+    /// option. Codecs, payload types, codec parameters, port and transport protocol are
+    /// derived from it. Packets with payload types that are not described in the SDP
+    /// are rejected. Can not be used together with `video`, `audio` and `transport_protocol`.
+    pub sdp: Option<String>,
+    /// (**default=`false`**) If input is required and the stream is not delivered
+    /// on time, then LiveCompositor will delay producing output frames.
+    pub required: Option<bool>,
+    /// Offset in milliseconds relative to the pipeline start (start request). If the offset is
+    /// not defined then the stream will be synchronized based on the delivery time of the initial
+    /// frames.
+    pub offset_ms: Option<f64>,
+    /// (**default=`50`**) Time in milliseconds that packets are held while waiting for
+    /// a missing packet. After that time the missing packet is considered lost. Higher
+    /// values handle more reordering on the network at the cost of latency.
+    pub jitter_buffer_latency_ms: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct RtpMulticastGroup {
+    /// IPv4 or IPv6 address of the multicast group.
+    pub group: String,
+    /// Address of the sender. If specified, the group is joined with source-specific
+    /// multicast (SSM) and only packets sent from this address are received. Supported
+    /// only for IPv4 groups.
+    pub source: Option<String>,
+    /// Local network interface used to join the group. IPv4 address of the interface for
+    /// IPv4 groups or an interface index for IPv6 groups. By default, the interface is
+    /// chosen by the operating system.
+    pub interface: Option<String>,
+}
+
+/// Parameters for an input stream received over RTMP. LiveCompositor acts as an RTMP server
+/// and the stream should be published to `rtmp://<compositor_ip>:<port>/<app>/<stream_key>`.
+/// At least one of `video` and `audio` has to be defined.
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct RtmpInputStream {
+    /// TCP port on which the compositor should listen for the RTMP connection.
+    pub port: u16,
+    /// (**default=`"live"`**) Name of the RTMP application.
+    pub app: Option<String>,
+    /// Stream key used by the publisher.
+    pub stream_key: String,
+    /// Parameters of a video source included in the RTMP stream.
+    pub video: Option<InputRtmpVideoOptions>,
+    /// Parameters of an audio source included in the RTMP stream.
+    pub audio: Option<InputRtmpAudioOptions>,
+    /// (**default=`false`**) If input is required and the stream is not delivered
+    /// on time, then LiveCompositor will delay producing output frames.
+    pub required: Option<bool>,
+    /// Offset in milliseconds relative to the pipeline start (start request). If the offset is
+    /// not defined then the stream will be synchronized based on the delivery time of the initial
+    /// frames.
+    pub offset_ms: Option<f64>,
+}
+
+/// Input stream published over WebRTC with WHIP (WebRTC-HTTP Ingestion Protocol). The
+/// publisher should send its SDP offer to `http://<compositor_ip>:<api_port>/whip/<input_id>`.
+/// The input accepts a single publisher. At least one of `video` and `audio` has to be defined.
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct WhipInput {
+    /// Parameters of a video source included in the WebRTC stream.
+    pub video: Option<InputWhipVideoOptions>,
+    /// Parameters of an audio source included in the WebRTC stream.
+    pub audio: Option<InputWhipAudioOptions>,
+    /// Token that the publisher has to send in the `Authorization: Bearer <token>` header.
+    /// If not specified, any publisher is accepted.
+    pub bearer_token: Option<String>,
+    /// (**default=`false`**) If input is required and the stream is not delivered
+    /// on time, then LiveCompositor will delay producing output frames.
+    pub required: Option<bool>,
+    /// Offset in milliseconds relative to the pipeline start (start request). If the offset is
+    /// not defined then the stream will be synchronized based on the delivery time of the initial
+    /// frames.
+    pub offset_ms: Option<f64>,
+    /// (**default=`50`**) Time in milliseconds that packets are held while waiting for
+    /// a missing packet. After that time the missing packet is considered lost. Higher
+    /// values handle more reordering on the network at the cost of latency.
+    pub jitter_buffer_latency_ms: Option<f64>,
+}
+
+/// Input stream from an HLS playlist. Both master and media playlists are supported, for
+/// a master playlist the variant with the best video quality is used. Live playlists are
+/// refreshed until the input is unregistered.
+/// Exactly one of `url` and `path` has to be defined.
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct HlsInputStream {
+    /// URL of the `.m3u8` playlist.
+    pub url: Option<String>,
+    /// Path to the `.m3u8` playlist on the local filesystem.
+    pub path: Option<String>,
+    /// (**default=`false`**) If input is required and the stream is not delivered
+    /// on time, then LiveCompositor will delay producing output frames.
+    pub required: Option<bool>,
+    /// Offset in milliseconds relative to the pipeline start (start request). If the offset is
+    /// not defined then the stream will be synchronized based on the delivery time of the initial
+    /// frames.
+    pub offset_ms: Option<f64>,
+}
+
+/// Input stream received over SRT (Secure Reliable Transport). The stream should be
+/// an MPEG-TS with H264 video and AAC or Opus audio. At least one of `video` and `audio`
+/// has to be defined.
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SrtInputStream {
+    /// Connection mode. In the `listener` mode the compositor waits for a single caller,
+    /// in the `caller` mode it connects to the remote listener and retries until the
+    /// connection is accepted.
+    pub mode: SrtMode,
+    /// Local UDP port in the `listener` mode or a port of the remote listener in the
+    /// `caller` mode.
+    pub port: u16,
+    /// IPv4 or IPv6 address of the remote listener. Required in the `caller` mode,
+    /// not allowed in the `listener` mode.
+    pub ip: Option<String>,
+    /// (**default=`120`**) SRT latency in milliseconds. Lost packets are retransmitted
+    /// within this time. The larger of the values set by the sender and the receiver is used.
+    pub latency_ms: Option<f64>,
+    /// Passphrase used to decrypt the stream (10 to 79 characters). It has to match
+    /// the passphrase of the sender.
+    pub passphrase: Option<String>,
+    /// Stream ID sent to the remote listener. Only valid in the `caller` mode.
+    pub stream_id: Option<String>,
+    /// Parameters of a video source included in the MPEG-TS stream.
+    pub video: Option<InputSrtVideoOptions>,
+    /// Parameters of an audio source included in the MPEG-TS stream.
+    pub audio: Option<InputSrtAudioOptions>,
+    /// (**default=`false`**) If input is required and the stream is not delivered
+    /// on time, then LiveCompositor will delay producing output frames.
+    pub required: Option<bool>,
+    /// Offset in milliseconds relative to the pipeline start (start request). If the offset is
+    /// not defined then the stream will be synchronized based on the delivery time of the initial
+    /// frames.
+    pub offset_ms: Option<f64>,
+}
+
+/// Input stream in the MPEG-TS format sent in UDP datagrams, e.g. from broadcast
+/// equipment or with `ffmpeg -f mpegts udp://<compositor_ip>:<port>`. The stream should
+/// contain H264 video and AAC or Opus audio. At least one of `video` and `audio` has to
+/// be defined.
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct MpegTsInputStream {
+    /// UDP port on which the compositor should listen for the stream.
+    pub port: u16,
+    /// Multicast group that the compositor should join to receive the stream. Interface
+    /// has to be specified as an IPv4 address, interface indexes of IPv6 groups are
+    /// not supported.
+    pub multicast: Option<RtpMulticastGroup>,
+    /// Parameters of a video source included in the MPEG-TS stream.
+    pub video: Option<InputMpegTsVideoOptions>,
+    /// Parameters of an audio source included in the MPEG-TS stream.
+    pub audio: Option<InputMpegTsAudioOptions>,
+    /// (**default=`false`**) If input is required and the stream is not delivered
+    /// on time, then LiveCompositor will delay producing output frames.
+    pub required: Option<bool>,
+    /// Offset in milliseconds relative to the pipeline start (start request). If the offset is
+    /// not defined then the stream will be synchronized based on the delivery time of the initial
+    /// frames.
+    pub offset_ms: Option<f64>,
+}
+
+/// Input stream in the fragmented MP4 format (e.g. CMAF). The stream starts with an
+/// initialization segment (`ftyp` + `moov`) followed by `moof` + `mdat` fragments.
+/// At least one of `video` and `audio` has to be defined.
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct FragmentedMp4InputStream {
+    /// (**default=`"http"`**) How the stream is delivered to the compositor.
+    pub transport_protocol: Option<FragmentedMp4TransportProtocol>,
+    /// TCP port on which the compositor should listen for the stream. Required for
+    /// `"tcp_server"` transport.
+    pub port: Option<u16>,
+    /// Parameters of a video source included in the stream.
+    pub video: Option<InputFragmentedMp4VideoOptions>,
+    /// Parameters of an audio source included in the stream.
+    pub audio: Option<InputFragmentedMp4AudioOptions>,
     /// (**default=`false`**) If input is required and the stream is not delivered
     /// on time, then LiveCompositor will delay producing output frames.
     pub required: Option<bool>,
@@ -27,6 +218,15 @@ pub struct RtpInputStream {
     pub offset_ms: Option<f64>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FragmentedMp4TransportProtocol {
+    /// Data is sent in the body of `POST /api/input/:input_id/data` requests.
+    Http,
+    /// TCP connection where LiveCompositor is the server side of the connection.
+    TcpServer,
+}
+
 /// Input stream from MP4 file.
 /// Exactly one of `url` and `path` has to be defined.
 #[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
@@ -36,6 +236,16 @@ pub struct Mp4 {
     pub url: Option<String>,
     /// Path to the MP4 file.
     pub path: Option<String>,
+    /// (**default=`false`**) If `true`, playback restarts from `start_at_ms` after reaching
+    /// the end of the file (or `end_at_ms`). Timestamps keep increasing across loop iterations.
+    #[serde(rename = "loop")]
+    pub should_loop: Option<bool>,
+    /// (**default=`0`**) Position in the file in milliseconds where the playback starts.
+    /// Playback starts at the last keyframe before this position.
+    pub start_at_ms: Option<f64>,
+    /// Position in the file in milliseconds where the playback ends. Defaults to the end
+    /// of the file.
+    pub end_at_ms: Option<f64>,
     /// (**default=`false`**) If input is required and frames are not processed
     /// on time, then LiveCompositor will delay producing output frames.
     pub required: Option<bool>,
@@ -44,6 +254,53 @@ pub struct Mp4 {
     pub offset_ms: Option<f64>,
 }
 
+/// Input that generates a synthetic test signal: a video pattern and optionally
+/// a tone or silence on audio. It does not require any external source.
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct TestSourceInput {
+    /// Resolution of generated frames. Both dimensions have to be divisible by 2.
+    pub resolution: Resolution,
+    /// (**default=`30`**) Framerate of generated frames. Either an integer or a string
+    /// in the `"NUM/DEN"` format.
+    pub framerate: Option<Framerate>,
+    /// (**default=`"smpte_bars"`**) Generated video pattern.
+    pub pattern: Option<TestSourcePattern>,
+    /// (**default=`"#000000"`**) Color in the `"#RRGGBB"` format. Used only by the
+    /// `"solid_color"` pattern.
+    pub color: Option<RGBColor>,
+    /// Generated audio. If not specified, the input does not produce audio.
+    pub audio: Option<TestSourceAudio>,
+    /// (**default=`false`**) If input is required and frames are not processed
+    /// on time, then LiveCompositor will delay producing output frames.
+    pub required: Option<bool>,
+    /// Offset in milliseconds relative to the pipeline start (start request). If offset is
+    /// not defined then stream is synchronized based on the first frames delivery time.
+    pub offset_ms: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TestSourcePattern {
+    /// SMPTE color bars.
+    SmpteBars,
+    /// Scrolling checkerboard. Makes dropped or frozen frames easy to notice.
+    MovingPattern,
+    /// Single color defined in the `color` field.
+    SolidColor,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum TestSourceAudio {
+    /// Stereo sine wave.
+    Sine {
+        /// (**default=`1000`**) Frequency of the tone in Hz.
+        frequency_hz: Option<f64>,
+    },
+    Silence,
+}
+
 /// Capture streams from devices connected to Blackmagic DeckLink card.
 #[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 #[serde(deny_unknown_fields)]
@@ -123,4 +380,92 @@ pub enum InputRtpAudioOptions {
 pub enum InputRtpVideoOptions {
     #[serde(rename = "ffmpeg_h264")]
     FfmepgH264,
+    /// VP8 stream payloaded as described in [RFC 7741](https://datatracker.ietf.org/doc/html/rfc7741).
+    #[serde(rename = "ffmpeg_vp8")]
+    FfmpegVp8,
+    /// VP9 stream payloaded as described in [RFC 9628](https://datatracker.ietf.org/doc/html/rfc9628).
+    #[serde(rename = "ffmpeg_vp9")]
+    FfmpegVp9,
+    /// AV1 stream payloaded as described in [RTP Payload Format for AV1](https://aomediacodec.github.io/av1-rtp-spec/).
+    /// Decoded with dav1d.
+    #[serde(rename = "ffmpeg_av1")]
+    FfmpegAv1,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(tag = "decoder", rename_all = "snake_case", deny_unknown_fields)]
+pub enum InputRtmpVideoOptions {
+    #[serde(rename = "ffmpeg_h264")]
+    FfmpegH264,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(tag = "decoder", rename_all = "snake_case", deny_unknown_fields)]
+pub enum InputRtmpAudioOptions {
+    Aac,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(tag = "decoder", rename_all = "snake_case", deny_unknown_fields)]
+pub enum InputWhipVideoOptions {
+    #[serde(rename = "ffmpeg_h264")]
+    FfmpegH264,
+    #[serde(rename = "ffmpeg_vp8")]
+    FfmpegVp8,
+    #[serde(rename = "ffmpeg_vp9")]
+    FfmpegVp9,
+    /// Decoded with dav1d.
+    #[serde(rename = "ffmpeg_av1")]
+    FfmpegAv1,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(tag = "decoder", rename_all = "snake_case", deny_unknown_fields)]
+pub enum InputWhipAudioOptions {
+    Opus {
+        /// (**default=`false`**) Specifies whether the stream uses forward error correction.
+        /// For more information, check out [RFC](https://datatracker.ietf.org/doc/html/rfc6716#section-2.1.7).
+        forward_error_correction: Option<bool>,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(tag = "decoder", rename_all = "snake_case", deny_unknown_fields)]
+pub enum InputSrtVideoOptions {
+    #[serde(rename = "ffmpeg_h264")]
+    FfmpegH264,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(tag = "decoder", rename_all = "snake_case", deny_unknown_fields)]
+pub enum InputSrtAudioOptions {
+    Aac,
+    Opus,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(tag = "decoder", rename_all = "snake_case", deny_unknown_fields)]
+pub enum InputMpegTsVideoOptions {
+    #[serde(rename = "ffmpeg_h264")]
+    FfmpegH264,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(tag = "decoder", rename_all = "snake_case", deny_unknown_fields)]
+pub enum InputMpegTsAudioOptions {
+    Aac,
+    Opus,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(tag = "decoder", rename_all = "snake_case", deny_unknown_fields)]
+pub enum InputFragmentedMp4VideoOptions {
+    #[serde(rename = "ffmpeg_h264")]
+    FfmpegH264,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(tag = "decoder", rename_all = "snake_case", deny_unknown_fields)]
+pub enum InputFragmentedMp4AudioOptions {
+    Aac,
 }
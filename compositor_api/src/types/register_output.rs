@@ -15,15 +15,143 @@ pub struct RtpOutputStream {
     /// Depends on the value of the `transport_protocol` field:
     ///   - `udp` - An UDP port number that RTP packets will be sent to.
     ///   - `tcp_server` - A local TCP port number or a port range that LiveCompositor will listen for incoming connections.
+    ///   - `tcp_client` - A TCP port number of the remote receiver that LiveCompositor will connect to.
     pub port: PortOrPortRange,
-    /// Only valid if `transport_protocol="udp"`. IP address where RTP packets should be sent to.
+    /// Only valid if `transport_protocol="udp"` or `transport_protocol="tcp_client"`. IPv4 or
+    /// IPv6 address where RTP packets should be sent to. For UDP, it can be a multicast group.
     pub ip: Option<Arc<str>>,
     /// (**default=`"udp"`**) Transport layer protocol that will be used to send RTP packets.
-    pub transport_protocol: Option<TransportProtocol>,
+    pub transport_protocol: Option<RtpOutputTransportProtocol>,
+    /// Only valid if `ip` is a multicast group. Time to live (IPv4) or hop limit (IPv6) of
+    /// sent packets. By default, packets do not leave the local network.
+    pub multicast_ttl: Option<u32>,
+    /// Only valid if `ip` is a multicast group. Local network interface used to send packets.
+    /// IPv4 address of the interface for IPv4 groups or an interface index for IPv6 groups.
+    /// By default, the interface is chosen by the operating system.
+    pub multicast_interface: Option<String>,
+    /// Encrypt the stream with SRTP.
+    pub srtp: Option<SrtpOptions>,
     pub video: Option<OutputRtpVideoOptions>,
     pub audio: Option<OutputRtpAudioOptions>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RtpOutputTransportProtocol {
+    /// UDP protocol.
+    Udp,
+    /// TCP protocol where LiveCompositor is the server side of the connection.
+    TcpServer,
+    /// TCP protocol where LiveCompositor is the client side of the connection.
+    TcpClient,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct RtmpOutputStream {
+    /// RTMP URL of the server the stream will be pushed to, including the application
+    /// and the stream key, e.g. `rtmp://a.rtmp.youtube.com/live2/STREAM_KEY`.
+    pub url: Arc<str>,
+    pub video: Option<OutputRtmpVideoOptions>,
+    pub audio: Option<OutputRtmpAudioOptions>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Mp4OutputStream {
+    /// Path to the output MP4 file. The file will be overwritten if it already exists.
+    pub path: String,
+    /// (**default=`false`**) Write fragmented MP4. Fragmented file stays playable even if
+    /// the compositor was terminated before the recording was finalized.
+    pub fragmented: Option<bool>,
+    pub video: Option<OutputMp4VideoOptions>,
+    pub audio: Option<OutputMp4AudioOptions>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct HlsOutputStream {
+    /// Path to the directory where the playlist (`playlist.m3u8`) and segments will be written.
+    /// The directory will be created if it does not exist.
+    pub directory: String,
+    /// (**default=`6000`**) Target duration of a single segment in milliseconds. Keyframes are
+    /// forced at segment boundaries, so every segment has exactly this duration.
+    pub segment_duration_ms: Option<f64>,
+    /// (**default=`5`**) Number of segments listed in the playlist. Segments that are removed
+    /// from the playlist are deleted.
+    pub playlist_size: Option<u32>,
+    /// (**default=`"mpeg_ts"`**) Container format of the segments.
+    pub segment_type: Option<HlsSegmentType>,
+    pub video: Option<OutputHlsVideoOptions>,
+    pub audio: Option<OutputHlsAudioOptions>,
+}
+
+/// Output served over WebRTC to any number of viewers that connect to the WHEP endpoint
+/// (`POST /whep/:output_id`). Streams are encoded once and sent to all viewers.
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct WhepOutputStream {
+    /// Token that viewers have to send in the `Authorization: Bearer <token>` header.
+    /// If not specified, any viewer is accepted.
+    pub bearer_token: Option<String>,
+    pub video: Option<OutputWhepVideoOptions>,
+    pub audio: Option<OutputWhepAudioOptions>,
+}
+
+/// Output sent over SRT (Secure Reliable Transport) as an MPEG-TS stream.
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SrtOutputStream {
+    /// Connection mode. In the `listener` mode the compositor waits for a caller,
+    /// in the `caller` mode it connects to the remote listener. The connection is
+    /// re-established if it is lost.
+    pub mode: SrtMode,
+    /// Local UDP port in the `listener` mode or a port of the remote listener in the
+    /// `caller` mode.
+    pub port: u16,
+    /// IPv4 or IPv6 address of the remote listener. Required in the `caller` mode,
+    /// not allowed in the `listener` mode.
+    pub ip: Option<String>,
+    /// (**default=`120`**) SRT latency in milliseconds. Lost packets are retransmitted
+    /// within this time. The larger of the values set by the sender and the receiver is used.
+    pub latency_ms: Option<f64>,
+    /// Passphrase used to encrypt the stream (10 to 79 characters).
+    pub passphrase: Option<String>,
+    /// Stream ID sent to the remote listener. Only valid in the `caller` mode.
+    pub stream_id: Option<String>,
+    pub video: Option<OutputSrtVideoOptions>,
+    pub audio: Option<OutputSrtAudioOptions>,
+}
+
+/// Output sent as an MPEG-TS stream in UDP datagrams (7 TS packets per datagram). It can
+/// be received by broadcast equipment or played with `ffplay udp://<ip>:<port>`.
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct MpegTsOutputStream {
+    /// IPv4 or IPv6 address where the stream should be sent to. It can be a multicast group.
+    pub ip: Arc<str>,
+    /// UDP port where the stream should be sent to.
+    pub port: u16,
+    /// Only valid if `ip` is a multicast group. Time to live (IPv4) or hop limit (IPv6) of
+    /// sent packets. By default, packets do not leave the local network.
+    pub multicast_ttl: Option<u32>,
+    /// Only valid if `ip` is an IPv4 multicast group. IPv4 address of the local network
+    /// interface used to send packets. By default, the interface is chosen by the
+    /// operating system.
+    pub multicast_interface: Option<String>,
+    pub video: Option<OutputMpegTsVideoOptions>,
+    pub audio: Option<OutputMpegTsAudioOptions>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HlsSegmentType {
+    /// MPEG Transport Stream segments (`.ts`).
+    MpegTs,
+    /// Fragmented MP4 segments (`.m4s`).
+    Fmp4,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct OutputRtpVideoOptions {
@@ -50,6 +178,162 @@ pub struct OutputRtpAudioOptions {
     pub initial: Audio,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct OutputRtmpVideoOptions {
+    /// Output resolution in pixels.
+    pub resolution: Resolution,
+    /// Defines when output stream should end if some of the input streams are finished. If output includes both audio and video streams, then EOS needs to be sent on both.
+    pub send_eos_when: Option<OutputEndCondition>,
+    /// Video encoder options.
+    pub encoder: VideoEncoderOptions,
+    /// Root of a component tree/scene that should be rendered for the output. Use [`update_output` request](../routes.md#update-output) to update this value after registration. [Learn more](../../concept/component.md).
+    pub initial: Video,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct OutputRtmpAudioOptions {
+    /// (**default="sum_clip"**) Specifies how audio should be mixed.
+    pub mixing_strategy: Option<MixingStrategy>,
+    /// Condition for termination of output stream based on the input streams states.
+    pub send_eos_when: Option<OutputEndCondition>,
+    /// Audio encoder options.
+    pub encoder: RtmpAudioEncoderOptions,
+    /// Initial audio mixer configuration for output.
+    pub initial: Audio,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct OutputMp4VideoOptions {
+    /// Output resolution in pixels.
+    pub resolution: Resolution,
+    /// Defines when output stream should end if some of the input streams are finished. If output includes both audio and video streams, then EOS needs to be sent on both.
+    pub send_eos_when: Option<OutputEndCondition>,
+    /// Video encoder options.
+    pub encoder: VideoEncoderOptions,
+    /// Root of a component tree/scene that should be rendered for the output. Use [`update_output` request](../routes.md#update-output) to update this value after registration. [Learn more](../../concept/component.md).
+    pub initial: Video,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct OutputMp4AudioOptions {
+    /// (**default="sum_clip"**) Specifies how audio should be mixed.
+    pub mixing_strategy: Option<MixingStrategy>,
+    /// Condition for termination of output stream based on the input streams states.
+    pub send_eos_when: Option<OutputEndCondition>,
+    /// Audio encoder options.
+    pub encoder: Mp4AudioEncoderOptions,
+    /// Initial audio mixer configuration for output.
+    pub initial: Audio,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct OutputHlsVideoOptions {
+    /// Output resolution in pixels.
+    pub resolution: Resolution,
+    /// Defines when output stream should end if some of the input streams are finished. If output includes both audio and video streams, then EOS needs to be sent on both.
+    pub send_eos_when: Option<OutputEndCondition>,
+    /// Video encoder options.
+    pub encoder: VideoEncoderOptions,
+    /// Root of a component tree/scene that should be rendered for the output. Use [`update_output` request](../routes.md#update-output) to update this value after registration. [Learn more](../../concept/component.md).
+    pub initial: Video,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct OutputHlsAudioOptions {
+    /// (**default="sum_clip"**) Specifies how audio should be mixed.
+    pub mixing_strategy: Option<MixingStrategy>,
+    /// Condition for termination of output stream based on the input streams states.
+    pub send_eos_when: Option<OutputEndCondition>,
+    /// Audio encoder options.
+    pub encoder: HlsAudioEncoderOptions,
+    /// Initial audio mixer configuration for output.
+    pub initial: Audio,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct OutputWhepVideoOptions {
+    /// Output resolution in pixels.
+    pub resolution: Resolution,
+    /// Defines when output stream should end if some of the input streams are finished. If output includes both audio and video streams, then EOS needs to be sent on both.
+    pub send_eos_when: Option<OutputEndCondition>,
+    /// Video encoder options.
+    pub encoder: VideoEncoderOptions,
+    /// Root of a component tree/scene that should be rendered for the output. Use [`update_output` request](../routes.md#update-output) to update this value after registration. [Learn more](../../concept/component.md).
+    pub initial: Video,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct OutputWhepAudioOptions {
+    /// (**default="sum_clip"**) Specifies how audio should be mixed.
+    pub mixing_strategy: Option<MixingStrategy>,
+    /// Condition for termination of output stream based on the input streams states.
+    pub send_eos_when: Option<OutputEndCondition>,
+    /// Audio encoder options.
+    pub encoder: AudioEncoderOptions,
+    /// Initial audio mixer configuration for output.
+    pub initial: Audio,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct OutputSrtVideoOptions {
+    /// Output resolution in pixels.
+    pub resolution: Resolution,
+    /// Defines when output stream should end if some of the input streams are finished. If output includes both audio and video streams, then EOS needs to be sent on both.
+    pub send_eos_when: Option<OutputEndCondition>,
+    /// Video encoder options.
+    pub encoder: VideoEncoderOptions,
+    /// Root of a component tree/scene that should be rendered for the output. Use [`update_output` request](../routes.md#update-output) to update this value after registration. [Learn more](../../concept/component.md).
+    pub initial: Video,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct OutputSrtAudioOptions {
+    /// (**default="sum_clip"**) Specifies how audio should be mixed.
+    pub mixing_strategy: Option<MixingStrategy>,
+    /// Condition for termination of output stream based on the input streams states.
+    pub send_eos_when: Option<OutputEndCondition>,
+    /// Audio encoder options.
+    pub encoder: SrtAudioEncoderOptions,
+    /// Initial audio mixer configuration for output.
+    pub initial: Audio,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct OutputMpegTsVideoOptions {
+    /// Output resolution in pixels.
+    pub resolution: Resolution,
+    /// Defines when output stream should end if some of the input streams are finished. If output includes both audio and video streams, then EOS needs to be sent on both.
+    pub send_eos_when: Option<OutputEndCondition>,
+    /// Video encoder options.
+    pub encoder: VideoEncoderOptions,
+    /// Root of a component tree/scene that should be rendered for the output. Use [`update_output` request](../routes.md#update-output) to update this value after registration. [Learn more](../../concept/component.md).
+    pub initial: Video,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct OutputMpegTsAudioOptions {
+    /// (**default="sum_clip"**) Specifies how audio should be mixed.
+    pub mixing_strategy: Option<MixingStrategy>,
+    /// Condition for termination of output stream based on the input streams states.
+    pub send_eos_when: Option<OutputEndCondition>,
+    /// Audio encoder options.
+    pub encoder: MpegTsAudioEncoderOptions,
+    /// Initial audio mixer configuration for output.
+    pub initial: Audio,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 #[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
 pub enum VideoEncoderOptions {
@@ -61,6 +345,58 @@ pub enum VideoEncoderOptions {
         /// Raw FFmpeg encoder options. See [docs](https://ffmpeg.org/ffmpeg-codecs.html) for more.
         ffmpeg_options: Option<HashMap<String, String>>,
     },
+    #[serde(rename = "ffmpeg_vp8")]
+    FfmpegVp8 {
+        /// (**default=`2000000`**) Target bitrate in bits per second.
+        bitrate: Option<u64>,
+
+        /// (**default=`"realtime"`**) Time the encoder can spend on a single frame.
+        deadline: Option<VpxEncoderDeadline>,
+
+        /// (**default=`8`**) Speed of the encoder in range 0-16. Higher values are faster
+        /// at the cost of quality.
+        cpu_used: Option<u8>,
+
+        /// (**default=`250`**) Maximum number of frames between keyframes.
+        keyframe_interval: Option<u32>,
+
+        /// Raw FFmpeg encoder options. See [docs](https://ffmpeg.org/ffmpeg-codecs.html#libvpx) for more.
+        ffmpeg_options: Option<HashMap<String, String>>,
+    },
+    #[serde(rename = "ffmpeg_vp9")]
+    FfmpegVp9 {
+        /// (**default=`2000000`**) Target bitrate in bits per second.
+        bitrate: Option<u64>,
+
+        /// (**default=`"realtime"`**) Time the encoder can spend on a single frame.
+        deadline: Option<VpxEncoderDeadline>,
+
+        /// (**default=`8`**) Speed of the encoder in range 0-9. Higher values are faster
+        /// at the cost of quality.
+        cpu_used: Option<u8>,
+
+        /// (**default=`250`**) Maximum number of frames between keyframes.
+        keyframe_interval: Option<u32>,
+
+        /// Raw FFmpeg encoder options. See [docs](https://ffmpeg.org/ffmpeg-codecs.html#libvpx) for more.
+        ffmpeg_options: Option<HashMap<String, String>>,
+    },
+    /// AV1 encoder backed by libaom, configured for real-time encoding.
+    #[serde(rename = "ffmpeg_av1")]
+    FfmpegAv1 {
+        /// (**default=`1000000`**) Target bitrate in bits per second.
+        bitrate: Option<u64>,
+
+        /// (**default=`8`**) Speed of the encoder in range 0-8. Higher values are faster
+        /// at the cost of quality.
+        cpu_used: Option<u8>,
+
+        /// (**default=`250`**) Maximum number of frames between keyframes.
+        keyframe_interval: Option<u32>,
+
+        /// Raw FFmpeg encoder options. See [docs](https://ffmpeg.org/ffmpeg-codecs.html#libaom_002dav1) for more.
+        ffmpeg_options: Option<HashMap<String, String>>,
+    },
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
@@ -74,6 +410,60 @@ pub enum AudioEncoderOptions {
     },
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum RtmpAudioEncoderOptions {
+    Aac { channels: AudioChannels },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Mp4AudioEncoderOptions {
+    Aac {
+        channels: AudioChannels,
+    },
+    Opus {
+        channels: AudioChannels,
+
+        /// (**default="quality"**) Specifies preset for audio output encoder.
+        preset: Option<OpusEncoderPreset>,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum HlsAudioEncoderOptions {
+    Aac { channels: AudioChannels },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum SrtAudioEncoderOptions {
+    Aac {
+        channels: AudioChannels,
+    },
+    Opus {
+        channels: AudioChannels,
+
+        /// (**default="voip"**) Specifies preset for audio output encoder.
+        preset: Option<OpusEncoderPreset>,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum MpegTsAudioEncoderOptions {
+    Aac {
+        channels: AudioChannels,
+    },
+    Opus {
+        channels: AudioChannels,
+
+        /// (**default="voip"**) Specifies preset for audio output encoder.
+        preset: Option<OpusEncoderPreset>,
+    },
+}
+
 /// This type defines when end of an input stream should trigger end of the output stream. Only one of those fields can be set at the time.
 /// Unless specified otherwise the input stream is considered finished/ended when:
 /// - TCP connection was dropped/closed.
@@ -108,6 +498,17 @@ pub enum H264EncoderPreset {
     Placebo,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum VpxEncoderDeadline {
+    /// Encoding speed is adjusted, so frames are produced in real time.
+    Realtime,
+    /// Balance between the quality and the encoding speed.
+    Good,
+    /// Best quality, very slow. Not suitable for live streaming.
+    Best,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum OpusEncoderPreset {
@@ -79,6 +79,25 @@ pub enum PortOrPortRange {
     U16(u16),
 }
 
+/// SRTP encryption of an RTP stream. The same key is used for packets sent in both
+/// directions, including RTCP.
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SrtpOptions {
+    /// (**default=`"aes_cm_128_hmac_sha1_80"`**) SRTP protection profile.
+    pub profile: Option<SrtpProfile>,
+    /// Base64 encoded master key (16 bytes) followed by master salt (14 bytes). The same
+    /// format as the `inline` key of the SDP `a=crypto` attribute.
+    pub key: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub enum SrtpProfile {
+    /// AES-128 in counter mode with an 80-bit HMAC-SHA1 authentication tag.
+    #[serde(rename = "aes_cm_128_hmac_sha1_80")]
+    AesCm128HmacSha1_80,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum TransportProtocol {
@@ -88,6 +107,15 @@ pub enum TransportProtocol {
     TcpServer,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SrtMode {
+    /// LiveCompositor listens on a local UDP port and waits for the remote peer to connect.
+    Listener,
+    /// LiveCompositor connects to a remote listener.
+    Caller,
+}
+
 impl<E> From<E> for TypeError
 where
     E: std::error::Error + Send + Sync + 'static,
@@ -1,3 +1,5 @@
+use std::net::SocketAddr;
+
 use compositor_render::{
     error::{
         InitRendererEngineError, RegisterError, RegisterRendererError, RequestKeyframeError,
@@ -6,7 +8,7 @@ use compositor_render::{
     InputId, OutputId,
 };
 
-use crate::pipeline::{decoder::AacDecoderError, VideoCodec};
+use crate::pipeline::{decoder::AacDecoderError, AudioCodec, VideoCodec};
 
 #[derive(Debug, thiserror::Error)]
 pub enum RegisterInputError {
@@ -49,6 +51,118 @@ pub enum UnregisterInputError {
     StillInUse(InputId),
 }
 
+#[derive(Debug, thiserror::Error)]
+pub enum PushInputDataError {
+    #[error("Failed to push data. Input stream \"{0}\" does not exist.")]
+    NotFound(InputId),
+
+    #[error("Failed to push data. Input stream \"{0}\" does not accept data over HTTP.")]
+    UnsupportedInput(InputId),
+
+    #[error("Failed to push data. Input stream \"{0}\" is already closed.")]
+    InputClosed(InputId),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum InputPlaybackError {
+    #[error("Input stream \"{0}\" does not exist.")]
+    NotFound(InputId),
+
+    #[error("Input stream \"{0}\" does not support playback control. Only MP4 inputs support pause, resume and seek.")]
+    UnsupportedInput(InputId),
+
+    #[error(transparent)]
+    Seek(#[from] crate::pipeline::input::mp4::playback::Mp4SeekError),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum InputStatsError {
+    #[error("Input stream \"{0}\" does not exist.")]
+    NotFound(InputId),
+
+    #[error(
+        "Input stream \"{0}\" does not provide statistics. Only RTP and WHIP inputs report them."
+    )]
+    UnsupportedInput(InputId),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum WhipError {
+    #[error("Input stream \"{0}\" does not exist.")]
+    NotFound(InputId),
+
+    #[error("Input stream \"{0}\" is not a WHIP input.")]
+    UnsupportedInput(InputId),
+
+    #[error("Missing or invalid bearer token for input stream \"{0}\".")]
+    Unauthorized(InputId),
+
+    #[error(
+        "WHIP input \"{0}\" already has a publisher. Register the input again to accept a new one."
+    )]
+    SessionAlreadyStarted(InputId),
+
+    #[error("WHIP session \"{1}\" of input stream \"{0}\" does not exist.")]
+    SessionNotFound(InputId, String),
+
+    #[error("Failed to negotiate a WebRTC session.")]
+    Negotiation(#[source] webrtc::Error),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum WhepError {
+    #[error("Output stream \"{0}\" does not exist.")]
+    NotFound(OutputId),
+
+    #[error("Output stream \"{0}\" is not a WHEP output.")]
+    UnsupportedOutput(OutputId),
+
+    #[error("Missing or invalid bearer token for output stream \"{0}\".")]
+    Unauthorized(OutputId),
+
+    #[error("WHEP session \"{1}\" of output stream \"{0}\" does not exist.")]
+    SessionNotFound(OutputId, String),
+
+    #[error("Failed to negotiate a WebRTC session.")]
+    Negotiation(#[source] webrtc::Error),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum InputSdpError {
+    #[error("Input stream \"{0}\" does not exist.")]
+    NotFound(InputId),
+
+    #[error("Input stream \"{0}\" can not be described with SDP. Only RTP inputs support it.")]
+    UnsupportedInput(InputId),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum OutputSdpError {
+    #[error("Output stream \"{0}\" does not exist.")]
+    NotFound(OutputId),
+
+    #[error("Output stream \"{0}\" can not be described with SDP. Only RTP outputs support it.")]
+    UnsupportedOutput(OutputId),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum OutputDestinationError {
+    #[error("Output stream \"{0}\" does not exist.")]
+    NotFound(OutputId),
+
+    #[error("Output stream \"{0}\" does not support additional destinations. Only RTP outputs sent over UDP support it.")]
+    UnsupportedOutput(OutputId),
+
+    #[error("Destination {1} uses a different IP version than output stream \"{0}\".")]
+    AddressFamilyMismatch(OutputId, SocketAddr),
+
+    #[error("Destination {1} already receives output stream \"{0}\".")]
+    AlreadyAttached(OutputId, SocketAddr),
+
+    #[error("Destination {1} is not attached to output stream \"{0}\".")]
+    NotAttached(OutputId, SocketAddr),
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum UnregisterOutputError {
     #[error("Failed to unregister output stream. Stream \"{0}\" does not exist.")]
@@ -65,6 +179,9 @@ pub enum OutputInitError {
     #[error("An unsupported codec was requested: {0:?}.")]
     UnsupportedVideoCodec(VideoCodec),
 
+    #[error("An unsupported codec was requested: {0:?}.")]
+    UnsupportedAudioCodec(AudioCodec),
+
     #[error(transparent)]
     SocketError(#[from] std::io::Error),
 
@@ -73,6 +190,12 @@ pub enum OutputInitError {
 
     #[error("Failed to register output. All ports in range {lower_bound} to {upper_bound} are already used or not available.")]
     AllPortsAlreadyInUse { lower_bound: u16, upper_bound: u16 },
+
+    #[error(transparent)]
+    FfmpegError(#[from] ffmpeg_next::Error),
+
+    #[error("Failed to initialize SRTP.")]
+    SrtpError(#[from] crate::pipeline::rtp::srtp::SrtpError),
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -85,6 +208,9 @@ pub enum EncoderInitError {
 
     #[error(transparent)]
     OpusError(#[from] opus::Error),
+
+    #[error(transparent)]
+    AacError(#[from] crate::pipeline::encoder::fdk_aac::AacEncoderError),
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -92,6 +218,21 @@ pub enum InputInitError {
     #[error(transparent)]
     Rtp(#[from] crate::pipeline::input::rtp::RtpReceiverError),
 
+    #[error(transparent)]
+    Rtmp(#[from] crate::pipeline::input::rtmp::RtmpReceiverError),
+
+    #[error(transparent)]
+    Hls(#[from] crate::pipeline::input::hls::HlsReceiverError),
+
+    #[error(transparent)]
+    Srt(#[from] crate::pipeline::input::srt::SrtReceiverError),
+
+    #[error(transparent)]
+    MpegTs(#[from] crate::pipeline::input::mpeg_ts::MpegTsReceiverError),
+
+    #[error(transparent)]
+    FragmentedMp4(#[from] crate::pipeline::input::mp4::fragmented_receiver::FragmentedMp4Error),
+
     #[error(transparent)]
     Mp4(#[from] crate::pipeline::input::mp4::Mp4Error),
 
@@ -119,6 +260,8 @@ pub enum ErrorType {
     UserError,
     ServerError,
     EntityNotFound,
+    Unauthorized,
+    Conflict,
 }
 
 pub struct PipelineErrorInfo {
@@ -200,6 +343,132 @@ impl From<&UnregisterInputError> for PipelineErrorInfo {
     }
 }
 
+const INPUT_DATA_NOT_SUPPORTED: &str = "INPUT_DATA_NOT_SUPPORTED";
+const INPUT_STREAM_CLOSED: &str = "INPUT_STREAM_CLOSED";
+
+impl From<&PushInputDataError> for PipelineErrorInfo {
+    fn from(err: &PushInputDataError) -> Self {
+        match err {
+            PushInputDataError::NotFound(_) => {
+                PipelineErrorInfo::new(INPUT_STREAM_NOT_FOUND, ErrorType::EntityNotFound)
+            }
+            PushInputDataError::UnsupportedInput(_) => {
+                PipelineErrorInfo::new(INPUT_DATA_NOT_SUPPORTED, ErrorType::UserError)
+            }
+            PushInputDataError::InputClosed(_) => {
+                PipelineErrorInfo::new(INPUT_STREAM_CLOSED, ErrorType::UserError)
+            }
+        }
+    }
+}
+
+const INPUT_PLAYBACK_NOT_SUPPORTED: &str = "INPUT_PLAYBACK_NOT_SUPPORTED";
+const INPUT_SEEK_OUT_OF_RANGE: &str = "INPUT_SEEK_OUT_OF_RANGE";
+
+impl From<&InputPlaybackError> for PipelineErrorInfo {
+    fn from(err: &InputPlaybackError) -> Self {
+        match err {
+            InputPlaybackError::NotFound(_) => {
+                PipelineErrorInfo::new(INPUT_STREAM_NOT_FOUND, ErrorType::EntityNotFound)
+            }
+            InputPlaybackError::UnsupportedInput(_) => {
+                PipelineErrorInfo::new(INPUT_PLAYBACK_NOT_SUPPORTED, ErrorType::UserError)
+            }
+            InputPlaybackError::Seek(_) => {
+                PipelineErrorInfo::new(INPUT_SEEK_OUT_OF_RANGE, ErrorType::UserError)
+            }
+        }
+    }
+}
+
+const INPUT_STATS_NOT_SUPPORTED: &str = "INPUT_STATS_NOT_SUPPORTED";
+
+impl From<&InputStatsError> for PipelineErrorInfo {
+    fn from(err: &InputStatsError) -> Self {
+        match err {
+            InputStatsError::NotFound(_) => {
+                PipelineErrorInfo::new(INPUT_STREAM_NOT_FOUND, ErrorType::EntityNotFound)
+            }
+            InputStatsError::UnsupportedInput(_) => {
+                PipelineErrorInfo::new(INPUT_STATS_NOT_SUPPORTED, ErrorType::UserError)
+            }
+        }
+    }
+}
+
+const INPUT_SDP_NOT_SUPPORTED: &str = "INPUT_SDP_NOT_SUPPORTED";
+
+impl From<&InputSdpError> for PipelineErrorInfo {
+    fn from(err: &InputSdpError) -> Self {
+        match err {
+            InputSdpError::NotFound(_) => {
+                PipelineErrorInfo::new(INPUT_STREAM_NOT_FOUND, ErrorType::EntityNotFound)
+            }
+            InputSdpError::UnsupportedInput(_) => {
+                PipelineErrorInfo::new(INPUT_SDP_NOT_SUPPORTED, ErrorType::UserError)
+            }
+        }
+    }
+}
+
+const WHIP_NOT_SUPPORTED: &str = "WHIP_NOT_SUPPORTED";
+const WHIP_UNAUTHORIZED: &str = "WHIP_UNAUTHORIZED";
+const WHIP_SESSION_ALREADY_STARTED: &str = "WHIP_SESSION_ALREADY_STARTED";
+const WHIP_SESSION_NOT_FOUND: &str = "WHIP_SESSION_NOT_FOUND";
+const WHIP_NEGOTIATION_ERROR: &str = "WHIP_NEGOTIATION_ERROR";
+
+impl From<&WhipError> for PipelineErrorInfo {
+    fn from(err: &WhipError) -> Self {
+        match err {
+            WhipError::NotFound(_) => {
+                PipelineErrorInfo::new(INPUT_STREAM_NOT_FOUND, ErrorType::EntityNotFound)
+            }
+            WhipError::UnsupportedInput(_) => {
+                PipelineErrorInfo::new(WHIP_NOT_SUPPORTED, ErrorType::UserError)
+            }
+            WhipError::Unauthorized(_) => {
+                PipelineErrorInfo::new(WHIP_UNAUTHORIZED, ErrorType::Unauthorized)
+            }
+            WhipError::SessionAlreadyStarted(_) => {
+                PipelineErrorInfo::new(WHIP_SESSION_ALREADY_STARTED, ErrorType::Conflict)
+            }
+            WhipError::SessionNotFound(_, _) => {
+                PipelineErrorInfo::new(WHIP_SESSION_NOT_FOUND, ErrorType::EntityNotFound)
+            }
+            WhipError::Negotiation(_) => {
+                PipelineErrorInfo::new(WHIP_NEGOTIATION_ERROR, ErrorType::UserError)
+            }
+        }
+    }
+}
+
+const WHEP_NOT_SUPPORTED: &str = "WHEP_NOT_SUPPORTED";
+const WHEP_UNAUTHORIZED: &str = "WHEP_UNAUTHORIZED";
+const WHEP_SESSION_NOT_FOUND: &str = "WHEP_SESSION_NOT_FOUND";
+const WHEP_NEGOTIATION_ERROR: &str = "WHEP_NEGOTIATION_ERROR";
+
+impl From<&WhepError> for PipelineErrorInfo {
+    fn from(err: &WhepError) -> Self {
+        match err {
+            WhepError::NotFound(_) => {
+                PipelineErrorInfo::new(OUTPUT_STREAM_NOT_FOUND, ErrorType::EntityNotFound)
+            }
+            WhepError::UnsupportedOutput(_) => {
+                PipelineErrorInfo::new(WHEP_NOT_SUPPORTED, ErrorType::UserError)
+            }
+            WhepError::Unauthorized(_) => {
+                PipelineErrorInfo::new(WHEP_UNAUTHORIZED, ErrorType::Unauthorized)
+            }
+            WhepError::SessionNotFound(_, _) => {
+                PipelineErrorInfo::new(WHEP_SESSION_NOT_FOUND, ErrorType::EntityNotFound)
+            }
+            WhepError::Negotiation(_) => {
+                PipelineErrorInfo::new(WHEP_NEGOTIATION_ERROR, ErrorType::UserError)
+            }
+        }
+    }
+}
+
 const OUTPUT_STREAM_STILL_IN_USE: &str = "OUTPUT_STREAM_STILL_IN_USE";
 const OUTPUT_STREAM_NOT_FOUND: &str = "OUTPUT_STREAM_NOT_FOUND";
 const NO_AUDIO_AND_VIDEO_SPECIFIED: &str = "NO_AUDIO_AND_VIDEO_SPECIFIED";
@@ -218,6 +487,48 @@ impl From<&UnregisterOutputError> for PipelineErrorInfo {
     }
 }
 
+const OUTPUT_SDP_NOT_SUPPORTED: &str = "OUTPUT_SDP_NOT_SUPPORTED";
+
+impl From<&OutputSdpError> for PipelineErrorInfo {
+    fn from(err: &OutputSdpError) -> Self {
+        match err {
+            OutputSdpError::NotFound(_) => {
+                PipelineErrorInfo::new(OUTPUT_STREAM_NOT_FOUND, ErrorType::EntityNotFound)
+            }
+            OutputSdpError::UnsupportedOutput(_) => {
+                PipelineErrorInfo::new(OUTPUT_SDP_NOT_SUPPORTED, ErrorType::UserError)
+            }
+        }
+    }
+}
+
+const OUTPUT_DESTINATIONS_NOT_SUPPORTED: &str = "OUTPUT_DESTINATIONS_NOT_SUPPORTED";
+const OUTPUT_DESTINATION_INVALID: &str = "OUTPUT_DESTINATION_INVALID";
+const OUTPUT_DESTINATION_ALREADY_ATTACHED: &str = "OUTPUT_DESTINATION_ALREADY_ATTACHED";
+const OUTPUT_DESTINATION_NOT_ATTACHED: &str = "OUTPUT_DESTINATION_NOT_ATTACHED";
+
+impl From<&OutputDestinationError> for PipelineErrorInfo {
+    fn from(err: &OutputDestinationError) -> Self {
+        match err {
+            OutputDestinationError::NotFound(_) => {
+                PipelineErrorInfo::new(OUTPUT_STREAM_NOT_FOUND, ErrorType::EntityNotFound)
+            }
+            OutputDestinationError::UnsupportedOutput(_) => {
+                PipelineErrorInfo::new(OUTPUT_DESTINATIONS_NOT_SUPPORTED, ErrorType::UserError)
+            }
+            OutputDestinationError::AddressFamilyMismatch(_, _) => {
+                PipelineErrorInfo::new(OUTPUT_DESTINATION_INVALID, ErrorType::UserError)
+            }
+            OutputDestinationError::AlreadyAttached(_, _) => {
+                PipelineErrorInfo::new(OUTPUT_DESTINATION_ALREADY_ATTACHED, ErrorType::UserError)
+            }
+            OutputDestinationError::NotAttached(_, _) => {
+                PipelineErrorInfo::new(OUTPUT_DESTINATION_NOT_ATTACHED, ErrorType::UserError)
+            }
+        }
+    }
+}
+
 const BUILD_SCENE_ERROR: &str = "BUILD_SCENE_ERROR";
 
 impl From<&UpdateSceneError> for PipelineErrorInfo {
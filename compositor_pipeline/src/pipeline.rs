@@ -1,10 +1,12 @@
 use std::collections::HashMap;
+use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::thread;
 use std::time::Duration;
 
+use bytes::Bytes;
 use compositor_render::error::{
     ErrorStack, InitPipelineError, RegisterRendererError, RequestKeyframeError,
     UnregisterRendererError,
@@ -18,7 +20,7 @@ use compositor_render::RendererOptions;
 use compositor_render::WgpuFeatures;
 use compositor_render::{error::UpdateSceneError, Renderer};
 use compositor_render::{EventLoop, InputId, OutputId, RendererId, RendererSpec};
-use crossbeam_channel::{bounded, Receiver};
+use crossbeam_channel::{bounded, Receiver, Sender};
 use input::InputInitInfo;
 use input::RawDataInputOptions;
 use output::EncodedDataOutputOptions;
@@ -31,7 +33,9 @@ use crate::audio_mixer::AudioMixer;
 use crate::audio_mixer::MixingStrategy;
 use crate::audio_mixer::{AudioChannels, AudioMixingParams};
 use crate::error::{
-    RegisterInputError, RegisterOutputError, UnregisterInputError, UnregisterOutputError,
+    InputPlaybackError, InputSdpError, InputStatsError, OutputDestinationError, OutputSdpError,
+    PushInputDataError, RegisterInputError, RegisterOutputError, UnregisterInputError,
+    UnregisterOutputError, WhepError, WhipError,
 };
 
 use crate::pipeline::pipeline_output::OutputSender;
@@ -40,16 +44,25 @@ use crate::queue::QueueAudioOutput;
 use crate::queue::QueueInputOptions;
 use crate::queue::{self, Queue, QueueOptions, QueueVideoOutput};
 
+use self::encoder::Encoder;
+use self::input::mp4::Mp4;
+use self::input::whip::WhipEndpoint;
 use self::input::InputOptions;
+use self::output::rtp::UdpDestinations;
+use self::output::whep::WhepEndpoint;
 
+mod av1;
 pub mod decoder;
 pub mod encoder;
 pub mod input;
+mod mpeg_ts;
 pub mod output;
 mod pipeline_input;
 mod pipeline_output;
 pub mod rtp;
+pub mod srt;
 mod types;
+mod webrtc_utils;
 
 use self::pipeline_input::register_pipeline_input;
 use self::pipeline_input::PipelineInput;
@@ -181,6 +194,201 @@ impl Pipeline {
         register_pipeline_input(pipeline, input_id, &raw_input_options, queue_options)
     }
 
+    pub fn pause_input(&self, input_id: &InputId) -> Result<(), InputPlaybackError> {
+        let mp4 = self.mp4_input(input_id)?;
+        self.queue.pause_input(input_id);
+        mp4.pause();
+        Ok(())
+    }
+
+    pub fn resume_input(&self, input_id: &InputId) -> Result<(), InputPlaybackError> {
+        let mp4 = self.mp4_input(input_id)?;
+        mp4.resume();
+        self.queue.resume_input(input_id);
+        Ok(())
+    }
+
+    /// Moves the playback to `position` in the file. It does not change the paused state.
+    pub fn seek_input(
+        &self,
+        input_id: &InputId,
+        position: Duration,
+    ) -> Result<(), InputPlaybackError> {
+        self.mp4_input(input_id)?.seek(position)?;
+        Ok(())
+    }
+
+    fn mp4_input(&self, input_id: &InputId) -> Result<&Mp4, InputPlaybackError> {
+        let input = self
+            .inputs
+            .get(input_id)
+            .ok_or_else(|| InputPlaybackError::NotFound(input_id.clone()))?;
+        match &input.input {
+            input::Input::Mp4(mp4) => Ok(mp4),
+            _ => Err(InputPlaybackError::UnsupportedInput(input_id.clone())),
+        }
+    }
+
+    /// Statistics of RTP streams received by an RTP or WHIP input.
+    pub fn rtp_input_stats(
+        &self,
+        input_id: &InputId,
+    ) -> Result<Vec<input::rtp::RtpStreamStats>, InputStatsError> {
+        let input = self
+            .inputs
+            .get(input_id)
+            .ok_or_else(|| InputStatsError::NotFound(input_id.clone()))?;
+        match &input.input {
+            input::Input::Rtp(rtp) => Ok(rtp.stats()),
+            input::Input::Whip(whip) => Ok(whip.stats()),
+            _ => Err(InputStatsError::UnsupportedInput(input_id.clone())),
+        }
+    }
+
+    /// Endpoint that accepts the publisher of a WHIP input.
+    pub fn whip_endpoint(&self, input_id: &InputId) -> Result<WhipEndpoint, WhipError> {
+        let input = self
+            .inputs
+            .get(input_id)
+            .ok_or_else(|| WhipError::NotFound(input_id.clone()))?;
+        match &input.input {
+            input::Input::Whip(whip) => Ok(whip.endpoint()),
+            _ => Err(WhipError::UnsupportedInput(input_id.clone())),
+        }
+    }
+
+    /// Endpoint that accepts viewers of a WHEP output.
+    pub fn whep_endpoint(&self, output_id: &OutputId) -> Result<WhepEndpoint, WhepError> {
+        let output = self
+            .outputs
+            .get(output_id)
+            .ok_or_else(|| WhepError::NotFound(output_id.clone()))?;
+        match &output.output {
+            output::Output::Whep { sender, .. } => Ok(sender.endpoint()),
+            _ => Err(WhepError::UnsupportedOutput(output_id.clone())),
+        }
+    }
+
+    /// Session description of streams expected by an RTP input.
+    pub fn input_sdp(&self, input_id: &InputId) -> Result<String, InputSdpError> {
+        let input = self
+            .inputs
+            .get(input_id)
+            .ok_or_else(|| InputSdpError::NotFound(input_id.clone()))?;
+        match &input.input {
+            input::Input::Rtp(rtp) => Ok(rtp.sdp()),
+            _ => Err(InputSdpError::UnsupportedInput(input_id.clone())),
+        }
+    }
+
+    /// Session description of streams sent by an RTP output.
+    pub fn output_sdp(&self, output_id: &OutputId) -> Result<String, OutputSdpError> {
+        let output = self
+            .outputs
+            .get(output_id)
+            .ok_or_else(|| OutputSdpError::NotFound(output_id.clone()))?;
+        match &output.output {
+            output::Output::Rtp { sender, .. } => Ok(sender.sdp()),
+            _ => Err(OutputSdpError::UnsupportedOutput(output_id.clone())),
+        }
+    }
+
+    /// Starts sending the RTP stream of an output to an additional UDP address.
+    pub fn attach_output_destination(
+        &self,
+        output_id: &OutputId,
+        address: SocketAddr,
+    ) -> Result<(), OutputDestinationError> {
+        let (destinations, encoder) = self.output_udp_destinations(output_id)?;
+        if destinations.primary().is_ipv4() != address.is_ipv4() {
+            return Err(OutputDestinationError::AddressFamilyMismatch(
+                output_id.clone(),
+                address,
+            ));
+        }
+        if !destinations.attach(address) {
+            return Err(OutputDestinationError::AlreadyAttached(
+                output_id.clone(),
+                address,
+            ));
+        }
+        // New receiver can not decode anything until the next keyframe.
+        if let Some(video) = &encoder.video {
+            video.request_keyframe();
+        }
+        Ok(())
+    }
+
+    pub fn detach_output_destination(
+        &self,
+        output_id: &OutputId,
+        address: SocketAddr,
+    ) -> Result<(), OutputDestinationError> {
+        let (destinations, _) = self.output_udp_destinations(output_id)?;
+        if !destinations.detach(address) {
+            return Err(OutputDestinationError::NotAttached(
+                output_id.clone(),
+                address,
+            ));
+        }
+        Ok(())
+    }
+
+    fn output_udp_destinations(
+        &self,
+        output_id: &OutputId,
+    ) -> Result<(&UdpDestinations, &Encoder), OutputDestinationError> {
+        let output = self
+            .outputs
+            .get(output_id)
+            .ok_or_else(|| OutputDestinationError::NotFound(output_id.clone()))?;
+        match &output.output {
+            output::Output::Rtp { sender, encoder } => sender
+                .udp_destinations()
+                .map(|destinations| (destinations, encoder))
+                .ok_or_else(|| OutputDestinationError::UnsupportedOutput(output_id.clone())),
+            _ => Err(OutputDestinationError::UnsupportedOutput(output_id.clone())),
+        }
+    }
+
+    /// Passes data to an input that accepts a byte stream over HTTP (e.g. fragmented MP4).
+    /// Blocks if the input can not keep up with the data.
+    pub fn push_input_data(
+        pipeline: &Arc<Mutex<Self>>,
+        input_id: &InputId,
+        data: Bytes,
+    ) -> Result<(), PushInputDataError> {
+        Self::input_data_sender(pipeline, input_id)?
+            .send(PipelineEvent::Data(data))
+            .map_err(|_| PushInputDataError::InputClosed(input_id.clone()))
+    }
+
+    /// Ends the byte stream of an input that accepts data over HTTP.
+    pub fn end_input_data(
+        pipeline: &Arc<Mutex<Self>>,
+        input_id: &InputId,
+    ) -> Result<(), PushInputDataError> {
+        Self::input_data_sender(pipeline, input_id)?
+            .send(PipelineEvent::EOS)
+            .map_err(|_| PushInputDataError::InputClosed(input_id.clone()))
+    }
+
+    fn input_data_sender(
+        pipeline: &Arc<Mutex<Self>>,
+        input_id: &InputId,
+    ) -> Result<Sender<PipelineEvent<Bytes>>, PushInputDataError> {
+        let guard = pipeline.lock().unwrap();
+        let input = guard
+            .inputs
+            .get(input_id)
+            .ok_or_else(|| PushInputDataError::NotFound(input_id.clone()))?;
+        input
+            .input
+            .data_sender()
+            .cloned()
+            .ok_or_else(|| PushInputDataError::UnsupportedInput(input_id.clone()))
+    }
+
     pub fn unregister_input(&mut self, input_id: &InputId) -> Result<(), UnregisterInputError> {
         if !self.inputs.contains_key(input_id) {
             return Err(UnregisterInputError::NotFound(input_id.clone()));
@@ -0,0 +1,225 @@
+//! Parsing of AV1 bitstreams split into OBUs (open bitstream units), as described
+//! in the [AV1 specification](https://aomediacodec.github.io/av1-spec/), section 5.
+
+use bytes::{BufMut, Bytes, BytesMut};
+
+pub(super) const OBU_SEQUENCE_HEADER: u8 = 1;
+pub(super) const OBU_TEMPORAL_DELIMITER: u8 = 2;
+pub(super) const OBU_FRAME_HEADER: u8 = 3;
+pub(super) const OBU_FRAME: u8 = 6;
+pub(super) const OBU_TILE_LIST: u8 = 8;
+pub(super) const OBU_PADDING: u8 = 15;
+
+const OBU_EXTENSION_FLAG: u8 = 0x04;
+const OBU_HAS_SIZE_FIELD: u8 = 0x02;
+
+/// Values of `obu_size` can't be longer than 8 bytes.
+const MAX_LEB128_SIZE: usize = 8;
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum ObuParseError {
+    #[error("OBU is truncated.")]
+    Truncated,
+
+    #[error("OBU size field is not a valid LEB128 value.")]
+    InvalidSize,
+}
+
+/// OBU with its header stripped of the `obu_has_size_field` flag.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct Obu {
+    /// OBU header followed by the optional extension header.
+    header: Bytes,
+    payload: Bytes,
+}
+
+impl Obu {
+    pub fn obu_type(&self) -> u8 {
+        (self.header[0] >> 3) & 0x0f
+    }
+
+    /// Parses a single OBU. If the OBU has no size field, it spans the rest of the data.
+    /// Returns the OBU and the number of bytes it occupied.
+    pub fn parse(data: &Bytes) -> Result<(Self, usize), ObuParseError> {
+        let first_byte = *data.first().ok_or(ObuParseError::Truncated)?;
+        let header_size = match first_byte & OBU_EXTENSION_FLAG {
+            0 => 1,
+            _ => 2,
+        };
+        if data.len() < header_size {
+            return Err(ObuParseError::Truncated);
+        }
+
+        let mut header = BytesMut::from(&data[..header_size]);
+        header[0] &= !OBU_HAS_SIZE_FIELD;
+
+        let (payload_start, payload_end) = match first_byte & OBU_HAS_SIZE_FIELD {
+            0 => (header_size, data.len()),
+            _ => {
+                let (size, size_len) = read_leb128(&data[header_size..])?;
+                let payload_start = header_size + size_len;
+                let payload_end = usize::try_from(size)
+                    .ok()
+                    .and_then(|size| payload_start.checked_add(size))
+                    .filter(|end| *end <= data.len())
+                    .ok_or(ObuParseError::Truncated)?;
+                (payload_start, payload_end)
+            }
+        };
+
+        let obu = Self {
+            header: header.freeze(),
+            payload: data.slice(payload_start..payload_end),
+        };
+        Ok((obu, payload_end))
+    }
+
+    /// OBU without the size field, in the form used by RTP payloads.
+    pub fn to_bytes(&self) -> Bytes {
+        let mut bytes = BytesMut::with_capacity(self.header.len() + self.payload.len());
+        bytes.extend_from_slice(&self.header);
+        bytes.extend_from_slice(&self.payload);
+        bytes.freeze()
+    }
+
+    /// Writes the OBU with the size field, in the low overhead bitstream format used by
+    /// decoders and containers.
+    pub fn write_with_size(&self, out: &mut BytesMut) {
+        out.put_u8(self.header[0] | OBU_HAS_SIZE_FIELD);
+        out.extend_from_slice(&self.header[1..]);
+        write_leb128(self.payload.len(), out);
+        out.extend_from_slice(&self.payload);
+    }
+}
+
+/// Splits a temporal unit in the low overhead bitstream format into OBUs.
+pub(super) fn parse_obus(data: &Bytes) -> Result<Vec<Obu>, ObuParseError> {
+    let mut obus = Vec::new();
+    let mut offset = 0;
+    while offset < data.len() {
+        let (obu, size) = Obu::parse(&data.slice(offset..))?;
+        obus.push(obu);
+        offset += size;
+    }
+    Ok(obus)
+}
+
+/// Checks if the first frame of a temporal unit is a key frame that is not a repeated
+/// frame (`show_existing_frame`). Assumes the sequence does not use a reduced still
+/// picture header.
+pub(super) fn is_keyframe(data: &Bytes) -> bool {
+    let Ok(obus) = parse_obus(data) else {
+        return false;
+    };
+    let frame_header = obus
+        .iter()
+        .find(|obu| matches!(obu.obu_type(), OBU_FRAME_HEADER | OBU_FRAME));
+    let Some(first_byte) = frame_header.and_then(|obu| obu.payload.first()) else {
+        return false;
+    };
+    // show_existing_frame (1 bit), frame_type (2 bits), KEY_FRAME is 0
+    let show_existing_frame = first_byte >> 7;
+    let frame_type = (first_byte >> 5) & 0b11;
+    show_existing_frame == 0 && frame_type == 0
+}
+
+/// Sequence header OBU of a temporal unit, in the low overhead bitstream format.
+pub(super) fn sequence_header(data: &Bytes) -> Option<Bytes> {
+    let obus = parse_obus(data).ok()?;
+    let sequence_header = obus
+        .iter()
+        .find(|obu| obu.obu_type() == OBU_SEQUENCE_HEADER)?;
+    let mut bytes = BytesMut::new();
+    sequence_header.write_with_size(&mut bytes);
+    Some(bytes.freeze())
+}
+
+/// Reads an unsigned LEB128 value. Returns the value and the number of bytes it occupied.
+pub(super) fn read_leb128(data: &[u8]) -> Result<(u64, usize), ObuParseError> {
+    let mut value = 0;
+    for (index, byte) in data.iter().take(MAX_LEB128_SIZE).enumerate() {
+        value |= ((byte & 0x7f) as u64) << (index * 7);
+        if byte & 0x80 == 0 {
+            return Ok((value, index + 1));
+        }
+    }
+    match data.len() < MAX_LEB128_SIZE {
+        true => Err(ObuParseError::Truncated),
+        false => Err(ObuParseError::InvalidSize),
+    }
+}
+
+pub(super) fn write_leb128(mut value: usize, out: &mut BytesMut) {
+    while value >= 0x80 {
+        out.put_u8((value & 0x7f) as u8 | 0x80);
+        value >>= 7;
+    }
+    out.put_u8(value as u8);
+}
+
+pub(super) fn leb128_size(value: usize) -> usize {
+    let bits = usize::BITS - value.leading_zeros();
+    usize::max(1, bits.div_ceil(7) as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leb128_round_trip() {
+        for value in [0, 1, 127, 128, 300, 16_383, 16_384, 1 << 30] {
+            let mut bytes = BytesMut::new();
+            write_leb128(value, &mut bytes);
+            assert_eq!(bytes.len(), leb128_size(value));
+            assert_eq!(read_leb128(&bytes), Ok((value as u64, bytes.len())));
+        }
+        assert_eq!(read_leb128(&[0x80, 0x80]), Err(ObuParseError::Truncated));
+        assert_eq!(read_leb128(&[0xff; 9]), Err(ObuParseError::InvalidSize));
+    }
+
+    #[test]
+    fn parse_obus_with_and_without_size_field() {
+        let data = Bytes::from_static(&[
+            // temporal delimiter with size field
+            0x12, 0x00, //
+            // frame with extension header and size field
+            0x36, 0x10, 0x02, 0xaa, 0xbb, //
+            // frame without size field, spans the rest of the data
+            0x30, 0xcc, 0xdd, 0xee,
+        ]);
+        let obus = parse_obus(&data).unwrap();
+        assert_eq!(obus.len(), 3);
+        assert_eq!(obus[0].obu_type(), OBU_TEMPORAL_DELIMITER);
+        assert_eq!(obus[1].obu_type(), OBU_FRAME);
+        assert_eq!(&obus[1].to_bytes()[..], &[0x34, 0x10, 0xaa, 0xbb]);
+        assert_eq!(&obus[2].payload[..], &[0xcc, 0xdd, 0xee]);
+
+        let mut bytes = BytesMut::new();
+        obus[2].write_with_size(&mut bytes);
+        assert_eq!(&bytes[..], &[0x32, 0x03, 0xcc, 0xdd, 0xee]);
+
+        let truncated = Bytes::from_static(&[0x32, 0x05, 0x01]);
+        assert_eq!(parse_obus(&truncated), Err(ObuParseError::Truncated));
+    }
+
+    #[test]
+    fn keyframe_detection() {
+        // temporal delimiter, sequence header and frame with frame_type=KEY_FRAME
+        let keyframe = Bytes::from_static(&[0x12, 0x00, 0x0a, 0x01, 0x00, 0x32, 0x02, 0x10, 0x00]);
+        assert!(is_keyframe(&keyframe));
+        assert_eq!(
+            sequence_header(&keyframe).as_deref(),
+            Some(&[0x0a, 0x01, 0x00][..])
+        );
+
+        // frame_type=INTER_FRAME
+        let inter_frame = Bytes::from_static(&[0x12, 0x00, 0x32, 0x02, 0x30, 0x00]);
+        assert!(!is_keyframe(&inter_frame));
+        assert_eq!(sequence_header(&inter_frame), None);
+
+        // show_existing_frame=1 in a frame header OBU
+        let existing_frame = Bytes::from_static(&[0x12, 0x00, 0x1a, 0x01, 0x80]);
+        assert!(!is_keyframe(&existing_frame));
+    }
+}
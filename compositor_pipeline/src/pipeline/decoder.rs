@@ -13,6 +13,7 @@ mod video;
 
 pub(super) use audio::start_audio_decoder_thread;
 pub(super) use audio::start_audio_resampler_only_thread;
+pub(super) use audio::start_deferred_audio_decoder_thread;
 pub(super) use video::start_video_decoder_thread;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -158,6 +158,53 @@ pub fn start_audio_decoder_thread(
     }
 }
 
+/// Starts decoding after the decoder options are received. Inputs send them before
+/// the first chunk, if the sender is dropped instead, the stream ends. Initialization
+/// errors can not be returned from the registration, so they are only logged.
+pub fn start_deferred_audio_decoder_thread(
+    opts_receiver: Receiver<AudioDecoderOptions>,
+    output_sample_rate: u32,
+    chunks_receiver: Receiver<PipelineEvent<EncodedChunk>>,
+    samples_sender: Sender<PipelineEvent<InputSamples>>,
+    input_id: InputId,
+) {
+    std::thread::Builder::new()
+        .name(format!("Decoder thread for input {}", input_id.clone()))
+        .spawn(move || {
+            let _span = span!(
+                Level::INFO,
+                "Audio decoder",
+                input_id = input_id.to_string()
+            )
+            .entered();
+
+            let sender = |samples: InputSamples| {
+                if samples_sender.send(PipelineEvent::Data(samples)).is_err() {
+                    debug!("Failed to send decoded input samples.");
+                };
+            };
+
+            if let Ok(opts) = opts_receiver.recv() {
+                let (init_result_sender, init_result_receiver) = bounded(1);
+                run_decoding(
+                    opts,
+                    output_sample_rate,
+                    chunks_receiver,
+                    sender,
+                    init_result_sender,
+                );
+                if let Ok(Err(err)) = init_result_receiver.try_recv() {
+                    error!("Fatal audio decoder initialization error. {}", err);
+                }
+            }
+
+            if samples_sender.send(PipelineEvent::EOS).is_err() {
+                debug!("Failed to send EOS message.")
+            }
+        })
+        .unwrap();
+}
+
 /// init_result_sender sends:
 /// - true init result for Opus
 /// - always ok for AAC (aac sample rate is unknown at register time, first chunk is need to determine it)
@@ -1,3 +1,4 @@
+use bytes::Bytes;
 use fdk_aac_sys as fdk;
 use std::sync::Arc;
 use tracing::error;
@@ -48,7 +49,15 @@ impl AacDecoder {
 
         let instance = unsafe { fdk::aacDecoder_Open(transport, 1) };
 
-        if let Some(config) = options.asc {
+        // ADTS streams carry the config in every frame header, but the decoder reads it only
+        // after it synchronizes with the stream, so the stream info would not be available yet.
+        let asc = match (options.asc, transport) {
+            (Some(asc), _) => Some(asc),
+            (None, fdk::TRANSPORT_TYPE_TT_MP4_ADTS) => asc_from_adts_header(&first_chunk.data),
+            (None, _) => None,
+        };
+
+        if let Some(config) = asc {
             let result = unsafe {
                 fdk::aacDecoder_ConfigRaw(
                     instance,
@@ -80,6 +89,23 @@ impl AacDecoder {
     }
 }
 
+/// Builds the AudioSpecificConfig from the fields of an ADTS header
+/// [MPEG-4 part 3, section 1.A.2.2 Audio_Data_Transport_Stream frame]
+fn asc_from_adts_header(header: &[u8]) -> Option<Bytes> {
+    if header.len() < 7 {
+        return None;
+    }
+    // ADTS stores the audio object type decremented by 1
+    let object_type = (header[2] >> 6) + 1;
+    let frequency_index = (header[2] >> 2) & 0b1111;
+    let channel_config = ((header[2] & 0b1) << 2) | (header[3] >> 6);
+
+    Some(Bytes::from(vec![
+        (object_type << 3) | (frequency_index >> 1),
+        ((frequency_index & 0b1) << 7) | (channel_config << 3),
+    ]))
+}
+
 impl Drop for AacDecoder {
     fn drop(&mut self) {
         unsafe {
@@ -98,6 +124,12 @@ impl AudioDecoderExt for AacDecoder {
         let mut bytes_valid = buffer_size;
         let mut buffer = chunk.data.to_vec();
         let mut output_buffer = Vec::new();
+        // Tells the decoder that the data is not continuous, so it does not use
+        // the state of previous frames.
+        let mut flags = match chunk.discontinuity {
+            true => fdk::AACDEC_INTR,
+            false => 0,
+        };
 
         while bytes_valid > 0 {
             // This fills the decoder with data.
@@ -127,7 +159,7 @@ impl AudioDecoderExt for AacDecoder {
                     self.instance,
                     decoded_samples.as_mut_ptr(),
                     decoded_samples.len() as i32,
-                    0,
+                    flags,
                 )
             };
 
@@ -139,6 +171,7 @@ impl AudioDecoderExt for AacDecoder {
             if result != fdk::AAC_DECODER_ERROR_AAC_DEC_OK {
                 return Err(AacDecoderError::FdkDecoderError(result).into());
             }
+            flags = 0;
 
             let samples = match info.channelConfig {
                 1 => Arc::new(Samples::Mono16Bit(decoded_samples)),
@@ -177,3 +210,149 @@ impl AudioDecoderExt for AacDecoder {
         self.sample_rate
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{ffi::c_void, mem, ptr, time::Duration};
+
+    use bytes::Bytes;
+    use fdk_aac_sys as fdk;
+
+    use crate::pipeline::{
+        decoder::AacDecoderOptions,
+        types::{EncodedChunk, EncodedChunkKind, Samples},
+        AudioCodec,
+    };
+
+    use super::{asc_from_adts_header, AacDecoder, AudioDecoderExt};
+
+    /// Encodes a stereo sine wave at 44.1 kHz into AAC-LC frames with ADTS headers.
+    fn encode_adts(frame_count: usize) -> Vec<Bytes> {
+        let mut encoder = ptr::null_mut();
+        let params = [
+            (fdk::AACENC_PARAM_AACENC_AOT, 2),
+            (fdk::AACENC_PARAM_AACENC_SAMPLERATE, 44_100),
+            (fdk::AACENC_PARAM_AACENC_CHANNELMODE, 2),
+            (fdk::AACENC_PARAM_AACENC_BITRATE, 128_000),
+            (fdk::AACENC_PARAM_AACENC_TRANSMUX, 2),
+        ];
+        unsafe {
+            assert_eq!(fdk::aacEncOpen(&mut encoder, 0, 2), 0);
+            for (param, value) in params {
+                assert_eq!(fdk::aacEncoder_SetParam(encoder, param, value), 0);
+            }
+            let result = fdk::aacEncEncode(
+                encoder,
+                ptr::null(),
+                ptr::null(),
+                ptr::null(),
+                ptr::null_mut(),
+            );
+            assert_eq!(result, 0);
+        }
+
+        let mut frames = Vec::new();
+        for frame in 0..frame_count {
+            let mut input: Vec<i16> = (0..2048)
+                .map(|i| (((frame * 1024 + i / 2) as f64 * 0.05).sin() * 8000.0) as i16)
+                .collect();
+            let mut input_ptr = input.as_mut_ptr() as *mut c_void;
+            let mut input_id = fdk::AACENC_BufferIdentifier_IN_AUDIO_DATA as i32;
+            let mut input_size = (input.len() * mem::size_of::<i16>()) as i32;
+            let mut input_element_size = mem::size_of::<i16>() as i32;
+            let input_desc = fdk::AACENC_BufDesc {
+                numBufs: 1,
+                bufs: &mut input_ptr,
+                bufferIdentifiers: &mut input_id,
+                bufSizes: &mut input_size,
+                bufElSizes: &mut input_element_size,
+            };
+
+            let mut output = vec![0u8; 8192];
+            let mut output_ptr = output.as_mut_ptr() as *mut c_void;
+            let mut output_id = fdk::AACENC_BufferIdentifier_OUT_BITSTREAM_DATA as i32;
+            let mut output_size = output.len() as i32;
+            let mut output_element_size = 1;
+            let output_desc = fdk::AACENC_BufDesc {
+                numBufs: 1,
+                bufs: &mut output_ptr,
+                bufferIdentifiers: &mut output_id,
+                bufSizes: &mut output_size,
+                bufElSizes: &mut output_element_size,
+            };
+
+            let in_args = fdk::AACENC_InArgs {
+                numInSamples: input.len() as i32,
+                numAncBytes: 0,
+            };
+            let mut out_args: fdk::AACENC_OutArgs = unsafe { mem::zeroed() };
+            let result = unsafe {
+                fdk::aacEncEncode(encoder, &input_desc, &output_desc, &in_args, &mut out_args)
+            };
+            assert_eq!(result, 0);
+
+            if out_args.numOutBytes > 0 {
+                output.truncate(out_args.numOutBytes as usize);
+                frames.push(Bytes::from(output));
+            }
+        }
+        unsafe { fdk::aacEncClose(&mut encoder) };
+        frames
+    }
+
+    #[test]
+    fn asc_from_adts() {
+        // AAC-LC, 44.1 kHz, stereo
+        let header = [0xff, 0xf1, 0x50, 0x80, 0x2e, 0x7f, 0xfc];
+        assert_eq!(
+            asc_from_adts_header(&header).as_deref(),
+            Some(&[0x12, 0x10][..])
+        );
+
+        // AAC-LC, 48 kHz, mono
+        let header = [0xff, 0xf1, 0x4c, 0x40, 0x2e, 0x7f, 0xfc];
+        assert_eq!(
+            asc_from_adts_header(&header).as_deref(),
+            Some(&[0x11, 0x88][..])
+        );
+
+        assert_eq!(asc_from_adts_header(&header[..4]), None);
+    }
+
+    #[test]
+    fn decode_adts_without_asc() {
+        let chunks: Vec<EncodedChunk> = encode_adts(50)
+            .into_iter()
+            .enumerate()
+            .map(|(index, data)| EncodedChunk {
+                data,
+                pts: Duration::from_secs_f64(index as f64 * 1024.0 / 44_100.0),
+                dts: None,
+                discontinuity: false,
+                kind: EncodedChunkKind::Audio(AudioCodec::Aac),
+            })
+            .collect();
+
+        let options = AacDecoderOptions {
+            depayloader_mode: None,
+            asc: None,
+        };
+        let mut decoder = AacDecoder::new(options, &chunks[0]).unwrap();
+        assert_eq!(decoder.decoded_sample_rate(), 44_100);
+
+        let mut decoded = Vec::new();
+        for chunk in chunks {
+            decoded.extend(decoder.decode(chunk).unwrap());
+        }
+
+        // The decoder needs a few frames to synchronize with the ADTS stream.
+        assert!(decoded.len() > 40);
+        for samples in decoded {
+            assert_eq!(samples.sample_rate, 44_100);
+            match samples.samples.as_ref() {
+                Samples::Stereo16Bit(samples) => assert_eq!(samples.len(), 1024),
+                samples => panic!("Expected stereo samples, got {samples:?}"),
+            }
+        }
+    }
+}
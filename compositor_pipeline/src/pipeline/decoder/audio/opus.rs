@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use crate::{
     error::InputInitError,
@@ -10,11 +10,18 @@ use crate::{
 
 use super::{AudioDecoderExt, DecodedSamples, DecodingError};
 
+/// Longer gaps are not concealed, they are filled with silence by the queue.
+const MAX_CONCEALED_DURATION: Duration = Duration::from_millis(100);
+/// Maximal duration of a single Opus frame.
+const MAX_FRAME_DURATION: Duration = Duration::from_millis(120);
+
 pub(super) struct OpusDecoder {
     decoder: opus::Decoder,
     decoded_samples_buffer: [i16; 100_000],
     forward_error_correction: bool,
     decoded_sample_rate: u32,
+    /// End of the last decoded chunk. It is the start of a gap if packets were lost.
+    next_pts: Option<Duration>,
 }
 
 impl OpusDecoder {
@@ -36,9 +43,72 @@ impl OpusDecoder {
             decoded_samples_buffer,
             forward_error_correction: opts.forward_error_correction,
             decoded_sample_rate,
+            next_pts: None,
         })
     }
 
+    fn samples_to_duration(&self, samples_count: usize) -> Duration {
+        Duration::from_secs_f64(samples_count as f64 / self.decoded_sample_rate as f64)
+    }
+
+    /// Produces audio for packets lost before `chunk`. The last lost frame is recovered
+    /// from the in-band FEC data of `chunk` (if enabled), the rest is generated by
+    /// the Opus packet loss concealment.
+    fn conceal_gap(&mut self, chunk: &EncodedChunk) -> Result<Vec<DecodedSamples>, DecodingError> {
+        let Some(gap_start) = self.next_pts else {
+            return Ok(Vec::new());
+        };
+        let gap = chunk
+            .pts
+            .saturating_sub(gap_start)
+            .min(MAX_CONCEALED_DURATION);
+
+        // Decoded frame sizes have to be multiples of 2.5ms.
+        let step = self.decoded_sample_rate as usize / 400;
+        let gap_samples =
+            (gap.as_secs_f64() * self.decoded_sample_rate as f64) as usize / step * step;
+        let frame_samples = opus::packet::get_nb_samples(&chunk.data, self.decoded_sample_rate)
+            .unwrap_or(self.decoded_sample_rate as usize / 50);
+        let fec_samples = match self.forward_error_correction && frame_samples <= gap_samples {
+            true => frame_samples,
+            false => 0,
+        };
+        let max_frame_samples =
+            (MAX_FRAME_DURATION.as_secs_f64() * self.decoded_sample_rate as f64) as usize;
+
+        let mut decoded = Vec::new();
+        let mut pts = gap_start;
+        let mut plc_samples = gap_samples - fec_samples;
+        while plc_samples > 0 {
+            let frame_size = usize::min(plc_samples, max_frame_samples);
+            let count = self.decoder.decode(
+                &[],
+                &mut self.decoded_samples_buffer[..2 * frame_size],
+                false,
+            )?;
+            decoded.push(DecodedSamples {
+                samples: Self::read_buffer(&self.decoded_samples_buffer, count),
+                start_pts: pts,
+                sample_rate: self.decoded_sample_rate,
+            });
+            pts += self.samples_to_duration(count);
+            plc_samples = plc_samples.saturating_sub(count.max(1));
+        }
+        if fec_samples > 0 {
+            let count = self.decoder.decode(
+                &chunk.data,
+                &mut self.decoded_samples_buffer[..2 * fec_samples],
+                true,
+            )?;
+            decoded.push(DecodedSamples {
+                samples: Self::read_buffer(&self.decoded_samples_buffer, count),
+                start_pts: pts,
+                sample_rate: self.decoded_sample_rate,
+            });
+        }
+        Ok(decoded)
+    }
+
     /// Panics if buffer.len() < 2 * decoded_samples_count
     fn read_buffer(buffer: &[i16], decoded_samples_count: usize) -> Arc<Samples> {
         Samples::Stereo16Bit(
@@ -56,19 +126,24 @@ impl AudioDecoderExt for OpusDecoder {
         &mut self,
         encoded_chunk: EncodedChunk,
     ) -> Result<Vec<DecodedSamples>, DecodingError> {
-        let decoded_samples_count = self.decoder.decode(
-            &encoded_chunk.data,
-            &mut self.decoded_samples_buffer,
-            self.forward_error_correction,
-        )?;
+        let mut decoded = match encoded_chunk.discontinuity {
+            true => self.conceal_gap(&encoded_chunk)?,
+            false => Vec::new(),
+        };
+
+        // FEC data describes the previous frame, so it is only used to recover lost packets.
+        let decoded_samples_count =
+            self.decoder
+                .decode(&encoded_chunk.data, &mut self.decoded_samples_buffer, false)?;
 
         let samples = Self::read_buffer(&self.decoded_samples_buffer, decoded_samples_count);
-        let decoded_samples = DecodedSamples {
+        decoded.push(DecodedSamples {
             samples,
             start_pts: encoded_chunk.pts,
             sample_rate: self.decoded_sample_rate,
-        };
-        Ok(Vec::from([decoded_samples]))
+        });
+        self.next_pts = Some(encoded_chunk.pts + self.samples_to_duration(decoded_samples_count));
+        Ok(decoded)
     }
 
     fn decoded_sample_rate(&self) -> u32 {
@@ -9,7 +9,7 @@ use crate::{
 
 use super::VideoDecoderOptions;
 
-mod ffmpeg_h264;
+mod ffmpeg;
 
 pub fn start_video_decoder_thread(
     options: VideoDecoderOptions,
@@ -18,8 +18,13 @@ pub fn start_video_decoder_thread(
     input_id: InputId,
 ) -> Result<(), InputInitError> {
     match options.codec {
-        VideoCodec::H264 => {
-            ffmpeg_h264::start_ffmpeg_decoder_thread(chunks_receiver, frame_sender, input_id)
+        VideoCodec::H264 | VideoCodec::Vp8 | VideoCodec::Vp9 | VideoCodec::Av1 => {
+            ffmpeg::start_ffmpeg_decoder_thread(
+                options.codec,
+                chunks_receiver,
+                frame_sender,
+                input_id,
+            )
         }
     }
 }
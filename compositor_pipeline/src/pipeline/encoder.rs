@@ -1,29 +1,52 @@
+use std::time::Duration;
+
+use bytes::Bytes;
 use compositor_render::{Frame, OutputId, Resolution};
 use crossbeam_channel::{bounded, Receiver, Sender};
 use log::error;
 
-use crate::{audio_mixer::OutputSamples, error::EncoderInitError, queue::PipelineEvent};
+use crate::{
+    audio_mixer::{AudioChannels, OutputSamples},
+    error::EncoderInitError,
+    queue::PipelineEvent,
+};
 
-use self::{ffmpeg_h264::LibavH264Encoder, opus::OpusEncoder};
+use self::{
+    fdk_aac::AacEncoder,
+    ffmpeg_av1::LibavAv1Encoder,
+    ffmpeg_h264::LibavH264Encoder,
+    ffmpeg_vpx::{LibavVpxEncoder, VpxCodec},
+    opus::OpusEncoder,
+};
 
-use super::types::EncoderOutputEvent;
+use super::{types::EncoderOutputEvent, AudioCodec, VideoCodec};
 
+pub mod fdk_aac;
+pub mod ffmpeg_av1;
 pub mod ffmpeg_h264;
+mod ffmpeg_utils;
+pub mod ffmpeg_vpx;
 pub mod opus;
 
 pub struct EncoderOptions {
     pub video: Option<VideoEncoderOptions>,
     pub audio: Option<AudioEncoderOptions>,
+    /// Keyframes are forced at multiples of this interval, e.g. at segment boundaries.
+    pub forced_keyframe_interval: Option<Duration>,
 }
 
 #[derive(Debug, Clone)]
 pub enum VideoEncoderOptions {
     H264(ffmpeg_h264::Options),
+    Vp8(ffmpeg_vpx::Options),
+    Vp9(ffmpeg_vpx::Options),
+    Av1(ffmpeg_av1::Options),
 }
 
 #[derive(Debug, Clone)]
 pub enum AudioEncoderOptions {
     Opus(opus::Options),
+    Aac(fdk_aac::Options),
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -40,10 +63,24 @@ pub struct Encoder {
 
 pub enum VideoEncoder {
     H264(LibavH264Encoder),
+    Vp8(LibavVpxEncoder),
+    Vp9(LibavVpxEncoder),
+    Av1(LibavAv1Encoder),
 }
 
 pub enum AudioEncoder {
     Opus(OpusEncoder),
+    Aac(AacEncoder),
+}
+
+/// Properties of the encoded audio stream that are required to describe it in a container.
+#[derive(Debug, Clone)]
+pub struct AudioEncoderInfo {
+    pub codec: AudioCodec,
+    pub sample_rate: u32,
+    pub channels: AudioChannels,
+    /// Codec specific configuration, AudioSpecificConfig for AAC and OpusHead for Opus.
+    pub extradata: Bytes,
 }
 
 impl Encoder {
@@ -58,6 +95,7 @@ impl Encoder {
             Some(video_encoder_options) => Some(VideoEncoder::new(
                 output_id,
                 video_encoder_options,
+                options.forced_keyframe_interval,
                 encoded_chunks_sender.clone(),
             )?),
             None => None,
@@ -84,6 +122,10 @@ impl Encoder {
     pub fn frame_sender(&self) -> Option<&Sender<PipelineEvent<Frame>>> {
         match &self.video {
             Some(VideoEncoder::H264(encoder)) => Some(encoder.frame_sender()),
+            Some(VideoEncoder::Vp8(encoder)) | Some(VideoEncoder::Vp9(encoder)) => {
+                Some(encoder.frame_sender())
+            }
+            Some(VideoEncoder::Av1(encoder)) => Some(encoder.frame_sender()),
             None => {
                 error!("Non video encoder received frame to send.");
                 None
@@ -94,18 +136,38 @@ impl Encoder {
     pub fn samples_batch_sender(&self) -> Option<&Sender<PipelineEvent<OutputSamples>>> {
         match &self.audio {
             Some(AudioEncoder::Opus(encoder)) => Some(encoder.samples_batch_sender()),
+            Some(AudioEncoder::Aac(encoder)) => Some(encoder.samples_batch_sender()),
             None => {
                 error!("Non audio encoder received samples to send.");
                 None
             }
         }
     }
+
+    pub fn audio_info(&self) -> Option<AudioEncoderInfo> {
+        match &self.audio {
+            Some(AudioEncoder::Opus(encoder)) => Some(encoder.info()),
+            Some(AudioEncoder::Aac(encoder)) => Some(encoder.info()),
+            None => None,
+        }
+    }
 }
 
 impl VideoEncoderOptions {
     pub fn resolution(&self) -> Resolution {
         match self {
             VideoEncoderOptions::H264(opt) => opt.resolution,
+            VideoEncoderOptions::Vp8(opt) | VideoEncoderOptions::Vp9(opt) => opt.resolution,
+            VideoEncoderOptions::Av1(opt) => opt.resolution,
+        }
+    }
+
+    pub fn codec(&self) -> VideoCodec {
+        match self {
+            VideoEncoderOptions::H264(_) => VideoCodec::H264,
+            VideoEncoderOptions::Vp8(_) => VideoCodec::Vp8,
+            VideoEncoderOptions::Vp9(_) => VideoCodec::Vp9,
+            VideoEncoderOptions::Av1(_) => VideoCodec::Av1,
         }
     }
 }
@@ -114,24 +176,70 @@ impl VideoEncoder {
     pub fn new(
         output_id: &OutputId,
         options: VideoEncoderOptions,
+        forced_keyframe_interval: Option<Duration>,
         sender: Sender<EncoderOutputEvent>,
     ) -> Result<Self, EncoderInitError> {
         match options {
             VideoEncoderOptions::H264(options) => Ok(Self::H264(LibavH264Encoder::new(
-                output_id, options, sender,
+                output_id,
+                options,
+                forced_keyframe_interval,
+                sender,
+            )?)),
+            VideoEncoderOptions::Vp8(options) => Ok(Self::Vp8(LibavVpxEncoder::new(
+                output_id,
+                VpxCodec::Vp8,
+                options,
+                forced_keyframe_interval,
+                sender,
+            )?)),
+            VideoEncoderOptions::Vp9(options) => Ok(Self::Vp9(LibavVpxEncoder::new(
+                output_id,
+                VpxCodec::Vp9,
+                options,
+                forced_keyframe_interval,
+                sender,
+            )?)),
+            VideoEncoderOptions::Av1(options) => Ok(Self::Av1(LibavAv1Encoder::new(
+                output_id,
+                options,
+                forced_keyframe_interval,
+                sender,
             )?)),
         }
     }
 
+    pub fn codec(&self) -> VideoCodec {
+        match self {
+            Self::H264(_) => VideoCodec::H264,
+            Self::Vp8(_) => VideoCodec::Vp8,
+            Self::Vp9(_) => VideoCodec::Vp9,
+            Self::Av1(_) => VideoCodec::Av1,
+        }
+    }
+
     pub fn resolution(&self) -> Resolution {
         match self {
             Self::H264(encoder) => encoder.resolution(),
+            Self::Vp8(encoder) | Self::Vp9(encoder) => encoder.resolution(),
+            Self::Av1(encoder) => encoder.resolution(),
         }
     }
 
     pub fn request_keyframe(&self) {
         match self {
             Self::H264(encoder) => encoder.request_keyframe(),
+            Self::Vp8(encoder) | Self::Vp9(encoder) => encoder.request_keyframe(),
+            Self::Av1(encoder) => encoder.request_keyframe(),
+        }
+    }
+
+    /// Sender that can be used to request a keyframe from other threads.
+    pub fn keyframe_request_sender(&self) -> Sender<()> {
+        match self {
+            Self::H264(encoder) => encoder.keyframe_request_sender(),
+            Self::Vp8(encoder) | Self::Vp9(encoder) => encoder.keyframe_request_sender(),
+            Self::Av1(encoder) => encoder.keyframe_request_sender(),
         }
     }
 }
@@ -146,6 +254,9 @@ impl AudioEncoder {
             AudioEncoderOptions::Opus(opus_encoder_options) => {
                 OpusEncoder::new(opus_encoder_options, sample_rate, sender).map(AudioEncoder::Opus)
             }
+            AudioEncoderOptions::Aac(aac_encoder_options) => {
+                AacEncoder::new(aac_encoder_options, sample_rate, sender).map(AudioEncoder::Aac)
+            }
         }
     }
 }
@@ -0,0 +1,305 @@
+use std::{ffi::c_void, mem, ptr, time::Duration};
+
+use bytes::Bytes;
+use crossbeam_channel::{bounded, Receiver, Sender};
+use fdk_aac_sys as fdk;
+use tracing::{error, span, trace, warn, Level};
+
+use super::AudioEncoderInfo;
+use crate::{
+    audio_mixer::{AudioChannels, AudioSamples, OutputSamples},
+    error::EncoderInitError,
+    pipeline::{
+        types::{EncodedChunk, EncodedChunkKind, EncoderOutputEvent},
+        AudioCodec,
+    },
+    queue::PipelineEvent,
+};
+
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub channels: AudioChannels,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum AacEncoderError {
+    #[error("The internal fdk encoder returned an error: {0:?}.")]
+    FdkEncoderError(fdk::AACENC_ERROR),
+}
+
+pub struct AacEncoder {
+    samples_batch_sender: Sender<PipelineEvent<OutputSamples>>,
+    info: AudioEncoderInfo,
+}
+
+impl AacEncoder {
+    pub fn new(
+        options: Options,
+        sample_rate: u32,
+        packets_sender: Sender<EncoderOutputEvent>,
+    ) -> Result<Self, EncoderInitError> {
+        let (samples_batch_sender, samples_batch_receiver) = bounded(2);
+
+        let encoder = FdkEncoder::new(options.channels, sample_rate)?;
+        let info = AudioEncoderInfo {
+            codec: AudioCodec::Aac,
+            sample_rate,
+            channels: options.channels,
+            extradata: encoder.asc.clone(),
+        };
+
+        std::thread::Builder::new()
+            .name("AAC encoder thread".to_string())
+            .spawn(move || {
+                let _span = span!(Level::INFO, "AAC encoder thread",).entered();
+                run_encoder_thread(encoder, samples_batch_receiver, packets_sender)
+            })
+            .unwrap();
+
+        Ok(Self {
+            samples_batch_sender,
+            info,
+        })
+    }
+
+    pub fn samples_batch_sender(&self) -> &Sender<PipelineEvent<OutputSamples>> {
+        &self.samples_batch_sender
+    }
+
+    /// Encoded chunks contain raw AAC frames, AudioSpecificConfig is only available
+    /// in the `extradata`.
+    pub fn info(&self) -> AudioEncoderInfo {
+        self.info.clone()
+    }
+}
+
+struct FdkEncoder {
+    instance: fdk::HANDLE_AACENCODER,
+    channels: usize,
+    sample_rate: u32,
+    /// Number of samples per channel in a single AAC frame.
+    frame_length: usize,
+    /// Number of samples per channel the encoder output is delayed by, the decoded
+    /// stream starts with this many priming samples.
+    delay: usize,
+    max_output_size: usize,
+    asc: Bytes,
+}
+
+// The encoder handle is only accessed from the encoder thread after initialization.
+unsafe impl Send for FdkEncoder {}
+
+impl FdkEncoder {
+    fn new(channels: AudioChannels, sample_rate: u32) -> Result<Self, AacEncoderError> {
+        let (channel_count, channel_mode) = match channels {
+            AudioChannels::Mono => (1, fdk::CHANNEL_MODE_MODE_1 as u32),
+            AudioChannels::Stereo => (2, fdk::CHANNEL_MODE_MODE_2 as u32),
+        };
+
+        let mut instance = ptr::null_mut();
+        check(unsafe { fdk::aacEncOpen(&mut instance, 0, channel_count) })?;
+        // Dropping here closes the instance if the configuration fails.
+        let mut encoder = Self {
+            instance,
+            channels: channel_count as usize,
+            sample_rate,
+            frame_length: 0,
+            delay: 0,
+            max_output_size: 0,
+            asc: Bytes::new(),
+        };
+
+        let params = [
+            // AAC-LC
+            (fdk::AACENC_PARAM_AACENC_AOT, 2),
+            (fdk::AACENC_PARAM_AACENC_SAMPLERATE, sample_rate),
+            (fdk::AACENC_PARAM_AACENC_CHANNELMODE, channel_mode),
+            (fdk::AACENC_PARAM_AACENC_CHANNELORDER, 1),
+            // Variable bitrate, mode 4 is roughly 64 kbps per channel
+            (fdk::AACENC_PARAM_AACENC_BITRATEMODE, 4),
+            (fdk::AACENC_PARAM_AACENC_AFTERBURNER, 1),
+            // Raw access units, AudioSpecificConfig is passed out-of-band
+            (fdk::AACENC_PARAM_AACENC_TRANSMUX, 0),
+        ];
+        for (param, value) in params {
+            check(unsafe { fdk::aacEncoder_SetParam(encoder.instance, param, value) })?;
+        }
+
+        // Calling encode without buffers initializes the encoder with provided params.
+        check(unsafe {
+            fdk::aacEncEncode(
+                encoder.instance,
+                ptr::null(),
+                ptr::null(),
+                ptr::null(),
+                ptr::null_mut(),
+            )
+        })?;
+
+        let mut info: fdk::AACENC_InfoStruct = unsafe { mem::zeroed() };
+        check(unsafe { fdk::aacEncInfo(encoder.instance, &mut info) })?;
+        encoder.frame_length = info.frameLength as usize;
+        encoder.delay = info.nDelay as usize;
+        encoder.max_output_size = info.maxOutBufBytes as usize;
+        encoder.asc = Bytes::copy_from_slice(&info.confBuf[..info.confSize as usize]);
+
+        Ok(encoder)
+    }
+
+    /// Encodes interleaved samples (at most one frame), or flushes the encoder if `samples`
+    /// is `None`. Returns `None` if the encoder did not produce any data.
+    fn encode(&mut self, samples: Option<&[i16]>) -> Result<Option<Bytes>, AacEncoderError> {
+        let mut input = samples.map(|s| s.to_vec()).unwrap_or_default();
+        let mut input_ptr = input.as_mut_ptr() as *mut c_void;
+        let mut input_id = fdk::AACENC_BufferIdentifier_IN_AUDIO_DATA as i32;
+        let mut input_size = (input.len() * mem::size_of::<i16>()) as i32;
+        let mut input_element_size = mem::size_of::<i16>() as i32;
+        let input_desc = fdk::AACENC_BufDesc {
+            numBufs: 1,
+            bufs: &mut input_ptr,
+            bufferIdentifiers: &mut input_id,
+            bufSizes: &mut input_size,
+            bufElSizes: &mut input_element_size,
+        };
+
+        let mut output = vec![0u8; self.max_output_size];
+        let mut output_ptr = output.as_mut_ptr() as *mut c_void;
+        let mut output_id = fdk::AACENC_BufferIdentifier_OUT_BITSTREAM_DATA as i32;
+        let mut output_size = output.len() as i32;
+        let mut output_element_size = 1;
+        let output_desc = fdk::AACENC_BufDesc {
+            numBufs: 1,
+            bufs: &mut output_ptr,
+            bufferIdentifiers: &mut output_id,
+            bufSizes: &mut output_size,
+            bufElSizes: &mut output_element_size,
+        };
+
+        let in_args = fdk::AACENC_InArgs {
+            // -1 signals the end of the stream
+            numInSamples: samples.map(|s| s.len() as i32).unwrap_or(-1),
+            numAncBytes: 0,
+        };
+        let mut out_args: fdk::AACENC_OutArgs = unsafe { mem::zeroed() };
+
+        let result = unsafe {
+            fdk::aacEncEncode(
+                self.instance,
+                &input_desc,
+                &output_desc,
+                &in_args,
+                &mut out_args,
+            )
+        };
+        if result == fdk::AACENC_ERROR_AACENC_ENCODE_EOF {
+            return Ok(None);
+        }
+        check(result)?;
+
+        match out_args.numOutBytes {
+            0 => Ok(None),
+            len => {
+                output.truncate(len as usize);
+                Ok(Some(Bytes::from(output)))
+            }
+        }
+    }
+}
+
+impl Drop for FdkEncoder {
+    fn drop(&mut self) {
+        unsafe {
+            fdk::aacEncClose(&mut self.instance);
+        }
+    }
+}
+
+fn check(result: fdk::AACENC_ERROR) -> Result<(), AacEncoderError> {
+    match result {
+        fdk::AACENC_ERROR_AACENC_OK => Ok(()),
+        err => Err(AacEncoderError::FdkEncoderError(err)),
+    }
+}
+
+fn run_encoder_thread(
+    mut encoder: FdkEncoder,
+    samples_batch_receiver: Receiver<PipelineEvent<OutputSamples>>,
+    packets_sender: Sender<EncoderOutputEvent>,
+) {
+    let frame_size = encoder.frame_length * encoder.channels;
+    let frame_duration =
+        Duration::from_secs_f64(encoder.frame_length as f64 / encoder.sample_rate as f64);
+    let delay = Duration::from_secs_f64(encoder.delay as f64 / encoder.sample_rate as f64);
+
+    let mut buffer: Vec<i16> = Vec::with_capacity(frame_size * 2);
+    let mut first_pts = None;
+    let mut frame_count: u32 = 0;
+
+    let mut send_chunk = |data: Bytes, first_pts: Duration| {
+        let chunk = EncodedChunk {
+            data,
+            // Shifted by the encoder delay, so the first input sample keeps its pts
+            // after the priming samples are decoded.
+            pts: (first_pts + frame_duration * frame_count).saturating_sub(delay),
+            dts: None,
+            discontinuity: false,
+            kind: EncodedChunkKind::Audio(AudioCodec::Aac),
+        };
+        frame_count += 1;
+
+        trace!(pts=?chunk.pts, "AAC encoder produced an encoded chunk.");
+        packets_sender.send(EncoderOutputEvent::Data(chunk)).is_ok()
+    };
+
+    for msg in samples_batch_receiver {
+        let batch = match msg {
+            PipelineEvent::Data(batch) => batch,
+            PipelineEvent::EOS => break,
+        };
+        let first_pts = *first_pts.get_or_insert(batch.start_pts);
+
+        match batch.samples {
+            AudioSamples::Mono(samples) => buffer.extend(samples),
+            AudioSamples::Stereo(samples) => {
+                buffer.extend(samples.iter().flat_map(|(l, r)| [*l, *r]))
+            }
+        }
+
+        while buffer.len() >= frame_size {
+            let data = match encoder.encode(Some(&buffer[..frame_size])) {
+                Ok(data) => data,
+                Err(err) => {
+                    error!("AAC encoding error: {}", err);
+                    None
+                }
+            };
+            buffer.drain(..frame_size);
+
+            if let Some(data) = data {
+                if !send_chunk(data, first_pts) {
+                    warn!("Failed to send encoded audio from AAC encoder. Channel closed.");
+                    return;
+                }
+            }
+        }
+    }
+
+    // Encode remaining samples and flush frames buffered inside the encoder.
+    if let Some(first_pts) = first_pts {
+        let remaining = match buffer.is_empty() {
+            true => None,
+            false => encoder.encode(Some(&buffer)).ok().flatten(),
+        };
+        let flushed = std::iter::from_fn(|| encoder.encode(None).ok().flatten());
+        for data in remaining.into_iter().chain(flushed) {
+            if !send_chunk(data, first_pts) {
+                warn!("Failed to send encoded audio from AAC encoder. Channel closed.");
+                return;
+            }
+        }
+    }
+
+    if let Err(_err) = packets_sender.send(EncoderOutputEvent::AudioEOS) {
+        warn!("Failed to send EOS from AAC encoder. Channel closed.")
+    }
+}
@@ -0,0 +1,158 @@
+use std::time::Duration;
+
+use compositor_render::{Frame, OutputId, Resolution};
+use crossbeam_channel::{Receiver, Sender};
+use ffmpeg_next::{codec::Context, format::Pixel, Dictionary, Rational};
+use tracing::{debug, span, warn, Level};
+
+use crate::{
+    error::EncoderInitError,
+    pipeline::types::{EncoderOutputEvent, VideoCodec},
+    queue::PipelineEvent,
+};
+
+use super::ffmpeg_utils::{merge_options_with_defaults, run_encoding_loop};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Options {
+    pub resolution: Resolution,
+    /// Target bitrate in bits per second.
+    pub bitrate: u64,
+    /// Speed of the encoder, higher values are faster at the cost of quality.
+    pub cpu_used: u8,
+    /// Maximum number of frames between keyframes.
+    pub keyframe_interval: u32,
+    pub raw_options: Vec<(String, String)>,
+}
+
+/// AV1 encoder backed by libaom.
+pub struct LibavAv1Encoder {
+    resolution: Resolution,
+    frame_sender: Sender<PipelineEvent<Frame>>,
+    keyframe_req_sender: Sender<()>,
+}
+
+impl LibavAv1Encoder {
+    pub fn new(
+        output_id: &OutputId,
+        options: Options,
+        forced_keyframe_interval: Option<Duration>,
+        chunks_sender: Sender<EncoderOutputEvent>,
+    ) -> Result<Self, EncoderInitError> {
+        let (frame_sender, frame_receiver) = crossbeam_channel::bounded(5);
+        let (result_sender, result_receiver) = crossbeam_channel::bounded(0);
+        let (keyframe_req_sender, keyframe_req_receiver) = crossbeam_channel::unbounded();
+
+        let options_clone = options.clone();
+        let output_id = output_id.clone();
+
+        std::thread::Builder::new()
+            .name(format!("Encoder thread for output {}", output_id))
+            .spawn(move || {
+                let _span = span!(
+                    Level::INFO,
+                    "av1 ffmpeg encoder",
+                    output_id = output_id.to_string(),
+                )
+                .entered();
+                let encoder_result = run_encoder_thread(
+                    options_clone,
+                    forced_keyframe_interval,
+                    frame_receiver,
+                    keyframe_req_receiver,
+                    chunks_sender,
+                    &result_sender,
+                );
+
+                if let Err(err) = encoder_result {
+                    warn!(%err, "Encoder thread finished with an error.");
+                    if let Err(err) = result_sender.send(Err(err)) {
+                        warn!(%err, "Failed to send error info. Result channel already closed.");
+                    }
+                }
+                debug!("Encoder thread finished.");
+            })
+            .unwrap();
+
+        result_receiver.recv().unwrap()?;
+
+        Ok(Self {
+            frame_sender,
+            resolution: options.resolution,
+            keyframe_req_sender,
+        })
+    }
+
+    pub fn frame_sender(&self) -> &Sender<PipelineEvent<Frame>> {
+        &self.frame_sender
+    }
+
+    pub fn resolution(&self) -> Resolution {
+        self.resolution
+    }
+
+    pub fn request_keyframe(&self) {
+        if let Err(err) = self.keyframe_req_sender.send(()) {
+            debug!(%err, "Failed to send keyframe request to the encoder.");
+        }
+    }
+
+    pub fn keyframe_request_sender(&self) -> Sender<()> {
+        self.keyframe_req_sender.clone()
+    }
+}
+
+fn run_encoder_thread(
+    options: Options,
+    forced_keyframe_interval: Option<Duration>,
+    frame_receiver: Receiver<PipelineEvent<Frame>>,
+    keyframe_req_receiver: Receiver<()>,
+    packet_sender: Sender<EncoderOutputEvent>,
+    result_sender: &Sender<Result<(), EncoderInitError>>,
+) -> Result<(), EncoderInitError> {
+    let ffmpeg_codec =
+        ffmpeg_next::codec::encoder::find_by_name("libaom-av1").ok_or(EncoderInitError::NoCodec)?;
+
+    let mut encoder = Context::new().encoder().video()?;
+
+    // We set this to 1 / 1_000_000, bc we use `as_micros` to convert frames to AV packets.
+    let pts_unit_secs = Rational::new(1, 1_000_000);
+    encoder.set_time_base(pts_unit_secs);
+    encoder.set_format(Pixel::YUV420P);
+    encoder.set_width(options.resolution.width as u32);
+    encoder.set_height(options.resolution.height as u32);
+    encoder.set_bit_rate(options.bitrate as usize);
+
+    let cpu_used = options.cpu_used.to_string();
+    let keyframe_interval = options.keyframe_interval.to_string();
+    let defaults = [
+        // Encoder is tuned for live encoding, it is much faster than the default mode.
+        ("usage", "realtime"),
+        ("cpu-used", cpu_used.as_str()),
+        ("g", keyframe_interval.as_str()),
+        // Frames are not buffered to look ahead, it would add latency and produce
+        // alternate reference frames that are never displayed.
+        ("lag-in-frames", "0"),
+        // Encode rows of superblocks in parallel.
+        ("row-mt", "1"),
+        // Number of threads is based on the number of CPU cores.
+        ("threads", "0"),
+    ];
+
+    let encoder_opts_iter = merge_options_with_defaults(&defaults, &options.raw_options);
+    let mut encoder =
+        encoder.open_as_with(ffmpeg_codec, Dictionary::from_iter(encoder_opts_iter))?;
+
+    result_sender.send(Ok(())).unwrap();
+
+    run_encoding_loop(
+        &mut encoder,
+        VideoCodec::Av1,
+        options.resolution,
+        frame_receiver,
+        keyframe_req_receiver,
+        forced_keyframe_interval,
+        packet_sender,
+    );
+    Ok(())
+}
@@ -1,22 +1,22 @@
 use std::time::Duration;
 
-use compositor_render::{Frame, FrameData, OutputId, Resolution};
+use compositor_render::{Frame, OutputId, Resolution};
 use crossbeam_channel::{Receiver, Sender};
 use ffmpeg_next::{
     codec::{Context, Id},
     format::Pixel,
-    frame, Dictionary, Packet, Rational,
+    Dictionary, Rational,
 };
-use tracing::{debug, error, span, trace, warn, Level};
+use tracing::{debug, span, warn, Level};
 
 use crate::{
     error::EncoderInitError,
-    pipeline::types::{
-        ChunkFromFfmpegError, EncodedChunk, EncodedChunkKind, EncoderOutputEvent, VideoCodec,
-    },
+    pipeline::types::{EncoderOutputEvent, VideoCodec},
     queue::PipelineEvent,
 };
 
+use super::ffmpeg_utils::{merge_options_with_defaults, run_encoding_loop};
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum EncoderPreset {
     Ultrafast,
@@ -95,6 +95,7 @@ impl LibavH264Encoder {
     pub fn new(
         output_id: &OutputId,
         options: Options,
+        forced_keyframe_interval: Option<Duration>,
         chunks_sender: Sender<EncoderOutputEvent>,
     ) -> Result<Self, EncoderInitError> {
         let (frame_sender, frame_receiver) = crossbeam_channel::bounded(5);
@@ -115,6 +116,7 @@ impl LibavH264Encoder {
                 .entered();
                 let encoder_result = run_encoder_thread(
                     options_clone,
+                    forced_keyframe_interval,
                     frame_receiver,
                     keyframe_req_receiver,
                     chunks_sender,
@@ -153,10 +155,15 @@ impl LibavH264Encoder {
             debug!(%err, "Failed to send keyframe request to the encoder.");
         }
     }
+
+    pub fn keyframe_request_sender(&self) -> Sender<()> {
+        self.keyframe_req_sender.clone()
+    }
 }
 
 fn run_encoder_thread(
     options: Options,
+    forced_keyframe_interval: Option<Duration>,
     frame_receiver: Receiver<PipelineEvent<Frame>>,
     keyframe_req_receiver: Receiver<()>,
     packet_sender: Sender<EncoderOutputEvent>,
@@ -209,170 +216,14 @@ fn run_encoder_thread(
 
     result_sender.send(Ok(())).unwrap();
 
-    let mut packet = Packet::empty();
-
-    loop {
-        let frame = match frame_receiver.recv() {
-            Ok(PipelineEvent::Data(f)) => f,
-            Ok(PipelineEvent::EOS) => break,
-            Err(_) => break,
-        };
-
-        let mut av_frame = frame::Video::new(
-            Pixel::YUV420P,
-            options.resolution.width as u32,
-            options.resolution.height as u32,
-        );
-
-        if let Err(e) = frame_into_av(frame, &mut av_frame) {
-            error!(
-                "Failed to convert a frame to an ffmpeg frame: {}. Dropping",
-                e.0
-            );
-            continue;
-        }
-
-        if keyframe_req_receiver.try_recv().is_ok() {
-            av_frame.set_kind(ffmpeg_next::picture::Type::I);
-        }
-
-        if let Err(e) = encoder.send_frame(&av_frame) {
-            error!("Encoder error: {e}.");
-            continue;
-        }
-
-        loop {
-            match encoder.receive_packet(&mut packet) {
-                Ok(_) => {
-                    match encoded_chunk_from_av_packet(
-                        &packet,
-                        EncodedChunkKind::Video(VideoCodec::H264),
-                        1_000_000,
-                    ) {
-                        Ok(chunk) => {
-                            trace!(pts=?packet.pts(), "H264 encoder produced an encoded packet.");
-                            if packet_sender.send(EncoderOutputEvent::Data(chunk)).is_err() {
-                                warn!("Failed to send encoded video from H264 encoder. Channel closed.");
-                                return Ok(());
-                            }
-                        }
-                        Err(e) => {
-                            warn!("failed to parse an ffmpeg packet received from encoder: {e}",);
-                            break;
-                        }
-                    }
-                }
-
-                Err(ffmpeg_next::Error::Other {
-                    errno: ffmpeg_next::error::EAGAIN,
-                }) => break, // encoder needs more frames to produce a packet
-
-                Err(e) => {
-                    error!("Encoder error: {e}.");
-                    break;
-                }
-            }
-        }
-    }
-
-    if let Err(_err) = packet_sender.send(EncoderOutputEvent::VideoEOS) {
-        warn!("Failed to send EOS from H264 encoder. Channel closed.")
-    }
+    run_encoding_loop(
+        &mut encoder,
+        VideoCodec::H264,
+        options.resolution,
+        frame_receiver,
+        keyframe_req_receiver,
+        forced_keyframe_interval,
+        packet_sender,
+    );
     Ok(())
 }
-
-#[derive(Debug)]
-struct FrameConversionError(String);
-
-fn frame_into_av(frame: Frame, av_frame: &mut frame::Video) -> Result<(), FrameConversionError> {
-    let FrameData::PlanarYuv420(data) = frame.data else {
-        return Err(FrameConversionError(format!(
-            "Unsupported pixel format {:?}",
-            frame.data
-        )));
-    };
-    let expected_y_plane_size = (av_frame.plane_width(0) * av_frame.plane_height(0)) as usize;
-    let expected_u_plane_size = (av_frame.plane_width(1) * av_frame.plane_height(1)) as usize;
-    let expected_v_plane_size = (av_frame.plane_width(2) * av_frame.plane_height(2)) as usize;
-    if expected_y_plane_size != data.y_plane.len() {
-        return Err(FrameConversionError(format!(
-            "Y plane is a wrong size, expected: {} received: {}",
-            expected_y_plane_size,
-            data.y_plane.len()
-        )));
-    }
-    if expected_u_plane_size != data.u_plane.len() {
-        return Err(FrameConversionError(format!(
-            "U plane is a wrong size, expected: {} received: {}",
-            expected_u_plane_size,
-            data.u_plane.len()
-        )));
-    }
-    if expected_v_plane_size != data.v_plane.len() {
-        return Err(FrameConversionError(format!(
-            "V plane is a wrong size, expected: {} received: {}",
-            expected_v_plane_size,
-            data.v_plane.len()
-        )));
-    }
-
-    av_frame.set_pts(Some(frame.pts.as_micros() as i64));
-
-    write_plane_to_av(av_frame, 0, &data.y_plane);
-    write_plane_to_av(av_frame, 1, &data.u_plane);
-    write_plane_to_av(av_frame, 2, &data.v_plane);
-
-    Ok(())
-}
-
-fn write_plane_to_av(frame: &mut frame::Video, plane: usize, data: &[u8]) {
-    let stride = frame.stride(plane);
-    let width = frame.plane_width(plane) as usize;
-
-    data.chunks(width)
-        .zip(frame.data_mut(plane).chunks_mut(stride))
-        .for_each(|(data, target)| target[..width].copy_from_slice(data));
-}
-
-fn merge_options_with_defaults<'a>(
-    defaults: &'a [(&str, &str)],
-    overrides: &'a [(String, String)],
-) -> impl Iterator<Item = (&'a str, &'a str)> {
-    defaults
-        .iter()
-        .copied()
-        .filter(|(key, _value)| {
-            // filter out any defaults that are in overrides
-            !overrides
-                .iter()
-                .any(|(override_key, _)| key == override_key)
-        })
-        .chain(
-            overrides
-                .iter()
-                .map(|(key, value)| (key.as_str(), value.as_str())),
-        )
-}
-
-fn encoded_chunk_from_av_packet(
-    value: &ffmpeg_next::Packet,
-    kind: EncodedChunkKind,
-    timescale: i64,
-) -> Result<EncodedChunk, ChunkFromFfmpegError> {
-    let data = match value.data() {
-        Some(data) => bytes::Bytes::copy_from_slice(data),
-        None => return Err(ChunkFromFfmpegError::NoData),
-    };
-
-    let rescale = |v: i64| Duration::from_secs_f64((v as f64) * (1.0 / timescale as f64));
-
-    Ok(EncodedChunk {
-        data,
-        pts: value
-            .pts()
-            .map(rescale)
-            .ok_or(ChunkFromFfmpegError::NoPts)?,
-        dts: value.dts().map(rescale),
-        kind,
-    })
-}
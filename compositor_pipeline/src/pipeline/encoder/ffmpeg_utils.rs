@@ -0,0 +1,250 @@
+use std::time::Duration;
+
+use compositor_render::{Frame, FrameData, Resolution};
+use crossbeam_channel::{Receiver, Sender};
+use ffmpeg_next::{encoder, format::Pixel, frame, Packet};
+use tracing::{error, trace, warn};
+
+use crate::{
+    pipeline::types::{
+        ChunkFromFfmpegError, EncodedChunk, EncodedChunkKind, EncoderOutputEvent, VideoCodec,
+    },
+    queue::PipelineEvent,
+};
+
+/// Encodes frames until EOS is received or the frames channel is closed. Encoder
+/// has to be opened with YUV420P pixel format and 1 / 1_000_000 time base.
+pub(super) fn run_encoding_loop(
+    encoder: &mut encoder::video::Encoder,
+    codec: VideoCodec,
+    resolution: Resolution,
+    frame_receiver: Receiver<PipelineEvent<Frame>>,
+    keyframe_req_receiver: Receiver<()>,
+    forced_keyframe_interval: Option<Duration>,
+    packet_sender: Sender<EncoderOutputEvent>,
+) {
+    let mut packet = Packet::empty();
+    let mut forced_keyframes = forced_keyframe_interval.map(ForcedKeyframes::new);
+
+    loop {
+        let frame = match frame_receiver.recv() {
+            Ok(PipelineEvent::Data(f)) => f,
+            Ok(PipelineEvent::EOS) => break,
+            Err(_) => break,
+        };
+
+        let pts = frame.pts;
+        let mut av_frame = frame::Video::new(
+            Pixel::YUV420P,
+            resolution.width as u32,
+            resolution.height as u32,
+        );
+
+        if let Err(e) = frame_into_av(frame, &mut av_frame) {
+            error!(
+                "Failed to convert a frame to an ffmpeg frame: {}. Dropping",
+                e.0
+            );
+            continue;
+        }
+
+        let keyframe_requested = keyframe_req_receiver.try_recv().is_ok();
+        let keyframe_forced = forced_keyframes
+            .as_mut()
+            .is_some_and(|forced_keyframes| forced_keyframes.is_forced(pts));
+        if keyframe_requested || keyframe_forced {
+            av_frame.set_kind(ffmpeg_next::picture::Type::I);
+        }
+
+        if let Err(e) = encoder.send_frame(&av_frame) {
+            error!("Encoder error: {e}.");
+            continue;
+        }
+
+        loop {
+            match encoder.receive_packet(&mut packet) {
+                Ok(_) => {
+                    match encoded_chunk_from_av_packet(
+                        &packet,
+                        EncodedChunkKind::Video(codec),
+                        1_000_000,
+                    ) {
+                        Ok(chunk) => {
+                            trace!(pts=?packet.pts(), "Encoder produced an encoded packet.");
+                            if packet_sender.send(EncoderOutputEvent::Data(chunk)).is_err() {
+                                warn!("Failed to send encoded video from encoder. Channel closed.");
+                                return;
+                            }
+                        }
+                        Err(e) => {
+                            warn!("failed to parse an ffmpeg packet received from encoder: {e}",);
+                            break;
+                        }
+                    }
+                }
+
+                Err(ffmpeg_next::Error::Other {
+                    errno: ffmpeg_next::error::EAGAIN,
+                }) => break, // encoder needs more frames to produce a packet
+
+                Err(e) => {
+                    error!("Encoder error: {e}.");
+                    break;
+                }
+            }
+        }
+    }
+
+    if let Err(_err) = packet_sender.send(EncoderOutputEvent::VideoEOS) {
+        warn!("Failed to send EOS from video encoder. Channel closed.")
+    }
+}
+
+/// Keyframes are forced on the first frames at or after every multiple of the interval,
+/// counted from the pts of the first frame.
+struct ForcedKeyframes {
+    interval: Duration,
+    next_pts: Option<Duration>,
+}
+
+impl ForcedKeyframes {
+    fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            next_pts: None,
+        }
+    }
+
+    fn is_forced(&mut self, pts: Duration) -> bool {
+        let next_pts = self.next_pts.get_or_insert(pts);
+        if pts < *next_pts {
+            return false;
+        }
+        while *next_pts <= pts {
+            *next_pts += self.interval;
+        }
+        true
+    }
+}
+
+#[derive(Debug)]
+struct FrameConversionError(String);
+
+fn frame_into_av(frame: Frame, av_frame: &mut frame::Video) -> Result<(), FrameConversionError> {
+    let FrameData::PlanarYuv420(data) = frame.data else {
+        return Err(FrameConversionError(format!(
+            "Unsupported pixel format {:?}",
+            frame.data
+        )));
+    };
+    let expected_y_plane_size = (av_frame.plane_width(0) * av_frame.plane_height(0)) as usize;
+    let expected_u_plane_size = (av_frame.plane_width(1) * av_frame.plane_height(1)) as usize;
+    let expected_v_plane_size = (av_frame.plane_width(2) * av_frame.plane_height(2)) as usize;
+    if expected_y_plane_size != data.y_plane.len() {
+        return Err(FrameConversionError(format!(
+            "Y plane is a wrong size, expected: {} received: {}",
+            expected_y_plane_size,
+            data.y_plane.len()
+        )));
+    }
+    if expected_u_plane_size != data.u_plane.len() {
+        return Err(FrameConversionError(format!(
+            "U plane is a wrong size, expected: {} received: {}",
+            expected_u_plane_size,
+            data.u_plane.len()
+        )));
+    }
+    if expected_v_plane_size != data.v_plane.len() {
+        return Err(FrameConversionError(format!(
+            "V plane is a wrong size, expected: {} received: {}",
+            expected_v_plane_size,
+            data.v_plane.len()
+        )));
+    }
+
+    av_frame.set_pts(Some(frame.pts.as_micros() as i64));
+
+    write_plane_to_av(av_frame, 0, &data.y_plane);
+    write_plane_to_av(av_frame, 1, &data.u_plane);
+    write_plane_to_av(av_frame, 2, &data.v_plane);
+
+    Ok(())
+}
+
+fn write_plane_to_av(frame: &mut frame::Video, plane: usize, data: &[u8]) {
+    let stride = frame.stride(plane);
+    let width = frame.plane_width(plane) as usize;
+
+    data.chunks(width)
+        .zip(frame.data_mut(plane).chunks_mut(stride))
+        .for_each(|(data, target)| target[..width].copy_from_slice(data));
+}
+
+pub(super) fn merge_options_with_defaults<'a>(
+    defaults: &'a [(&str, &str)],
+    overrides: &'a [(String, String)],
+) -> impl Iterator<Item = (&'a str, &'a str)> {
+    defaults
+        .iter()
+        .copied()
+        .filter(|(key, _value)| {
+            // filter out any defaults that are in overrides
+            !overrides
+                .iter()
+                .any(|(override_key, _)| key == override_key)
+        })
+        .chain(
+            overrides
+                .iter()
+                .map(|(key, value)| (key.as_str(), value.as_str())),
+        )
+}
+
+fn encoded_chunk_from_av_packet(
+    value: &ffmpeg_next::Packet,
+    kind: EncodedChunkKind,
+    timescale: i64,
+) -> Result<EncodedChunk, ChunkFromFfmpegError> {
+    let data = match value.data() {
+        Some(data) => bytes::Bytes::copy_from_slice(data),
+        None => return Err(ChunkFromFfmpegError::NoData),
+    };
+
+    let rescale = |v: i64| Duration::from_secs_f64((v as f64) * (1.0 / timescale as f64));
+
+    Ok(EncodedChunk {
+        data,
+        pts: value
+            .pts()
+            .map(rescale)
+            .ok_or(ChunkFromFfmpegError::NoPts)?,
+        dts: value.dts().map(rescale),
+        discontinuity: false,
+        kind,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::ForcedKeyframes;
+
+    #[test]
+    fn keyframes_forced_at_interval_boundaries() {
+        let mut forced_keyframes = ForcedKeyframes::new(Duration::from_secs(2));
+
+        // 10 fps for 5 seconds, starting at 1s.
+        let forced: Vec<_> = (1000..6000)
+            .step_by(100)
+            .filter(|pts_ms| forced_keyframes.is_forced(Duration::from_millis(*pts_ms)))
+            .collect();
+        assert_eq!(forced, vec![1000, 3000, 5000]);
+
+        // The first frame after a skipped boundary is a keyframe.
+        assert!(!forced_keyframes.is_forced(Duration::from_millis(6900)));
+        assert!(forced_keyframes.is_forced(Duration::from_millis(7100)));
+        assert!(!forced_keyframes.is_forced(Duration::from_millis(8900)));
+        assert!(forced_keyframes.is_forced(Duration::from_millis(9000)));
+    }
+}
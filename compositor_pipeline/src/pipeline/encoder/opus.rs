@@ -1,3 +1,4 @@
+use bytes::{BufMut, Bytes, BytesMut};
 use crossbeam_channel::{bounded, Receiver, Sender};
 use log::error;
 use tracing::{span, trace, warn, Level};
@@ -12,7 +13,7 @@ use crate::{
     queue::PipelineEvent,
 };
 
-use super::AudioEncoderPreset;
+use super::{AudioEncoderInfo, AudioEncoderPreset};
 
 #[derive(Debug, Clone)]
 pub struct Options {
@@ -22,6 +23,7 @@ pub struct Options {
 
 pub struct OpusEncoder {
     samples_batch_sender: Sender<PipelineEvent<OutputSamples>>,
+    info: AudioEncoderInfo,
 }
 
 impl OpusEncoder {
@@ -45,12 +47,48 @@ impl OpusEncoder {
 
         Ok(Self {
             samples_batch_sender,
+            info: AudioEncoderInfo {
+                codec: AudioCodec::Opus,
+                sample_rate,
+                channels: options.channels,
+                extradata: opus_head(&options, sample_rate),
+            },
         })
     }
 
     pub fn samples_batch_sender(&self) -> &Sender<PipelineEvent<OutputSamples>> {
         &self.samples_batch_sender
     }
+
+    pub fn info(&self) -> AudioEncoderInfo {
+        self.info.clone()
+    }
+}
+
+/// [RFC 7845, section 5.1 Identification Header]
+fn opus_head(options: &Options, sample_rate: u32) -> Bytes {
+    let channel_count: u8 = match options.channels {
+        AudioChannels::Mono => 1,
+        AudioChannels::Stereo => 2,
+    };
+    // Encoder lookahead in 48kHz samples, libopus uses 2.5ms for the low delay mode
+    // and additional 4ms of delay compensation otherwise.
+    let pre_skip: u16 = match options.preset {
+        AudioEncoderPreset::LowestLatency => 120,
+        AudioEncoderPreset::Quality | AudioEncoderPreset::Voip => 312,
+    };
+
+    let mut head = BytesMut::with_capacity(19);
+    head.put_slice(b"OpusHead");
+    head.put_u8(1);
+    head.put_u8(channel_count);
+    head.put_u16_le(pre_skip);
+    head.put_u32_le(sample_rate);
+    // output gain
+    head.put_i16_le(0);
+    // channel mapping family
+    head.put_u8(0);
+    head.freeze()
 }
 
 fn run_encoder_thread(
@@ -94,6 +132,7 @@ fn run_encoder_thread(
             data,
             pts: batch.start_pts,
             dts: None,
+            discontinuity: false,
             kind: EncodedChunkKind::Audio(AudioCodec::Opus),
         };
 
@@ -3,16 +3,27 @@ use crate::{
     queue::PipelineEvent,
 };
 
+use bytes::Bytes;
 use compositor_render::{Frame, InputId};
-use crossbeam_channel::{bounded, Receiver};
+use crossbeam_channel::{bounded, Receiver, Sender};
+use hls::{HlsReceiver, HlsReceiverOptions};
+use mpeg_ts::{MpegTsReceiver, MpegTsReceiverOptions};
+use rtmp::{RtmpReceiver, RtmpReceiverOptions};
 use rtp::{RtpReceiver, RtpReceiverOptions};
+use srt::{SrtReceiver, SrtReceiverOptions};
+use test_source::{TestSource, TestSourceOptions};
+use whip::{WhipReceiver, WhipReceiverOptions};
 
-use self::mp4::{Mp4, Mp4Options};
+use self::mp4::{
+    fragmented_receiver::{FragmentedMp4Options, FragmentedMp4Receiver},
+    Mp4, Mp4Options,
+};
 
 use super::{
     decoder::{
-        start_audio_decoder_thread, start_audio_resampler_only_thread, start_video_decoder_thread,
-        AudioDecoderOptions, DecodedDataReceiver, VideoDecoderOptions,
+        start_audio_decoder_thread, start_audio_resampler_only_thread,
+        start_deferred_audio_decoder_thread, start_video_decoder_thread, AudioDecoderOptions,
+        DecodedDataReceiver, VideoDecoderOptions,
     },
     types::{DecodedSamples, EncodedChunk, RawDataSender},
     PipelineCtx, Port,
@@ -20,12 +31,26 @@ use super::{
 
 #[cfg(feature = "decklink")]
 pub mod decklink;
+mod ffmpeg_utils;
+pub mod hls;
 pub mod mp4;
+pub mod mpeg_ts;
+pub mod rtmp;
 pub mod rtp;
+pub mod srt;
+pub mod test_source;
+pub mod whip;
 
 pub enum Input {
     Rtp(RtpReceiver),
+    Rtmp(RtmpReceiver),
+    Hls(HlsReceiver),
+    Srt(SrtReceiver),
+    MpegTs(MpegTsReceiver),
     Mp4(Mp4),
+    FragmentedMp4(FragmentedMp4Receiver),
+    TestSource(TestSource),
+    Whip(WhipReceiver),
     #[cfg(feature = "decklink")]
     DeckLink(decklink::DeckLink),
     RawDataInput,
@@ -34,11 +59,34 @@ pub enum Input {
 #[derive(Debug, Clone)]
 pub enum InputOptions {
     Rtp(RtpReceiverOptions),
+    Rtmp(RtmpReceiverOptions),
+    Hls(HlsReceiverOptions),
+    Srt(SrtReceiverOptions),
+    MpegTs(MpegTsReceiverOptions),
     Mp4(Mp4Options),
+    FragmentedMp4(FragmentedMp4Options),
+    TestSource(TestSourceOptions),
+    Whip(WhipReceiverOptions),
     #[cfg(feature = "decklink")]
     DeckLink(decklink::DeckLinkOptions),
 }
 
+impl Input {
+    /// Sender for inputs that receive a byte stream over HTTP.
+    pub fn data_sender(&self) -> Option<&Sender<PipelineEvent<Bytes>>> {
+        match self {
+            Input::FragmentedMp4(receiver) => receiver.data_sender(),
+            _ => None,
+        }
+    }
+
+    /// Audio and video timestamps are in the same frame of reference, so the queue
+    /// should start both tracks together instead of aligning each of them separately.
+    pub(super) fn has_common_timeline(&self) -> bool {
+        matches!(self, Input::Rtp(_) | Input::Whip(_))
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct RawDataInputOptions {
     pub video: bool,
@@ -57,7 +105,6 @@ struct InputInitResult {
 }
 
 pub(super) enum VideoInputReceiver {
-    #[allow(dead_code)]
     Raw {
         frame_receiver: Receiver<PipelineEvent<Frame>>,
     },
@@ -68,7 +115,6 @@ pub(super) enum VideoInputReceiver {
 }
 
 pub(super) enum AudioInputReceiver {
-    #[allow(dead_code)]
     Raw {
         sample_receiver: Receiver<PipelineEvent<DecodedSamples>>,
         sample_rate: u32,
@@ -77,6 +123,12 @@ pub(super) enum AudioInputReceiver {
         chunk_receiver: Receiver<PipelineEvent<EncodedChunk>>,
         decoder_options: AudioDecoderOptions,
     },
+    /// Decoder options are only known after the stream starts (e.g. AudioSpecificConfig
+    /// from the fMP4 initialization segment), they are sent before the first chunk.
+    EncodedWithDeferredOptions {
+        chunk_receiver: Receiver<PipelineEvent<EncodedChunk>>,
+        decoder_options_receiver: Receiver<AudioDecoderOptions>,
+    },
 }
 
 pub(super) trait InputOptionsExt<NewInputResult> {
@@ -145,9 +197,18 @@ fn start_input_threads(
         init_info,
     } = match options {
         InputOptions::Rtp(opts) => RtpReceiver::start_new_input(input_id, opts)?,
+        InputOptions::Rtmp(opts) => RtmpReceiver::start_new_input(input_id, opts)?,
+        InputOptions::Hls(opts) => HlsReceiver::start_new_input(input_id, opts)?,
+        InputOptions::Srt(opts) => SrtReceiver::start_new_input(input_id, opts)?,
+        InputOptions::MpegTs(opts) => MpegTsReceiver::start_new_input(input_id, opts)?,
         InputOptions::Mp4(opts) => {
             Mp4::start_new_input(input_id, opts, &pipeline_ctx.download_dir)?
         }
+        InputOptions::FragmentedMp4(opts) => {
+            FragmentedMp4Receiver::start_new_input(input_id, opts)?
+        }
+        InputOptions::TestSource(opts) => TestSource::start_new_input(input_id, opts),
+        InputOptions::Whip(opts) => WhipReceiver::start_new_input(input_id, opts)?,
         #[cfg(feature = "decklink")]
         InputOptions::DeckLink(opts) => decklink::DeckLink::start_new_input(input_id, opts)?,
     };
@@ -203,6 +264,20 @@ fn start_input_threads(
                 )?;
                 Some(receiver)
             }
+            AudioInputReceiver::EncodedWithDeferredOptions {
+                chunk_receiver,
+                decoder_options_receiver,
+            } => {
+                let (sender, receiver) = bounded(10);
+                start_deferred_audio_decoder_thread(
+                    decoder_options_receiver,
+                    pipeline_ctx.output_sample_rate,
+                    chunk_receiver,
+                    sender,
+                    input_id.clone(),
+                );
+                Some(receiver)
+            }
         }
     } else {
         None
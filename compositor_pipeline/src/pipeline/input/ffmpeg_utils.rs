@@ -0,0 +1,206 @@
+use std::time::Duration;
+
+use bytes::{Bytes, BytesMut};
+use ffmpeg_next::Rational;
+use tracing::warn;
+
+pub(super) fn extradata(stream: &ffmpeg_next::Stream) -> Bytes {
+    unsafe {
+        let parameters = stream.parameters();
+        let parameters = &*parameters.as_ptr();
+        if parameters.extradata.is_null() || parameters.extradata_size <= 0 {
+            return Bytes::new();
+        }
+        Bytes::copy_from_slice(std::slice::from_raw_parts(
+            parameters.extradata,
+            parameters.extradata_size as usize,
+        ))
+    }
+}
+
+pub(super) fn to_duration(value: i64, time_base: Rational) -> Duration {
+    Duration::from_secs_f64(
+        i64::max(value, 0) as f64 * time_base.numerator() as f64 / time_base.denominator() as f64,
+    )
+}
+
+/// FLV and MP4 store H264 in the AVCC format (NAL units prefixed with their length, SPS and
+/// PPS in the decoder configuration record). The decoder expects Annex B byte stream.
+pub(super) struct AvccToAnnexB {
+    /// SPS and PPS in Annex B format, prepended to every keyframe.
+    parameter_sets: Bytes,
+    /// `None` if the stream is already in Annex B format.
+    length_size: Option<usize>,
+}
+
+impl AvccToAnnexB {
+    pub fn new(extradata: &[u8]) -> Self {
+        match Self::parse_avcc(extradata) {
+            Some((parameter_sets, length_size)) => Self {
+                parameter_sets,
+                length_size: Some(length_size),
+            },
+            None => Self {
+                parameter_sets: Bytes::copy_from_slice(extradata),
+                length_size: None,
+            },
+        }
+    }
+
+    /// [ISO/IEC 14496-15, section 5.3.3.1 AVC decoder configuration record]
+    fn parse_avcc(extradata: &[u8]) -> Option<(Bytes, usize)> {
+        if extradata.len() < 7 || extradata[0] != 1 {
+            return None;
+        }
+        let length_size = (extradata[4] & 0b11) as usize + 1;
+
+        let mut parameter_sets = BytesMut::new();
+        let mut offset = 5;
+        for count_mask in [0b11111, 0xff] {
+            let count = *extradata.get(offset)? & count_mask;
+            offset += 1;
+            for _ in 0..count {
+                let len =
+                    u16::from_be_bytes([*extradata.get(offset)?, *extradata.get(offset + 1)?]);
+                offset += 2;
+                let nal = extradata.get(offset..offset + len as usize)?;
+                offset += len as usize;
+                parameter_sets.extend_from_slice(&[0, 0, 0, 1]);
+                parameter_sets.extend_from_slice(nal);
+            }
+        }
+
+        Some((parameter_sets.freeze(), length_size))
+    }
+
+    pub fn convert(&self, data: &[u8], is_keyframe: bool) -> Bytes {
+        let mut output = BytesMut::with_capacity(data.len() + self.parameter_sets.len());
+        if is_keyframe {
+            output.extend_from_slice(&self.parameter_sets);
+        }
+
+        let Some(length_size) = self.length_size else {
+            output.extend_from_slice(data);
+            return output.freeze();
+        };
+
+        let mut offset = 0;
+        while offset + length_size <= data.len() {
+            let len = data[offset..offset + length_size]
+                .iter()
+                .fold(0usize, |len, byte| (len << 8) | *byte as usize);
+            offset += length_size;
+            let Some(nal) = data.get(offset..offset + len) else {
+                warn!("Truncated NAL unit in AVCC video packet.");
+                break;
+            };
+            offset += len;
+            output.extend_from_slice(&[0, 0, 0, 1]);
+            output.extend_from_slice(nal);
+        }
+        output.freeze()
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub(super) enum AdtsHeaderError {
+    #[error("AudioSpecificConfig is too short.")]
+    AscTooShort,
+
+    #[error("Audio object type {0} can not be signalled in ADTS.")]
+    UnsupportedObjectType(u8),
+
+    #[error("Explicit sampling frequency can not be signalled in ADTS.")]
+    ExplicitFrequency,
+}
+
+/// FLV and MP4 store raw AAC frames and the AudioSpecificConfig separately. Prefixing each frame
+/// with an ADTS header lets the decoder initialize from the first chunk.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct AdtsHeader {
+    profile: u8,
+    frequency_index: u8,
+    channel_config: u8,
+}
+
+impl AdtsHeader {
+    /// [MPEG-4 part 3, section 1.6.2.1 AudioSpecificConfig]
+    pub fn from_asc(asc: &[u8]) -> Result<Self, AdtsHeaderError> {
+        if asc.len() < 2 {
+            return Err(AdtsHeaderError::AscTooShort);
+        }
+        let object_type = asc[0] >> 3;
+        let frequency_index = ((asc[0] & 0b111) << 1) | (asc[1] >> 7);
+        let channel_config = (asc[1] >> 3) & 0b1111;
+
+        if !(1..=4).contains(&object_type) {
+            return Err(AdtsHeaderError::UnsupportedObjectType(object_type));
+        }
+        if frequency_index == 0xf {
+            return Err(AdtsHeaderError::ExplicitFrequency);
+        }
+
+        Ok(Self {
+            profile: object_type - 1,
+            frequency_index,
+            channel_config,
+        })
+    }
+
+    /// [MPEG-4 part 3, section 1.A.2.2 Audio_Data_Transport_Stream frame]
+    pub fn wrap(&self, frame: &[u8]) -> Bytes {
+        let frame_length = frame.len() + 7;
+        let mut output = BytesMut::with_capacity(frame_length);
+        output.extend_from_slice(&[
+            0xff,
+            0xf1,
+            (self.profile << 6) | (self.frequency_index << 2) | (self.channel_config >> 2),
+            ((self.channel_config & 0b11) << 6) | ((frame_length >> 11) & 0b11) as u8,
+            ((frame_length >> 3) & 0xff) as u8,
+            (((frame_length & 0b111) << 5) as u8) | 0b11111,
+            0xfc,
+        ]);
+        output.extend_from_slice(frame);
+        output.freeze()
+    }
+}
+
+/// AAC in MPEG-TS is usually already in the ADTS format.
+pub(super) fn is_adts(data: &[u8]) -> bool {
+    data.len() >= 7 && data[0] == 0xff && data[1] & 0xf0 == 0xf0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn avcc_to_annex_b() {
+        let extradata = [
+            1, 0x64, 0, 0x1f, 0xff, 0xe1, 0, 3, 0x67, 1, 2, 1, 0, 2, 0x68, 3,
+        ];
+        let converter = AvccToAnnexB::new(&extradata);
+
+        let packet = [0, 0, 0, 2, 0x65, 4, 0, 0, 0, 1, 0x06];
+        assert_eq!(
+            converter.convert(&packet, true),
+            Bytes::from_static(&[
+                0, 0, 0, 1, 0x67, 1, 2, 0, 0, 0, 1, 0x68, 3, 0, 0, 0, 1, 0x65, 4, 0, 0, 0, 1, 0x06
+            ])
+        );
+        assert_eq!(
+            converter.convert(&packet, false),
+            Bytes::from_static(&[0, 0, 0, 1, 0x65, 4, 0, 0, 0, 1, 0x06])
+        );
+    }
+
+    #[test]
+    fn adts_header_from_asc() {
+        // AAC-LC, 44100Hz, stereo
+        let header = AdtsHeader::from_asc(&[0x12, 0x10]).unwrap();
+        let frame = header.wrap(&[0xaa; 10]);
+
+        assert_eq!(&frame[..7], &[0xff, 0xf1, 0x50, 0x80, 0x02, 0x3f, 0xfc]);
+        assert_eq!(&frame[7..], &[0xaa; 10]);
+    }
+}
@@ -0,0 +1,344 @@
+use std::{
+    ptr,
+    sync::{atomic::AtomicBool, Arc},
+    time::Duration,
+};
+
+use bytes::Bytes;
+use compositor_render::InputId;
+use crossbeam_channel::{bounded, Sender};
+use ffmpeg_next::{codec::Id, ffi, format::context, media::Type, Packet, Rational};
+use tracing::{debug, info, span, trace, warn, Level};
+
+use crate::{
+    pipeline::{
+        decoder::{AacDecoderOptions, AudioDecoderOptions, VideoDecoderOptions},
+        types::{EncodedChunk, EncodedChunkKind},
+        AudioCodec, VideoCodec,
+    },
+    queue::PipelineEvent,
+};
+
+use super::{
+    ffmpeg_utils::{extradata, is_adts, to_duration, AdtsHeader, AvccToAnnexB},
+    AudioInputReceiver, Input, InputInitInfo, InputInitResult, VideoInputReceiver,
+};
+
+#[derive(Debug, Clone)]
+pub struct HlsReceiverOptions {
+    /// URL or local path of a master or media playlist.
+    pub url: Arc<str>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum HlsReceiverError {
+    #[error("Failed to open HLS playlist.")]
+    OpenPlaylist(#[source] ffmpeg_next::Error),
+
+    #[error("HLS playlist does not contain any supported stream. Only H264 video and AAC audio are supported.")]
+    NoSupportedStream,
+}
+
+pub struct HlsReceiver {
+    should_close: Arc<AtomicBool>,
+}
+
+struct VideoStream {
+    index: usize,
+    time_base: Rational,
+    converter: AvccToAnnexB,
+    sender: Sender<PipelineEvent<EncodedChunk>>,
+}
+
+struct AudioStream {
+    index: usize,
+    time_base: Rational,
+    /// `None` if the stream is already in ADTS format (e.g. MPEG-TS segments).
+    adts_header: Option<AdtsHeader>,
+    sender: Sender<PipelineEvent<EncodedChunk>>,
+}
+
+impl HlsReceiver {
+    pub(super) fn start_new_input(
+        input_id: &InputId,
+        opts: HlsReceiverOptions,
+    ) -> Result<InputInitResult, HlsReceiverError> {
+        let should_close = Arc::new(AtomicBool::new(false));
+
+        // Opening the playlist here reports unreachable URLs and unsupported streams
+        // in the register request.
+        let mut input =
+            open_input(&opts.url, should_close.clone()).map_err(HlsReceiverError::OpenPlaylist)?;
+
+        // Master playlist variants are exposed as separate streams. The best video
+        // stream selects the variant and audio is picked from the same program if possible.
+        let video_index = find_best_stream(&input, Type::Video, None).filter(|index| {
+            let codec = input.stream(*index).map(|s| s.parameters().id());
+            if codec != Some(Id::H264) {
+                warn!(
+                    ?codec,
+                    "Unsupported HLS video codec. Video will be ignored."
+                );
+                return false;
+            }
+            true
+        });
+        let audio_index = find_best_stream(&input, Type::Audio, video_index).filter(|index| {
+            let codec = input.stream(*index).map(|s| s.parameters().id());
+            if codec != Some(Id::AAC) {
+                warn!(
+                    ?codec,
+                    "Unsupported HLS audio codec. Audio will be ignored."
+                );
+                return false;
+            }
+            true
+        });
+        if video_index.is_none() && audio_index.is_none() {
+            return Err(HlsReceiverError::NoSupportedStream);
+        }
+
+        // Segments of discarded variants are not downloaded.
+        for mut stream in input.streams_mut() {
+            let index = stream.index();
+            if Some(index) != video_index && Some(index) != audio_index {
+                unsafe {
+                    (*stream.as_mut_ptr()).discard = ffi::AVDiscard::AVDISCARD_ALL;
+                }
+            }
+        }
+
+        let (video, video_receiver) = match video_index.and_then(|i| input.stream(i)) {
+            Some(stream) => {
+                let (sender, receiver) = bounded(5);
+                let video = VideoStream {
+                    index: stream.index(),
+                    time_base: stream.time_base(),
+                    converter: AvccToAnnexB::new(&extradata(&stream)),
+                    sender,
+                };
+                (Some(video), Some(receiver))
+            }
+            None => (None, None),
+        };
+        let (audio, audio_receiver, asc) = match audio_index.and_then(|i| input.stream(i)) {
+            Some(stream) => {
+                let (sender, receiver) = bounded(5);
+                let asc = extradata(&stream);
+                let audio = AudioStream {
+                    index: stream.index(),
+                    time_base: stream.time_base(),
+                    adts_header: AdtsHeader::from_asc(&asc).ok(),
+                    sender,
+                };
+                (
+                    Some(audio),
+                    Some(receiver),
+                    Some(asc).filter(|asc| !asc.is_empty()),
+                )
+            }
+            None => (None, None, None),
+        };
+
+        let input_id_clone = input_id.clone();
+        let should_close_clone = should_close.clone();
+        std::thread::Builder::new()
+            .name(format!("HLS reader {}", input_id))
+            .spawn(move || {
+                let _span = span!(
+                    Level::INFO,
+                    "HLS reader",
+                    input_id = input_id_clone.to_string()
+                )
+                .entered();
+                run_hls_reader_thread(input, video, audio, should_close_clone);
+                debug!("Closing HLS reader thread.");
+            })
+            .unwrap();
+
+        let video = video_receiver.map(|chunk_receiver| VideoInputReceiver::Encoded {
+            chunk_receiver,
+            decoder_options: VideoDecoderOptions {
+                codec: VideoCodec::H264,
+            },
+        });
+        // AAC frames are sent in the ADTS format. MPEG-TS segments usually do not provide
+        // the AudioSpecificConfig upfront, the decoder reads it from the first ADTS header then.
+        let audio = audio_receiver.map(|chunk_receiver| AudioInputReceiver::Encoded {
+            chunk_receiver,
+            decoder_options: AudioDecoderOptions::Aac(AacDecoderOptions {
+                depayloader_mode: None,
+                asc,
+            }),
+        });
+
+        Ok(InputInitResult {
+            input: Input::Hls(Self { should_close }),
+            video,
+            audio,
+            init_info: InputInitInfo { port: None },
+        })
+    }
+}
+
+impl Drop for HlsReceiver {
+    fn drop(&mut self) {
+        self.should_close
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+}
+
+/// Reads packets until the end of a VOD playlist. Live playlists are refreshed by
+/// the FFmpeg demuxer, so for them it runs until the input is unregistered.
+fn run_hls_reader_thread(
+    mut input: context::Input,
+    video: Option<VideoStream>,
+    audio: Option<AudioStream>,
+    should_close: Arc<AtomicBool>,
+) {
+    // Timestamps in HLS usually do not start at zero, they are shifted so the first
+    // packet of the input is at zero.
+    let mut start_pts: Option<Duration> = None;
+
+    loop {
+        if should_close.load(std::sync::atomic::Ordering::Relaxed) {
+            break;
+        }
+
+        let mut packet = Packet::empty();
+        match packet.read(&mut input) {
+            Ok(()) => {}
+            Err(ffmpeg_next::Error::Other {
+                errno: ffmpeg_next::error::EAGAIN,
+            }) => continue,
+            Err(ffmpeg_next::Error::Eof) | Err(ffmpeg_next::Error::Exit) => {
+                info!("HLS stream finished.");
+                break;
+            }
+            Err(err) => {
+                warn!("Failed to read HLS packet: {err}");
+                break;
+            }
+        }
+        let Some(data) = packet.data() else {
+            continue;
+        };
+
+        let time_base = match (&video, &audio) {
+            (Some(video), _) if video.index == packet.stream() => video.time_base,
+            (_, Some(audio)) if audio.index == packet.stream() => audio.time_base,
+            _ => continue,
+        };
+        let Some(pts) = packet.pts().or(packet.dts()) else {
+            continue;
+        };
+        let pts = to_duration(pts, time_base);
+        let start_pts = *start_pts.get_or_insert(pts);
+        let dts = packet
+            .dts()
+            .map(|dts| to_duration(dts, time_base).saturating_sub(start_pts));
+        let pts = pts.saturating_sub(start_pts);
+
+        if let Some(video) = &video {
+            if packet.stream() == video.index {
+                let chunk = EncodedChunk {
+                    data: video.converter.convert(data, packet.is_key()),
+                    pts,
+                    dts,
+                    discontinuity: false,
+                    kind: EncodedChunkKind::Video(VideoCodec::H264),
+                };
+                trace!(pts=?chunk.pts, "HLS reader produced a video chunk.");
+                if video.sender.send(PipelineEvent::Data(chunk)).is_err() {
+                    debug!("Failed to send HLS video chunk. Channel closed.");
+                    return;
+                }
+                continue;
+            }
+        }
+
+        if let Some(audio) = &audio {
+            if packet.stream() == audio.index {
+                let data = match (is_adts(data), &audio.adts_header) {
+                    (true, _) => Bytes::copy_from_slice(data),
+                    (false, Some(adts_header)) => adts_header.wrap(data),
+                    (false, None) => {
+                        warn!("Received raw AAC frame without AudioSpecificConfig. Dropping.");
+                        continue;
+                    }
+                };
+                let chunk = EncodedChunk {
+                    data,
+                    pts,
+                    dts: None,
+                    discontinuity: false,
+                    kind: EncodedChunkKind::Audio(AudioCodec::Aac),
+                };
+                trace!(pts=?chunk.pts, "HLS reader produced an audio chunk.");
+                if audio.sender.send(PipelineEvent::Data(chunk)).is_err() {
+                    debug!("Failed to send HLS audio chunk. Channel closed.");
+                    return;
+                }
+            }
+        }
+    }
+
+    if let Some(video) = &video {
+        if video.sender.send(PipelineEvent::EOS).is_err() {
+            debug!("Failed to send EOS from HLS video stream. Channel closed.");
+        }
+    }
+    if let Some(audio) = &audio {
+        if audio.sender.send(PipelineEvent::EOS).is_err() {
+            debug!("Failed to send EOS from HLS audio stream. Channel closed.");
+        }
+    }
+}
+
+/// Opens the playlist. Downloads can be interrupted with `should_close`.
+fn open_input(
+    url: &str,
+    should_close: Arc<AtomicBool>,
+) -> Result<context::Input, ffmpeg_next::Error> {
+    let path = std::ffi::CString::new(url).unwrap();
+
+    unsafe {
+        let mut ctx = ffi::avformat_alloc_context();
+        (*ctx).interrupt_callback = ffmpeg_next::util::interrupt::new(Box::new(move || {
+            should_close.load(std::sync::atomic::Ordering::Relaxed)
+        }))
+        .interrupt;
+
+        let result =
+            ffi::avformat_open_input(&mut ctx, path.as_ptr(), ptr::null(), ptr::null_mut());
+        if result < 0 {
+            return Err(ffmpeg_next::Error::from(result));
+        }
+
+        let result = ffi::avformat_find_stream_info(ctx, ptr::null_mut());
+        if result < 0 {
+            ffi::avformat_close_input(&mut ctx);
+            return Err(ffmpeg_next::Error::from(result));
+        }
+
+        Ok(context::Input::wrap(ctx))
+    }
+}
+
+fn find_best_stream(
+    input: &context::Input,
+    media_type: Type,
+    related_stream: Option<usize>,
+) -> Option<usize> {
+    let index = unsafe {
+        ffi::av_find_best_stream(
+            input.as_ptr() as *mut _,
+            media_type.into(),
+            -1,
+            related_stream.map(|index| index as i32).unwrap_or(-1),
+            ptr::null_mut(),
+            0,
+        )
+    };
+    usize::try_from(index).ok()
+}
@@ -1,35 +1,53 @@
-use std::path::{Path, PathBuf};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 
 use bytes::Bytes;
 use compositor_render::InputId;
-use crossbeam_channel::Receiver;
 use tracing::error;
 
-use crate::{
-    pipeline::decoder::{AudioDecoderOptions, VideoDecoderOptions},
-    queue::PipelineEvent,
-};
+use crate::pipeline::decoder::{AudioDecoderOptions, VideoDecoderOptions};
 
-use mp4_file_reader::Mp4FileReader;
+use mp4_file_reader::{read_playback_control, Mp4FileReader};
+use playback::{Mp4PlaybackControl, Mp4SeekError};
 
 use super::{AudioInputReceiver, Input, InputInitInfo, InputInitResult, VideoInputReceiver};
 
+pub mod fragmented_receiver;
 pub mod mp4_file_reader;
+pub mod playback;
 
 #[derive(Debug, Clone)]
 pub struct Mp4Options {
     pub source: Source,
+    /// Restart playback from `start_at` after reaching the end. Timestamps keep
+    /// increasing across iterations.
+    pub should_loop: bool,
+    /// Playback starts at the last keyframe before this point.
+    pub start_at: Option<Duration>,
+    pub end_at: Option<Duration>,
 }
 
 pub(crate) enum Mp4ReaderOptions {
     NonFragmented {
         file: PathBuf,
+        playback: Arc<Mp4PlaybackControl>,
     },
-    #[allow(dead_code)]
-    Fragmented {
-        header: Bytes,
-        fragment_receiver: Receiver<PipelineEvent<Bytes>>,
-    },
+    /// Initialization segment (`ftyp` + `moov`), fragments are sent with
+    /// `Mp4FileReader::fragment_sender`.
+    Fragmented { header: Bytes },
+}
+
+/// Part of the file that is played. Both tracks use the same range to stay in sync.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Mp4PlaybackRange {
+    /// Aligned to a video keyframe.
+    pub start: Duration,
+    /// Not later than the end of the file.
+    pub end: Duration,
+    pub should_loop: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -51,6 +69,9 @@ pub enum Mp4Error {
 
     #[error("No suitable track in the mp4 file")]
     NoTrack,
+
+    #[error("Playback range is empty. `start_at` ({start_at:?}) has to be before the end of the file or `end_at` ({end:?}).")]
+    EmptyPlaybackRange { start_at: Duration, end: Duration },
 }
 
 pub struct Mp4 {
@@ -59,6 +80,7 @@ pub struct Mp4 {
     _audio_thread: Option<Mp4FileReader<AudioDecoderOptions>>,
     source: Source,
     path_to_file: PathBuf,
+    playback: Arc<Mp4PlaybackControl>,
 }
 
 impl Mp4 {
@@ -87,9 +109,17 @@ impl Mp4 {
             Source::File(ref path) => path.clone(),
         };
 
+        let playback = Arc::new(read_playback_control(
+            &input_path,
+            options.should_loop,
+            options.start_at,
+            options.end_at,
+        )?);
+
         let video = Mp4FileReader::new_video(
             Mp4ReaderOptions::NonFragmented {
                 file: input_path.clone(),
+                playback: playback.clone(),
             },
             input_id.clone(),
         )?;
@@ -108,6 +138,7 @@ impl Mp4 {
         let audio = Mp4FileReader::new_audio(
             Mp4ReaderOptions::NonFragmented {
                 file: input_path.clone(),
+                playback: playback.clone(),
             },
             input_id.clone(),
         )?;
@@ -130,6 +161,7 @@ impl Mp4 {
                 _audio_thread: audio_reader,
                 source: options.source,
                 path_to_file: input_path,
+                playback,
             }),
             video: video_receiver,
             audio: audio_receiver,
@@ -138,6 +170,22 @@ impl Mp4 {
     }
 }
 
+impl Mp4 {
+    /// Reader threads stop reading samples until the input is resumed.
+    pub fn pause(&self) {
+        self.playback.pause();
+    }
+
+    pub fn resume(&self) {
+        self.playback.resume();
+    }
+
+    /// Position in the file, playback continues from the last keyframe before it.
+    pub fn seek(&self, position: Duration) -> Result<(), Mp4SeekError> {
+        self.playback.seek(position)
+    }
+}
+
 impl Drop for Mp4 {
     fn drop(&mut self) {
         if let Source::Url(_) = self.source {
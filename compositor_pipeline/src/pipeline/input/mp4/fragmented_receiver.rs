@@ -0,0 +1,616 @@
+use std::{
+    io::Read,
+    net::{Ipv4Addr, TcpListener},
+    sync::{atomic::AtomicBool, Arc, OnceLock},
+    time::Duration,
+};
+
+use bytes::{Bytes, BytesMut};
+use compositor_render::InputId;
+use crossbeam_channel::{bounded, Receiver, Sender};
+use tracing::{debug, error, info, span, warn, Level};
+
+use crate::{
+    pipeline::{
+        decoder::{AudioDecoderOptions, VideoDecoderOptions},
+        types::EncodedChunk,
+        Port, VideoCodec,
+    },
+    queue::PipelineEvent,
+};
+
+use super::{
+    super::{AudioInputReceiver, Input, InputInitInfo, InputInitResult, VideoInputReceiver},
+    mp4_file_reader::Mp4FileReader,
+    Mp4ReaderOptions,
+};
+
+#[derive(Debug, Clone)]
+pub struct FragmentedMp4Options {
+    pub transport: FragmentedMp4Transport,
+    pub video: bool,
+    pub audio: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum FragmentedMp4Transport {
+    /// Data is pushed with `Pipeline::push_input_data`.
+    Http,
+    /// Data is read from a single TCP connection.
+    TcpServer { port: Port },
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum FragmentedMp4Error {
+    #[error("Failed to register input. Port: {0} is already used or not available.")]
+    PortAlreadyInUse(u16),
+
+    #[error("Error while binding the socket.")]
+    SocketBind(#[source] std::io::Error),
+}
+
+/// Boxes are buffered until they are received whole, so their size is limited.
+const MAX_MDAT_SIZE: u64 = 16 * 1024 * 1024;
+const MAX_BOX_SIZE: u64 = 1024 * 1024;
+
+#[derive(Debug, thiserror::Error)]
+enum Mp4BoxError {
+    #[error("Unsupported size {size} of the \"{}\" MP4 box.", String::from_utf8_lossy(.box_type))]
+    UnsupportedSize { box_type: [u8; 4], size: u64 },
+
+    #[error("The \"{}\" MP4 box is too large ({size} bytes, the limit is {max_size} bytes).", String::from_utf8_lossy(.box_type))]
+    TooLarge {
+        box_type: [u8; 4],
+        size: u64,
+        max_size: u64,
+    },
+}
+
+/// Receives a fragmented MP4 byte stream (`ftyp` + `moov` followed by `moof` + `mdat`
+/// pairs, e.g. CMAF). Data can be split into chunks at arbitrary positions.
+pub struct FragmentedMp4Receiver {
+    /// Only available for HTTP transport.
+    data_sender: Option<Sender<PipelineEvent<Bytes>>>,
+    should_close: Arc<AtomicBool>,
+}
+
+impl FragmentedMp4Receiver {
+    pub(in crate::pipeline::input) fn start_new_input(
+        input_id: &InputId,
+        opts: FragmentedMp4Options,
+    ) -> Result<InputInitResult, FragmentedMp4Error> {
+        let should_close = Arc::new(AtomicBool::new(false));
+        let (data_sender, data_receiver) = bounded(100);
+
+        let (data_sender, port) = match opts.transport {
+            FragmentedMp4Transport::Http => (Some(data_sender), None),
+            FragmentedMp4Transport::TcpServer { port } => {
+                let listener = match TcpListener::bind((Ipv4Addr::UNSPECIFIED, port.0)) {
+                    Ok(listener) => listener,
+                    Err(err) if err.kind() == std::io::ErrorKind::AddrInUse => {
+                        return Err(FragmentedMp4Error::PortAlreadyInUse(port.0))
+                    }
+                    Err(err) => return Err(FragmentedMp4Error::SocketBind(err)),
+                };
+                let should_close = should_close.clone();
+                let input_id = input_id.clone();
+                std::thread::Builder::new()
+                    .name(format!("fMP4 TCP server {}", input_id))
+                    .spawn(move || {
+                        let _span = span!(
+                            Level::INFO,
+                            "fMP4 TCP server",
+                            input_id = input_id.to_string()
+                        )
+                        .entered();
+                        run_tcp_server_thread(listener, data_sender, should_close);
+                        debug!("Closing fMP4 TCP server thread.");
+                    })
+                    .unwrap();
+                (None, Some(port))
+            }
+        };
+
+        let (video_sender, video_receiver) = match opts.video {
+            true => {
+                let (sender, receiver) = bounded(5);
+                (Some(sender), Some(receiver))
+            }
+            false => (None, None),
+        };
+        let (audio_sender, audio_receiver) = match opts.audio {
+            true => {
+                let (sender, receiver) = bounded(5);
+                let (options_sender, options_receiver) = bounded(1);
+                (
+                    Some((sender, options_sender)),
+                    Some((receiver, options_receiver)),
+                )
+            }
+            false => (None, None),
+        };
+
+        let input_id_clone = input_id.clone();
+        let should_close_clone = should_close.clone();
+        std::thread::Builder::new()
+            .name(format!("fMP4 demuxer {}", input_id))
+            .spawn(move || {
+                let _span = span!(
+                    Level::INFO,
+                    "fMP4 demuxer",
+                    input_id = input_id_clone.to_string()
+                )
+                .entered();
+                run_demuxer_thread(
+                    input_id_clone,
+                    data_receiver,
+                    video_sender,
+                    audio_sender,
+                    should_close_clone,
+                );
+                debug!("Closing fMP4 demuxer thread.");
+            })
+            .unwrap();
+
+        let video = video_receiver.map(|chunk_receiver| VideoInputReceiver::Encoded {
+            chunk_receiver,
+            decoder_options: VideoDecoderOptions {
+                codec: VideoCodec::H264,
+            },
+        });
+        // The AudioSpecificConfig is only known after the initialization segment is received.
+        let audio = audio_receiver.map(|(chunk_receiver, decoder_options_receiver)| {
+            AudioInputReceiver::EncodedWithDeferredOptions {
+                chunk_receiver,
+                decoder_options_receiver,
+            }
+        });
+
+        Ok(InputInitResult {
+            input: Input::FragmentedMp4(Self {
+                data_sender,
+                should_close,
+            }),
+            video,
+            audio,
+            init_info: InputInitInfo { port },
+        })
+    }
+
+    pub fn data_sender(&self) -> Option<&Sender<PipelineEvent<Bytes>>> {
+        self.data_sender.as_ref()
+    }
+}
+
+impl Drop for FragmentedMp4Receiver {
+    fn drop(&mut self) {
+        self.should_close
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+}
+
+/// Accepts a single connection and forwards everything it receives. Closing the
+/// connection ends the stream.
+fn run_tcp_server_thread(
+    listener: TcpListener,
+    data_sender: Sender<PipelineEvent<Bytes>>,
+    should_close: Arc<AtomicBool>,
+) {
+    // Non-blocking accept allows checking `should_close` while waiting for a connection.
+    if let Err(err) = listener.set_nonblocking(true) {
+        error!(%err, "Failed to configure TCP listener.");
+        return;
+    }
+    let mut socket = loop {
+        if should_close.load(std::sync::atomic::Ordering::Relaxed) {
+            return;
+        }
+        match listener.accept() {
+            Ok((socket, addr)) => {
+                info!(%addr, "fMP4 TCP client connected.");
+                break socket;
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {
+                std::thread::sleep(Duration::from_millis(50));
+            }
+            Err(err) => {
+                error!(%err, "Failed to accept TCP connection.");
+                return;
+            }
+        }
+    };
+    if let Err(err) = socket
+        .set_nonblocking(false)
+        .and_then(|_| socket.set_read_timeout(Some(Duration::from_millis(500))))
+    {
+        error!(%err, "Failed to configure TCP socket.");
+        return;
+    }
+
+    let mut buffer = vec![0u8; 65536];
+    loop {
+        if should_close.load(std::sync::atomic::Ordering::Relaxed) {
+            return;
+        }
+        match socket.read(&mut buffer) {
+            Ok(0) => {
+                info!("fMP4 TCP client disconnected.");
+                return;
+            }
+            Ok(len) => {
+                let data = Bytes::copy_from_slice(&buffer[..len]);
+                if data_sender.send(PipelineEvent::Data(data)).is_err() {
+                    debug!("Failed to send fMP4 data. Channel closed.");
+                    return;
+                }
+            }
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                ) => {}
+            Err(err) => {
+                warn!(%err, "Failed to read from TCP socket.");
+                return;
+            }
+        }
+    }
+}
+
+struct TrackReaders {
+    video: Option<Mp4FileReader<VideoDecoderOptions>>,
+    audio: Option<Mp4FileReader<AudioDecoderOptions>>,
+}
+
+impl TrackReaders {
+    fn fragment_senders(&self) -> impl Iterator<Item = Sender<PipelineEvent<Bytes>>> {
+        let video = self.video.as_ref().and_then(|r| r.fragment_sender());
+        let audio = self.audio.as_ref().and_then(|r| r.fragment_sender());
+        video.into_iter().chain(audio)
+    }
+}
+
+fn run_demuxer_thread(
+    input_id: InputId,
+    data_receiver: Receiver<PipelineEvent<Bytes>>,
+    mut video_sender: Option<Sender<PipelineEvent<EncodedChunk>>>,
+    mut audio_sender: Option<(
+        Sender<PipelineEvent<EncodedChunk>>,
+        Sender<AudioDecoderOptions>,
+    )>,
+    should_close: Arc<AtomicBool>,
+) {
+    let mut splitter = Mp4BoxSplitter::default();
+    let mut header = BytesMut::new();
+    let mut fragment = BytesMut::new();
+    let mut readers: Option<TrackReaders> = None;
+
+    // Timestamps are shifted, so the first sample of the input is at zero.
+    let start_pts = Arc::new(OnceLock::new());
+
+    'stream: for event in data_receiver {
+        // HTTP transport sends EOS when the request body ends, TCP transport closes
+        // the channel when the connection is closed.
+        let PipelineEvent::Data(data) = event else {
+            break;
+        };
+        if should_close.load(std::sync::atomic::Ordering::Relaxed) {
+            break;
+        }
+        splitter.push(&data);
+
+        loop {
+            let mp4_box = match splitter.next_box() {
+                Ok(Some(mp4_box)) => mp4_box,
+                Ok(None) => break,
+                Err(err) => {
+                    error!(%err, "Invalid fMP4 stream. Closing the input.");
+                    break 'stream;
+                }
+            };
+            let Some(track_readers) = &readers else {
+                if (header.len() + mp4_box.data.len()) as u64 > MAX_BOX_SIZE {
+                    error!("fMP4 initialization segment is too large. Closing the input.");
+                    break 'stream;
+                }
+                header.extend_from_slice(&mp4_box.data);
+                if &mp4_box.box_type == b"moov" {
+                    info!("Received fMP4 initialization segment.");
+                    let header = header.split().freeze();
+                    readers = Some(TrackReaders {
+                        video: video_sender.take().and_then(|sender| {
+                            start_video_reader(&input_id, header.clone(), sender, &start_pts)
+                        }),
+                        audio: audio_sender.take().and_then(|(sender, options_sender)| {
+                            start_audio_reader(
+                                &input_id,
+                                header.clone(),
+                                sender,
+                                options_sender,
+                                &start_pts,
+                            )
+                        }),
+                    });
+                }
+                continue;
+            };
+
+            match &mp4_box.box_type {
+                b"moof" => {
+                    fragment.clear();
+                    fragment.extend_from_slice(&mp4_box.data);
+                }
+                b"mdat" => {
+                    if fragment.is_empty() {
+                        warn!("Received mdat box without preceding moof box.");
+                        continue;
+                    }
+                    fragment.extend_from_slice(&mp4_box.data);
+                    let fragment = fragment.split().freeze();
+                    for sender in track_readers.fragment_senders() {
+                        if sender.send(PipelineEvent::Data(fragment.clone())).is_err() {
+                            debug!("Failed to send fMP4 fragment. Channel closed.");
+                        }
+                    }
+                }
+                // e.g. styp, sidx, emsg
+                _ => {}
+            }
+        }
+    }
+
+    info!("fMP4 stream finished.");
+    if let Some(readers) = &readers {
+        for sender in readers.fragment_senders() {
+            if sender.send(PipelineEvent::EOS).is_err() {
+                debug!("Failed to send EOS to MP4 reader. Channel closed.");
+            }
+        }
+    }
+    let audio_sender = audio_sender.as_ref().map(|(sender, _)| sender);
+    for sender in video_sender.iter().chain(audio_sender) {
+        if sender.send(PipelineEvent::EOS).is_err() {
+            debug!("Failed to send EOS from fMP4 input. Channel closed.");
+        }
+    }
+}
+
+fn start_video_reader(
+    input_id: &InputId,
+    header: Bytes,
+    sender: Sender<PipelineEvent<EncodedChunk>>,
+    start_pts: &Arc<OnceLock<Duration>>,
+) -> Option<Mp4FileReader<VideoDecoderOptions>> {
+    let result =
+        Mp4FileReader::new_video(Mp4ReaderOptions::Fragmented { header }, input_id.clone());
+    match result {
+        Ok(Some((reader, chunk_receiver))) => {
+            spawn_forwarder(input_id, chunk_receiver, sender, start_pts.clone());
+            Some(reader)
+        }
+        Ok(None) => {
+            warn!("No H264 track in the fMP4 stream.");
+            send_eos(&sender);
+            None
+        }
+        Err(err) => {
+            error!(%err, "Failed to read fMP4 initialization segment.");
+            send_eos(&sender);
+            None
+        }
+    }
+}
+
+fn start_audio_reader(
+    input_id: &InputId,
+    header: Bytes,
+    sender: Sender<PipelineEvent<EncodedChunk>>,
+    options_sender: Sender<AudioDecoderOptions>,
+    start_pts: &Arc<OnceLock<Duration>>,
+) -> Option<Mp4FileReader<AudioDecoderOptions>> {
+    let result =
+        Mp4FileReader::new_audio(Mp4ReaderOptions::Fragmented { header }, input_id.clone());
+    match result {
+        Ok(Some((reader, chunk_receiver))) => {
+            // Options with the AudioSpecificConfig from the `esds` box, the decoder
+            // starts when it receives them.
+            if options_sender.send(reader.decoder_options()).is_err() {
+                debug!("Failed to send AAC decoder options. Channel closed.");
+            }
+            spawn_forwarder(input_id, chunk_receiver, sender, start_pts.clone());
+            Some(reader)
+        }
+        Ok(None) => {
+            warn!("No AAC track in the fMP4 stream.");
+            send_eos(&sender);
+            None
+        }
+        Err(err) => {
+            error!(%err, "Failed to read fMP4 initialization segment.");
+            send_eos(&sender);
+            None
+        }
+    }
+}
+
+/// Passes chunks from the MP4 reader to the decoder, shifting timestamps, so the first
+/// sample of the input is at zero.
+fn spawn_forwarder(
+    input_id: &InputId,
+    chunk_receiver: Receiver<PipelineEvent<EncodedChunk>>,
+    sender: Sender<PipelineEvent<EncodedChunk>>,
+    start_pts: Arc<OnceLock<Duration>>,
+) {
+    std::thread::Builder::new()
+        .name(format!("fMP4 forwarder {}", input_id))
+        .spawn(move || {
+            for event in chunk_receiver {
+                let event = match event {
+                    PipelineEvent::Data(mut chunk) => {
+                        let start = *start_pts.get_or_init(|| chunk.dts.unwrap_or(chunk.pts));
+                        chunk.pts = chunk.pts.saturating_sub(start);
+                        chunk.dts = chunk.dts.map(|dts| dts.saturating_sub(start));
+                        PipelineEvent::Data(chunk)
+                    }
+                    PipelineEvent::EOS => PipelineEvent::EOS,
+                };
+                if sender.send(event).is_err() {
+                    debug!("Failed to send fMP4 chunk. Channel closed.");
+                    return;
+                }
+            }
+        })
+        .unwrap();
+}
+
+fn send_eos(sender: &Sender<PipelineEvent<EncodedChunk>>) {
+    if sender.send(PipelineEvent::EOS).is_err() {
+        debug!("Failed to send EOS from fMP4 input. Channel closed.");
+    }
+}
+
+struct Mp4Box {
+    box_type: [u8; 4],
+    /// Whole box including the header.
+    data: Bytes,
+}
+
+/// Splits a byte stream into top-level MP4 boxes.
+#[derive(Default)]
+struct Mp4BoxSplitter {
+    buffer: BytesMut,
+}
+
+impl Mp4BoxSplitter {
+    fn push(&mut self, data: &[u8]) {
+        self.buffer.extend_from_slice(data);
+    }
+
+    /// Returns `None` if the next box was not received whole yet. The stream can not be
+    /// split after an error, because the position of the next box is unknown.
+    fn next_box(&mut self) -> Result<Option<Mp4Box>, Mp4BoxError> {
+        if self.buffer.len() < 8 {
+            return Ok(None);
+        }
+        let box_type: [u8; 4] = self.buffer[4..8].try_into().unwrap();
+        let size = match u32::from_be_bytes(self.buffer[0..4].try_into().unwrap()) {
+            // 64-bit size follows the box type
+            1 => {
+                if self.buffer.len() < 16 {
+                    return Ok(None);
+                }
+                u64::from_be_bytes(self.buffer[8..16].try_into().unwrap())
+            }
+            size => size as u64,
+        };
+        if size < 8 {
+            // Size 0 (box extends to the end of the file) can not be handled in a stream.
+            return Err(Mp4BoxError::UnsupportedSize { box_type, size });
+        }
+        let max_size = match &box_type {
+            b"mdat" => MAX_MDAT_SIZE,
+            _ => MAX_BOX_SIZE,
+        };
+        if size > max_size {
+            return Err(Mp4BoxError::TooLarge {
+                box_type,
+                size,
+                max_size,
+            });
+        }
+        let size = size as usize;
+        if self.buffer.len() < size {
+            return Ok(None);
+        }
+
+        Ok(Some(Mp4Box {
+            box_type,
+            data: self.buffer.split_to(size).freeze(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mp4_box(box_type: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let size = (payload.len() + 8) as u32;
+        [size.to_be_bytes().as_slice(), box_type, payload].concat()
+    }
+
+    #[test]
+    fn split_boxes_from_chunked_stream() {
+        let stream = [
+            mp4_box(b"ftyp", &[1, 2, 3, 4]),
+            mp4_box(b"moof", &[5; 20]),
+            mp4_box(b"mdat", &[6; 100]),
+        ]
+        .concat();
+
+        let mut splitter = Mp4BoxSplitter::default();
+        let mut boxes = vec![];
+        for chunk in stream.chunks(7) {
+            splitter.push(chunk);
+            while let Some(mp4_box) = splitter.next_box().unwrap() {
+                boxes.push(mp4_box);
+            }
+        }
+
+        let box_types: Vec<_> = boxes.iter().map(|b| &b.box_type).collect();
+        assert_eq!(box_types, [b"ftyp", b"moof", b"mdat"]);
+        assert_eq!(boxes[2].data.len(), 108);
+        assert_eq!(
+            boxes.iter().map(|b| b.data.len()).sum::<usize>(),
+            stream.len()
+        );
+    }
+
+    #[test]
+    fn large_size_box() {
+        let mut data = vec![0, 0, 0, 1];
+        data.extend_from_slice(b"mdat");
+        data.extend_from_slice(&20u64.to_be_bytes());
+        data.extend_from_slice(&[7; 4]);
+
+        let mut splitter = Mp4BoxSplitter::default();
+        splitter.push(&data[..10]);
+        assert!(splitter.next_box().unwrap().is_none());
+        splitter.push(&data[10..]);
+        let mp4_box = splitter.next_box().unwrap().unwrap();
+        assert_eq!(&mp4_box.box_type, b"mdat");
+        assert_eq!(mp4_box.data.len(), 20);
+    }
+
+    #[test]
+    fn invalid_box_size_is_rejected() {
+        // 64-bit size of an mdat box larger than the limit.
+        let mut data = vec![0, 0, 0, 1];
+        data.extend_from_slice(b"mdat");
+        data.extend_from_slice(&(MAX_MDAT_SIZE + 1).to_be_bytes());
+        let mut splitter = Mp4BoxSplitter::default();
+        splitter.push(&data);
+        assert!(matches!(
+            splitter.next_box(),
+            Err(Mp4BoxError::TooLarge { box_type, .. }) if &box_type == b"mdat"
+        ));
+
+        // Boxes other than mdat have a lower limit.
+        let mut splitter = Mp4BoxSplitter::default();
+        splitter.push(&(MAX_BOX_SIZE as u32 + 1).to_be_bytes());
+        splitter.push(b"moov");
+        assert!(matches!(
+            splitter.next_box(),
+            Err(Mp4BoxError::TooLarge { .. })
+        ));
+
+        // Box that extends to the end of the file.
+        let mut splitter = Mp4BoxSplitter::default();
+        splitter.push(&[0, 0, 0, 0]);
+        splitter.push(b"mdat");
+        assert!(matches!(
+            splitter.next_box(),
+            Err(Mp4BoxError::UnsupportedSize { size: 0, .. })
+        ));
+    }
+}
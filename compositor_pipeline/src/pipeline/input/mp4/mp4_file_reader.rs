@@ -1,6 +1,7 @@
 use std::{
     io::{Read, Seek},
     os::unix::fs::MetadataExt,
+    path::Path,
     sync::{atomic::AtomicBool, Arc},
     time::Duration,
 };
@@ -20,7 +21,10 @@ use crate::{
     queue::PipelineEvent,
 };
 
-use super::{Mp4Error, Mp4ReaderOptions};
+use super::{
+    playback::{keyframe_before, Mp4PlaybackControl},
+    Mp4Error, Mp4PlaybackRange, Mp4ReaderOptions,
+};
 
 type ChunkReceiver = Receiver<PipelineEvent<EncodedChunk>>;
 
@@ -30,7 +34,18 @@ pub(crate) struct Mp4FileReader<DecoderOptions> {
     decoder_options: DecoderOptions,
 }
 
-struct TrackInfo<DecoderOptions, SampleUnpacker: FnMut(mp4::Mp4Sample) -> Bytes> {
+/// Where samples of the track are described.
+enum ReaderSource {
+    /// Sample table of the `moov` box, only the part within the playback range is played.
+    File(Arc<Mp4PlaybackControl>),
+    /// `moof` boxes received with `Mp4FileReader::fragment_sender`.
+    Fragments,
+}
+
+struct TrackInfo<
+    DecoderOptions,
+    SampleUnpacker: FnMut(mp4::Mp4Sample) -> Result<Bytes, std::io::Error>,
+> {
     sample_count: u32,
     timescale: u32,
     track_id: u32,
@@ -48,29 +63,26 @@ impl Mp4FileReader<AudioDecoderOptions> {
         let span = span!(Level::INFO, "MP4 audio", input_id = input_id.to_string());
 
         match options {
-            Mp4ReaderOptions::NonFragmented { file } => {
+            Mp4ReaderOptions::NonFragmented { file, playback } => {
                 let input_file = std::fs::File::open(file)?;
                 let size = input_file.metadata()?.size();
                 Self::new(
                     input_file,
                     size,
                     Self::find_aac_info,
-                    None,
+                    ReaderSource::File(playback),
                     stop_thread,
                     span,
                 )
             }
-            Mp4ReaderOptions::Fragmented {
-                header,
-                fragment_receiver,
-            } => {
+            Mp4ReaderOptions::Fragmented { header } => {
                 let size = header.len() as u64;
                 let reader = std::io::Cursor::new(header);
                 Self::new(
                     reader,
                     size,
                     Self::find_aac_info,
-                    Some(fragment_receiver),
+                    ReaderSource::Fragments,
                     stop_thread,
                     span,
                 )
@@ -80,7 +92,9 @@ impl Mp4FileReader<AudioDecoderOptions> {
 
     fn find_aac_info<Reader: Read + Seek + Send + 'static>(
         reader: &mp4::Mp4Reader<Reader>,
-    ) -> Option<TrackInfo<AudioDecoderOptions, impl FnMut(mp4::Mp4Sample) -> Bytes>> {
+    ) -> Option<
+        TrackInfo<AudioDecoderOptions, impl FnMut(mp4::Mp4Sample) -> Result<Bytes, std::io::Error>>,
+    > {
         let (&track_id, track, aac) = reader.tracks().iter().find_map(|(id, track)| {
             let track_type = track.track_type().ok()?;
             let media_type = track.media_type().ok()?;
@@ -112,7 +126,7 @@ impl Mp4FileReader<AudioDecoderOptions> {
             timescale: track.timescale(),
             track_id,
             decoder_options,
-            sample_unpacker: |sample| sample.bytes,
+            sample_unpacker: |sample| Ok(sample.bytes),
             chunk_kind: EncodedChunkKind::Audio(AudioCodec::Aac),
         })
     }
@@ -127,29 +141,26 @@ impl Mp4FileReader<VideoDecoderOptions> {
         let span = span!(Level::INFO, "MP4 video", input_id = input_id.to_string());
 
         match options {
-            Mp4ReaderOptions::NonFragmented { file } => {
+            Mp4ReaderOptions::NonFragmented { file, playback } => {
                 let input_file = std::fs::File::open(file)?;
                 let size = input_file.metadata()?.size();
                 Self::new(
                     input_file,
                     size,
                     Self::find_h264_info,
-                    None,
+                    ReaderSource::File(playback),
                     stop_thread,
                     span,
                 )
             }
-            Mp4ReaderOptions::Fragmented {
-                header,
-                fragment_receiver,
-            } => {
+            Mp4ReaderOptions::Fragmented { header } => {
                 let size = header.len() as u64;
                 let reader = std::io::Cursor::new(header);
                 Self::new(
                     reader,
                     size,
                     Self::find_h264_info,
-                    Some(fragment_receiver),
+                    ReaderSource::Fragments,
                     stop_thread,
                     span,
                 )
@@ -159,7 +170,9 @@ impl Mp4FileReader<VideoDecoderOptions> {
 
     fn find_h264_info<Reader: Read + Seek + Send + 'static>(
         reader: &mp4::Mp4Reader<Reader>,
-    ) -> Option<TrackInfo<VideoDecoderOptions, impl FnMut(mp4::Mp4Sample) -> Bytes>> {
+    ) -> Option<
+        TrackInfo<VideoDecoderOptions, impl FnMut(mp4::Mp4Sample) -> Result<Bytes, std::io::Error>>,
+    > {
         let (&track_id, track, avc) = reader.tracks().iter().find_map(|(id, track)| {
             let track_type = track.track_type().ok()?;
             let media_type = track.media_type().ok()?;
@@ -197,8 +210,8 @@ impl Mp4FileReader<VideoDecoderOptions> {
             let mut sample_data = sample.bytes.reader();
             let mut data: BytesMut = Default::default();
 
-            if let Some(first_nal) = sps_and_pps_payload.take() {
-                data.extend_from_slice(&first_nal);
+            if let Some(first_nal) = &sps_and_pps_payload {
+                data.extend_from_slice(first_nal);
             }
 
             // the mp4 sample contains one h264 access unit (possibly more than one NAL).
@@ -215,16 +228,22 @@ impl Mp4FileReader<VideoDecoderOptions> {
                     break;
                 }
 
-                let len = u32::from_be_bytes(len);
+                let len = u32::from_be_bytes(len) as usize;
+                // The sample can be truncated, check the length before allocating the buffer.
+                if len > sample_data.get_ref().remaining() {
+                    return Err(std::io::ErrorKind::UnexpectedEof.into());
+                }
 
-                let mut nalu = bytes::BytesMut::zeroed(len as usize);
-                sample_data.read_exact(&mut nalu).unwrap();
+                let mut nalu = bytes::BytesMut::zeroed(len);
+                sample_data.read_exact(&mut nalu)?;
 
                 data.extend_from_slice(&[0, 0, 0, 1]);
                 data.extend_from_slice(&nalu);
             }
 
-            data.freeze()
+            // Parameter sets are sent with the first sample that was read successfully.
+            sps_and_pps_payload = None;
+            Ok(data.freeze())
         };
 
         let decoder_options = VideoDecoderOptions {
@@ -240,24 +259,19 @@ impl Mp4FileReader<VideoDecoderOptions> {
             chunk_kind: EncodedChunkKind::Video(VideoCodec::H264),
         })
     }
-
-    #[allow(dead_code)]
-    pub(crate) fn fragment_sender(&self) -> Option<Sender<PipelineEvent<Bytes>>> {
-        self.fragment_sender.clone()
-    }
 }
 
 impl<DecoderOptions: Clone + Send + 'static> Mp4FileReader<DecoderOptions> {
     fn new<
         Reader: Read + Seek + Send + 'static,
-        SampleUnpacker: FnMut(mp4::Mp4Sample) -> Bytes + Send + 'static,
+        SampleUnpacker: FnMut(mp4::Mp4Sample) -> Result<Bytes, std::io::Error> + Send + 'static,
     >(
         reader: Reader,
         size: u64,
         track_info_reader: impl Fn(
             &mp4::Mp4Reader<Reader>,
         ) -> Option<TrackInfo<DecoderOptions, SampleUnpacker>>,
-        fragment_receiver: Option<Receiver<PipelineEvent<Bytes>>>,
+        source: ReaderSource,
         stop_thread: Arc<AtomicBool>,
         span: Span,
     ) -> Result<Option<(Self, ChunkReceiver)>, Mp4Error> {
@@ -268,6 +282,13 @@ impl<DecoderOptions: Clone + Send + 'static> Mp4FileReader<DecoderOptions> {
         };
 
         let (sender, receiver) = crossbeam_channel::bounded(10);
+        let (fragment_sender, fragment_receiver, playback) = match source {
+            ReaderSource::Fragments => {
+                let (sender, receiver) = crossbeam_channel::bounded(10);
+                (Some(sender), Some(receiver), None)
+            }
+            ReaderSource::File(playback) => (None, None, Some(playback)),
+        };
 
         let stop_thread_clone = stop_thread.clone();
         let decoder_options = track_info.decoder_options.clone();
@@ -280,6 +301,7 @@ impl<DecoderOptions: Clone + Send + 'static> Mp4FileReader<DecoderOptions> {
                     sender,
                     stop_thread_clone,
                     fragment_receiver,
+                    playback,
                     track_info,
                 );
                 debug!("Closing MP4 reader thread");
@@ -289,7 +311,7 @@ impl<DecoderOptions: Clone + Send + 'static> Mp4FileReader<DecoderOptions> {
         Ok(Some((
             Mp4FileReader {
                 stop_thread,
-                fragment_sender: None,
+                fragment_sender,
                 decoder_options,
             },
             receiver,
@@ -299,6 +321,12 @@ impl<DecoderOptions: Clone + Send + 'static> Mp4FileReader<DecoderOptions> {
     pub(crate) fn decoder_options(&self) -> DecoderOptions {
         self.decoder_options.clone()
     }
+
+    /// Sender for `moof` + `mdat` pairs. Only available for readers created
+    /// with `Mp4ReaderOptions::Fragmented`.
+    pub(crate) fn fragment_sender(&self) -> Option<Sender<PipelineEvent<Bytes>>> {
+        self.fragment_sender.clone()
+    }
 }
 
 impl<D> Drop for Mp4FileReader<D> {
@@ -312,48 +340,350 @@ fn run_reader_thread<Reader: Read + Seek, DecoderOptions>(
     mut reader: Mp4Reader<Reader>,
     sender: Sender<PipelineEvent<EncodedChunk>>,
     stop_thread: Arc<AtomicBool>,
-    _fragment_receiver: Option<Receiver<PipelineEvent<Bytes>>>,
-    track_info: TrackInfo<DecoderOptions, impl FnMut(mp4::Mp4Sample) -> Bytes>,
+    fragment_receiver: Option<Receiver<PipelineEvent<Bytes>>>,
+    playback: Option<Arc<Mp4PlaybackControl>>,
+    track_info: TrackInfo<
+        DecoderOptions,
+        impl FnMut(mp4::Mp4Sample) -> Result<Bytes, std::io::Error>,
+    >,
 ) {
+    let timescale = track_info.timescale;
     let mut sample_unpacker = track_info.sample_unpacker;
+    // Timestamps are shifted by `offset` and relative to `start`.
+    let mut send_sample = |sample: mp4::Mp4Sample, start: Duration, offset: Duration| {
+        let (pts, dts) = sample_timestamps(&sample, timescale);
+        let data = match sample_unpacker(sample) {
+            Ok(data) => data,
+            Err(err) => {
+                warn!(%err, "Failed to read MP4 sample. Dropping.");
+                return true;
+            }
+        };
+        let chunk = EncodedChunk {
+            data,
+            pts: pts.saturating_sub(start) + offset,
+            dts: Some(dts.saturating_sub(start) + offset),
+            discontinuity: false,
+            kind: track_info.chunk_kind,
+        };
 
-    for i in 1..track_info.sample_count {
-        if stop_thread.load(std::sync::atomic::Ordering::Relaxed) {
+        trace!(pts=?chunk.pts, "MP4 reader produced a chunk.");
+        match sender.send(PipelineEvent::Data(chunk)) {
+            Ok(_) => true,
+            Err(_) => {
+                debug!("Failed to send MP4 chunk. Channel closed.");
+                false
+            }
+        }
+    };
+
+    if let Some(control) = playback {
+        let range = control.range();
+        let Some(mut segment) = control.wait_for_playback(&stop_thread) else {
             return;
+        };
+        let mut sample_id = first_sample_at(&reader, track_info.track_id, segment.start);
+        let mut sent_in_iteration = false;
+
+        loop {
+            if stop_thread.load(std::sync::atomic::Ordering::Relaxed) {
+                return;
+            }
+            // Blocks while paused.
+            let Some(current_segment) = control.wait_for_playback(&stop_thread) else {
+                return;
+            };
+            if current_segment.generation != segment.generation {
+                segment = current_segment;
+                sample_id = first_sample_at(&reader, track_info.track_id, segment.start);
+                sent_in_iteration = false;
+            }
+
+            // Sample ids start at 1.
+            let mut end_of_range = sample_id > track_info.sample_count;
+            if !end_of_range {
+                match reader.read_sample(track_info.track_id, sample_id) {
+                    Ok(Some(sample)) => {
+                        let (pts, _) = sample_timestamps(&sample, timescale);
+                        end_of_range = pts >= range.end;
+                        // Audio samples that started before the keyframe the segment
+                        // is aligned to are skipped.
+                        if !end_of_range && pts >= segment.start {
+                            let duration =
+                                Duration::from_secs_f64(sample.duration as f64 / timescale as f64);
+                            if !send_sample(sample, segment.start, segment.offset) {
+                                return;
+                            }
+                            control.on_sample_sent(pts - segment.start + segment.offset + duration);
+                            sent_in_iteration = true;
+                        }
+                    }
+                    Err(e) => {
+                        warn!("Error while reading MP4 video sample: {:?}", e);
+                    }
+                    _ => {}
+                }
+                sample_id += 1;
+            }
+            if !end_of_range {
+                continue;
+            }
+
+            if !range.should_loop || !sent_in_iteration {
+                break;
+            }
+            // Next iteration starts exactly where the range ends, so timestamps stay
+            // monotonic and in sync between tracks even if one of them is shorter.
+            segment.offset += range.end - segment.start;
+            segment.start = range.start;
+            sample_id = first_sample_at(&reader, track_info.track_id, range.start);
+            sent_in_iteration = false;
         }
+    }
 
-        match reader.read_sample(track_info.track_id, i) {
-            Ok(Some(sample)) => {
-                let rendering_offset = sample.rendering_offset;
-                let start_time = sample.start_time;
-                let data = sample_unpacker(sample);
-
-                let dts = Duration::from_secs_f64(start_time as f64 / track_info.timescale as f64);
-                let chunk = EncodedChunk {
-                    data,
-                    pts: Duration::from_secs_f64(
-                        (start_time as f64 + rendering_offset as f64) / track_info.timescale as f64,
-                    ),
-                    dts: Some(dts),
-                    kind: track_info.chunk_kind,
-                };
-
-                trace!(pts=?chunk.pts, "MP4 reader produced a chunk.");
-                match sender.send(PipelineEvent::Data(chunk)) {
-                    Ok(_) => {}
-                    Err(_) => {
-                        debug!("Failed to send MP4 chunk. Channel closed.");
+    // Samples of a fragmented file are described by `moof` boxes, each fragment is parsed
+    // using tracks from the initial `moov` box.
+    for event in fragment_receiver.into_iter().flatten() {
+        let PipelineEvent::Data(fragment) = event else {
+            break;
+        };
+        let size = fragment.len() as u64;
+        let mut fragment_reader =
+            match reader.read_fragment_header(std::io::Cursor::new(fragment), size) {
+                Ok(fragment_reader) => fragment_reader,
+                Err(e) => {
+                    warn!("Error while reading MP4 fragment: {:?}", e);
+                    continue;
+                }
+            };
+        let sample_count = fragment_reader
+            .tracks()
+            .get(&track_info.track_id)
+            .map(|track| track.sample_count())
+            .unwrap_or(0);
+
+        for i in 1..=sample_count {
+            if stop_thread.load(std::sync::atomic::Ordering::Relaxed) {
+                return;
+            }
+
+            match fragment_reader.read_sample(track_info.track_id, i) {
+                Ok(Some(sample)) => {
+                    if !send_sample(sample, Duration::ZERO, Duration::ZERO) {
                         return;
                     }
                 }
+                Err(e) => {
+                    warn!("Error while reading MP4 fragment sample: {:?}", e);
+                }
+                _ => {}
             }
-            Err(e) => {
-                warn!("Error while reading MP4 video sample: {:?}", e);
-            }
-            _ => {}
         }
     }
+
     if let Err(_err) = sender.send(PipelineEvent::EOS) {
         debug!("Failed to send EOS from MP4 video reader. Channel closed.");
     }
 }
+
+/// Returns presentation and decode timestamps of the sample.
+fn sample_timestamps(sample: &mp4::Mp4Sample, timescale: u32) -> (Duration, Duration) {
+    let dts = Duration::from_secs_f64(sample.start_time as f64 / timescale as f64);
+    let pts = Duration::from_secs_f64(
+        (sample.start_time as f64 + sample.rendering_offset as f64) / timescale as f64,
+    );
+    (pts, dts)
+}
+
+/// Resolves the part of the file that should be played. Video can only be decoded
+/// from a keyframe, so the start is moved to the last keyframe before `start_at`.
+pub(super) fn read_playback_control(
+    file: &Path,
+    should_loop: bool,
+    start_at: Option<Duration>,
+    end_at: Option<Duration>,
+) -> Result<Mp4PlaybackControl, Mp4Error> {
+    let input_file = std::fs::File::open(file)?;
+    let size = input_file.metadata()?.size();
+    let reader = mp4::Mp4Reader::read_header(input_file, size)?;
+
+    let duration = reader.duration();
+    let end = end_at.map_or(duration, |end_at| end_at.min(duration));
+    let start_at = start_at.unwrap_or(Duration::ZERO);
+
+    let keyframes = Mp4FileReader::<VideoDecoderOptions>::find_h264_info(&reader)
+        .and_then(|info| reader.tracks().get(&info.track_id))
+        .map(keyframe_times)
+        .unwrap_or_default();
+    let start = keyframe_before(&keyframes, start_at);
+
+    if start >= end {
+        return Err(Mp4Error::EmptyPlaybackRange { start_at, end });
+    }
+
+    let range = Mp4PlaybackRange {
+        start,
+        end,
+        should_loop,
+    };
+    Ok(Mp4PlaybackControl::new(range, keyframes))
+}
+
+fn first_sample_at<Reader>(reader: &Mp4Reader<Reader>, track_id: u32, position: Duration) -> u32 {
+    let Some(track) = reader.tracks().get(&track_id) else {
+        return 1;
+    };
+    let (stts, sync_samples) = sample_table(track);
+    let position = (position.as_secs_f64() * track.timescale() as f64).round() as u64;
+    find_sync_sample(&stts, sync_samples, position)
+}
+
+/// Decode times of all sync samples of the track.
+fn keyframe_times(track: &mp4::Mp4Track) -> Vec<Duration> {
+    let timescale = track.timescale() as f64;
+    let (stts, sync_samples) = sample_table(track);
+    decode_times(&stts)
+        .enumerate()
+        .filter(|(index, _)| is_sync_sample(sync_samples, *index as u32 + 1))
+        .map(|(_, decode_time)| Duration::from_secs_f64(decode_time as f64 / timescale))
+        .collect()
+}
+
+/// Returns `stts` entries (sample count, sample delta) and `stss` entries of the track.
+fn sample_table(track: &mp4::Mp4Track) -> (Vec<(u32, u32)>, Option<&[u32]>) {
+    let stbl = &track.trak.mdia.minf.stbl;
+    let stts = stbl
+        .stts
+        .entries
+        .iter()
+        .map(|entry| (entry.sample_count, entry.sample_delta))
+        .collect();
+    let sync_samples = stbl.stss.as_ref().map(|stss| stss.entries.as_slice());
+    (stts, sync_samples)
+}
+
+/// Looks up id of the last sync sample starting at or before `time` (in track timescale).
+fn find_sync_sample(stts: &[(u32, u32)], sync_samples: Option<&[u32]>, time: u64) -> u32 {
+    let mut result = 1;
+    for (index, decode_time) in decode_times(stts).enumerate() {
+        if decode_time > time {
+            break;
+        }
+        let sample_id = index as u32 + 1;
+        if is_sync_sample(sync_samples, sample_id) {
+            result = sample_id;
+        }
+    }
+    result
+}
+
+fn decode_times(stts: &[(u32, u32)]) -> impl Iterator<Item = u64> + '_ {
+    stts.iter()
+        .flat_map(|&(count, delta)| std::iter::repeat(delta as u64).take(count as usize))
+        .scan(0u64, |next_start, delta| {
+            let start = *next_start;
+            *next_start += delta;
+            Some(start)
+        })
+}
+
+/// If `stss` box is missing, every sample is a sync sample.
+fn is_sync_sample(sync_samples: Option<&[u32]>, sample_id: u32) -> bool {
+    sync_samples.map_or(true, |samples| samples.binary_search(&sample_id).is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::Path, sync::Arc, time::Duration};
+
+    use bytes::Bytes;
+    use compositor_render::InputId;
+
+    use crate::{
+        pipeline::input::mp4::{playback::Mp4PlaybackControl, Mp4PlaybackRange, Mp4ReaderOptions},
+        queue::PipelineEvent,
+    };
+
+    use super::{find_sync_sample, Mp4FileReader};
+
+    /// Writes an audio only file, data of each sample is its index.
+    fn write_aac_file(path: &Path, sample_count: u32) {
+        let file = std::fs::File::create(path).unwrap();
+        let config = mp4::Mp4Config {
+            major_brand: (*b"isom").into(),
+            minor_version: 512,
+            compatible_brands: vec![(*b"isom").into()],
+            timescale: 1000,
+        };
+        let mut writer = mp4::Mp4Writer::write_start(file, &config).unwrap();
+        writer
+            .add_track(&mp4::TrackConfig {
+                track_type: mp4::TrackType::Audio,
+                timescale: 48_000,
+                language: String::from("und"),
+                media_conf: mp4::MediaConfig::AacConfig(mp4::AacConfig::default()),
+            })
+            .unwrap();
+        for index in 0..sample_count {
+            let sample = mp4::Mp4Sample {
+                start_time: index as u64 * 1024,
+                duration: 1024,
+                rendering_offset: 0,
+                is_sync: true,
+                bytes: Bytes::from(vec![index as u8]),
+            };
+            writer.write_sample(1, &sample).unwrap();
+        }
+        writer.write_end().unwrap();
+    }
+
+    #[test]
+    fn looped_playback_reads_every_sample() {
+        let path = std::env::temp_dir().join(format!("mp4_reader_loop_{}.mp4", std::process::id()));
+        write_aac_file(&path, 5);
+
+        let range = Mp4PlaybackRange {
+            start: Duration::ZERO,
+            end: Duration::from_secs_f64(5.0 * 1024.0 / 48_000.0),
+            should_loop: true,
+        };
+        let playback = Arc::new(Mp4PlaybackControl::new(range, Vec::new()));
+        let options = Mp4ReaderOptions::NonFragmented {
+            file: path.clone(),
+            playback,
+        };
+        let (reader, receiver) = Mp4FileReader::new_audio(options, InputId(Arc::from("input")))
+            .unwrap()
+            .unwrap();
+
+        let samples: Vec<u8> = receiver
+            .iter()
+            .take(10)
+            .map(|event| match event {
+                PipelineEvent::Data(chunk) => chunk.data[0],
+                PipelineEvent::EOS => panic!("Unexpected EOS"),
+            })
+            .collect();
+        drop(reader);
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(samples, [0, 1, 2, 3, 4, 0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn sync_sample_lookup() {
+        // 10 samples with duration 100, then 5 samples with duration 200.
+        let stts = [(10, 100), (5, 200)];
+        let keyframes = [1, 6, 12];
+
+        assert_eq!(find_sync_sample(&stts, Some(&keyframes), 0), 1);
+        assert_eq!(find_sync_sample(&stts, Some(&keyframes), 499), 1);
+        assert_eq!(find_sync_sample(&stts, Some(&keyframes), 500), 6);
+        assert_eq!(find_sync_sample(&stts, Some(&keyframes), 1250), 12);
+        assert_eq!(find_sync_sample(&stts, Some(&keyframes), 10_000), 12);
+
+        // Without `stss` every sample is a sync sample.
+        assert_eq!(find_sync_sample(&stts, None, 350), 4);
+        assert_eq!(find_sync_sample(&stts, None, 1450), 13);
+    }
+}
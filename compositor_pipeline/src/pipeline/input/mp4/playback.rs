@@ -0,0 +1,186 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Condvar, Mutex,
+    },
+    time::Duration,
+};
+
+use super::Mp4PlaybackRange;
+
+/// Playback state shared by video and audio reader threads of a single MP4 input.
+/// Both readers map file positions to output timestamps using the same segment,
+/// so they stay in sync after seeks.
+#[derive(Debug)]
+pub(crate) struct Mp4PlaybackControl {
+    range: Mp4PlaybackRange,
+    /// Decode times of video keyframes in ascending order. Empty if the file
+    /// does not have a video track.
+    keyframes: Vec<Duration>,
+    state: Mutex<PlaybackState>,
+    state_changed: Condvar,
+}
+
+#[derive(Debug)]
+struct PlaybackState {
+    paused: bool,
+    segment: PlaybackSegment,
+    /// End of the latest sample sent by any of the readers (in output timestamps).
+    next_pts: Duration,
+}
+
+/// Continuous part of the playback. A new segment starts on every seek.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct PlaybackSegment {
+    /// Incremented on every seek.
+    pub generation: u64,
+    /// Position in the file where the segment starts, aligned to a keyframe.
+    pub start: Duration,
+    /// Output timestamp of `start`.
+    pub offset: Duration,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Mp4SeekError {
+    #[error("Seek position {position:?} is outside of the played range ({start:?} - {end:?}).")]
+    OutOfRange {
+        position: Duration,
+        start: Duration,
+        end: Duration,
+    },
+}
+
+impl Mp4PlaybackControl {
+    pub(super) fn new(range: Mp4PlaybackRange, keyframes: Vec<Duration>) -> Self {
+        Self {
+            range,
+            keyframes,
+            state: Mutex::new(PlaybackState {
+                paused: false,
+                segment: PlaybackSegment {
+                    generation: 0,
+                    start: range.start,
+                    offset: Duration::ZERO,
+                },
+                next_pts: Duration::ZERO,
+            }),
+            state_changed: Condvar::new(),
+        }
+    }
+
+    pub(super) fn range(&self) -> Mp4PlaybackRange {
+        self.range
+    }
+
+    pub(crate) fn pause(&self) {
+        self.state.lock().unwrap().paused = true;
+    }
+
+    pub(crate) fn resume(&self) {
+        self.state.lock().unwrap().paused = false;
+        self.state_changed.notify_all();
+    }
+
+    /// Playback continues from the last keyframe before `position`. Samples that
+    /// were already sent to the decoder are played before the seek takes effect.
+    pub(crate) fn seek(&self, position: Duration) -> Result<(), Mp4SeekError> {
+        if position < self.range.start || position >= self.range.end {
+            return Err(Mp4SeekError::OutOfRange {
+                position,
+                start: self.range.start,
+                end: self.range.end,
+            });
+        }
+
+        let start = keyframe_before(&self.keyframes, position).max(self.range.start);
+        let mut state = self.state.lock().unwrap();
+        state.segment = PlaybackSegment {
+            generation: state.segment.generation + 1,
+            start,
+            // Timestamps have to be monotonic, so the new segment starts after
+            // everything that was already sent by both readers.
+            offset: state.next_pts,
+        };
+        self.state_changed.notify_all();
+        Ok(())
+    }
+
+    /// Blocks while the playback is paused. Returns current segment or `None` if
+    /// `stop_thread` was set in the meantime.
+    pub(super) fn wait_for_playback(&self, stop_thread: &AtomicBool) -> Option<PlaybackSegment> {
+        let mut state = self.state.lock().unwrap();
+        while state.paused {
+            if stop_thread.load(Ordering::Relaxed) {
+                return None;
+            }
+            state = self
+                .state_changed
+                .wait_timeout(state, Duration::from_millis(100))
+                .unwrap()
+                .0;
+        }
+        Some(state.segment)
+    }
+
+    pub(super) fn on_sample_sent(&self, end_pts: Duration) {
+        let mut state = self.state.lock().unwrap();
+        state.next_pts = state.next_pts.max(end_pts);
+    }
+}
+
+/// Returns the last keyframe at or before `position`. If there are no keyframes
+/// (e.g. audio only file), any position can be used.
+pub(super) fn keyframe_before(keyframes: &[Duration], position: Duration) -> Duration {
+    if keyframes.is_empty() {
+        return position;
+    }
+    match keyframes.binary_search(&position) {
+        Ok(index) => keyframes[index],
+        Err(0) => keyframes[0],
+        Err(index) => keyframes[index - 1],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::pipeline::input::mp4::Mp4PlaybackRange;
+
+    use super::{keyframe_before, Mp4PlaybackControl};
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn seek_to_preceding_keyframe() {
+        let keyframes = [secs(0), secs(2), secs(4)];
+        assert_eq!(keyframe_before(&keyframes, secs(0)), secs(0));
+        assert_eq!(keyframe_before(&keyframes, secs(3)), secs(2));
+        assert_eq!(keyframe_before(&keyframes, secs(4)), secs(4));
+        assert_eq!(keyframe_before(&keyframes, secs(10)), secs(4));
+        assert_eq!(keyframe_before(&[], secs(3)), secs(3));
+    }
+
+    #[test]
+    fn seek_continues_after_sent_samples() {
+        let range = Mp4PlaybackRange {
+            start: secs(1),
+            end: secs(10),
+            should_loop: false,
+        };
+        let control = Mp4PlaybackControl::new(range, vec![secs(0), secs(2), secs(4), secs(6)]);
+        control.on_sample_sent(secs(3));
+        control.on_sample_sent(Duration::from_millis(2500));
+
+        assert!(control.seek(secs(0)).is_err());
+        assert!(control.seek(secs(10)).is_err());
+        control.seek(secs(5)).unwrap();
+
+        let segment = control.state.lock().unwrap().segment;
+        assert_eq!(segment.generation, 1);
+        assert_eq!(segment.start, secs(4));
+        assert_eq!(segment.offset, secs(3));
+    }
+}
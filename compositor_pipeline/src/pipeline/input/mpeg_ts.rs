@@ -0,0 +1,434 @@
+use std::{
+    net::{IpAddr, Ipv4Addr, UdpSocket},
+    sync::{atomic::AtomicBool, Arc},
+    time::Duration,
+};
+
+use bytes::Bytes;
+use compositor_render::InputId;
+use crossbeam_channel::{bounded, Sender};
+use ffmpeg_next::{codec::Id, format::context, media::Type, Dictionary, Packet, Rational};
+use tracing::{debug, error, info, span, trace, warn, Level};
+
+use crate::{
+    pipeline::{
+        decoder::{
+            AacDecoderOptions, AudioDecoderOptions, OpusDecoderOptions, VideoDecoderOptions,
+        },
+        mpeg_ts,
+        rtp::MulticastInterface,
+        types::{EncodedChunk, EncodedChunkKind},
+        AudioCodec, Port, VideoCodec,
+    },
+    queue::PipelineEvent,
+};
+
+use super::{
+    ffmpeg_utils::{extradata, is_adts, to_duration, AdtsHeader, AvccToAnnexB},
+    rtp::MulticastGroup,
+    AudioInputReceiver, Input, InputInitInfo, InputInitResult, VideoInputReceiver,
+};
+
+const MIN_REOPEN_DELAY: Duration = Duration::from_millis(500);
+const MAX_REOPEN_DELAY: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone)]
+pub struct MpegTsReceiverOptions {
+    pub port: Port,
+    /// Multicast group joined to receive the stream. Unicast packets sent to
+    /// the port are received if not specified.
+    pub multicast: Option<MulticastGroup>,
+    /// H264 video is expected if `true`.
+    pub video: bool,
+    /// AAC or Opus.
+    pub audio: Option<AudioCodec>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum MpegTsReceiverError {
+    #[error("Failed to register input. Port: {0} is already used or not available.")]
+    PortAlreadyInUse(u16),
+
+    #[error("Error while binding the socket.")]
+    SocketBind(#[source] std::io::Error),
+}
+
+/// Input that receives MPEG-TS with H264 video and AAC or Opus audio sent in UDP
+/// datagrams (usually 7 TS packets per datagram), e.g. from broadcast equipment or
+/// `ffmpeg -f mpegts udp://...`. The stream is demuxed by FFmpeg.
+pub struct MpegTsReceiver {
+    should_close: Arc<AtomicBool>,
+}
+
+impl MpegTsReceiver {
+    pub(super) fn start_new_input(
+        input_id: &InputId,
+        opts: MpegTsReceiverOptions,
+    ) -> Result<InputInitResult, MpegTsReceiverError> {
+        // Multicast receivers share the port, so it can not be checked in advance.
+        if opts.multicast.is_none() {
+            UdpSocket::bind((Ipv4Addr::UNSPECIFIED, opts.port.0)).map_err(|err| {
+                match err.kind() {
+                    std::io::ErrorKind::AddrInUse => {
+                        MpegTsReceiverError::PortAlreadyInUse(opts.port.0)
+                    }
+                    _ => MpegTsReceiverError::SocketBind(err),
+                }
+            })?;
+        }
+
+        let should_close = Arc::new(AtomicBool::new(false));
+        let (senders, video, audio) = MpegTsChunkSenders::new(opts.video, opts.audio);
+
+        let input_id_clone = input_id.clone();
+        let port = opts.port;
+        let should_close_clone = should_close.clone();
+        std::thread::Builder::new()
+            .name(format!("MPEG-TS receiver {}", input_id))
+            .spawn(move || {
+                let _span = span!(
+                    Level::INFO,
+                    "MPEG-TS receiver",
+                    input_id = input_id_clone.to_string()
+                )
+                .entered();
+                run_udp_receiver_thread(&opts, &should_close_clone, senders);
+                debug!("Closing MPEG-TS receiver thread.");
+            })
+            .unwrap();
+
+        Ok(InputInitResult {
+            input: Input::MpegTs(Self { should_close }),
+            video,
+            audio,
+            init_info: InputInitInfo { port: Some(port) },
+        })
+    }
+}
+
+impl Drop for MpegTsReceiver {
+    fn drop(&mut self) {
+        self.should_close
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+}
+
+fn udp_url(opts: &MpegTsReceiverOptions) -> String {
+    match opts.multicast.as_ref().map(|multicast| multicast.group) {
+        Some(IpAddr::V4(group)) => format!("udp://{}:{}", group, opts.port.0),
+        Some(IpAddr::V6(group)) => format!("udp://[{}]:{}", group, opts.port.0),
+        None => format!("udp://0.0.0.0:{}", opts.port.0),
+    }
+}
+
+/// Options of the FFmpeg `udp` protocol.
+fn ffmpeg_options(multicast: &Option<MulticastGroup>) -> Dictionary<'static> {
+    let mut options = Dictionary::new();
+    // Datagrams are read by a separate FFmpeg thread into a FIFO, so short stalls
+    // of the demuxer do not lose data.
+    options.set("fifo_size", "50000");
+    options.set("overrun_nonfatal", "1");
+
+    if let Some(multicast) = multicast {
+        options.set("reuse", "1");
+        if let Some(source) = multicast.source {
+            options.set("sources", &source.to_string());
+        }
+        // FFmpeg selects the interface only by its address, so interface
+        // indexes of IPv6 groups are not supported.
+        if let Some(MulticastInterface::V4(address)) = multicast.interface {
+            options.set("localaddr", &address.to_string());
+        }
+    }
+    options
+}
+
+fn run_udp_receiver_thread(
+    opts: &MpegTsReceiverOptions,
+    should_close: &Arc<AtomicBool>,
+    senders: MpegTsChunkSenders,
+) {
+    let url = udp_url(opts);
+    // UDP stream has no start, so opening fails only if the first packets can not be
+    // probed. In that case the input is opened again.
+    let mut reopen_delay = MIN_REOPEN_DELAY;
+    let input = loop {
+        match mpeg_ts::open_input(&url, ffmpeg_options(&opts.multicast), should_close.clone()) {
+            Ok(input) => break input,
+            Err(_) if should_close.load(std::sync::atomic::Ordering::Relaxed) => {
+                senders.send_eos();
+                return;
+            }
+            Err(err) => {
+                warn!(%err, "Failed to open MPEG-TS stream. Retrying in {reopen_delay:?}.")
+            }
+        }
+        std::thread::sleep(reopen_delay);
+        reopen_delay = Duration::min(reopen_delay * 2, MAX_REOPEN_DELAY);
+    };
+    info!("Receiving MPEG-TS stream on {url}.");
+
+    demux(input, &senders, should_close);
+    senders.send_eos();
+}
+
+/// Channels of the elementary streams that are forwarded from an MPEG-TS input
+/// to the decoders.
+pub(super) struct MpegTsChunkSenders {
+    video: Option<Sender<PipelineEvent<EncodedChunk>>>,
+    audio: Option<(AudioCodec, Sender<PipelineEvent<EncodedChunk>>)>,
+}
+
+impl MpegTsChunkSenders {
+    /// H264 video is expected if `video` is `true`.
+    pub(super) fn new(
+        video: bool,
+        audio: Option<AudioCodec>,
+    ) -> (Self, Option<VideoInputReceiver>, Option<AudioInputReceiver>) {
+        let (video_sender, video_receiver) = match video {
+            true => {
+                let (sender, receiver) = bounded(5);
+                (Some(sender), Some(receiver))
+            }
+            false => (None, None),
+        };
+        let (audio_sender, audio_receiver) = match audio {
+            Some(codec) => {
+                let (sender, receiver) = bounded(5);
+                (Some((codec, sender)), Some((codec, receiver)))
+            }
+            None => (None, None),
+        };
+
+        let video = video_receiver.map(|chunk_receiver| VideoInputReceiver::Encoded {
+            chunk_receiver,
+            decoder_options: VideoDecoderOptions {
+                codec: VideoCodec::H264,
+            },
+        });
+        // AAC frames are sent in the ADTS format. The AudioSpecificConfig is not known before
+        // the stream starts, so the decoder builds it from the first ADTS header.
+        let audio = audio_receiver.map(|(codec, chunk_receiver)| {
+            let decoder_options = match codec {
+                AudioCodec::Aac => AudioDecoderOptions::Aac(AacDecoderOptions {
+                    depayloader_mode: None,
+                    asc: None,
+                }),
+                AudioCodec::Opus => AudioDecoderOptions::Opus(OpusDecoderOptions {
+                    forward_error_correction: false,
+                }),
+            };
+            AudioInputReceiver::Encoded {
+                chunk_receiver,
+                decoder_options,
+            }
+        });
+
+        let senders = Self {
+            video: video_sender,
+            audio: audio_sender,
+        };
+        (senders, video, audio)
+    }
+
+    pub(super) fn send_eos(&self) {
+        if let Some(sender) = &self.video {
+            if sender.send(PipelineEvent::EOS).is_err() {
+                debug!("Failed to send EOS from MPEG-TS video stream. Channel closed.");
+            }
+        }
+        if let Some((_, sender)) = &self.audio {
+            if sender.send(PipelineEvent::EOS).is_err() {
+                debug!("Failed to send EOS from MPEG-TS audio stream. Channel closed.");
+            }
+        }
+    }
+}
+
+struct VideoStream<'a> {
+    index: usize,
+    time_base: Rational,
+    converter: AvccToAnnexB,
+    sender: &'a Sender<PipelineEvent<EncodedChunk>>,
+}
+
+struct AudioStream<'a> {
+    index: usize,
+    time_base: Rational,
+    codec: AudioCodec,
+    /// `None` for Opus or if AAC is already in the ADTS format.
+    adts_header: Option<AdtsHeader>,
+    sender: &'a Sender<PipelineEvent<EncodedChunk>>,
+}
+
+/// Forwards H264 and AAC or Opus packets of the MPEG-TS input until the stream
+/// ends or `should_close` is set.
+pub(super) fn demux(
+    input: context::Input,
+    senders: &MpegTsChunkSenders,
+    should_close: &AtomicBool,
+) {
+    let video = senders.video.as_ref().and_then(|sender| {
+        let stream = input.streams().best(Type::Video)?;
+        if stream.parameters().id() != Id::H264 {
+            error!(codec=?stream.parameters().id(), "Unsupported MPEG-TS video codec.");
+            return None;
+        }
+        Some(VideoStream {
+            index: stream.index(),
+            time_base: stream.time_base(),
+            converter: AvccToAnnexB::new(&extradata(&stream)),
+            sender,
+        })
+    });
+    let audio = senders.audio.as_ref().and_then(|(codec, sender)| {
+        let codec = *codec;
+        let stream = input.streams().best(Type::Audio)?;
+        let expected_codec_id = match codec {
+            AudioCodec::Aac => Id::AAC,
+            AudioCodec::Opus => Id::OPUS,
+        };
+        if stream.parameters().id() != expected_codec_id {
+            error!(
+                codec=?stream.parameters().id(),
+                expected=?codec,
+                "Unsupported MPEG-TS audio codec."
+            );
+            return None;
+        }
+        let adts_header = match codec {
+            AudioCodec::Aac => AdtsHeader::from_asc(&extradata(&stream)).ok(),
+            AudioCodec::Opus => None,
+        };
+        Some(AudioStream {
+            index: stream.index(),
+            time_base: stream.time_base(),
+            codec,
+            adts_header,
+            sender,
+        })
+    });
+
+    read_packets(input, &video, &audio, should_close);
+}
+
+fn read_packets(
+    mut input: context::Input,
+    video: &Option<VideoStream>,
+    audio: &Option<AudioStream>,
+    should_close: &AtomicBool,
+) {
+    // MPEG-TS timestamps do not start at zero, they are shifted so the first
+    // packet of the input is at zero.
+    let mut start_pts: Option<Duration> = None;
+
+    loop {
+        if should_close.load(std::sync::atomic::Ordering::Relaxed) {
+            return;
+        }
+
+        let mut packet = Packet::empty();
+        match packet.read(&mut input) {
+            Ok(()) => {}
+            Err(ffmpeg_next::Error::Other {
+                errno: ffmpeg_next::error::EAGAIN,
+            }) => continue,
+            Err(ffmpeg_next::Error::Eof) | Err(ffmpeg_next::Error::Exit) => {
+                info!("MPEG-TS stream finished.");
+                return;
+            }
+            Err(err) => {
+                warn!("Failed to read MPEG-TS packet: {err}");
+                return;
+            }
+        }
+        let Some(data) = packet.data() else {
+            continue;
+        };
+
+        let time_base = match (video, audio) {
+            (Some(video), _) if video.index == packet.stream() => video.time_base,
+            (_, Some(audio)) if audio.index == packet.stream() => audio.time_base,
+            _ => continue,
+        };
+        let Some(pts) = packet.pts().or(packet.dts()) else {
+            continue;
+        };
+        let pts = to_duration(pts, time_base);
+        let start_pts = *start_pts.get_or_insert(pts);
+        let dts = packet
+            .dts()
+            .map(|dts| to_duration(dts, time_base).saturating_sub(start_pts));
+        let pts = pts.saturating_sub(start_pts);
+
+        if let Some(video) = video {
+            if packet.stream() == video.index {
+                let chunk = EncodedChunk {
+                    data: video.converter.convert(data, packet.is_key()),
+                    pts,
+                    dts,
+                    discontinuity: false,
+                    kind: EncodedChunkKind::Video(VideoCodec::H264),
+                };
+                trace!(pts=?chunk.pts, "MPEG-TS demuxer produced a video chunk.");
+                if video.sender.send(PipelineEvent::Data(chunk)).is_err() {
+                    debug!("Failed to send MPEG-TS video chunk. Channel closed.");
+                    return;
+                }
+                continue;
+            }
+        }
+
+        if let Some(audio) = audio {
+            if packet.stream() == audio.index {
+                let data = match &audio.adts_header {
+                    Some(adts_header) if !is_adts(data) => adts_header.wrap(data),
+                    _ => Bytes::copy_from_slice(data),
+                };
+                let chunk = EncodedChunk {
+                    data,
+                    pts,
+                    dts: None,
+                    discontinuity: false,
+                    kind: EncodedChunkKind::Audio(audio.codec),
+                };
+                trace!(pts=?chunk.pts, "MPEG-TS demuxer produced an audio chunk.");
+                if audio.sender.send(PipelineEvent::Data(chunk)).is_err() {
+                    debug!("Failed to send MPEG-TS audio chunk. Channel closed.");
+                    return;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multicast_udp_url() {
+        let multicast = Some(MulticastGroup {
+            group: "239.0.0.1".parse().unwrap(),
+            source: Some("10.0.0.2".parse().unwrap()),
+            interface: Some(MulticastInterface::V4("10.0.0.1".parse().unwrap())),
+        });
+        let opts = MpegTsReceiverOptions {
+            port: Port(5000),
+            multicast,
+            video: true,
+            audio: None,
+        };
+        assert_eq!(udp_url(&opts), "udp://239.0.0.1:5000");
+        let options = ffmpeg_options(&opts.multicast);
+        assert_eq!(options.get("sources"), Some("10.0.0.2"));
+        assert_eq!(options.get("localaddr"), Some("10.0.0.1"));
+
+        let opts = MpegTsReceiverOptions {
+            multicast: None,
+            ..opts
+        };
+        assert_eq!(udp_url(&opts), "udp://0.0.0.0:5000");
+        assert_eq!(ffmpeg_options(&opts.multicast).get("sources"), None);
+    }
+}
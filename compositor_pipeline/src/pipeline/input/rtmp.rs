@@ -0,0 +1,289 @@
+use std::{
+    ptr,
+    sync::{atomic::AtomicBool, Arc},
+};
+
+use compositor_render::InputId;
+use crossbeam_channel::{bounded, Sender};
+use ffmpeg_next::{codec::Id, ffi, format::context, media::Type, Dictionary, Packet};
+use tracing::{debug, error, info, span, trace, warn, Level};
+
+use crate::{
+    pipeline::{
+        decoder::{AacDecoderOptions, AudioDecoderOptions, VideoDecoderOptions},
+        types::{EncodedChunk, EncodedChunkKind},
+        AudioCodec, Port, VideoCodec,
+    },
+    queue::PipelineEvent,
+};
+
+use super::{
+    ffmpeg_utils::{extradata, to_duration, AdtsHeader, AvccToAnnexB},
+    AudioInputReceiver, Input, InputInitInfo, InputInitResult, VideoInputReceiver,
+};
+
+#[derive(Debug, Clone)]
+pub struct RtmpReceiverOptions {
+    pub port: Port,
+    pub app: Arc<str>,
+    pub stream_key: Arc<str>,
+    pub video: bool,
+    pub audio: bool,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RtmpReceiverError {
+    #[error("Failed to register input. Port: {0} is already used or not available.")]
+    PortAlreadyInUse(u16),
+
+    #[error("Error while binding the socket.")]
+    SocketBind(#[source] std::io::Error),
+}
+
+pub struct RtmpReceiver {
+    should_close: Arc<AtomicBool>,
+    pub port: u16,
+}
+
+impl RtmpReceiver {
+    pub(super) fn start_new_input(
+        input_id: &InputId,
+        opts: RtmpReceiverOptions,
+    ) -> Result<InputInitResult, RtmpReceiverError> {
+        // FFmpeg binds the socket only after the reader thread starts, so we check here
+        // if the port is available to report the error in the register request.
+        std::net::TcpListener::bind((std::net::Ipv4Addr::UNSPECIFIED, opts.port.0)).map_err(
+            |err| match err.kind() {
+                std::io::ErrorKind::AddrInUse => RtmpReceiverError::PortAlreadyInUse(opts.port.0),
+                _ => RtmpReceiverError::SocketBind(err),
+            },
+        )?;
+
+        let should_close = Arc::new(AtomicBool::new(false));
+
+        let (video_sender, video_receiver) = match opts.video {
+            true => {
+                let (sender, receiver) = bounded(5);
+                (Some(sender), Some(receiver))
+            }
+            false => (None, None),
+        };
+        let (audio_sender, audio_receiver) = match opts.audio {
+            true => {
+                let (sender, receiver) = bounded(5);
+                (Some(sender), Some(receiver))
+            }
+            false => (None, None),
+        };
+
+        let url = format!(
+            "rtmp://0.0.0.0:{}/{}/{}",
+            opts.port.0, opts.app, opts.stream_key
+        );
+        let input_id_clone = input_id.clone();
+        let should_close_clone = should_close.clone();
+        std::thread::Builder::new()
+            .name(format!("RTMP server {}", input_id))
+            .spawn(move || {
+                let _span = span!(
+                    Level::INFO,
+                    "RTMP server",
+                    input_id = input_id_clone.to_string()
+                )
+                .entered();
+                run_rtmp_server_thread(url, should_close_clone, video_sender, audio_sender);
+                debug!("Closing RTMP server thread.");
+            })
+            .unwrap();
+
+        let video = video_receiver.map(|chunk_receiver| VideoInputReceiver::Encoded {
+            chunk_receiver,
+            decoder_options: VideoDecoderOptions {
+                codec: VideoCodec::H264,
+            },
+        });
+        // AAC frames are converted to ADTS in the server thread, so the decoder does not
+        // need the AudioSpecificConfig, which is only known after the publisher connects.
+        let audio = audio_receiver.map(|chunk_receiver| AudioInputReceiver::Encoded {
+            chunk_receiver,
+            decoder_options: AudioDecoderOptions::Aac(AacDecoderOptions {
+                depayloader_mode: None,
+                asc: None,
+            }),
+        });
+
+        Ok(InputInitResult {
+            input: Input::Rtmp(Self {
+                should_close,
+                port: opts.port.0,
+            }),
+            video,
+            audio,
+            init_info: InputInitInfo {
+                port: Some(opts.port),
+            },
+        })
+    }
+}
+
+impl Drop for RtmpReceiver {
+    fn drop(&mut self) {
+        self.should_close
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+}
+
+fn run_rtmp_server_thread(
+    url: String,
+    should_close: Arc<AtomicBool>,
+    video_sender: Option<Sender<PipelineEvent<EncodedChunk>>>,
+    audio_sender: Option<Sender<PipelineEvent<EncodedChunk>>>,
+) {
+    let send_eos = || {
+        if let Some(sender) = &video_sender {
+            if sender.send(PipelineEvent::EOS).is_err() {
+                debug!("Failed to send EOS from RTMP video stream. Channel closed.");
+            }
+        }
+        if let Some(sender) = &audio_sender {
+            if sender.send(PipelineEvent::EOS).is_err() {
+                debug!("Failed to send EOS from RTMP audio stream. Channel closed.");
+            }
+        }
+    };
+
+    let mut input = match open_listening_input(&url, should_close.clone()) {
+        Ok(input) => input,
+        Err(err) => {
+            if !should_close.load(std::sync::atomic::Ordering::Relaxed) {
+                error!("Failed to accept RTMP connection: {err}");
+            }
+            send_eos();
+            return;
+        }
+    };
+    info!("RTMP publisher connected.");
+
+    let video = video_sender.as_ref().and_then(|sender| {
+        let stream = input.streams().best(Type::Video)?;
+        if stream.parameters().id() != Id::H264 {
+            error!(codec=?stream.parameters().id(), "Unsupported RTMP video codec.");
+            return None;
+        }
+        let converter = AvccToAnnexB::new(&extradata(&stream));
+        Some((stream.index(), stream.time_base(), converter, sender))
+    });
+
+    let audio = audio_sender.as_ref().and_then(|sender| {
+        let stream = input.streams().best(Type::Audio)?;
+        if stream.parameters().id() != Id::AAC {
+            error!(codec=?stream.parameters().id(), "Unsupported RTMP audio codec.");
+            return None;
+        }
+        let adts_header = match AdtsHeader::from_asc(&extradata(&stream)) {
+            Ok(header) => header,
+            Err(err) => {
+                error!("Unsupported AAC stream: {err}");
+                return None;
+            }
+        };
+        Some((stream.index(), stream.time_base(), adts_header, sender))
+    });
+
+    loop {
+        if should_close.load(std::sync::atomic::Ordering::Relaxed) {
+            break;
+        }
+
+        let mut packet = Packet::empty();
+        match packet.read(&mut input) {
+            Ok(()) => {}
+            Err(ffmpeg_next::Error::Other {
+                errno: ffmpeg_next::error::EAGAIN,
+            }) => continue,
+            Err(ffmpeg_next::Error::Eof) | Err(ffmpeg_next::Error::Exit) => {
+                info!("RTMP stream finished.");
+                break;
+            }
+            Err(err) => {
+                warn!("Failed to read RTMP packet: {err}");
+                break;
+            }
+        }
+        let Some(data) = packet.data() else {
+            continue;
+        };
+
+        if let Some((index, time_base, converter, sender)) = &video {
+            if packet.stream() == *index {
+                let pts = packet.pts().or(packet.dts()).unwrap_or(0);
+                let chunk = EncodedChunk {
+                    data: converter.convert(data, packet.is_key()),
+                    pts: to_duration(pts, *time_base),
+                    dts: packet.dts().map(|dts| to_duration(dts, *time_base)),
+                    discontinuity: false,
+                    kind: EncodedChunkKind::Video(VideoCodec::H264),
+                };
+                trace!(pts=?chunk.pts, "RTMP server produced a video chunk.");
+                if sender.send(PipelineEvent::Data(chunk)).is_err() {
+                    debug!("Failed to send RTMP video chunk. Channel closed.");
+                    return;
+                }
+                continue;
+            }
+        }
+
+        if let Some((index, time_base, adts_header, sender)) = &audio {
+            if packet.stream() == *index {
+                let pts = packet.pts().or(packet.dts()).unwrap_or(0);
+                let chunk = EncodedChunk {
+                    data: adts_header.wrap(data),
+                    pts: to_duration(pts, *time_base),
+                    dts: None,
+                    discontinuity: false,
+                    kind: EncodedChunkKind::Audio(AudioCodec::Aac),
+                };
+                trace!(pts=?chunk.pts, "RTMP server produced an audio chunk.");
+                if sender.send(PipelineEvent::Data(chunk)).is_err() {
+                    debug!("Failed to send RTMP audio chunk. Channel closed.");
+                    return;
+                }
+            }
+        }
+    }
+    send_eos();
+}
+
+/// Opens RTMP URL in the listen mode. It blocks until a publisher connects
+/// or `should_close` is set.
+fn open_listening_input(
+    url: &str,
+    should_close: Arc<AtomicBool>,
+) -> Result<context::Input, ffmpeg_next::Error> {
+    let path = std::ffi::CString::new(url).unwrap();
+    let mut options = Dictionary::new();
+    options.set("listen", "1");
+
+    unsafe {
+        let mut ctx = ffi::avformat_alloc_context();
+        (*ctx).interrupt_callback = ffmpeg_next::util::interrupt::new(Box::new(move || {
+            should_close.load(std::sync::atomic::Ordering::Relaxed)
+        }))
+        .interrupt;
+
+        let mut options = options.disown();
+        let result = ffi::avformat_open_input(&mut ctx, path.as_ptr(), ptr::null(), &mut options);
+        Dictionary::own(options);
+        if result < 0 {
+            return Err(ffmpeg_next::Error::from(result));
+        }
+
+        let result = ffi::avformat_find_stream_info(ctx, ptr::null_mut());
+        if result < 0 {
+            ffi::avformat_close_input(&mut ctx);
+            return Err(ffmpeg_next::Error::from(result));
+        }
+
+        Ok(context::Input::wrap(ctx))
+    }
+}
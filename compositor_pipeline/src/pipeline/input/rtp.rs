@@ -1,29 +1,48 @@
-use std::sync::{atomic::AtomicBool, Arc};
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::{atomic::AtomicBool, Arc, Mutex},
+    time::{Duration, Instant},
+};
 
 use crate::{
     pipeline::{
         decoder::{self},
         encoder,
-        rtp::{BindToPortError, RequestedPort, TransportProtocol},
+        rtp::{
+            sdp::{MediaDirection, SessionDescription, UNSPECIFIED_ADDRESS},
+            srtp::{SrtpContext, SrtpError, SrtpOptions},
+            BindToPortError, MulticastInterface, RequestedPort, TransportProtocol,
+        },
         types::{EncodedChunk, EncodedChunkKind},
     },
     queue::PipelineEvent,
 };
 use compositor_render::InputId;
-use crossbeam_channel::{bounded, Receiver, Sender};
-use rtcp::header::PacketType;
-use tracing::{debug, error, span, warn, Level};
+use crossbeam_channel::{bounded, Receiver, RecvTimeoutError, Sender};
+use rtcp::{header::PacketType, sender_report::SenderReport};
+use tracing::{debug, error, span, trace, warn, Level};
 use webrtc_util::Unmarshal;
 
 use self::{
     depayloader::{Depayloader, DepayloaderNewError},
+    feedback::RtcpFeedback,
+    jitter_buffer::{JitterBuffer, JitterBufferEvent},
+    lip_sync::{LipSync, Track},
     tcp_server::start_tcp_server_thread,
     udp::start_udp_reader_thread,
 };
 
 use super::{AudioInputReceiver, Input, InputInitInfo, InputInitResult, VideoInputReceiver};
 
+pub(super) use feedback::RtcpSender;
+pub use jitter_buffer::RtpStreamStats;
+
 mod depayloader;
+mod feedback;
+mod jitter_buffer;
+mod lip_sync;
+mod sdp;
 mod tcp_server;
 mod udp;
 
@@ -41,8 +60,17 @@ pub enum RtpReceiverError {
     #[error("Failed to register input. All ports in range {lower_bound} to {upper_bound} are already used or not available.")]
     AllPortsAlreadyInUse { lower_bound: u16, upper_bound: u16 },
 
+    #[error("RTP input does not support TCP client mode.")]
+    TcpClientNotSupported,
+
+    #[error("Failed to join multicast group {0}.")]
+    MulticastJoin(IpAddr, #[source] std::io::Error),
+
     #[error(transparent)]
     DepayloaderError(#[from] DepayloaderNewError),
+
+    #[error("Failed to initialize SRTP.")]
+    Srtp(#[from] SrtpError),
 }
 
 #[derive(Debug, Clone)]
@@ -50,16 +78,32 @@ pub struct RtpReceiverOptions {
     pub port: RequestedPort,
     pub transport_protocol: TransportProtocol,
     pub stream: RtpStream,
+    /// How long packets are held while waiting for a missing one before it
+    /// is considered lost.
+    pub jitter_buffer_latency: Duration,
+    /// Multicast group joined by UDP inputs.
+    pub multicast: Option<MulticastGroup>,
+    pub srtp: Option<SrtpOptions>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MulticastGroup {
+    pub group: IpAddr,
+    /// Only packets sent from this address are received (source-specific multicast).
+    pub source: Option<IpAddr>,
+    pub interface: Option<MulticastInterface>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct InputVideoStream {
     pub options: decoder::VideoDecoderOptions,
+    pub payload_type: u8,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct InputAudioStream {
     pub options: decoder::AudioDecoderOptions,
+    pub payload_type: u8,
 }
 
 pub struct OutputAudioStream {
@@ -73,13 +117,12 @@ pub struct RtpStream {
     pub audio: Option<InputAudioStream>,
 }
 
-struct DepayloaderThreadReceivers {
-    video: Option<Receiver<PipelineEvent<EncodedChunk>>>,
-    audio: Option<Receiver<PipelineEvent<EncodedChunk>>>,
-}
+pub(super) type SharedRtpStreamStats = Arc<Mutex<HashMap<u32, RtpStreamStats>>>;
 
 pub struct RtpReceiver {
     should_close: Arc<AtomicBool>,
+    stats: SharedRtpStreamStats,
+    sdp: SessionDescription,
     pub port: u16,
 }
 
@@ -89,39 +132,52 @@ impl RtpReceiver {
         opts: RtpReceiverOptions,
     ) -> Result<InputInitResult, RtpReceiverError> {
         let should_close = Arc::new(AtomicBool::new(false));
+        // Received packets and sent RTCP feedback use separate SRTP contexts.
+        let srtp = opts.srtp.as_ref().map(SrtpContext::new).transpose()?;
+        let feedback_srtp = opts.srtp.as_ref().map(SrtpContext::new).transpose()?;
+        let rtcp_sender = RtcpSender::new(feedback_srtp);
 
         let (port, packets_rx) = match opts.transport_protocol {
             TransportProtocol::Udp => {
-                start_udp_reader_thread(input_id, &opts, should_close.clone())?
+                start_udp_reader_thread(input_id, &opts, should_close.clone(), rtcp_sender.clone())?
             }
             TransportProtocol::TcpServer => {
-                start_tcp_server_thread(input_id, &opts, should_close.clone())?
+                start_tcp_server_thread(input_id, &opts, should_close.clone(), rtcp_sender.clone())?
             }
+            TransportProtocol::TcpClient => return Err(RtpReceiverError::TcpClientNotSupported),
         };
 
-        let depayloader = Depayloader::new(&opts.stream)?;
-
-        let depayloader_receivers =
-            Self::start_depayloader_thread(input_id, packets_rx, depayloader);
-
-        let video = match (depayloader_receivers.video, opts.stream.video) {
-            (Some(chunk_receiver), Some(stream)) => Some(VideoInputReceiver::Encoded {
-                chunk_receiver,
-                decoder_options: stream.options,
-            }),
-            _ => None,
+        let address = match &opts.multicast {
+            Some(multicast) => multicast.group.to_string(),
+            None => UNSPECIFIED_ADDRESS.to_string(),
         };
-        let audio = match (depayloader_receivers.audio, opts.stream.audio) {
-            (Some(chunk_receiver), Some(stream)) => Some(AudioInputReceiver::Encoded {
-                chunk_receiver,
-                decoder_options: stream.options,
-            }),
-            _ => None,
+        let sdp = SessionDescription {
+            address,
+            port: port.0,
+            transport_protocol: opts.transport_protocol,
+            direction: MediaDirection::RecvOnly,
+            media: Depayloader::new(&opts.stream)?.media_formats(),
+            srtp: opts.srtp.clone(),
         };
 
+        let RtpStreamReceiver {
+            video,
+            audio,
+            stats,
+        } = start_rtp_stream_receiver(
+            input_id,
+            packets_rx,
+            opts.stream,
+            opts.jitter_buffer_latency,
+            rtcp_sender,
+            srtp,
+        )?;
+
         Ok(InputInitResult {
             input: Input::Rtp(Self {
                 should_close,
+                stats,
+                sdp,
                 port: port.0,
             }),
             video,
@@ -130,43 +186,110 @@ impl RtpReceiver {
         })
     }
 
-    fn start_depayloader_thread(
-        input_id: &InputId,
-        receiver: Receiver<bytes::Bytes>,
-        depayloader: Depayloader,
-    ) -> DepayloaderThreadReceivers {
-        let (video_sender, video_receiver) = depayloader
-            .video
-            .as_ref()
-            .map(|_| bounded(5))
-            .map_or((None, None), |(tx, rx)| (Some(tx), Some(rx)));
-        let (audio_sender, audio_receiver) = depayloader
-            .audio
-            .as_ref()
-            .map(|_| bounded(5))
-            .map_or((None, None), |(tx, rx)| (Some(tx), Some(rx)));
-
-        let input_id = input_id.clone();
-        std::thread::Builder::new()
-            .name(format!("Depayloading thread for input: {}", input_id.0))
-            .spawn(move || {
-                let _span = span!(
-                    Level::INFO,
-                    "RTP depayloader",
-                    input_id = input_id.to_string()
-                )
-                .entered();
-                run_depayloader_thread(receiver, depayloader, video_sender, audio_sender)
-            })
-            .unwrap();
-
-        DepayloaderThreadReceivers {
-            video: video_receiver,
-            audio: audio_receiver,
-        }
+    /// Statistics of all RTP streams (SSRCs) received so far.
+    pub fn stats(&self) -> Vec<RtpStreamStats> {
+        sorted_stats(&self.stats)
+    }
+
+    /// Session description of streams that the input expects to receive.
+    pub fn sdp(&self) -> String {
+        self.sdp.to_string()
     }
 }
 
+/// Part of the input that does not depend on the transport: jitter buffers,
+/// depayloaders, lip sync and RTCP feedback.
+pub(super) struct RtpStreamReceiver {
+    pub video: Option<VideoInputReceiver>,
+    pub audio: Option<AudioInputReceiver>,
+    pub stats: SharedRtpStreamStats,
+}
+
+pub(super) fn sorted_stats(stats: &SharedRtpStreamStats) -> Vec<RtpStreamStats> {
+    let mut stats: Vec<_> = stats.lock().unwrap().values().copied().collect();
+    stats.sort_by_key(|stats| stats.ssrc);
+    stats
+}
+
+/// `packets` carry both RTP and RTCP packets in the order they were received.
+pub(super) fn start_rtp_stream_receiver(
+    input_id: &InputId,
+    packets: Receiver<bytes::Bytes>,
+    stream: RtpStream,
+    jitter_buffer_latency: Duration,
+    rtcp_sender: RtcpSender,
+    srtp: Option<SrtpContext>,
+) -> Result<RtpStreamReceiver, RtpReceiverError> {
+    let depayloader = Depayloader::new(&stream)?;
+    let (video_sender, video_receiver) = depayloader
+        .video
+        .as_ref()
+        .map(|_| bounded(5))
+        .map_or((None, None), |(tx, rx)| (Some(tx), Some(rx)));
+    let (audio_sender, audio_receiver) = depayloader
+        .audio
+        .as_ref()
+        .map(|_| bounded(5))
+        .map_or((None, None), |(tx, rx)| (Some(tx), Some(rx)));
+
+    let stats = SharedRtpStreamStats::default();
+    let thread_stats = stats.clone();
+    let thread_input_id = input_id.clone();
+    std::thread::Builder::new()
+        .name(format!("Depayloading thread for input: {}", input_id.0))
+        .spawn(move || {
+            let _span = span!(
+                Level::INFO,
+                "RTP depayloader",
+                input_id = thread_input_id.to_string()
+            )
+            .entered();
+            let lip_sync = LipSync::new(
+                depayloader
+                    .video_payload_type()
+                    .and_then(|payload_type| depayloader.clock_rate(payload_type)),
+                depayloader
+                    .audio_payload_type()
+                    .and_then(|payload_type| depayloader.clock_rate(payload_type)),
+            );
+            DepayloaderThread {
+                depayloader,
+                video_sender,
+                audio_sender,
+                jitter_buffers: HashMap::new(),
+                jitter_buffer_latency,
+                stats: thread_stats,
+                feedback: RtcpFeedback::new(rtcp_sender),
+                lip_sync,
+                video_ssrc: None,
+                audio_ssrc: None,
+                srtp,
+            }
+            .run(packets)
+        })
+        .unwrap();
+
+    let video = match (video_receiver, stream.video) {
+        (Some(chunk_receiver), Some(stream)) => Some(VideoInputReceiver::Encoded {
+            chunk_receiver,
+            decoder_options: stream.options,
+        }),
+        _ => None,
+    };
+    let audio = match (audio_receiver, stream.audio) {
+        (Some(chunk_receiver), Some(stream)) => Some(AudioInputReceiver::Encoded {
+            chunk_receiver,
+            decoder_options: stream.options,
+        }),
+        _ => None,
+    };
+    Ok(RtpStreamReceiver {
+        video,
+        audio,
+        stats,
+    })
+}
+
 impl Drop for RtpReceiver {
     fn drop(&mut self) {
         self.should_close
@@ -174,39 +297,59 @@ impl Drop for RtpReceiver {
     }
 }
 
-fn run_depayloader_thread(
-    receiver: Receiver<bytes::Bytes>,
-    mut depayloader: Depayloader,
+struct DepayloaderThread {
+    depayloader: Depayloader,
     video_sender: Option<Sender<PipelineEvent<EncodedChunk>>>,
     audio_sender: Option<Sender<PipelineEvent<EncodedChunk>>>,
-) {
-    let mut audio_eos_received = audio_sender.as_ref().map(|_| false);
-    let mut video_eos_received = video_sender.as_ref().map(|_| false);
-    let mut audio_ssrc = None;
-    let mut video_ssrc = None;
-
-    let mut maybe_send_video_eos = || {
-        if let (Some(sender), Some(false)) = (&video_sender, video_eos_received) {
-            video_eos_received = Some(true);
-            if sender.send(PipelineEvent::EOS).is_err() {
-                debug!("Failed to send EOS from RTP video depayloader. Channel closed.");
-            }
-        }
-    };
-    let mut maybe_send_audio_eos = || {
-        if let (Some(sender), Some(false)) = (&audio_sender, audio_eos_received) {
-            audio_eos_received = Some(true);
-            if sender.send(PipelineEvent::EOS).is_err() {
-                debug!("Failed to send EOS from RTP audio depayloader. Channel closed.");
-            }
+    jitter_buffers: HashMap<u32, JitterBuffer>,
+    jitter_buffer_latency: Duration,
+    stats: SharedRtpStreamStats,
+    feedback: RtcpFeedback,
+    lip_sync: LipSync,
+    video_ssrc: Option<u32>,
+    audio_ssrc: Option<u32>,
+    srtp: Option<SrtpContext>,
+}
+
+impl DepayloaderThread {
+    fn run(mut self, receiver: Receiver<bytes::Bytes>) {
+        loop {
+            // Wake up when packets waiting for a missing one should be released
+            // or when the next receiver report should be sent.
+            let deadline = self
+                .jitter_buffers
+                .values()
+                .filter_map(JitterBuffer::deadline)
+                .fold(self.feedback.next_report_at(), Instant::min);
+            match receiver.recv_deadline(deadline) {
+                Ok(buffer) => self.handle_packet(buffer),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => {
+                    debug!("Closing RTP depayloader thread.");
+                    let ssrcs: Vec<u32> = self.jitter_buffers.keys().copied().collect();
+                    for ssrc in ssrcs {
+                        self.flush_jitter_buffer(ssrc);
+                    }
+                    break;
+                }
+            };
+            self.release_packets();
         }
-    };
-    loop {
-        let Ok(mut buffer) = receiver.recv() else {
-            debug!("Closing RTP depayloader thread.");
-            break;
-        };
+        self.send_audio_eos();
+        self.send_video_eos();
+    }
 
+    fn handle_packet(&mut self, mut buffer: bytes::Bytes) {
+        let received_at = Instant::now();
+        if let Some(srtp) = &mut self.srtp {
+            buffer = match srtp.unprotect(&buffer) {
+                Ok(buffer) => buffer,
+                Err(err) => {
+                    debug!(%err, "Failed to decrypt SRTP packet. Dropping.");
+                    return;
+                }
+            };
+        }
         match rtp::packet::Packet::unmarshal(&mut buffer.clone()) {
             // https://datatracker.ietf.org/doc/html/rfc5761#section-4
             //
@@ -215,67 +358,184 @@ fn run_depayloader_thread(
             // with the additional restriction that payload type values in the range
             // 64-95 MUST NOT be used.
             Ok(packet) if packet.header.payload_type < 64 || packet.header.payload_type > 95 => {
-                if packet.header.payload_type == 96 && video_ssrc.is_none() {
-                    video_ssrc = Some(packet.header.ssrc);
+                let ssrc = packet.header.ssrc;
+                let payload_type = packet.header.payload_type;
+                if !self.depayloader.is_known_payload_type(payload_type) {
+                    trace!(
+                        payload_type,
+                        ssrc,
+                        "Rejecting RTP packet with unknown payload type."
+                    );
+                    return;
                 }
-                if packet.header.payload_type == 97 && audio_ssrc.is_none() {
-                    audio_ssrc = Some(packet.header.ssrc);
+                if Some(payload_type) == self.depayloader.video_payload_type()
+                    && self.video_ssrc.is_none()
+                {
+                    self.video_ssrc = Some(ssrc);
+                }
+                if Some(payload_type) == self.depayloader.audio_payload_type()
+                    && self.audio_ssrc.is_none()
+                {
+                    self.audio_ssrc = Some(ssrc);
                 }
 
-                match depayloader.depayload(packet) {
-                    Ok(chunks) => {
-                        for chunk in chunks {
-                            match &chunk.kind {
-                                EncodedChunkKind::Video(_) => {
-                                    video_sender.as_ref().map(|video_sender| {
-                                        video_sender.send(PipelineEvent::Data(chunk))
-                                    })
-                                }
-                                EncodedChunkKind::Audio(_) => {
-                                    audio_sender.as_ref().map(|audio_sender| {
-                                        audio_sender.send(PipelineEvent::Data(chunk))
-                                    })
-                                }
-                            };
-                        }
+                self.feedback.on_rtp_packet(
+                    &packet.header,
+                    self.depayloader.clock_rate(payload_type),
+                    received_at,
+                );
+                let jitter_buffer_latency = self.jitter_buffer_latency;
+                let missing = self
+                    .jitter_buffers
+                    .entry(ssrc)
+                    .or_insert_with(|| JitterBuffer::new(jitter_buffer_latency, ssrc, payload_type))
+                    .push(packet, received_at);
+                self.feedback.on_packets_missing(ssrc, missing);
+            }
+            Ok(_) | Err(_) => match rtcp::packet::unmarshal(&mut buffer) {
+                Ok(rtcp_packets) => {
+                    for rtcp_packet in rtcp_packets {
+                        self.handle_rtcp_packet(rtcp_packet.as_ref(), received_at);
                     }
-                    Err(err) => {
-                        warn!("RTP depayloading error: {}", err);
-                        continue;
+                }
+                Err(err) => {
+                    warn!(%err, "Received an unexpected packet, which is not recognized either as RTP or RTCP. Dropping.");
+                }
+            },
+        }
+    }
+
+    fn handle_rtcp_packet(
+        &mut self,
+        rtcp_packet: &(dyn rtcp::packet::Packet + Send + Sync),
+        received_at: Instant,
+    ) {
+        match rtcp_packet.header().packet_type {
+            PacketType::Goodbye => {
+                for ssrc in rtcp_packet.destination_ssrc() {
+                    // Nothing else will arrive, so there is no point in waiting
+                    // for missing packets.
+                    self.flush_jitter_buffer(ssrc);
+                    if Some(ssrc) == self.audio_ssrc {
+                        self.send_audio_eos()
+                    }
+                    if Some(ssrc) == self.video_ssrc {
+                        self.send_video_eos()
                     }
                 }
             }
-            Ok(_) | Err(_) => {
-                match rtcp::packet::unmarshal(&mut buffer) {
-                    Ok(rtcp_packets) => {
-                        for rtcp_packet in rtcp_packets {
-                            if let PacketType::Goodbye = rtcp_packet.header().packet_type {
-                                for ssrc in rtcp_packet.destination_ssrc() {
-                                    if Some(ssrc) == audio_ssrc {
-                                        maybe_send_audio_eos()
-                                    }
-                                    if Some(ssrc) == video_ssrc {
-                                        maybe_send_video_eos()
-                                    }
-                                }
-                            } else {
-                                debug!(
-                                    packet_type=?rtcp_packet.header().packet_type,
-                                    "Received RTCP packet"
-                                )
-                            }
-                        }
+            PacketType::SenderReport => {
+                if let Some(report) = rtcp_packet.as_any().downcast_ref::<SenderReport>() {
+                    self.feedback.on_sender_report(report, received_at);
+                    if Some(report.ssrc) == self.video_ssrc {
+                        self.lip_sync.on_sender_report(Track::Video, report);
                     }
-                    Err(err) => {
-                        warn!(%err, "Received an unexpected packet, which is not recognized either as RTP or RTCP. Dropping.");
+                    if Some(report.ssrc) == self.audio_ssrc {
+                        self.lip_sync.on_sender_report(Track::Audio, report);
                     }
                 }
-                continue;
+            }
+            packet_type => debug!(?packet_type, "Received RTCP packet"),
+        }
+    }
+
+    /// Passes packets that are ready in jitter buffers to the depayloader, updates
+    /// the shared statistics and sends receiver reports.
+    fn release_packets(&mut self) {
+        let now = Instant::now();
+        let mut events = Vec::new();
+        for jitter_buffer in self.jitter_buffers.values_mut() {
+            let payload_type = jitter_buffer.stats().payload_type;
+            while let Some(event) = jitter_buffer.pop(now) {
+                events.push((payload_type, event));
+            }
+        }
+        for (payload_type, event) in events {
+            self.handle_jitter_buffer_event(payload_type, event);
+        }
+
+        let mut stats = self.stats.lock().unwrap();
+        for (ssrc, jitter_buffer) in self.jitter_buffers.iter() {
+            stats.insert(*ssrc, jitter_buffer.stats());
+        }
+        drop(stats);
+        self.feedback.maybe_send_reports(&self.jitter_buffers);
+    }
+
+    /// Releases all buffered packets without waiting for missing ones, e.g. when
+    /// the stream has ended.
+    fn flush_jitter_buffer(&mut self, ssrc: u32) {
+        let Some(jitter_buffer) = self.jitter_buffers.get_mut(&ssrc) else {
+            return;
+        };
+        let payload_type = jitter_buffer.stats().payload_type;
+        let events: Vec<_> = std::iter::from_fn(|| jitter_buffer.pop_without_waiting()).collect();
+        for event in events {
+            self.handle_jitter_buffer_event(payload_type, event);
+        }
+    }
+
+    fn handle_jitter_buffer_event(&mut self, payload_type: u8, event: JitterBufferEvent) {
+        let packet = match event {
+            JitterBufferEvent::Packet(packet) => packet,
+            JitterBufferEvent::Lost { count } => {
+                debug!(count, payload_type, "RTP packets lost.");
+                self.depayloader.on_packets_lost(payload_type);
+                if Some(payload_type) == self.depayloader.video_payload_type() {
+                    self.feedback.on_video_packets_lost();
+                }
+                return;
             }
         };
+
+        match self.depayloader.depayload(packet) {
+            Ok(chunks) => {
+                for chunk in chunks {
+                    self.send_chunk(chunk);
+                }
+            }
+            Err(err) => {
+                warn!("RTP depayloading error: {}", err);
+            }
+        }
+    }
+
+    fn send_chunk(&mut self, mut chunk: EncodedChunk) {
+        let now = Instant::now();
+        let sender = match chunk.kind {
+            EncodedChunkKind::Video(_) => {
+                self.feedback
+                    .on_video_chunk(self.video_ssrc, chunk.is_keyframe());
+                chunk.pts = self.lip_sync.map_pts(Track::Video, chunk.pts, now);
+                &self.video_sender
+            }
+            EncodedChunkKind::Audio(_) => {
+                chunk.pts = self.lip_sync.map_pts(Track::Audio, chunk.pts, now);
+                &self.audio_sender
+            }
+        };
+        if let Some(sender) = sender {
+            if sender.send(PipelineEvent::Data(chunk)).is_err() {
+                debug!("Failed to send chunk from RTP depayloader. Channel closed.");
+            }
+        }
+    }
+
+    fn send_video_eos(&mut self) {
+        if let Some(sender) = self.video_sender.take() {
+            if sender.send(PipelineEvent::EOS).is_err() {
+                debug!("Failed to send EOS from RTP video depayloader. Channel closed.");
+            }
+        }
+    }
+
+    fn send_audio_eos(&mut self) {
+        if let Some(sender) = self.audio_sender.take() {
+            if sender.send(PipelineEvent::EOS).is_err() {
+                debug!("Failed to send EOS from RTP audio depayloader. Channel closed.");
+            }
+        }
     }
-    maybe_send_audio_eos();
-    maybe_send_video_eos();
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -286,6 +546,8 @@ pub enum DepayloadingError {
     Rtp(#[from] rtp::Error),
     #[error("AAC depayoading error")]
     Aac(#[from] depayloader::AacDepayloadingError),
+    #[error("AV1 depayloading error")]
+    Av1(#[from] depayloader::Av1DepayloadingError),
 }
 
 impl From<BindToPortError> for RtpReceiverError {
@@ -8,8 +8,8 @@ use rtp::{
 };
 
 use crate::pipeline::{
-    decoder::{self, AacDecoderOptions},
-    rtp::{AUDIO_PAYLOAD_TYPE, VIDEO_PAYLOAD_TYPE},
+    decoder::{self, AacDecoderOptions, OpusDecoderOptions},
+    rtp::sdp::MediaFormat,
     types::{AudioCodec, EncodedChunk, EncodedChunkKind, VideoCodec},
 };
 
@@ -18,8 +18,12 @@ use self::aac::AacDepayloaderNewError;
 use super::{DepayloadingError, RtpStream};
 
 pub use aac::{AacDepayloader, AacDepayloadingError};
+pub use av1::{Av1Depayloader, Av1DepayloadingError};
+pub use vpx::VpxDepayloader;
 
 mod aac;
+mod av1;
+mod vpx;
 
 #[derive(Debug, thiserror::Error)]
 pub enum DepayloaderNewError {
@@ -29,8 +33,8 @@ pub enum DepayloaderNewError {
 
 pub(crate) struct Depayloader {
     /// (Depayloader, payload type)
-    pub video: Option<VideoDepayloader>,
-    pub audio: Option<AudioDepayloader>,
+    pub video: Option<(VideoDepayloader, u8)>,
+    pub audio: Option<(AudioDepayloader, u8)>,
 }
 
 impl Depayloader {
@@ -38,12 +42,15 @@ impl Depayloader {
         let video = stream
             .video
             .as_ref()
-            .map(|video| VideoDepayloader::new(&video.options));
+            .map(|video| (VideoDepayloader::new(&video.options), video.payload_type));
 
         let audio = stream
             .audio
             .as_ref()
-            .map(|audio| AudioDepayloader::new(&audio.options))
+            .map(|audio| {
+                AudioDepayloader::new(&audio.options)
+                    .map(|depayloader| (depayloader, audio.payload_type))
+            })
             .transpose()?;
 
         Ok(Self { video, audio })
@@ -53,22 +60,88 @@ impl Depayloader {
         &mut self,
         packet: rtp::packet::Packet,
     ) -> Result<Vec<EncodedChunk>, DepayloadingError> {
-        match packet.header.payload_type {
-            VIDEO_PAYLOAD_TYPE => match self.video.as_mut() {
-                Some(video_depayloader) => video_depayloader.depayload(packet),
-                None => Err(DepayloadingError::BadPayloadType(
-                    packet.header.payload_type,
-                )),
-            },
-            AUDIO_PAYLOAD_TYPE => match self.audio.as_mut() {
-                Some(audio_depayloader) => audio_depayloader.depayload(packet),
-                None => Err(DepayloadingError::BadPayloadType(
-                    packet.header.payload_type,
-                )),
-            },
-            other => Err(DepayloadingError::BadPayloadType(other)),
+        let payload_type = packet.header.payload_type;
+        if let Some((video_depayloader, _)) = self.video_mut(payload_type) {
+            return video_depayloader.depayload(packet);
+        }
+        if let Some((audio_depayloader, _)) = self.audio_mut(payload_type) {
+            return audio_depayloader.depayload(packet);
+        }
+        Err(DepayloadingError::BadPayloadType(payload_type))
+    }
+
+    pub fn video_payload_type(&self) -> Option<u8> {
+        self.video.as_ref().map(|(_, payload_type)| *payload_type)
+    }
+
+    pub fn audio_payload_type(&self) -> Option<u8> {
+        self.audio.as_ref().map(|(_, payload_type)| *payload_type)
+    }
+
+    /// Packets with other payload types are not part of the stream.
+    pub fn is_known_payload_type(&self, payload_type: u8) -> bool {
+        self.video_payload_type() == Some(payload_type)
+            || self.audio_payload_type() == Some(payload_type)
+    }
+
+    /// Clock rate of RTP timestamps for `payload_type`.
+    pub fn clock_rate(&self, payload_type: u8) -> Option<u32> {
+        if self.video_payload_type() == Some(payload_type) {
+            return Some(90_000);
+        }
+        self.audio
+            .as_ref()
+            .filter(|(_, audio_payload_type)| *audio_payload_type == payload_type)
+            .map(|(audio_depayloader, _)| audio_depayloader.clock_rate())
+    }
+
+    /// Formats of depayloaded streams, used to describe the input in SDP.
+    pub fn media_formats(&self) -> Vec<MediaFormat> {
+        let video = self
+            .video
+            .as_ref()
+            .map(|(video, payload_type)| match video {
+                VideoDepayloader::H264 { .. } => MediaFormat::h264(*payload_type),
+                VideoDepayloader::Vp8(_) => MediaFormat::vp8(*payload_type),
+                VideoDepayloader::Vp9(_) => MediaFormat::vp9(*payload_type),
+                VideoDepayloader::Av1(_) => MediaFormat::av1(*payload_type),
+            });
+        let audio = self
+            .audio
+            .as_ref()
+            .map(|(audio, payload_type)| match audio {
+                AudioDepayloader::Opus {
+                    forward_error_correction,
+                    ..
+                } => MediaFormat::opus(*payload_type, *forward_error_correction),
+                AudioDepayloader::Aac(aac) => aac.media_format(*payload_type),
+            });
+        video.into_iter().chain(audio).collect()
+    }
+
+    /// Called when the jitter buffer gave up waiting for packets of `payload_type`.
+    /// Partially collected frames are dropped and the next chunk is marked as
+    /// a discontinuity.
+    pub fn on_packets_lost(&mut self, payload_type: u8) {
+        if let Some((video_depayloader, _)) = self.video_mut(payload_type) {
+            video_depayloader.on_packets_lost();
+        }
+        if let Some((audio_depayloader, _)) = self.audio_mut(payload_type) {
+            audio_depayloader.on_packets_lost();
         }
     }
+
+    fn video_mut(&mut self, payload_type: u8) -> Option<&mut (VideoDepayloader, u8)> {
+        self.video
+            .as_mut()
+            .filter(|(_, video_payload_type)| *video_payload_type == payload_type)
+    }
+
+    fn audio_mut(&mut self, payload_type: u8) -> Option<&mut (AudioDepayloader, u8)> {
+        self.audio
+            .as_mut()
+            .filter(|(_, audio_payload_type)| *audio_payload_type == payload_type)
+    }
 }
 
 pub enum VideoDepayloader {
@@ -76,7 +149,17 @@ pub enum VideoDepayloader {
         depayloader: H264Packet,
         buffer: Vec<Bytes>,
         rollover_state: RolloverState,
+        /// RTP timestamp of the last received packet.
+        last_timestamp: Option<u32>,
+        /// RTP timestamp of the access unit that lost some of its packets. Remaining
+        /// packets of that access unit are dropped.
+        damaged_timestamp: Option<u32>,
+        /// Packets were lost after the last produced chunk.
+        discontinuity: bool,
     },
+    Vp8(VpxDepayloader),
+    Vp9(VpxDepayloader),
+    Av1(Av1Depayloader),
 }
 
 impl VideoDepayloader {
@@ -86,7 +169,13 @@ impl VideoDepayloader {
                 depayloader: H264Packet::default(),
                 buffer: vec![],
                 rollover_state: RolloverState::default(),
+                last_timestamp: None,
+                damaged_timestamp: None,
+                discontinuity: false,
             },
+            VideoCodec::Vp8 => VideoDepayloader::Vp8(VpxDepayloader::new_vp8()),
+            VideoCodec::Vp9 => VideoDepayloader::Vp9(VpxDepayloader::new_vp9()),
+            VideoCodec::Av1 => VideoDepayloader::Av1(Av1Depayloader::default()),
         }
     }
 
@@ -99,8 +188,17 @@ impl VideoDepayloader {
                 depayloader,
                 buffer,
                 rollover_state,
+                last_timestamp,
+                damaged_timestamp,
+                discontinuity,
             } => {
                 let kind = EncodedChunkKind::Video(VideoCodec::H264);
+                *last_timestamp = Some(packet.header.timestamp);
+                if *damaged_timestamp == Some(packet.header.timestamp) {
+                    return Ok(Vec::new());
+                }
+                *damaged_timestamp = None;
+
                 let h264_chunk = depayloader.depacketize(&packet.payload)?;
 
                 if h264_chunk.is_empty() {
@@ -118,11 +216,36 @@ impl VideoDepayloader {
                     data: mem::take(buffer).concat().into(),
                     pts: Duration::from_secs_f64(timestamp as f64 / 90000.0),
                     dts: None,
+                    discontinuity: mem::take(discontinuity),
                     kind,
                 };
 
                 Ok(vec![new_chunk])
             }
+            VideoDepayloader::Vp8(vp8) => vp8.depayload(packet),
+            VideoDepayloader::Vp9(vp9) => vp9.depayload(packet),
+            VideoDepayloader::Av1(av1) => Ok(av1.depayload(packet)?),
+        }
+    }
+
+    fn on_packets_lost(&mut self) {
+        match self {
+            VideoDepayloader::H264 {
+                depayloader,
+                buffer,
+                last_timestamp,
+                damaged_timestamp,
+                discontinuity,
+                ..
+            } => {
+                buffer.clear();
+                *depayloader = H264Packet::default();
+                *damaged_timestamp = *last_timestamp;
+                *discontinuity = true;
+            }
+            VideoDepayloader::Vp8(vp8) => vp8.on_packets_lost(),
+            VideoDepayloader::Vp9(vp9) => vp9.on_packets_lost(),
+            VideoDepayloader::Av1(av1) => av1.on_packets_lost(),
         }
     }
 }
@@ -143,6 +266,10 @@ pub enum AudioDepayloader {
     Opus {
         depayloader: OpusPacket,
         rollover_state: RolloverState,
+        /// Packets were lost after the last produced chunk.
+        discontinuity: bool,
+        /// Advertised in SDP, so the sender knows that FEC data will be used.
+        forward_error_correction: bool,
     },
     Aac(AacDepayloader),
 }
@@ -150,9 +277,13 @@ pub enum AudioDepayloader {
 impl AudioDepayloader {
     pub fn new(options: &decoder::AudioDecoderOptions) -> Result<Self, AudioDepayloaderNewError> {
         match options {
-            decoder::AudioDecoderOptions::Opus(_) => Ok(AudioDepayloader::Opus {
+            decoder::AudioDecoderOptions::Opus(OpusDecoderOptions {
+                forward_error_correction,
+            }) => Ok(AudioDepayloader::Opus {
                 depayloader: OpusPacket,
                 rollover_state: RolloverState::default(),
+                discontinuity: false,
+                forward_error_correction: *forward_error_correction,
             }),
             decoder::AudioDecoderOptions::Aac(AacDecoderOptions {
                 depayloader_mode,
@@ -173,6 +304,8 @@ impl AudioDepayloader {
             AudioDepayloader::Opus {
                 depayloader,
                 rollover_state,
+                discontinuity,
+                ..
             } => {
                 let kind = EncodedChunkKind::Audio(AudioCodec::Opus);
                 let opus_packet = depayloader.depacketize(&packet.payload)?;
@@ -186,6 +319,7 @@ impl AudioDepayloader {
                     data: opus_packet,
                     pts: Duration::from_secs_f64(timestamp as f64 / 48000.0),
                     dts: None,
+                    discontinuity: mem::take(discontinuity),
                     kind,
                 }])
             }
@@ -193,6 +327,20 @@ impl AudioDepayloader {
             AudioDepayloader::Aac(aac) => Ok(aac.depayload(packet)?),
         }
     }
+
+    fn clock_rate(&self) -> u32 {
+        match self {
+            AudioDepayloader::Opus { .. } => 48_000,
+            AudioDepayloader::Aac(aac) => aac.clock_rate(),
+        }
+    }
+
+    fn on_packets_lost(&mut self) {
+        match self {
+            AudioDepayloader::Opus { discontinuity, .. } => *discontinuity = true,
+            AudioDepayloader::Aac(aac) => aac.on_packets_lost(),
+        }
+    }
 }
 
 #[derive(Default)]
@@ -1,9 +1,10 @@
-use std::{io::Read, time::Duration};
+use std::{io::Read, mem, time::Duration};
 
-use bytes::{Buf, BytesMut};
+use bytes::{Buf, Bytes, BytesMut};
 
 use crate::pipeline::{
     decoder::AacDepayloaderMode,
+    rtp::sdp::{MediaFormat, MediaKind},
     types::{EncodedChunk, EncodedChunkKind},
     AudioCodec,
 };
@@ -34,6 +35,13 @@ impl AacDepayloaderMode {
         }
     }
 
+    fn sdp_mode(&self) -> &'static str {
+        match self {
+            AacDepayloaderMode::LowBitrate => "AAC-lbr",
+            AacDepayloaderMode::HighBitrate => "AAC-hbr",
+        }
+    }
+
     fn header_len_in_bytes(&self) -> usize {
         match self {
             AacDepayloaderMode::LowBitrate => 1,
@@ -45,7 +53,10 @@ impl AacDepayloaderMode {
 pub struct AacDepayloader {
     mode: AacDepayloaderMode,
     asc: Asc,
+    raw_asc: Bytes,
     rollover_state: RolloverState,
+    /// Packets were lost after the last produced chunk.
+    discontinuity: bool,
 }
 
 /// MPEG-4 part 3, 1.6.3.4
@@ -79,7 +90,7 @@ fn frame_length_flag_to_frame_length(flag: bool) -> u32 {
 struct Asc {
     _profile: u8,
     frequency: u32,
-    _channel: u8,
+    channel: u8,
     frame_length: u32,
 }
 
@@ -175,7 +186,7 @@ fn parse_asc(asc: &[u8]) -> Result<Asc, AudioSpecificConfigParseError> {
     Ok(Asc {
         _profile: profile,
         frequency,
-        _channel: channel,
+        channel,
         frame_length,
     })
 }
@@ -191,14 +202,48 @@ impl AacDepayloader {
         mode: AacDepayloaderMode,
         asc: &[u8],
     ) -> Result<Self, AacDepayloaderNewError> {
+        let raw_asc = Bytes::copy_from_slice(asc);
         let asc = parse_asc(asc)?;
         Ok(Self {
             mode,
             asc,
+            raw_asc,
             rollover_state: RolloverState::default(),
+            discontinuity: false,
         })
     }
 
+    pub(super) fn clock_rate(&self) -> u32 {
+        self.asc.frequency
+    }
+
+    pub(super) fn on_packets_lost(&mut self) {
+        self.discontinuity = true;
+    }
+
+    /// [RFC 3640, section 4.1](https://datatracker.ietf.org/doc/html/rfc3640#section-4.1)
+    pub(super) fn media_format(&self, payload_type: u8) -> MediaFormat {
+        let config: String = self
+            .raw_asc
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect();
+        let size_length = self.mode.size_len_in_bits();
+        let index_length = self.mode.index_len_in_bits();
+        MediaFormat {
+            kind: MediaKind::Audio,
+            payload_type,
+            encoding_name: "MPEG4-GENERIC".to_string(),
+            clock_rate: self.asc.frequency,
+            // Channel configuration 0 means that it is defined in the stream.
+            channels: Some(self.asc.channel).filter(|channels| *channels > 0),
+            fmtp: Some(format!(
+                "streamtype=5;profile-level-id=1;mode={};sizelength={size_length};indexlength={index_length};indexdeltalength={index_length};config={config}",
+                self.mode.sdp_mode()
+            )),
+        }
+    }
+
     /// Related spec:
     ///  - [RFC 3640, section 3.2. RTP Payload Structure](https://datatracker.ietf.org/doc/html/rfc3640#section-3.2)
     ///  - [RFC 3640, section 3.3.5. Low Bit-rate AAC](https://datatracker.ietf.org/doc/html/rfc3640#section-3.3.5)
@@ -269,6 +314,7 @@ impl AacDepayloader {
                 pts,
                 data: payload,
                 dts: None,
+                discontinuity: mem::take(&mut self.discontinuity),
                 kind: EncodedChunkKind::Audio(AudioCodec::Aac),
             });
         }
@@ -288,7 +334,7 @@ mod tests {
 
         assert_eq!(parsed._profile, 2);
         assert_eq!(parsed.frequency, 44_100);
-        assert_eq!(parsed._channel, 2);
+        assert_eq!(parsed.channel, 2);
         assert_eq!(parsed.frame_length, 1024);
     }
 
@@ -299,7 +345,7 @@ mod tests {
 
         assert_eq!(parsed._profile, 2);
         assert_eq!(parsed.frequency, 0x2137);
-        assert_eq!(parsed._channel, 2);
+        assert_eq!(parsed.channel, 2);
         assert_eq!(parsed.frame_length, 960);
     }
 
@@ -310,7 +356,7 @@ mod tests {
 
         assert_eq!(parsed._profile, 42);
         assert_eq!(parsed.frequency, 48_000);
-        assert_eq!(parsed._channel, 1);
+        assert_eq!(parsed.channel, 1);
         assert_eq!(parsed.frame_length, 1024);
     }
 
@@ -323,7 +369,23 @@ mod tests {
 
         assert_eq!(parsed._profile, 42);
         assert_eq!(parsed.frequency, 0x2137);
-        assert_eq!(parsed._channel, 2);
+        assert_eq!(parsed.channel, 2);
         assert_eq!(parsed.frame_length, 1024);
     }
+
+    #[test]
+    fn sdp_media_format() {
+        let depayloader =
+            AacDepayloader::new(AacDepayloaderMode::HighBitrate, &[0b00010010, 0b00010000])
+                .unwrap();
+        let format = depayloader.media_format(97);
+
+        assert_eq!(format.encoding_name, "MPEG4-GENERIC");
+        assert_eq!(format.clock_rate, 44_100);
+        assert_eq!(format.channels, Some(2));
+        assert_eq!(
+            format.fmtp.as_deref(),
+            Some("streamtype=5;profile-level-id=1;mode=AAC-hbr;sizelength=13;indexlength=3;indexdeltalength=3;config=1210")
+        );
+    }
 }
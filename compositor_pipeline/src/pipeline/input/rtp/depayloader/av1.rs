@@ -0,0 +1,277 @@
+use std::{mem, time::Duration};
+
+use bytes::{Buf, Bytes, BytesMut};
+use tracing::debug;
+
+use crate::pipeline::{
+    av1::{read_leb128, Obu, ObuParseError, OBU_PADDING, OBU_TEMPORAL_DELIMITER, OBU_TILE_LIST},
+    types::{EncodedChunk, EncodedChunkKind},
+    VideoCodec,
+};
+
+use super::RolloverState;
+
+const AV1_CLOCK_RATE: f64 = 90000.0;
+
+/// Temporal delimiter OBU with an empty payload, it starts every temporal unit in
+/// the low overhead bitstream format.
+const TEMPORAL_DELIMITER: [u8; 2] = [0x12, 0x00];
+
+#[derive(Debug, thiserror::Error)]
+pub enum Av1DepayloadingError {
+    #[error("Packet too short")]
+    PacketTooShort,
+
+    #[error("Invalid OBU: {0}")]
+    InvalidObu(#[from] ObuParseError),
+}
+
+/// Depayloader for AV1 streams ([RTP Payload Format for AV1](https://aomediacodec.github.io/av1-rtp-spec/)).
+/// OBUs of a temporal unit are collected until the packet with a marker bit and converted
+/// to the low overhead bitstream format, so each chunk contains a whole temporal unit.
+#[derive(Default)]
+pub struct Av1Depayloader {
+    /// OBUs of the temporal unit that is currently collected, with size fields.
+    buffer: BytesMut,
+    /// Beginning of an OBU that continues in the next packet.
+    fragment: Option<BytesMut>,
+    /// RTP timestamp of the temporal unit that is currently collected in the buffer.
+    frame_timestamp: Option<u32>,
+    rollover_state: RolloverState,
+    /// Packets were lost after the last produced chunk.
+    discontinuity: bool,
+}
+
+/// First byte of the payload (`|Z|Y| W |N|-|-|-|`).
+struct AggregationHeader {
+    /// The first element continues an OBU fragment from the previous packet.
+    continues_fragment: bool,
+    /// The last element is an OBU fragment that continues in the next packet.
+    ends_with_fragment: bool,
+    /// Number of elements, if 0 every element is preceded by its length.
+    element_count: u8,
+}
+
+impl AggregationHeader {
+    fn parse(byte: u8) -> Self {
+        Self {
+            continues_fragment: byte & 0x80 != 0,
+            ends_with_fragment: byte & 0x40 != 0,
+            element_count: (byte >> 4) & 0b11,
+        }
+    }
+}
+
+impl Av1Depayloader {
+    /// Drops the partially collected temporal unit. Packets are skipped until the
+    /// beginning of the next one.
+    pub fn on_packets_lost(&mut self) {
+        self.reset();
+        self.discontinuity = true;
+    }
+
+    pub fn depayload(
+        &mut self,
+        packet: rtp::packet::Packet,
+    ) -> Result<Vec<EncodedChunk>, Av1DepayloadingError> {
+        let Some(&first_byte) = packet.payload.first() else {
+            return Ok(Vec::new());
+        };
+        let header = AggregationHeader::parse(first_byte);
+
+        if self
+            .frame_timestamp
+            .is_some_and(|timestamp| timestamp != packet.header.timestamp)
+        {
+            // Packet with a marker bit was lost, decoding a temporal unit without the
+            // remaining data would produce artifacts.
+            debug!("Dropping incomplete AV1 temporal unit.");
+            self.reset();
+        }
+
+        if self.frame_timestamp.is_none() {
+            if header.continues_fragment {
+                // Wait for the beginning of the next temporal unit.
+                return Ok(Vec::new());
+            }
+            self.frame_timestamp = Some(packet.header.timestamp);
+        }
+
+        let elements = read_elements(packet.payload.slice(1..), header.element_count)?;
+        let element_count = elements.len();
+        for (index, element) in elements.into_iter().enumerate() {
+            let obu = match self.fragment.take() {
+                Some(mut fragment) if index == 0 && header.continues_fragment => {
+                    fragment.extend_from_slice(&element);
+                    fragment
+                }
+                // Beginning of the fragment was not received.
+                None if index == 0 && header.continues_fragment => continue,
+                // Previous fragment was not continued, it can't be decoded.
+                _ => BytesMut::from(&element[..]),
+            };
+
+            if index + 1 == element_count && header.ends_with_fragment {
+                self.fragment = Some(obu);
+            } else {
+                self.push_obu(obu.freeze())?;
+            }
+        }
+
+        if !packet.header.marker {
+            // the marker bit is set on the last packet of a temporal unit
+            return Ok(Vec::new());
+        }
+
+        self.frame_timestamp = None;
+        self.fragment = None;
+        if self.buffer.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut data = BytesMut::from(&TEMPORAL_DELIMITER[..]);
+        data.extend_from_slice(&mem::take(&mut self.buffer));
+        let timestamp = self.rollover_state.timestamp(packet.header.timestamp);
+        Ok(vec![EncodedChunk {
+            data: data.freeze(),
+            pts: Duration::from_secs_f64(timestamp as f64 / AV1_CLOCK_RATE),
+            dts: None,
+            discontinuity: mem::take(&mut self.discontinuity),
+            kind: EncodedChunkKind::Video(VideoCodec::Av1),
+        }])
+    }
+
+    /// Temporal delimiters, tile lists and padding should not be sent, they are
+    /// dropped if they are.
+    fn push_obu(&mut self, data: Bytes) -> Result<(), Av1DepayloadingError> {
+        let (obu, _) = Obu::parse(&data)?;
+        match obu.obu_type() {
+            OBU_TEMPORAL_DELIMITER | OBU_TILE_LIST | OBU_PADDING => {}
+            _ => obu.write_with_size(&mut self.buffer),
+        }
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        self.buffer.clear();
+        self.fragment = None;
+        self.frame_timestamp = None;
+    }
+}
+
+/// Splits the payload into OBU elements. If `element_count` is 0 every element is
+/// preceded by its length, otherwise the last element spans the rest of the payload.
+fn read_elements(
+    mut payload: Bytes,
+    element_count: u8,
+) -> Result<Vec<Bytes>, Av1DepayloadingError> {
+    let mut elements = Vec::new();
+    while payload.has_remaining() {
+        if elements.len() + 1 == element_count as usize {
+            elements.push(mem::take(&mut payload));
+            break;
+        }
+        let (length, length_size) = read_leb128(&payload)?;
+        payload.advance(length_size);
+        let length = usize::try_from(length)
+            .ok()
+            .filter(|length| *length <= payload.len())
+            .ok_or(Av1DepayloadingError::PacketTooShort)?;
+        elements.push(payload.split_to(length));
+    }
+    Ok(elements)
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use rtp::{header::Header, packet::Packet};
+
+    use super::Av1Depayloader;
+
+    fn av1_packet(aggregation_header: u8, data: &[u8], timestamp: u32, marker: bool) -> Packet {
+        Packet {
+            header: Header {
+                timestamp,
+                marker,
+                payload_type: 96,
+                ..Default::default()
+            },
+            payload: Bytes::from([&[aggregation_header], data].concat()),
+        }
+    }
+
+    #[test]
+    fn aggregated_obus_are_converted_to_low_overhead_format() {
+        let mut depayloader = Av1Depayloader::default();
+
+        // W=2, N=1: sequence header with length, frame without length
+        let chunks = depayloader
+            .depayload(av1_packet(
+                0x28,
+                &[2, 0x08, 0xaa, 0x30, 0xbb, 0xcc],
+                90000,
+                true,
+            ))
+            .unwrap();
+
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(
+            chunks[0].data,
+            Bytes::from_static(&[0x12, 0x00, 0x0a, 0x01, 0xaa, 0x32, 0x02, 0xbb, 0xcc])
+        );
+        assert_eq!(chunks[0].pts.as_secs(), 1);
+    }
+
+    #[test]
+    fn fragmented_obu_is_joined() {
+        let mut depayloader = Av1Depayloader::default();
+
+        // W=1, Y=1
+        let chunks = depayloader
+            .depayload(av1_packet(0x50, &[0x30, 1, 2], 0, false))
+            .unwrap();
+        assert!(chunks.is_empty());
+        // W=1, Z=1, Y=1
+        depayloader
+            .depayload(av1_packet(0xd0, &[3, 4], 0, false))
+            .unwrap();
+        // W=0, Z=1: end of the fragment and a padding OBU, both with lengths
+        let chunks = depayloader
+            .depayload(av1_packet(0x80, &[1, 5, 2, 0x78, 0xff], 0, true))
+            .unwrap();
+
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(
+            chunks[0].data,
+            Bytes::from_static(&[0x12, 0x00, 0x32, 0x05, 1, 2, 3, 4, 5])
+        );
+    }
+
+    #[test]
+    fn temporal_unit_after_loss_is_discontinuous() {
+        let mut depayloader = Av1Depayloader::default();
+
+        depayloader
+            .depayload(av1_packet(0x50, &[0x30, 1, 1], 0, false))
+            .unwrap();
+        depayloader.on_packets_lost();
+
+        // Remaining part of the damaged temporal unit.
+        let chunks = depayloader
+            .depayload(av1_packet(0x90, &[2, 2], 0, true))
+            .unwrap();
+        assert!(chunks.is_empty());
+
+        let chunks = depayloader
+            .depayload(av1_packet(0x10, &[0x30, 3, 3], 3000, true))
+            .unwrap();
+        assert_eq!(chunks.len(), 1);
+        assert!(chunks[0].discontinuity);
+
+        let chunks = depayloader
+            .depayload(av1_packet(0x10, &[0x30, 4, 4], 6000, true))
+            .unwrap();
+        assert!(!chunks[0].discontinuity);
+    }
+}
@@ -0,0 +1,227 @@
+use std::{mem, time::Duration};
+
+use bytes::Bytes;
+use rtp::{
+    codecs::{vp8::Vp8Packet, vp9::Vp9Packet},
+    packetizer::Depacketizer,
+};
+use tracing::debug;
+
+use crate::pipeline::{
+    input::rtp::DepayloadingError,
+    types::{EncodedChunk, EncodedChunkKind},
+    VideoCodec,
+};
+
+use super::RolloverState;
+
+const VPX_CLOCK_RATE: f64 = 90000.0;
+
+/// Depayloader for VP8 ([RFC 7741](https://datatracker.ietf.org/doc/html/rfc7741)) and
+/// VP9 ([RFC 9628](https://datatracker.ietf.org/doc/html/rfc9628)) streams. Payloads are
+/// collected until the packet with a marker bit, so each chunk contains a whole frame.
+pub struct VpxDepayloader {
+    depacketizer: VpxDepacketizer,
+    buffer: Vec<Bytes>,
+    /// RTP timestamp of the frame that is currently collected in the buffer.
+    frame_timestamp: Option<u32>,
+    rollover_state: RolloverState,
+    /// Packets were lost after the last produced chunk.
+    discontinuity: bool,
+}
+
+enum VpxDepacketizer {
+    Vp8(Vp8Packet),
+    Vp9(Vp9Packet),
+}
+
+impl VpxDepayloader {
+    pub fn new_vp8() -> Self {
+        Self::new(VpxDepacketizer::Vp8(Vp8Packet::default()))
+    }
+
+    pub fn new_vp9() -> Self {
+        Self::new(VpxDepacketizer::Vp9(Vp9Packet::default()))
+    }
+
+    fn new(depacketizer: VpxDepacketizer) -> Self {
+        Self {
+            depacketizer,
+            buffer: vec![],
+            frame_timestamp: None,
+            rollover_state: RolloverState::default(),
+            discontinuity: false,
+        }
+    }
+
+    /// Drops the partially collected frame. Packets are skipped until the beginning
+    /// of the next frame.
+    pub fn on_packets_lost(&mut self) {
+        self.buffer.clear();
+        self.frame_timestamp = None;
+        self.discontinuity = true;
+    }
+
+    pub fn depayload(
+        &mut self,
+        packet: rtp::packet::Packet,
+    ) -> Result<Vec<EncodedChunk>, DepayloadingError> {
+        if packet.payload.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        if self
+            .frame_timestamp
+            .is_some_and(|timestamp| timestamp != packet.header.timestamp)
+        {
+            // Packet with a marker bit was lost, decoding a frame without the
+            // remaining data would produce artifacts.
+            debug!("Dropping incomplete {:?} frame.", self.codec());
+            self.buffer.clear();
+            self.frame_timestamp = None;
+        }
+
+        if self.frame_timestamp.is_none() {
+            if !self.depacketizer.is_frame_start(&packet.payload) {
+                // Wait for the beginning of the next frame.
+                return Ok(Vec::new());
+            }
+            self.frame_timestamp = Some(packet.header.timestamp);
+        }
+
+        let payload = self.depacketizer.depacketize(&packet.payload)?;
+        self.buffer.push(payload);
+        if !packet.header.marker {
+            // the marker bit is set on the last packet of a frame
+            return Ok(Vec::new());
+        }
+
+        self.frame_timestamp = None;
+        let timestamp = self.rollover_state.timestamp(packet.header.timestamp);
+        Ok(vec![EncodedChunk {
+            data: mem::take(&mut self.buffer).concat().into(),
+            pts: Duration::from_secs_f64(timestamp as f64 / VPX_CLOCK_RATE),
+            dts: None,
+            discontinuity: mem::take(&mut self.discontinuity),
+            kind: EncodedChunkKind::Video(self.codec()),
+        }])
+    }
+
+    fn codec(&self) -> VideoCodec {
+        match self.depacketizer {
+            VpxDepacketizer::Vp8(_) => VideoCodec::Vp8,
+            VpxDepacketizer::Vp9(_) => VideoCodec::Vp9,
+        }
+    }
+}
+
+impl VpxDepacketizer {
+    fn depacketize(&mut self, payload: &Bytes) -> Result<Bytes, rtp::Error> {
+        match self {
+            VpxDepacketizer::Vp8(depacketizer) => depacketizer.depacketize(payload),
+            VpxDepacketizer::Vp9(depacketizer) => depacketizer.depacketize(payload),
+        }
+    }
+
+    /// Checks the first byte of the payload descriptor.
+    fn is_frame_start(&self, payload: &[u8]) -> bool {
+        let Some(first_byte) = payload.first() else {
+            return false;
+        };
+        match self {
+            // |X|R|N|S|R| PID | - start of the first partition (S=1, PID=0)
+            VpxDepacketizer::Vp8(_) => first_byte & 0x10 != 0 && first_byte & 0x07 == 0,
+            // |I|P|L|F|B|E|V|Z| - beginning of a frame (B=1)
+            VpxDepacketizer::Vp9(_) => first_byte & 0x08 != 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use rtp::{header::Header, packet::Packet};
+
+    use crate::pipeline::{types::EncodedChunkKind, VideoCodec};
+
+    use super::VpxDepayloader;
+
+    fn vp8_packet(descriptor: u8, data: &[u8], timestamp: u32, marker: bool) -> Packet {
+        Packet {
+            header: Header {
+                timestamp,
+                marker,
+                payload_type: 96,
+                ..Default::default()
+            },
+            payload: Bytes::from([&[descriptor], data].concat()),
+        }
+    }
+
+    #[test]
+    fn vp8_frame_from_multiple_packets() {
+        let mut depayloader = VpxDepayloader::new_vp8();
+
+        let chunks = depayloader
+            .depayload(vp8_packet(0x10, &[1, 2, 3], 90000, false))
+            .unwrap();
+        assert!(chunks.is_empty());
+        let chunks = depayloader
+            .depayload(vp8_packet(0x00, &[4, 5, 6], 90000, true))
+            .unwrap();
+
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].data, Bytes::from_static(&[1, 2, 3, 4, 5, 6]));
+        assert_eq!(chunks[0].kind, EncodedChunkKind::Video(VideoCodec::Vp8));
+        assert_eq!(chunks[0].pts.as_secs(), 1);
+    }
+
+    #[test]
+    fn vp8_incomplete_frame_is_dropped() {
+        let mut depayloader = VpxDepayloader::new_vp8();
+
+        // Frame without the beginning.
+        let chunks = depayloader
+            .depayload(vp8_packet(0x00, &[1, 1, 1], 0, true))
+            .unwrap();
+        assert!(chunks.is_empty());
+
+        // Frame without the last packet.
+        depayloader
+            .depayload(vp8_packet(0x10, &[2, 2, 2], 3000, false))
+            .unwrap();
+
+        let chunks = depayloader
+            .depayload(vp8_packet(0x10, &[3, 3, 3], 6000, true))
+            .unwrap();
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].data, Bytes::from_static(&[3, 3, 3]));
+    }
+
+    #[test]
+    fn vp8_frame_after_loss_is_discontinuous() {
+        let mut depayloader = VpxDepayloader::new_vp8();
+
+        depayloader
+            .depayload(vp8_packet(0x10, &[1, 1, 1], 0, false))
+            .unwrap();
+        depayloader.on_packets_lost();
+
+        // Remaining part of the damaged frame.
+        let chunks = depayloader
+            .depayload(vp8_packet(0x00, &[2, 2, 2], 0, true))
+            .unwrap();
+        assert!(chunks.is_empty());
+
+        let chunks = depayloader
+            .depayload(vp8_packet(0x10, &[3, 3, 3], 3000, true))
+            .unwrap();
+        assert_eq!(chunks.len(), 1);
+        assert!(chunks[0].discontinuity);
+
+        let chunks = depayloader
+            .depayload(vp8_packet(0x10, &[4, 4, 4], 6000, true))
+            .unwrap();
+        assert!(!chunks[0].discontinuity);
+    }
+}
@@ -0,0 +1,368 @@
+use std::{
+    collections::HashMap,
+    io::Write,
+    net::{SocketAddr, TcpStream, UdpSocket},
+    ops::Range,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use rtcp::{
+    payload_feedbacks::{
+        full_intra_request::{FirEntry, FullIntraRequest},
+        picture_loss_indication::PictureLossIndication,
+    },
+    receiver_report::ReceiverReport,
+    reception_report::ReceptionReport,
+    sender_report::SenderReport,
+    transport_feedbacks::transport_layer_nack::{
+        nack_pairs_from_sequence_numbers, TransportLayerNack,
+    },
+};
+use tracing::{debug, trace, warn};
+
+use crate::pipeline::rtp::srtp::SrtpContext;
+
+use super::jitter_buffer::JitterBuffer;
+
+const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+/// If the keyframe did not arrive after this time, the request is repeated.
+const KEYFRAME_REQUEST_INTERVAL: Duration = Duration::from_secs(1);
+/// Larger gaps are most likely caused by a restart of the sender, so
+/// retransmission is not requested.
+const MAX_NACKED_PACKETS: usize = 128;
+/// Reception report blocks that fit in a single receiver report.
+const MAX_REPORTS_PER_PACKET: usize = 31;
+
+type RtcpPackets = Vec<Box<dyn rtcp::packet::Packet + Send + Sync>>;
+
+/// Sends RTCP packets back to the peer that sends the RTP stream.
+#[derive(Clone, Default)]
+pub(crate) struct RtcpSender {
+    peer: Arc<Mutex<Option<RtcpPeer>>>,
+    srtp: Option<Arc<Mutex<SrtpContext>>>,
+}
+
+enum RtcpPeer {
+    Udp {
+        socket: Arc<UdpSocket>,
+        address: SocketAddr,
+    },
+    /// Packets are framed the same way as received ones ([RFC 4571](https://datatracker.ietf.org/doc/html/rfc4571)).
+    Tcp(TcpStream),
+    /// Serialized packets are sent over the WebRTC session by its own task.
+    WebRtc(tokio::sync::mpsc::UnboundedSender<bytes::Bytes>),
+}
+
+impl RtcpSender {
+    pub fn new(srtp: Option<SrtpContext>) -> Self {
+        Self {
+            peer: Arc::default(),
+            srtp: srtp.map(|srtp| Arc::new(Mutex::new(srtp))),
+        }
+    }
+
+    pub fn set_udp_peer(&self, socket: Arc<UdpSocket>, address: SocketAddr) {
+        *self.peer.lock().unwrap() = Some(RtcpPeer::Udp { socket, address });
+    }
+
+    pub fn set_tcp_peer(&self, stream: TcpStream) {
+        *self.peer.lock().unwrap() = Some(RtcpPeer::Tcp(stream));
+    }
+
+    pub fn set_webrtc_peer(&self, sender: tokio::sync::mpsc::UnboundedSender<bytes::Bytes>) {
+        *self.peer.lock().unwrap() = Some(RtcpPeer::WebRtc(sender));
+    }
+
+    fn send(&self, packets: &RtcpPackets) {
+        let data = match rtcp::packet::marshal(packets) {
+            Ok(data) => data,
+            Err(err) => {
+                warn!(%err, "Failed to serialize RTCP packets.");
+                return;
+            }
+        };
+        let data = match &self.srtp {
+            Some(srtp) => match srtp.lock().unwrap().protect(&data) {
+                Ok(data) => data,
+                Err(err) => {
+                    warn!(%err, "Failed to encrypt RTCP packets.");
+                    return;
+                }
+            },
+            None => data,
+        };
+
+        let mut peer = self.peer.lock().unwrap();
+        let result = match peer.as_mut() {
+            Some(RtcpPeer::Udp { socket, address }) => socket.send_to(&data, *address).map(|_| ()),
+            Some(RtcpPeer::Tcp(stream)) => {
+                let framed = [&(data.len() as u16).to_be_bytes()[..], &data].concat();
+                stream.write_all(&framed)
+            }
+            Some(RtcpPeer::WebRtc(sender)) => sender
+                .send(data)
+                .map_err(|_| std::io::Error::from(std::io::ErrorKind::NotConnected)),
+            None => return,
+        };
+        if let Err(err) = result {
+            debug!(%err, "Failed to send RTCP packets.");
+            if let Some(RtcpPeer::Tcp(_)) = *peer {
+                // Partially written packet would break the framing.
+                *peer = None;
+            }
+        }
+    }
+}
+
+/// Receiver side of the RTP session. It sends reception reports for received
+/// streams, requests retransmission of missing packets (NACK) and requests keyframes
+/// (PLI/FIR) when the video decoder can not continue without one.
+pub(super) struct RtcpFeedback {
+    sender: RtcpSender,
+    ssrc: u32,
+    streams: HashMap<u32, StreamFeedbackState>,
+    /// Reference point for arrival times used in the jitter calculation.
+    start: Instant,
+    next_report_at: Instant,
+    /// Video can not be decoded until the next keyframe, e.g. at the start of the
+    /// stream or after video packets were lost.
+    waiting_for_keyframe: bool,
+    last_keyframe_request: Option<Instant>,
+    fir_sequence_number: u8,
+}
+
+#[derive(Default)]
+struct StreamFeedbackState {
+    /// Difference between arrival time and RTP timestamp of the previous packet.
+    last_transit: Option<u32>,
+    /// Interarrival jitter ([RFC 3550, section 6.4.1](https://datatracker.ietf.org/doc/html/rfc3550#section-6.4.1))
+    jitter: f64,
+    /// Middle 32 bits of the NTP timestamp of the last sender report and its arrival time.
+    last_sender_report: Option<(u32, Instant)>,
+    expected_prior: u64,
+    received_prior: u64,
+}
+
+impl RtcpFeedback {
+    pub fn new(sender: RtcpSender) -> Self {
+        let now = Instant::now();
+        Self {
+            sender,
+            ssrc: rand::random(),
+            streams: HashMap::new(),
+            start: now,
+            next_report_at: now + REPORT_INTERVAL,
+            waiting_for_keyframe: true,
+            last_keyframe_request: None,
+            fir_sequence_number: 0,
+        }
+    }
+
+    pub fn next_report_at(&self) -> Instant {
+        self.next_report_at
+    }
+
+    pub fn on_rtp_packet(
+        &mut self,
+        header: &rtp::header::Header,
+        clock_rate: Option<u32>,
+        received_at: Instant,
+    ) {
+        let stream = self.streams.entry(header.ssrc).or_default();
+        let Some(clock_rate) = clock_rate else {
+            return;
+        };
+
+        // RFC 3550, appendix A.8
+        let arrival = (received_at.duration_since(self.start).as_secs_f64() * clock_rate as f64)
+            as u64 as u32;
+        let transit = arrival.wrapping_sub(header.timestamp);
+        if let Some(last_transit) = stream.last_transit {
+            let diff = (transit.wrapping_sub(last_transit) as i32).unsigned_abs() as f64;
+            stream.jitter += (diff - stream.jitter) / 16.0;
+        }
+        stream.last_transit = Some(transit);
+    }
+
+    pub fn on_sender_report(&mut self, report: &SenderReport, received_at: Instant) {
+        let stream = self.streams.entry(report.ssrc).or_default();
+        stream.last_sender_report = Some(((report.ntp_time >> 16) as u32, received_at));
+    }
+
+    /// Requests retransmission of packets with extended sequence numbers in `missing`.
+    pub fn on_packets_missing(&mut self, media_ssrc: u32, missing: Range<u64>) {
+        let count = (missing.end - missing.start) as usize;
+        if count == 0 || count > MAX_NACKED_PACKETS {
+            return;
+        }
+        let sequence_numbers: Vec<u16> = missing.map(|number| number as u16).collect();
+        trace!(media_ssrc, ?sequence_numbers, "Sending NACK.");
+        let packets: RtcpPackets = vec![Box::new(TransportLayerNack {
+            sender_ssrc: self.ssrc,
+            media_ssrc,
+            nacks: nack_pairs_from_sequence_numbers(&sequence_numbers),
+        })];
+        self.sender.send(&packets);
+    }
+
+    /// Video packets were lost, so the decoder lost its reference frames.
+    pub fn on_video_packets_lost(&mut self) {
+        self.waiting_for_keyframe = true;
+    }
+
+    pub fn on_video_chunk(&mut self, media_ssrc: Option<u32>, is_keyframe: bool) {
+        if is_keyframe {
+            self.waiting_for_keyframe = false;
+            self.last_keyframe_request = None;
+            return;
+        }
+        let Some(media_ssrc) = media_ssrc else {
+            return;
+        };
+        if !self.waiting_for_keyframe {
+            return;
+        }
+
+        let now = Instant::now();
+        let is_retry = match self.last_keyframe_request {
+            Some(requested_at) if now - requested_at < KEYFRAME_REQUEST_INTERVAL => return,
+            Some(_) => true,
+            None => false,
+        };
+
+        let mut packets: RtcpPackets = vec![Box::new(PictureLossIndication {
+            sender_ssrc: self.ssrc,
+            media_ssrc,
+        })];
+        if is_retry {
+            // PLI was ignored, some senders only handle FIR.
+            self.fir_sequence_number = self.fir_sequence_number.wrapping_add(1);
+            packets.push(Box::new(FullIntraRequest {
+                sender_ssrc: self.ssrc,
+                media_ssrc,
+                fir: vec![FirEntry {
+                    ssrc: media_ssrc,
+                    sequence_number: self.fir_sequence_number,
+                }],
+            }));
+        }
+        debug!(media_ssrc, "Requesting keyframe.");
+        self.sender.send(&packets);
+        self.last_keyframe_request = Some(now);
+    }
+
+    /// Sends receiver reports if the report interval has passed.
+    pub fn maybe_send_reports(&mut self, jitter_buffers: &HashMap<u32, JitterBuffer>) {
+        let now = Instant::now();
+        if now < self.next_report_at {
+            return;
+        }
+        self.next_report_at = now + REPORT_INTERVAL;
+
+        let reports: Vec<_> = jitter_buffers
+            .iter()
+            .filter_map(|(ssrc, jitter_buffer)| self.reception_report(*ssrc, jitter_buffer, now))
+            .collect();
+        if reports.is_empty() {
+            return;
+        }
+
+        let packets: RtcpPackets = reports
+            .chunks(MAX_REPORTS_PER_PACKET)
+            .map(|reports| -> Box<dyn rtcp::packet::Packet + Send + Sync> {
+                Box::new(ReceiverReport {
+                    ssrc: self.ssrc,
+                    reports: reports.to_vec(),
+                    ..Default::default()
+                })
+            })
+            .collect();
+        self.sender.send(&packets);
+    }
+
+    fn reception_report(
+        &mut self,
+        ssrc: u32,
+        jitter_buffer: &JitterBuffer,
+        now: Instant,
+    ) -> Option<ReceptionReport> {
+        let last_sequence_number = jitter_buffer.extended_highest_sequence_number()?;
+        let stream = self.streams.entry(ssrc).or_default();
+
+        let expected = jitter_buffer.expected_packets();
+        let received = jitter_buffer.stats().received;
+        let expected_interval = expected.saturating_sub(stream.expected_prior);
+        let received_interval = received.saturating_sub(stream.received_prior);
+        stream.expected_prior = expected;
+        stream.received_prior = received;
+
+        let lost_interval = expected_interval.saturating_sub(received_interval);
+        let fraction_lost = match expected_interval {
+            0 => 0,
+            _ => ((lost_interval << 8) / expected_interval).min(255) as u8,
+        };
+        // Cumulative number of lost packets is a 24-bit field.
+        let total_lost = expected.saturating_sub(received).min(0x7fffff) as u32;
+
+        let (last_sender_report, delay) = match stream.last_sender_report {
+            // Delay since the last sender report in units of 1/65536 seconds.
+            Some((ntp, received_at)) => (ntp, ((now - received_at).as_secs_f64() * 65536.0) as u32),
+            None => (0, 0),
+        };
+
+        Some(ReceptionReport {
+            ssrc,
+            fraction_lost,
+            total_lost,
+            last_sequence_number,
+            jitter: stream.jitter as u32,
+            last_sender_report,
+            delay,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use rtp::{header::Header, packet::Packet};
+
+    use crate::pipeline::input::rtp::jitter_buffer::JitterBuffer;
+
+    use super::{RtcpFeedback, RtcpSender};
+
+    fn packet(sequence_number: u16) -> Packet {
+        Packet {
+            header: Header {
+                sequence_number,
+                ssrc: 1,
+                payload_type: 96,
+                ..Default::default()
+            },
+            payload: vec![0].into(),
+        }
+    }
+
+    #[test]
+    fn reception_report_counts_lost_packets() {
+        let now = Instant::now();
+        let mut feedback = RtcpFeedback::new(RtcpSender::default());
+        let mut jitter_buffer = JitterBuffer::new(Duration::from_millis(100), 1, 96);
+        for sequence_number in [1, 2, 4] {
+            jitter_buffer.push(packet(sequence_number), now);
+        }
+
+        let report = feedback.reception_report(1, &jitter_buffer, now).unwrap();
+        assert_eq!(report.last_sequence_number, 4);
+        assert_eq!(report.total_lost, 1);
+        assert_eq!(report.fraction_lost, 64);
+
+        // Nothing was lost since the previous report.
+        jitter_buffer.push(packet(5), now);
+        let report = feedback.reception_report(1, &jitter_buffer, now).unwrap();
+        assert_eq!(report.total_lost, 1);
+        assert_eq!(report.fraction_lost, 0);
+    }
+}
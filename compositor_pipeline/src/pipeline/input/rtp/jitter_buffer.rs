@@ -0,0 +1,290 @@
+use std::{
+    collections::BTreeMap,
+    ops::Range,
+    time::{Duration, Instant},
+};
+
+/// If more packets are waiting for a missing one, the gap is reported as lost
+/// without waiting for the latency to pass.
+const MAX_BUFFERED_PACKETS: usize = 1000;
+
+const SEQUENCE_NUMBER_CYCLE: u64 = 1 << 16;
+
+/// Reorders RTP packets of a single stream (SSRC) by sequence number.
+///
+/// Packets are released as soon as all preceding packets were released, so in-order
+/// streams are not delayed. When a packet is missing, following packets are held for
+/// at most `latency`. After that the gap is reported as lost and packets that arrive
+/// later are dropped.
+pub(super) struct JitterBuffer {
+    latency: Duration,
+    packets: BTreeMap<u64, BufferedPacket>,
+    /// Extended sequence number of the next packet that should be released.
+    next_sequence_number: Option<u64>,
+    /// Highest extended sequence number received so far.
+    highest_sequence_number: Option<u64>,
+    /// Extended sequence number of the first received packet.
+    base_sequence_number: Option<u64>,
+    stats: RtpStreamStats,
+}
+
+struct BufferedPacket {
+    packet: rtp::packet::Packet,
+    received_at: Instant,
+}
+
+#[derive(Debug)]
+pub(super) enum JitterBufferEvent {
+    Packet(rtp::packet::Packet),
+    /// `count` consecutive packets were lost. It is emitted before the first packet
+    /// after the gap.
+    Lost {
+        count: u64,
+    },
+}
+
+/// Counters of a single RTP stream received by an input.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RtpStreamStats {
+    pub ssrc: u32,
+    pub payload_type: u8,
+    /// Number of all received packets, including duplicated and late ones.
+    pub received: u64,
+    /// Number of packets that did not arrive within the jitter buffer latency.
+    pub lost: u64,
+    /// Number of packets that arrived after they were already reported as lost.
+    pub late: u64,
+    pub duplicated: u64,
+    /// Number of packets that arrived out of order, but early enough to be reordered.
+    pub reordered: u64,
+}
+
+impl JitterBuffer {
+    pub fn new(latency: Duration, ssrc: u32, payload_type: u8) -> Self {
+        Self {
+            latency,
+            packets: BTreeMap::new(),
+            next_sequence_number: None,
+            highest_sequence_number: None,
+            base_sequence_number: None,
+            stats: RtpStreamStats {
+                ssrc,
+                payload_type,
+                ..Default::default()
+            },
+        }
+    }
+
+    pub fn stats(&self) -> RtpStreamStats {
+        self.stats
+    }
+
+    /// Returns extended sequence numbers of packets that are missing since this packet
+    /// arrived. It is empty if the packet did not open a new gap.
+    pub fn push(&mut self, packet: rtp::packet::Packet, received_at: Instant) -> Range<u64> {
+        self.stats.received += 1;
+        let sequence_number = self.extended_sequence_number(packet.header.sequence_number);
+
+        let next_sequence_number = *self.next_sequence_number.get_or_insert(sequence_number);
+        self.base_sequence_number.get_or_insert(sequence_number);
+        if sequence_number < next_sequence_number {
+            self.stats.late += 1;
+            return 0..0;
+        }
+        if self.packets.contains_key(&sequence_number) {
+            self.stats.duplicated += 1;
+            return 0..0;
+        }
+
+        let missing = match self.highest_sequence_number {
+            Some(highest) if sequence_number < highest => {
+                self.stats.reordered += 1;
+                0..0
+            }
+            Some(highest) => {
+                self.highest_sequence_number = Some(sequence_number);
+                (highest + 1)..sequence_number
+            }
+            None => {
+                self.highest_sequence_number = Some(sequence_number);
+                0..0
+            }
+        };
+        self.packets.insert(
+            sequence_number,
+            BufferedPacket {
+                packet,
+                received_at,
+            },
+        );
+        missing
+    }
+
+    /// Extended highest sequence number as defined for RTCP reception reports. The
+    /// upper 16 bits count sequence number cycles.
+    pub fn extended_highest_sequence_number(&self) -> Option<u32> {
+        let highest = self.highest_sequence_number?;
+        Some(highest.saturating_sub(SEQUENCE_NUMBER_CYCLE) as u32)
+    }
+
+    /// Number of packets that should have been received so far, based on the range of
+    /// received sequence numbers.
+    pub fn expected_packets(&self) -> u64 {
+        match (self.base_sequence_number, self.highest_sequence_number) {
+            (Some(base), Some(highest)) => highest - base + 1,
+            _ => 0,
+        }
+    }
+
+    /// Returns the next packet or a gap if packets after it waited longer than the latency.
+    pub fn pop(&mut self, now: Instant) -> Option<JitterBufferEvent> {
+        let deadline_passed = self
+            .deadline()
+            .is_some_and(|deadline| deadline <= now || self.packets.len() > MAX_BUFFERED_PACKETS);
+        self.pop_inner(deadline_passed)
+    }
+
+    /// Returns buffered data without waiting for missing packets, e.g. when
+    /// the stream has ended.
+    pub fn pop_without_waiting(&mut self) -> Option<JitterBufferEvent> {
+        self.pop_inner(true)
+    }
+
+    /// Time when the gap before buffered packets will be reported as lost. `None`
+    /// if nothing is waiting.
+    pub fn deadline(&self) -> Option<Instant> {
+        let next_sequence_number = self.next_sequence_number?;
+        let (first_sequence_number, _) = self.packets.first_key_value()?;
+        if *first_sequence_number == next_sequence_number {
+            return None;
+        }
+        // All buffered packets arrived while waiting for the missing one.
+        self.packets
+            .values()
+            .map(|packet| packet.received_at + self.latency)
+            .min()
+    }
+
+    fn pop_inner(&mut self, skip_missing: bool) -> Option<JitterBufferEvent> {
+        let next_sequence_number = self.next_sequence_number?;
+        let first_sequence_number = *self.packets.first_key_value()?.0;
+
+        if first_sequence_number == next_sequence_number {
+            let (_, packet) = self.packets.pop_first()?;
+            self.next_sequence_number = Some(next_sequence_number + 1);
+            return Some(JitterBufferEvent::Packet(packet.packet));
+        }
+        if !skip_missing {
+            return None;
+        }
+
+        let count = first_sequence_number - next_sequence_number;
+        self.stats.lost += count;
+        self.next_sequence_number = Some(first_sequence_number);
+        Some(JitterBufferEvent::Lost { count })
+    }
+
+    /// Extends 16-bit sequence number with the rollover count, choosing the value
+    /// closest to the highest received one.
+    fn extended_sequence_number(&self, sequence_number: u16) -> u64 {
+        const CYCLE: u64 = SEQUENCE_NUMBER_CYCLE;
+        let Some(reference) = self.highest_sequence_number.or(self.next_sequence_number) else {
+            // Start from the second cycle, so packets reordered before the first
+            // one can still be represented.
+            return CYCLE + sequence_number as u64;
+        };
+
+        let candidate = (reference & !(CYCLE - 1)) | sequence_number as u64;
+        [
+            candidate.saturating_sub(CYCLE),
+            candidate,
+            candidate + CYCLE,
+        ]
+        .into_iter()
+        .min_by_key(|value| value.abs_diff(reference))
+        .unwrap_or(candidate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use rtp::{header::Header, packet::Packet};
+
+    use super::{JitterBuffer, JitterBufferEvent};
+
+    const LATENCY: Duration = Duration::from_millis(100);
+
+    fn packet(sequence_number: u16) -> Packet {
+        Packet {
+            header: Header {
+                sequence_number,
+                payload_type: 96,
+                ..Default::default()
+            },
+            payload: vec![0].into(),
+        }
+    }
+
+    /// Returns sequence numbers of released packets, gaps are represented as `None`.
+    fn pop_all(buffer: &mut JitterBuffer, now: Instant) -> Vec<Option<u16>> {
+        std::iter::from_fn(|| buffer.pop(now))
+            .map(|event| match event {
+                JitterBufferEvent::Packet(packet) => Some(packet.header.sequence_number),
+                JitterBufferEvent::Lost { .. } => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reorders_packets() {
+        let start = Instant::now();
+        let mut buffer = JitterBuffer::new(LATENCY, 1, 96);
+
+        buffer.push(packet(10), start);
+        buffer.push(packet(12), start);
+        assert_eq!(pop_all(&mut buffer, start), [Some(10)]);
+
+        buffer.push(packet(11), start);
+        assert_eq!(pop_all(&mut buffer, start), [Some(11), Some(12)]);
+        assert_eq!(buffer.stats().reordered, 1);
+        assert_eq!(buffer.stats().lost, 0);
+    }
+
+    #[test]
+    fn reports_loss_after_latency() {
+        let start = Instant::now();
+        let mut buffer = JitterBuffer::new(LATENCY, 1, 96);
+
+        buffer.push(packet(1), start);
+        let missing = buffer.push(packet(4), start);
+        assert_eq!(missing.count(), 2);
+        assert_eq!(pop_all(&mut buffer, start), [Some(1)]);
+        assert_eq!(buffer.deadline(), Some(start + LATENCY));
+
+        assert_eq!(pop_all(&mut buffer, start + LATENCY), [None, Some(4)]);
+        assert_eq!(buffer.stats().lost, 2);
+        assert_eq!(buffer.expected_packets(), 4);
+
+        // Packet that arrives after it was reported as lost is dropped.
+        buffer.push(packet(2), start + LATENCY);
+        assert_eq!(pop_all(&mut buffer, start + LATENCY), []);
+        assert_eq!(buffer.stats().late, 1);
+    }
+
+    #[test]
+    fn sequence_number_rollover() {
+        let start = Instant::now();
+        let mut buffer = JitterBuffer::new(LATENCY, 1, 96);
+
+        buffer.push(packet(u16::MAX), start);
+        buffer.push(packet(1), start);
+        buffer.push(packet(0), start);
+        assert_eq!(
+            pop_all(&mut buffer, start),
+            [Some(u16::MAX), Some(0), Some(1)]
+        );
+        assert_eq!(buffer.stats().lost, 0);
+    }
+}
@@ -0,0 +1,164 @@
+use std::time::{Duration, Instant};
+
+use rtcp::sender_report::SenderReport;
+use tracing::debug;
+
+/// Smaller differences between the current and NTP based alignment are ignored,
+/// so jitter of sender reports does not cause jumps in video timestamps.
+const SYNC_TOLERANCE: f64 = 0.010;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Track {
+    Video,
+    Audio,
+}
+
+/// Maps timestamps of audio and video of an RTP input onto a common timeline.
+///
+/// RTP timestamps of each track start at a random value, so initially tracks are
+/// aligned based on the arrival time of their first chunks. When sender reports for
+/// both tracks are received, video is shifted so its relation to audio matches
+/// the NTP timestamps of the sender. Audio is never moved to avoid audible gaps.
+pub(super) struct LipSync {
+    start: Instant,
+    video: TrackTimeline,
+    audio: TrackTimeline,
+}
+
+struct TrackTimeline {
+    clock_rate: Option<u32>,
+    /// Added to the RTP based pts (in seconds) to get the pts on the common timeline.
+    offset: Option<f64>,
+    /// RTP based pts of the last chunk.
+    last_pts: Option<Duration>,
+    /// NTP time (in seconds) and RTP timestamp from the last sender report.
+    sender_report: Option<(f64, u32)>,
+}
+
+impl LipSync {
+    pub fn new(video_clock_rate: Option<u32>, audio_clock_rate: Option<u32>) -> Self {
+        Self {
+            start: Instant::now(),
+            video: TrackTimeline::new(video_clock_rate),
+            audio: TrackTimeline::new(audio_clock_rate),
+        }
+    }
+
+    /// Converts pts calculated from RTP timestamps to the common timeline.
+    pub fn map_pts(&mut self, track: Track, pts: Duration, received_at: Instant) -> Duration {
+        let elapsed = received_at.duration_since(self.start).as_secs_f64();
+        let timeline = self.track(track);
+        timeline.last_pts = Some(pts);
+        if timeline.offset.is_none() {
+            timeline.offset = Some(elapsed - pts.as_secs_f64());
+            self.synchronize();
+        }
+        let offset = self.track(track).offset.unwrap_or_default();
+        Duration::from_secs_f64((pts.as_secs_f64() + offset).max(0.0))
+    }
+
+    pub fn on_sender_report(&mut self, track: Track, report: &SenderReport) {
+        let ntp_time =
+            (report.ntp_time >> 32) as f64 + (report.ntp_time as u32) as f64 / 2f64.powi(32);
+        self.track(track).sender_report = Some((ntp_time, report.rtp_time));
+        self.synchronize();
+    }
+
+    fn synchronize(&mut self) {
+        let (Some(video_offset), Some(audio_offset)) = (self.video.offset, self.audio.offset)
+        else {
+            return;
+        };
+        let (Some(video_ntp_offset), Some(audio_ntp_offset)) =
+            (self.video.ntp_offset(), self.audio.ntp_offset())
+        else {
+            return;
+        };
+
+        // Difference of offsets has to be the same on the common timeline and in NTP time.
+        let synchronized_video_offset = audio_offset + video_ntp_offset - audio_ntp_offset;
+        let correction = synchronized_video_offset - video_offset;
+        if correction.abs() > SYNC_TOLERANCE {
+            debug!(correction, "Synchronizing video with audio of RTP input.");
+            self.video.offset = Some(synchronized_video_offset);
+        }
+    }
+
+    fn track(&mut self, track: Track) -> &mut TrackTimeline {
+        match track {
+            Track::Video => &mut self.video,
+            Track::Audio => &mut self.audio,
+        }
+    }
+}
+
+impl TrackTimeline {
+    fn new(clock_rate: Option<u32>) -> Self {
+        Self {
+            clock_rate,
+            offset: None,
+            last_pts: None,
+            sender_report: None,
+        }
+    }
+
+    /// Value that added to the RTP based pts (in seconds) gives the NTP time of the sender.
+    fn ntp_offset(&self) -> Option<f64> {
+        let (ntp_time, rtp_timestamp) = self.sender_report?;
+        let clock_rate = self.clock_rate? as f64;
+        let last_pts = self.last_pts?.as_secs_f64();
+
+        // RTP based pts is an unwrapped RTP timestamp, so the report timestamp is
+        // expressed relative to the last chunk.
+        let last_rtp_timestamp = (last_pts * clock_rate).round() as u64 as u32;
+        let report_pts =
+            last_pts + rtp_timestamp.wrapping_sub(last_rtp_timestamp) as i32 as f64 / clock_rate;
+        Some(ntp_time - report_pts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use rtcp::sender_report::SenderReport;
+
+    use super::{LipSync, Track};
+
+    fn assert_pts(pts: Duration, expected: Duration) {
+        assert!(
+            (pts.as_secs_f64() - expected.as_secs_f64()).abs() < 1e-6,
+            "{pts:?} != {expected:?}"
+        );
+    }
+
+    fn sender_report(ntp_seconds: u64, rtp_time: u32) -> SenderReport {
+        SenderReport {
+            ntp_time: ntp_seconds << 32,
+            rtp_time,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn video_is_aligned_to_audio_after_sender_reports() {
+        let mut lip_sync = LipSync::new(Some(90_000), Some(48_000));
+        let start = lip_sync.start;
+        let secs = Duration::from_secs;
+
+        // Frames with RTP timestamps 90_000 (video) and 48_000 (audio) were captured
+        // at the same moment, but audio arrived 200ms later.
+        let video_pts = lip_sync.map_pts(Track::Video, secs(1), start);
+        let audio_pts = lip_sync.map_pts(Track::Audio, secs(1), start + Duration::from_millis(200));
+        assert_pts(video_pts, Duration::ZERO);
+        assert_pts(audio_pts, Duration::from_millis(200));
+
+        lip_sync.on_sender_report(Track::Video, &sender_report(100, 90_000));
+        lip_sync.on_sender_report(Track::Audio, &sender_report(100, 48_000));
+
+        let video_pts = lip_sync.map_pts(Track::Video, secs(2), start + secs(1));
+        let audio_pts = lip_sync.map_pts(Track::Audio, secs(2), start + secs(1));
+        assert_pts(video_pts, Duration::from_millis(1200));
+        assert_pts(audio_pts, Duration::from_millis(1200));
+    }
+}
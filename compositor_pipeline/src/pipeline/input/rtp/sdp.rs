@@ -0,0 +1,168 @@
+use bytes::Bytes;
+
+use crate::pipeline::{
+    decoder::{
+        AacDecoderOptions, AacDepayloaderMode, AudioDecoderOptions, OpusDecoderOptions,
+        VideoDecoderOptions,
+    },
+    rtp::sdp::{MediaFormat, MediaKind, SdpParseError, SessionDescription},
+    VideoCodec,
+};
+
+use super::{InputAudioStream, InputVideoStream, RtpStream};
+
+impl RtpStream {
+    /// Derives codecs and payload types from an SDP offer. The first supported
+    /// format of each media type is used.
+    pub fn from_sdp(sdp: &SessionDescription) -> Result<Self, SdpParseError> {
+        let formats = |kind| sdp.media.iter().filter(move |format| format.kind == kind);
+
+        let video = formats(MediaKind::Video).find_map(video_stream);
+        if video.is_none() {
+            if let Some(codecs) = unsupported_codecs(formats(MediaKind::Video)) {
+                return Err(SdpParseError::UnsupportedVideoCodecs(codecs));
+            }
+        }
+
+        let mut audio = None;
+        for format in formats(MediaKind::Audio) {
+            if let Some(stream) = audio_stream(format)? {
+                audio = Some(stream);
+                break;
+            }
+        }
+        if audio.is_none() {
+            if let Some(codecs) = unsupported_codecs(formats(MediaKind::Audio)) {
+                return Err(SdpParseError::UnsupportedAudioCodecs(codecs));
+            }
+        }
+
+        if video.is_none() && audio.is_none() {
+            return Err(SdpParseError::NoMedia);
+        }
+        Ok(Self { video, audio })
+    }
+}
+
+fn video_stream(format: &MediaFormat) -> Option<InputVideoStream> {
+    let codec = match format.encoding_name.to_ascii_uppercase().as_str() {
+        "H264" => VideoCodec::H264,
+        "VP8" => VideoCodec::Vp8,
+        "VP9" => VideoCodec::Vp9,
+        "AV1" => VideoCodec::Av1,
+        _ => return None,
+    };
+    Some(InputVideoStream {
+        options: VideoDecoderOptions { codec },
+        payload_type: format.payload_type,
+    })
+}
+
+fn audio_stream(format: &MediaFormat) -> Result<Option<InputAudioStream>, SdpParseError> {
+    let invalid = |message| SdpParseError::InvalidFormatParameters {
+        payload_type: format.payload_type,
+        message,
+    };
+    let options = match format.encoding_name.to_ascii_uppercase().as_str() {
+        "OPUS" => AudioDecoderOptions::Opus(OpusDecoderOptions {
+            forward_error_correction: format.fmtp_parameter("useinbandfec") == Some("1"),
+        }),
+        // RFC 3640, section 4.1
+        "MPEG4-GENERIC" => {
+            let depayloader_mode = match format.fmtp_parameter("mode") {
+                Some(mode) if mode.eq_ignore_ascii_case("AAC-hbr") => {
+                    AacDepayloaderMode::HighBitrate
+                }
+                Some(mode) if mode.eq_ignore_ascii_case("AAC-lbr") => {
+                    AacDepayloaderMode::LowBitrate
+                }
+                _ => return Err(invalid("only AAC-hbr and AAC-lbr modes are supported")),
+            };
+            let asc = format
+                .fmtp_parameter("config")
+                .and_then(parse_hex)
+                .filter(|asc| !asc.is_empty())
+                .ok_or_else(|| invalid("\"config\" has to contain AudioSpecificConfig"))?;
+            AudioDecoderOptions::Aac(AacDecoderOptions {
+                depayloader_mode: Some(depayloader_mode),
+                asc: Some(asc),
+            })
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(InputAudioStream {
+        options,
+        payload_type: format.payload_type,
+    }))
+}
+
+/// Comma separated encoding names, `None` if there are no formats.
+fn unsupported_codecs<'a>(formats: impl Iterator<Item = &'a MediaFormat>) -> Option<String> {
+    let codecs: Vec<_> = formats
+        .map(|format| format.encoding_name.as_str())
+        .collect();
+    (!codecs.is_empty()).then(|| codecs.join(", "))
+}
+
+fn parse_hex(value: &str) -> Option<Bytes> {
+    if value.len() % 2 != 0 || !value.is_ascii() {
+        return None;
+    }
+    (0..value.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&value[i..i + 2], 16).ok())
+        .collect::<Option<Vec<u8>>>()
+        .map(Bytes::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::pipeline::{
+        decoder::{AacDecoderOptions, AacDepayloaderMode, AudioDecoderOptions},
+        input::rtp::RtpStream,
+        rtp::sdp::SessionDescription,
+        VideoCodec,
+    };
+
+    fn parse(lines: &[&str]) -> RtpStream {
+        let sdp = SessionDescription::parse(&lines.join("\r\n")).unwrap();
+        RtpStream::from_sdp(&sdp).unwrap()
+    }
+
+    #[test]
+    fn first_supported_codec_is_used() {
+        let stream = parse(&[
+            "v=0",
+            "m=video 5004 RTP/AVP 100 101",
+            "a=rtpmap:100 AV2/90000",
+            "a=rtpmap:101 VP8/90000",
+            "m=audio 5004 RTP/AVP 111",
+            "a=rtpmap:111 MPEG4-GENERIC/48000/2",
+            "a=fmtp:111 streamtype=5;mode=AAC-hbr;config=1190",
+        ]);
+
+        let video = stream.video.unwrap();
+        assert_eq!(video.options.codec, VideoCodec::Vp8);
+        assert_eq!(video.payload_type, 101);
+
+        let audio = stream.audio.unwrap();
+        assert_eq!(audio.payload_type, 111);
+        assert_eq!(
+            audio.options,
+            AudioDecoderOptions::Aac(AacDecoderOptions {
+                depayloader_mode: Some(AacDepayloaderMode::HighBitrate),
+                asc: Some(Bytes::from_static(&[0x11, 0x90])),
+            })
+        );
+    }
+
+    #[test]
+    fn unsupported_codec_is_rejected() {
+        let sdp =
+            SessionDescription::parse("v=0\r\nm=audio 5004 RTP/AVP 0\r\na=rtpmap:0 PCMU/8000\r\n")
+                .unwrap();
+        assert!(RtpStream::from_sdp(&sdp).is_err());
+    }
+}
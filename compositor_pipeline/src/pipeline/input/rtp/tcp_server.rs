@@ -11,29 +11,29 @@ use bytes::BytesMut;
 use compositor_render::{error::ErrorStack, InputId};
 use crossbeam_channel::{bounded, Receiver, Sender};
 use log::error;
-use tracing::{debug, info, span, trace, Level};
+use tracing::{debug, info, span, trace, warn, Level};
 
-use crate::pipeline::{rtp::bind_to_requested_port, Port};
+use crate::pipeline::{
+    rtp::{bind_to_requested_port, dual_stack_socket},
+    Port,
+};
 
-use super::{RtpReceiverError, RtpReceiverOptions};
+use super::{feedback::RtcpSender, RtpReceiverError, RtpReceiverOptions};
 
 pub(super) fn start_tcp_server_thread(
     input_id: &InputId,
     opts: &RtpReceiverOptions,
     should_close: Arc<AtomicBool>,
+    rtcp_sender: RtcpSender,
 ) -> Result<(Port, Receiver<bytes::Bytes>), RtpReceiverError> {
     let (packets_tx, packets_rx) = bounded(1000);
     let input_id = input_id.clone();
     info!(?input_id, "Starting tcp socket");
 
-    let socket = socket2::Socket::new(
-        socket2::Domain::IPV4,
-        socket2::Type::STREAM,
-        Some(socket2::Protocol::TCP),
-    )
-    .map_err(RtpReceiverError::SocketOptions)?;
+    let (socket, ip) = dual_stack_socket(socket2::Type::STREAM, socket2::Protocol::TCP)
+        .map_err(RtpReceiverError::SocketOptions)?;
 
-    let port = bind_to_requested_port(opts.port, &socket)?;
+    let port = bind_to_requested_port(opts.port, ip, &socket)?;
 
     socket.listen(1).map_err(RtpReceiverError::SocketBind)?;
 
@@ -48,7 +48,7 @@ pub(super) fn start_tcp_server_thread(
                 input_id = input_id.to_string()
             )
             .entered();
-            run_tcp_server_thread(socket, packets_tx, should_close);
+            run_tcp_server_thread(socket, packets_tx, should_close, rtcp_sender);
             debug!("Closing RTP receiver thread (TCP server).");
         })
         .unwrap();
@@ -60,6 +60,7 @@ fn run_tcp_server_thread(
     socket: std::net::TcpListener,
     packets_tx: Sender<bytes::Bytes>,
     should_close: Arc<AtomicBool>,
+    rtcp_sender: RtcpSender,
 ) {
     // make accept non blocking so we have a chance to handle should_close value
     socket
@@ -77,7 +78,17 @@ fn run_tcp_server_thread(
     }
 
     let socket = match connected_socket {
-        Some(socket) => TcpReadPacketStream::new(socket, should_close.clone()),
+        Some(socket) => {
+            // RTCP feedback is sent over the same connection.
+            match socket.try_clone() {
+                Ok(write_socket) => {
+                    let _ = write_socket.set_write_timeout(Some(Duration::from_millis(50)));
+                    rtcp_sender.set_tcp_peer(write_socket);
+                }
+                Err(err) => warn!(%err, "Failed to clone TCP socket, RTCP feedback is disabled."),
+            }
+            TcpReadPacketStream::new(socket, should_close.clone())
+        }
         None => {
             return;
         }
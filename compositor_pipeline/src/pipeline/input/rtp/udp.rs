@@ -1,4 +1,6 @@
 use std::{
+    io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket},
     sync::{atomic::AtomicBool, Arc},
     thread,
 };
@@ -8,23 +10,26 @@ use compositor_render::InputId;
 use crossbeam_channel::{unbounded, Receiver, Sender};
 use tracing::{debug, span, warn, Level};
 
-use crate::pipeline::{rtp::bind_to_requested_port, Port};
+use crate::pipeline::{
+    rtp::{bind_to_requested_port, dual_stack_socket, MulticastInterface},
+    Port,
+};
 
-use super::{RtpReceiverError, RtpReceiverOptions};
+use super::{feedback::RtcpSender, MulticastGroup, RtpReceiverError, RtpReceiverOptions};
 
 pub(super) fn start_udp_reader_thread(
     input_id: &InputId,
     opts: &RtpReceiverOptions,
     should_close: Arc<AtomicBool>,
+    rtcp_sender: RtcpSender,
 ) -> Result<(Port, Receiver<bytes::Bytes>), RtpReceiverError> {
     let (packets_tx, packets_rx) = unbounded();
 
-    let socket = socket2::Socket::new(
-        socket2::Domain::IPV4,
-        socket2::Type::DGRAM,
-        Some(socket2::Protocol::UDP),
-    )
-    .map_err(RtpReceiverError::SocketOptions)?;
+    let (socket, ip) = match &opts.multicast {
+        Some(multicast) => multicast_socket(multicast)?,
+        None => dual_stack_socket(socket2::Type::DGRAM, socket2::Protocol::UDP)
+            .map_err(RtpReceiverError::SocketOptions)?,
+    };
 
     match socket
         .set_recv_buffer_size(16 * 1024 * 1024)
@@ -36,13 +41,18 @@ pub(super) fn start_udp_reader_thread(
         }
     }
 
-    let port = bind_to_requested_port(opts.port, &socket)?;
+    let port = bind_to_requested_port(opts.port, ip, &socket)?;
+
+    if let Some(multicast) = &opts.multicast {
+        join_multicast_group(&socket, multicast)
+            .map_err(|err| RtpReceiverError::MulticastJoin(multicast.group, err))?;
+    }
 
     socket
         .set_read_timeout(Some(std::time::Duration::from_millis(50)))
         .map_err(RtpReceiverError::SocketOptions)?;
 
-    let socket = std::net::UdpSocket::from(socket);
+    let socket = Arc::new(UdpSocket::from(socket));
 
     let input_id = input_id.clone();
     thread::Builder::new()
@@ -54,7 +64,7 @@ pub(super) fn start_udp_reader_thread(
                 input_id = input_id.to_string()
             )
             .entered();
-            run_udp_receiver_thread(socket, packets_tx, should_close);
+            run_udp_receiver_thread(socket, packets_tx, should_close, rtcp_sender);
             debug!("Closing RTP receiver thread (UDP).");
         })
         .unwrap();
@@ -62,12 +72,66 @@ pub(super) fn start_udp_reader_thread(
     Ok((port, packets_rx))
 }
 
+/// The socket is bound to the unspecified address of the group's IP version. Address
+/// reuse allows other receivers on the host to join the same group and port.
+fn multicast_socket(
+    multicast: &MulticastGroup,
+) -> Result<(socket2::Socket, IpAddr), RtpReceiverError> {
+    let (domain, ip) = match multicast.group {
+        IpAddr::V4(_) => (socket2::Domain::IPV4, Ipv4Addr::UNSPECIFIED.into()),
+        IpAddr::V6(_) => (socket2::Domain::IPV6, Ipv6Addr::UNSPECIFIED.into()),
+    };
+    let socket = socket2::Socket::new(domain, socket2::Type::DGRAM, Some(socket2::Protocol::UDP))
+        .map_err(RtpReceiverError::SocketOptions)?;
+    socket
+        .set_reuse_address(true)
+        .map_err(RtpReceiverError::SocketOptions)?;
+    Ok((socket, ip))
+}
+
+fn join_multicast_group(socket: &socket2::Socket, multicast: &MulticastGroup) -> io::Result<()> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidInput, message.to_string());
+    match multicast.group {
+        IpAddr::V4(group) => {
+            let interface = match multicast.interface {
+                Some(MulticastInterface::V4(interface)) => interface,
+                None => Ipv4Addr::UNSPECIFIED,
+                Some(MulticastInterface::V6(_)) => {
+                    return Err(invalid("IPv4 group requires an IPv4 interface address."))
+                }
+            };
+            match multicast.source {
+                Some(IpAddr::V4(source)) => socket.join_ssm_v4(&source, &group, &interface),
+                Some(IpAddr::V6(_)) => Err(invalid("IPv4 group requires an IPv4 source.")),
+                None => socket.join_multicast_v4(&group, &interface),
+            }
+        }
+        IpAddr::V6(group) => {
+            let interface = match multicast.interface {
+                Some(MulticastInterface::V6(index)) => index,
+                None => 0,
+                Some(MulticastInterface::V4(_)) => {
+                    return Err(invalid("IPv6 group requires an interface index."))
+                }
+            };
+            if multicast.source.is_some() {
+                return Err(invalid(
+                    "Source-specific multicast is supported only for IPv4 groups.",
+                ));
+            }
+            socket.join_multicast_v6(&group, interface)
+        }
+    }
+}
+
 fn run_udp_receiver_thread(
-    socket: std::net::UdpSocket,
+    socket: Arc<UdpSocket>,
     packets_tx: Sender<Bytes>,
     should_close: Arc<AtomicBool>,
+    rtcp_sender: RtcpSender,
 ) {
     let mut buffer = BytesMut::zeroed(65536);
+    let mut peer_address: Option<SocketAddr> = None;
 
     loop {
         if should_close.load(std::sync::atomic::Ordering::Relaxed) {
@@ -75,7 +139,7 @@ fn run_udp_receiver_thread(
         }
 
         // This can be faster if we batched sending the packets through the channel
-        let (received_bytes, _) = match socket.recv_from(&mut buffer) {
+        let (received_bytes, address) = match socket.recv_from(&mut buffer) {
             Ok(n) => n,
             Err(e) => match e.kind() {
                 std::io::ErrorKind::WouldBlock => continue,
@@ -86,6 +150,12 @@ fn run_udp_receiver_thread(
             },
         };
 
+        // RTCP feedback is sent back to the address the stream comes from.
+        if peer_address != Some(address) {
+            peer_address = Some(address);
+            rtcp_sender.set_udp_peer(socket.clone(), address);
+        }
+
         if packets_tx
             .send(Bytes::copy_from_slice(&buffer[..received_bytes]))
             .is_err()
@@ -0,0 +1,139 @@
+use std::{
+    sync::{atomic::AtomicBool, Arc},
+    time::Duration,
+};
+
+use compositor_render::InputId;
+use ffmpeg_next::format::context;
+use tracing::{debug, error, info, span, warn, Level};
+
+use crate::pipeline::{
+    mpeg_ts,
+    srt::{self, SrtConnectionOptions, SrtMode},
+    AudioCodec,
+};
+
+use super::{
+    mpeg_ts::{demux, MpegTsChunkSenders},
+    Input, InputInitInfo, InputInitResult,
+};
+
+const MIN_RECONNECT_DELAY: Duration = Duration::from_millis(500);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone)]
+pub struct SrtReceiverOptions {
+    pub connection: SrtConnectionOptions,
+    /// H264 video is expected if `true`.
+    pub video: bool,
+    /// AAC or Opus.
+    pub audio: Option<AudioCodec>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SrtReceiverError {
+    #[error("Failed to register input. Port: {0} is already used or not available.")]
+    PortAlreadyInUse(u16),
+
+    #[error("Error while binding the socket.")]
+    SocketBind(#[source] std::io::Error),
+}
+
+/// Input that receives MPEG-TS with H264 video and AAC or Opus audio over SRT.
+/// In the listener mode it accepts a single caller, in the caller mode it keeps
+/// connecting until the remote listener accepts the connection. The input receives
+/// the end of the stream when the connection is closed.
+pub struct SrtReceiver {
+    should_close: Arc<AtomicBool>,
+}
+
+impl SrtReceiver {
+    pub(super) fn start_new_input(
+        input_id: &InputId,
+        opts: SrtReceiverOptions,
+    ) -> Result<InputInitResult, SrtReceiverError> {
+        let port = match opts.connection.mode {
+            SrtMode::Listener { port } => {
+                srt::check_port_available(port).map_err(|err| match err.kind() {
+                    std::io::ErrorKind::AddrInUse => SrtReceiverError::PortAlreadyInUse(port.0),
+                    _ => SrtReceiverError::SocketBind(err),
+                })?;
+                Some(port)
+            }
+            SrtMode::Caller { .. } => None,
+        };
+
+        let should_close = Arc::new(AtomicBool::new(false));
+        let (senders, video, audio) = MpegTsChunkSenders::new(opts.video, opts.audio);
+
+        let input_id_clone = input_id.clone();
+        let should_close_clone = should_close.clone();
+        std::thread::Builder::new()
+            .name(format!("SRT receiver {}", input_id))
+            .spawn(move || {
+                let _span = span!(
+                    Level::INFO,
+                    "SRT receiver",
+                    input_id = input_id_clone.to_string()
+                )
+                .entered();
+                if let Some(input) = connect(&opts.connection, &should_close_clone) {
+                    info!("SRT connection established.");
+                    demux(input, &senders, &should_close_clone);
+                }
+                senders.send_eos();
+                debug!("Closing SRT receiver thread.");
+            })
+            .unwrap();
+
+        Ok(InputInitResult {
+            input: Input::Srt(Self { should_close }),
+            video,
+            audio,
+            init_info: InputInitInfo { port },
+        })
+    }
+}
+
+impl Drop for SrtReceiver {
+    fn drop(&mut self) {
+        self.should_close
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+}
+
+/// Returns `None` if the input was unregistered before the connection was established.
+fn connect(
+    connection: &SrtConnectionOptions,
+    should_close: &Arc<AtomicBool>,
+) -> Option<context::Input> {
+    let mut reconnect_delay = MIN_RECONNECT_DELAY;
+    loop {
+        match mpeg_ts::open_input(
+            &connection.url(),
+            connection.ffmpeg_options(),
+            should_close.clone(),
+        ) {
+            Ok(input) => return Some(input),
+            Err(_) if should_close.load(std::sync::atomic::Ordering::Relaxed) => return None,
+            Err(err) => match connection.mode {
+                SrtMode::Listener { .. } => {
+                    error!(%err, "Failed to accept SRT connection.");
+                    return None;
+                }
+                SrtMode::Caller { .. } => {
+                    warn!(
+                        %err,
+                        "Failed to connect to SRT listener. Retrying in {reconnect_delay:?}."
+                    );
+                }
+            },
+        }
+
+        std::thread::sleep(reconnect_delay);
+        reconnect_delay = Duration::min(reconnect_delay * 2, MAX_RECONNECT_DELAY);
+        if should_close.load(std::sync::atomic::Ordering::Relaxed) {
+            return None;
+        }
+    }
+}
@@ -0,0 +1,390 @@
+use std::{
+    f64::consts::PI,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use bytes::Bytes;
+use compositor_render::{
+    scene::RGBColor, Frame, FrameData, Framerate, InputId, Resolution, YuvPlanes,
+};
+use crossbeam_channel::{bounded, Sender};
+use tracing::{debug, span, Level};
+
+use crate::{
+    pipeline::types::{DecodedSamples, Samples},
+    queue::PipelineEvent,
+};
+
+use super::{AudioInputReceiver, Input, InputInitInfo, InputInitResult, VideoInputReceiver};
+
+const AUDIO_SAMPLE_RATE: u32 = 48_000;
+const AUDIO_CHUNK_DURATION: Duration = Duration::from_millis(20);
+/// Amplitude of the generated tone, -12 dBFS.
+const TONE_AMPLITUDE: f64 = 0.25;
+
+#[derive(Debug, Clone)]
+pub struct TestSourceOptions {
+    pub pattern: TestPattern,
+    pub resolution: Resolution,
+    pub framerate: Framerate,
+    /// If `None`, input does not produce audio.
+    pub audio: Option<TestAudio>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestPattern {
+    /// SMPTE color bars.
+    SmpteBars,
+    /// Diagonally scrolling checkerboard, useful to spot dropped or frozen frames.
+    MovingPattern,
+    SolidColor(RGBColor),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TestAudio {
+    Sine { frequency: f64 },
+    Silence,
+}
+
+/// Input that generates frames and samples in real time. Nothing is decoded, data is
+/// sent to the queue in the same form as an output of a decoder.
+pub struct TestSource {
+    should_close: Arc<AtomicBool>,
+}
+
+impl TestSource {
+    pub(super) fn start_new_input(input_id: &InputId, opts: TestSourceOptions) -> InputInitResult {
+        let should_close = Arc::new(AtomicBool::new(false));
+        // Video and audio threads are paced against the same clock, so they stay in sync.
+        let start = Instant::now();
+
+        let (frame_sender, frame_receiver) = bounded(5);
+        let input_id_clone = input_id.clone();
+        let should_close_clone = should_close.clone();
+        thread::Builder::new()
+            .name(format!("Test source video {}", input_id))
+            .spawn(move || {
+                let _span = span!(
+                    Level::INFO,
+                    "test source video",
+                    input_id = input_id_clone.to_string()
+                )
+                .entered();
+                run_video_thread(
+                    opts.pattern,
+                    opts.resolution,
+                    opts.framerate,
+                    start,
+                    frame_sender,
+                    should_close_clone,
+                );
+                debug!("Closing test source video thread.");
+            })
+            .unwrap();
+
+        let audio = opts.audio.map(|audio| {
+            let (sample_sender, sample_receiver) = bounded(5);
+            let input_id_clone = input_id.clone();
+            let should_close_clone = should_close.clone();
+            thread::Builder::new()
+                .name(format!("Test source audio {}", input_id))
+                .spawn(move || {
+                    let _span = span!(
+                        Level::INFO,
+                        "test source audio",
+                        input_id = input_id_clone.to_string()
+                    )
+                    .entered();
+                    run_audio_thread(audio, start, sample_sender, should_close_clone);
+                    debug!("Closing test source audio thread.");
+                })
+                .unwrap();
+            AudioInputReceiver::Raw {
+                sample_receiver,
+                sample_rate: AUDIO_SAMPLE_RATE,
+            }
+        });
+
+        InputInitResult {
+            input: Input::TestSource(Self { should_close }),
+            video: Some(VideoInputReceiver::Raw { frame_receiver }),
+            audio,
+            init_info: InputInitInfo { port: None },
+        }
+    }
+}
+
+impl Drop for TestSource {
+    fn drop(&mut self) {
+        self.should_close.store(true, Ordering::Relaxed);
+    }
+}
+
+fn run_video_thread(
+    pattern: TestPattern,
+    resolution: Resolution,
+    framerate: Framerate,
+    start: Instant,
+    sender: Sender<PipelineEvent<Frame>>,
+    should_close: Arc<AtomicBool>,
+) {
+    // Static patterns are rendered once, cloning `Bytes` does not copy the planes.
+    let static_planes = match pattern {
+        TestPattern::SmpteBars => Some(smpte_bars(resolution)),
+        TestPattern::SolidColor(color) => Some(solid_color(resolution, color)),
+        TestPattern::MovingPattern => None,
+    };
+
+    for frame_index in 0u64.. {
+        let pts = Duration::from_secs_f64(
+            frame_index as f64 * framerate.den as f64 / framerate.num as f64,
+        );
+        if !wait_until(start + pts, &should_close) {
+            return;
+        }
+
+        let planes = match &static_planes {
+            Some(planes) => planes.clone(),
+            None => moving_pattern(resolution, pts),
+        };
+        let frame = Frame {
+            data: FrameData::PlanarYuv420(planes),
+            resolution,
+            pts,
+        };
+        if sender.send(PipelineEvent::Data(frame)).is_err() {
+            debug!("Failed to send test source frame. Channel closed.");
+            return;
+        }
+    }
+}
+
+fn run_audio_thread(
+    audio: TestAudio,
+    start: Instant,
+    sender: Sender<PipelineEvent<DecodedSamples>>,
+    should_close: Arc<AtomicBool>,
+) {
+    let chunk_size = (AUDIO_SAMPLE_RATE as f64 * AUDIO_CHUNK_DURATION.as_secs_f64()) as u64;
+
+    for chunk_index in 0u64.. {
+        let first_sample = chunk_index * chunk_size;
+        let start_pts = Duration::from_secs_f64(first_sample as f64 / AUDIO_SAMPLE_RATE as f64);
+        if !wait_until(start + start_pts, &should_close) {
+            return;
+        }
+
+        let samples = match audio {
+            TestAudio::Sine { frequency } => sine_samples(frequency, first_sample, chunk_size),
+            TestAudio::Silence => vec![(0, 0); chunk_size as usize],
+        };
+        let samples = DecodedSamples {
+            samples: Arc::new(Samples::Stereo16Bit(samples)),
+            start_pts,
+            sample_rate: AUDIO_SAMPLE_RATE,
+        };
+        if sender.send(PipelineEvent::Data(samples)).is_err() {
+            debug!("Failed to send test source samples. Channel closed.");
+            return;
+        }
+    }
+}
+
+/// Sleeps until `deadline`. Returns `false` if the input was closed in the meantime.
+fn wait_until(deadline: Instant, should_close: &AtomicBool) -> bool {
+    loop {
+        if should_close.load(Ordering::Relaxed) {
+            return false;
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return true;
+        }
+        // Sleep in short steps, so unregistering does not wait for a long frame interval.
+        thread::sleep((deadline - now).min(Duration::from_millis(100)));
+    }
+}
+
+fn sine_samples(frequency: f64, first_sample: u64, count: u64) -> Vec<(i16, i16)> {
+    (first_sample..first_sample + count)
+        .map(|index| {
+            let t = index as f64 / AUDIO_SAMPLE_RATE as f64;
+            let value =
+                (TONE_AMPLITUDE * (2.0 * PI * frequency * t).sin() * i16::MAX as f64) as i16;
+            (value, value)
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Yuv(u8, u8, u8);
+
+impl Yuv {
+    /// Renderer interprets planar YUV frames as full range BT.601, so the same
+    /// coefficients are used here. Components are in the 0.0 - 1.0 range, values
+    /// outside of it are clamped (used for the below-black PLUGE bar).
+    fn from_rgb(r: f64, g: f64, b: f64) -> Self {
+        let y = 0.299 * r + 0.587 * g + 0.114 * b;
+        let u = (b - y) / 1.772 + 0.5;
+        let v = (r - y) / 1.402 + 0.5;
+        let to_u8 = |value: f64| (value * 255.0).round().clamp(0.0, 255.0) as u8;
+        Self(to_u8(y), to_u8(u), to_u8(v))
+    }
+}
+
+/// Renders a frame by evaluating `color_at` for every luma pixel. Chroma is taken
+/// from the top-left pixel of every 2x2 block.
+fn render(resolution: Resolution, color_at: impl Fn(usize, usize) -> Yuv) -> YuvPlanes {
+    let Resolution { width, height } = resolution;
+    let chroma_size = ((width + 1) / 2) * ((height + 1) / 2);
+    let mut y_plane = Vec::with_capacity(width * height);
+    let mut u_plane = Vec::with_capacity(chroma_size);
+    let mut v_plane = Vec::with_capacity(chroma_size);
+
+    for y in 0..height {
+        for x in 0..width {
+            let color = color_at(x, y);
+            y_plane.push(color.0);
+            if x % 2 == 0 && y % 2 == 0 {
+                u_plane.push(color.1);
+                v_plane.push(color.2);
+            }
+        }
+    }
+
+    YuvPlanes {
+        y_plane: Bytes::from(y_plane),
+        u_plane: Bytes::from(u_plane),
+        v_plane: Bytes::from(v_plane),
+    }
+}
+
+fn solid_color(resolution: Resolution, color: RGBColor) -> YuvPlanes {
+    let RGBColor(r, g, b) = color;
+    let color = Yuv::from_rgb(r as f64 / 255.0, g as f64 / 255.0, b as f64 / 255.0);
+    render(resolution, |_, _| color)
+}
+
+/// Color bars based on SMPTE EG 1: seven 75% bars, a strip of reversed blue bars
+/// and the bottom row with -I, white, +Q and PLUGE.
+fn smpte_bars(resolution: Resolution) -> YuvPlanes {
+    let rgb = Yuv::from_rgb;
+    let top = [
+        rgb(0.75, 0.75, 0.75),
+        rgb(0.75, 0.75, 0.0),
+        rgb(0.0, 0.75, 0.75),
+        rgb(0.0, 0.75, 0.0),
+        rgb(0.75, 0.0, 0.75),
+        rgb(0.75, 0.0, 0.0),
+        rgb(0.0, 0.0, 0.75),
+    ];
+    let black = rgb(0.0, 0.0, 0.0);
+    let middle = [top[6], black, top[4], black, top[2], black, top[0]];
+    let minus_i = rgb(0.0, 0.13, 0.3);
+    let white = rgb(1.0, 1.0, 1.0);
+    let plus_q = rgb(0.2, 0.0, 0.42);
+    let below_black = rgb(-0.04, -0.04, -0.04);
+    let above_black = rgb(0.04, 0.04, 0.04);
+
+    let Resolution { width, height } = resolution;
+    render(resolution, |x, y| {
+        // Position in bar widths (0.0 - 7.0).
+        let bar = x as f64 * 7.0 / width as f64;
+        if y < height * 2 / 3 {
+            top[bar as usize]
+        } else if y < height * 3 / 4 {
+            middle[bar as usize]
+        } else {
+            match bar {
+                bar if bar < 1.25 => minus_i,
+                bar if bar < 2.5 => white,
+                bar if bar < 3.75 => plus_q,
+                bar if bar < 5.0 => black,
+                bar if bar < 5.0 + 1.0 / 3.0 => below_black,
+                bar if bar < 5.0 + 2.0 / 3.0 => black,
+                bar if bar < 6.0 => above_black,
+                _ => black,
+            }
+        }
+    })
+}
+
+fn moving_pattern(resolution: Resolution, pts: Duration) -> YuvPlanes {
+    let square_size = usize::max(resolution.height / 8, 1);
+    // One square per second, regardless of the framerate.
+    let offset = (pts.as_secs_f64() * square_size as f64) as usize % (2 * square_size);
+    let light = Yuv::from_rgb(0.75, 0.75, 0.75);
+    let dark = Yuv::from_rgb(0.1, 0.1, 0.1);
+    render(resolution, |x, y| {
+        let column = (x + offset) / square_size;
+        let row = (y + offset) / square_size;
+        match (column + row) % 2 {
+            0 => light,
+            _ => dark,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use compositor_render::Resolution;
+
+    use super::{moving_pattern, sine_samples, smpte_bars, Yuv, AUDIO_SAMPLE_RATE};
+
+    #[test]
+    fn smpte_bars_layout() {
+        let resolution = Resolution {
+            width: 140,
+            height: 120,
+        };
+        let planes = smpte_bars(resolution);
+        assert_eq!(planes.y_plane.len(), 140 * 120);
+        assert_eq!(planes.u_plane.len(), 70 * 60);
+        assert_eq!(planes.v_plane.len(), 70 * 60);
+
+        let luma_at = |x: usize, y: usize| planes.y_plane[y * resolution.width + x];
+        // First and last of the top bars: 75% white and 75% blue.
+        assert_eq!(luma_at(10, 10), Yuv::from_rgb(0.75, 0.75, 0.75).0);
+        assert_eq!(luma_at(130, 10), Yuv::from_rgb(0.0, 0.0, 0.75).0);
+        // Reversed strip starts with blue.
+        assert_eq!(luma_at(10, 85), Yuv::from_rgb(0.0, 0.0, 0.75).0);
+        // 100% white in the bottom row.
+        assert_eq!(luma_at(30, 110), 255);
+    }
+
+    #[test]
+    fn sine_is_continuous_between_chunks() {
+        let frequency = 1000.0;
+        let whole = sine_samples(frequency, 0, 1920);
+        let first = sine_samples(frequency, 0, 960);
+        let second = sine_samples(frequency, 960, 960);
+        assert_eq!(whole, [first, second].concat());
+
+        // 1 kHz tone has 48 samples per period at 48 kHz.
+        let period = (AUDIO_SAMPLE_RATE as f64 / frequency) as usize;
+        assert_eq!(whole[0], whole[period]);
+        assert!(whole.iter().any(|(left, _)| *left > 0));
+        assert!(whole.iter().any(|(left, _)| *left < 0));
+    }
+
+    #[test]
+    fn moving_pattern_depends_on_pts() {
+        let resolution = Resolution {
+            width: 160,
+            height: 120,
+        };
+        let luma_at = |pts: Duration| moving_pattern(resolution, pts).y_plane;
+
+        // Squares move diagonally by one square per second, so the pattern repeats
+        // every second.
+        assert_eq!(luma_at(Duration::ZERO), luma_at(Duration::from_secs(1)));
+        assert_ne!(luma_at(Duration::ZERO), luma_at(Duration::from_millis(500)));
+    }
+}
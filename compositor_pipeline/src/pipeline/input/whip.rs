@@ -0,0 +1,551 @@
+use std::{
+    sync::{Arc, Mutex, Weak},
+    time::Duration,
+};
+
+use bytes::Bytes;
+use compositor_render::InputId;
+use crossbeam_channel::{unbounded, Sender};
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+use webrtc::{
+    interceptor::registry::Registry,
+    peer_connection::{
+        configuration::RTCConfiguration, peer_connection_state::RTCPeerConnectionState,
+        RTCPeerConnection,
+    },
+    rtp_transceiver::{rtp_codec::RTPCodecType, rtp_receiver::RTCRtpReceiver},
+    track::track_remote::TrackRemote,
+};
+use webrtc_util::Marshal;
+
+use crate::{
+    error::WhipError,
+    pipeline::{
+        decoder::{AudioDecoderOptions, OpusDecoderOptions, VideoDecoderOptions},
+        rtp::{AUDIO_PAYLOAD_TYPE, VIDEO_PAYLOAD_TYPE},
+        types::VideoCodec,
+        webrtc_utils,
+    },
+};
+
+use super::{
+    rtp::{
+        sorted_stats, start_rtp_stream_receiver, InputAudioStream, InputVideoStream, RtcpSender,
+        RtpReceiverError, RtpStream, RtpStreamReceiver, RtpStreamStats, SharedRtpStreamStats,
+    },
+    Input, InputInitInfo, InputInitResult,
+};
+
+#[derive(Debug, Clone)]
+pub struct WhipReceiverOptions {
+    pub video: Option<VideoDecoderOptions>,
+    pub audio: Option<OpusDecoderOptions>,
+    /// Publishers have to send it in the `Authorization: Bearer <token>` header.
+    pub bearer_token: Option<Arc<str>>,
+    /// How long packets are held while waiting for a missing one before it
+    /// is considered lost.
+    pub jitter_buffer_latency: Duration,
+}
+
+/// Input that receives a WebRTC stream from a single WHIP publisher
+/// ([RFC 9725](https://datatracker.ietf.org/doc/html/rfc9725)). Received RTP packets
+/// are processed the same way as packets of an RTP input.
+pub struct WhipReceiver {
+    endpoint: WhipEndpoint,
+    stats: SharedRtpStreamStats,
+}
+
+/// Handle to a WHIP input used by the HTTP server to start and end the session
+/// of a publisher. It stays valid after the input is unregistered, but it does not
+/// accept new sessions anymore.
+#[derive(Clone)]
+pub struct WhipEndpoint(Arc<EndpointState>);
+
+pub struct WhipSession {
+    pub id: String,
+    pub answer: String,
+}
+
+struct EndpointState {
+    input_id: InputId,
+    bearer_token: Option<Arc<str>>,
+    video_codec: Option<VideoCodec>,
+    audio: bool,
+    rtcp_sender: RtcpSender,
+    session: Mutex<SessionState>,
+}
+
+enum SessionState {
+    /// Packets of a publisher that has not connected yet will be sent here.
+    Waiting(Sender<Bytes>),
+    Negotiating,
+    Active {
+        id: String,
+        peer_connection: Arc<RTCPeerConnection>,
+    },
+    /// Decoders received the end of the stream, so another publisher can not be
+    /// accepted.
+    Ended,
+}
+
+impl WhipReceiver {
+    pub(super) fn start_new_input(
+        input_id: &InputId,
+        opts: WhipReceiverOptions,
+    ) -> Result<InputInitResult, RtpReceiverError> {
+        let (packet_sender, packet_receiver) = unbounded();
+        // Packets are encrypted and decrypted by the peer connection.
+        let rtcp_sender = RtcpSender::new(None);
+        let stream = RtpStream {
+            video: opts.video.map(|options| InputVideoStream {
+                options,
+                payload_type: VIDEO_PAYLOAD_TYPE,
+            }),
+            audio: opts.audio.map(|options| InputAudioStream {
+                options: AudioDecoderOptions::Opus(options),
+                payload_type: AUDIO_PAYLOAD_TYPE,
+            }),
+        };
+        let video_codec = stream.video.as_ref().map(|video| video.options.codec);
+        let has_audio = stream.audio.is_some();
+
+        let RtpStreamReceiver {
+            video,
+            audio,
+            stats,
+        } = start_rtp_stream_receiver(
+            input_id,
+            packet_receiver,
+            stream,
+            opts.jitter_buffer_latency,
+            rtcp_sender.clone(),
+            None,
+        )?;
+
+        let endpoint = WhipEndpoint(Arc::new(EndpointState {
+            input_id: input_id.clone(),
+            bearer_token: opts.bearer_token,
+            video_codec,
+            audio: has_audio,
+            rtcp_sender,
+            session: Mutex::new(SessionState::Waiting(packet_sender)),
+        }));
+
+        Ok(InputInitResult {
+            input: Input::Whip(Self { endpoint, stats }),
+            video,
+            audio,
+            init_info: InputInitInfo { port: None },
+        })
+    }
+
+    /// Statistics of all RTP streams (SSRCs) received so far.
+    pub fn stats(&self) -> Vec<RtpStreamStats> {
+        sorted_stats(&self.stats)
+    }
+
+    pub fn endpoint(&self) -> WhipEndpoint {
+        self.endpoint.clone()
+    }
+}
+
+impl Drop for WhipReceiver {
+    fn drop(&mut self) {
+        self.endpoint.0.end_session(None);
+    }
+}
+
+impl WhipEndpoint {
+    /// Accepts an SDP offer of a publisher and returns the answer.
+    pub async fn start_session(
+        &self,
+        bearer_token: Option<&str>,
+        offer: String,
+    ) -> Result<WhipSession, WhipError> {
+        self.authorize(bearer_token)?;
+        let packet_sender = {
+            let mut session = self.0.session.lock().unwrap();
+            match std::mem::replace(&mut *session, SessionState::Negotiating) {
+                SessionState::Waiting(packet_sender) => packet_sender,
+                state => {
+                    let is_ended = matches!(state, SessionState::Ended);
+                    *session = state;
+                    return Err(if is_ended {
+                        WhipError::NotFound(self.0.input_id.clone())
+                    } else {
+                        WhipError::SessionAlreadyStarted(self.0.input_id.clone())
+                    });
+                }
+            }
+        };
+
+        let state = self.0.clone();
+        webrtc_utils::run_on_runtime(state.negotiate(offer, packet_sender)).await
+    }
+
+    /// Ends the session. The input receives the end of the stream.
+    pub fn end_session(
+        &self,
+        bearer_token: Option<&str>,
+        session_id: &str,
+    ) -> Result<(), WhipError> {
+        self.authorize(bearer_token)?;
+        if !self.0.end_session(Some(session_id)) {
+            return Err(WhipError::SessionNotFound(
+                self.0.input_id.clone(),
+                session_id.to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn authorize(&self, bearer_token: Option<&str>) -> Result<(), WhipError> {
+        match &self.0.bearer_token {
+            Some(expected) if Some(expected.as_ref()) != bearer_token => {
+                Err(WhipError::Unauthorized(self.0.input_id.clone()))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl EndpointState {
+    async fn negotiate(
+        self: Arc<Self>,
+        offer: String,
+        packet_sender: Sender<Bytes>,
+    ) -> Result<WhipSession, WhipError> {
+        let id = format!("{:016x}", rand::random::<u64>());
+        let result = self.connect(&id, offer, &packet_sender).await;
+
+        let mut session = self.session.lock().unwrap();
+        // The input could have been unregistered during the negotiation.
+        let is_negotiating = matches!(*session, SessionState::Negotiating);
+        match (result, is_negotiating) {
+            (Ok((peer_connection, answer)), true) => {
+                info!(
+                    input_id = self.input_id.to_string(),
+                    session_id = id,
+                    "WHIP publisher connected."
+                );
+                *session = SessionState::Active {
+                    id: id.clone(),
+                    peer_connection,
+                };
+                Ok(WhipSession { id, answer })
+            }
+            (Ok((peer_connection, _)), false) => {
+                webrtc_utils::runtime().spawn(close_peer_connection(peer_connection));
+                Err(WhipError::NotFound(self.input_id.clone()))
+            }
+            (Err(err), true) => {
+                *session = SessionState::Waiting(packet_sender);
+                Err(WhipError::Negotiation(err))
+            }
+            (Err(err), false) => Err(WhipError::Negotiation(err)),
+        }
+    }
+
+    async fn connect(
+        self: &Arc<Self>,
+        session_id: &str,
+        offer: String,
+        packet_sender: &Sender<Bytes>,
+    ) -> Result<(Arc<RTCPeerConnection>, String), webrtc::Error> {
+        // Depayloading thread generates its own feedback, so no interceptors are used.
+        let media_engine = webrtc_utils::media_engine(self.video_codec, self.audio)?;
+        let api = webrtc_utils::new_api(media_engine, Registry::new());
+        let peer_connection = Arc::new(api.new_peer_connection(RTCConfiguration::default()).await?);
+
+        let track_packet_sender = packet_sender.clone();
+        peer_connection.on_track(Box::new(move |track, receiver, _transceiver| {
+            // Handlers of other tracks wait until this future completes.
+            tokio::spawn(forward_rtp(track, track_packet_sender.clone()));
+            tokio::spawn(forward_rtcp(receiver, track_packet_sender.clone()));
+            Box::pin(async {})
+        }));
+
+        let state = Arc::downgrade(self);
+        let id = session_id.to_string();
+        peer_connection.on_peer_connection_state_change(Box::new(move |connection_state| {
+            debug!(
+                session_id = id,
+                ?connection_state,
+                "WHIP connection state changed."
+            );
+            if matches!(
+                connection_state,
+                RTCPeerConnectionState::Failed | RTCPeerConnectionState::Closed
+            ) {
+                if let Some(state) = state.upgrade() {
+                    state.end_session(Some(&id));
+                }
+            }
+            Box::pin(async {})
+        }));
+
+        let answer = match webrtc_utils::answer_offer(&peer_connection, offer).await {
+            Ok(answer) => answer,
+            Err(err) => {
+                close_peer_connection(peer_connection).await;
+                return Err(err);
+            }
+        };
+
+        let (feedback_sender, feedback_receiver) = mpsc::unbounded_channel();
+        tokio::spawn(send_rtcp(
+            Arc::downgrade(&peer_connection),
+            feedback_receiver,
+        ));
+        self.rtcp_sender.set_webrtc_peer(feedback_sender);
+
+        Ok((peer_connection, answer))
+    }
+
+    /// Ends the active session if its ID matches, or any session if `session_id`
+    /// is not specified. Returns `false` if there was no matching session.
+    fn end_session(&self, session_id: Option<&str>) -> bool {
+        let mut session = self.session.lock().unwrap();
+        if let Some(session_id) = session_id {
+            if !matches!(&*session, SessionState::Active { id, .. } if id == session_id) {
+                return false;
+            }
+        }
+
+        if let SessionState::Active {
+            id,
+            peer_connection,
+        } = std::mem::replace(&mut *session, SessionState::Ended)
+        {
+            info!(
+                input_id = self.input_id.to_string(),
+                session_id = id,
+                "WHIP session ended."
+            );
+            webrtc_utils::runtime().spawn(close_peer_connection(peer_connection));
+        }
+        true
+    }
+}
+
+/// Depayloaders are configured with fixed payload types, while the publisher
+/// chooses them during the negotiation, so they are rewritten.
+async fn forward_rtp(track: Arc<TrackRemote>, packet_sender: Sender<Bytes>) {
+    let payload_type = match track.kind() {
+        RTPCodecType::Video => VIDEO_PAYLOAD_TYPE,
+        RTPCodecType::Audio => AUDIO_PAYLOAD_TYPE,
+        kind => {
+            warn!(?kind, "Received a track of unsupported kind.");
+            return;
+        }
+    };
+    debug!(kind = ?track.kind(), "Received WHIP track.");
+
+    loop {
+        let mut packet = match track.read_rtp().await {
+            Ok((packet, _)) => packet,
+            Err(err) => {
+                debug!(%err, "WHIP track ended.");
+                return;
+            }
+        };
+        packet.header.payload_type = payload_type;
+        let packet = match packet.marshal() {
+            Ok(packet) => packet,
+            Err(err) => {
+                warn!(%err, "Failed to serialize RTP packet.");
+                continue;
+            }
+        };
+        if packet_sender.send(packet).is_err() {
+            return;
+        }
+    }
+}
+
+/// Sender reports are needed to synchronize audio and video.
+async fn forward_rtcp(receiver: Arc<RTCRtpReceiver>, packet_sender: Sender<Bytes>) {
+    loop {
+        let packets = match receiver.read_rtcp().await {
+            Ok((packets, _)) => packets,
+            Err(err) => {
+                debug!(%err, "Stopped receiving RTCP packets.");
+                return;
+            }
+        };
+        let packets = match rtcp::packet::marshal(&packets) {
+            Ok(packets) => packets,
+            Err(err) => {
+                warn!(%err, "Failed to serialize RTCP packets.");
+                continue;
+            }
+        };
+        if packet_sender.send(packets).is_err() {
+            return;
+        }
+    }
+}
+
+/// Sends feedback generated by the depayloading thread (receiver reports, NACK, PLI).
+async fn send_rtcp(
+    peer_connection: Weak<RTCPeerConnection>,
+    mut receiver: mpsc::UnboundedReceiver<Bytes>,
+) {
+    while let Some(mut packets) = receiver.recv().await {
+        let Some(peer_connection) = peer_connection.upgrade() else {
+            return;
+        };
+        let packets = match rtcp::packet::unmarshal(&mut packets) {
+            Ok(packets) => packets,
+            Err(err) => {
+                warn!(%err, "Failed to parse RTCP packets.");
+                continue;
+            }
+        };
+        if let Err(err) = peer_connection.write_rtcp(&packets).await {
+            debug!(%err, "Failed to send RTCP packets.");
+        }
+    }
+}
+
+async fn close_peer_connection(peer_connection: Arc<RTCPeerConnection>) {
+    if let Err(err) = peer_connection.close().await {
+        debug!(%err, "Failed to close peer connection.");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use rtp::{header::Header, packet::Packet};
+    use webrtc::{
+        api::{
+            media_engine::{MediaEngine, MIME_TYPE_H264},
+            APIBuilder,
+        },
+        peer_connection::sdp::session_description::RTCSessionDescription,
+        rtp_transceiver::rtp_codec::RTCRtpCodecCapability,
+        track::track_local::{track_local_static_rtp::TrackLocalStaticRTP, TrackLocalWriter},
+    };
+
+    use crate::{
+        pipeline::{input::VideoInputReceiver, types::EncodedChunkKind},
+        queue::PipelineEvent,
+    };
+
+    use super::*;
+
+    /// Publishes an H264 stream with a local WebRTC client and checks that the input
+    /// depayloads it.
+    #[test]
+    fn publish_h264_stream() {
+        let input_id = InputId("whip_input".into());
+        let result = WhipReceiver::start_new_input(
+            &input_id,
+            WhipReceiverOptions {
+                video: Some(VideoDecoderOptions {
+                    codec: VideoCodec::H264,
+                }),
+                audio: Some(OpusDecoderOptions {
+                    forward_error_correction: false,
+                }),
+                bearer_token: Some("secret".into()),
+                jitter_buffer_latency: Duration::from_millis(10),
+            },
+        )
+        .unwrap();
+        let Input::Whip(receiver) = result.input else {
+            panic!("Expected WHIP input.");
+        };
+        let Some(VideoInputReceiver::Encoded { chunk_receiver, .. }) = result.video else {
+            panic!("Expected encoded video.");
+        };
+        let endpoint = receiver.endpoint();
+
+        let session_id = webrtc_utils::runtime().block_on(async {
+            let mut media_engine = MediaEngine::default();
+            media_engine.register_default_codecs().unwrap();
+            let api = APIBuilder::new().with_media_engine(media_engine).build();
+            let client = api
+                .new_peer_connection(RTCConfiguration::default())
+                .await
+                .unwrap();
+            let track = Arc::new(TrackLocalStaticRTP::new(
+                RTCRtpCodecCapability {
+                    mime_type: MIME_TYPE_H264.to_string(),
+                    clock_rate: 90_000,
+                    sdp_fmtp_line:
+                        "level-asymmetry-allowed=1;packetization-mode=1;profile-level-id=42e01f"
+                            .to_string(),
+                    ..Default::default()
+                },
+                "video".to_string(),
+                "whip_test".to_string(),
+            ));
+            client.add_track(track.clone()).await.unwrap();
+
+            let offer = client.create_offer(None).await.unwrap();
+            let mut gathering_complete = client.gathering_complete_promise().await;
+            client.set_local_description(offer).await.unwrap();
+            let _ = gathering_complete.recv().await;
+            let offer = client.local_description().await.unwrap().sdp;
+
+            assert!(matches!(
+                endpoint.start_session(Some("wrong"), offer.clone()).await,
+                Err(WhipError::Unauthorized(_))
+            ));
+            let WhipSession { id, answer } = endpoint
+                .start_session(Some("secret"), offer.clone())
+                .await
+                .unwrap();
+            assert!(matches!(
+                endpoint.start_session(Some("secret"), offer).await,
+                Err(WhipError::SessionAlreadyStarted(_))
+            ));
+            client
+                .set_remote_description(RTCSessionDescription::answer(answer).unwrap())
+                .await
+                .unwrap();
+
+            let deadline = Instant::now() + Duration::from_secs(10);
+            while client.connection_state() != RTCPeerConnectionState::Connected {
+                assert!(Instant::now() < deadline, "Connection was not established.");
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+
+            // Single NAL unit packets with an IDR slice, one per frame.
+            for frame in 0..10u32 {
+                let packet = Packet {
+                    header: Header {
+                        version: 2,
+                        marker: true,
+                        sequence_number: frame as u16,
+                        timestamp: frame * 3000,
+                        ..Default::default()
+                    },
+                    payload: Bytes::from_static(&[0x65, 0x88, 0x84, 0x00, 0x33]),
+                };
+                track.write_rtp(&packet).await.unwrap();
+                tokio::time::sleep(Duration::from_millis(30)).await;
+            }
+            id
+        });
+
+        let chunk = loop {
+            match chunk_receiver.recv_timeout(Duration::from_secs(5)).unwrap() {
+                PipelineEvent::Data(chunk) => break chunk,
+                PipelineEvent::EOS => panic!("Unexpected end of stream."),
+            }
+        };
+        assert_eq!(chunk.kind, EncodedChunkKind::Video(VideoCodec::H264));
+        assert_eq!(&chunk.data[4..], &[0x65, 0x88, 0x84, 0x00, 0x33]);
+
+        endpoint.end_session(Some("secret"), &session_id).unwrap();
+        assert!(matches!(
+            endpoint.end_session(Some("secret"), &session_id),
+            Err(WhipError::SessionNotFound(_, _))
+        ));
+    }
+}
@@ -0,0 +1,91 @@
+use std::{
+    ptr,
+    sync::{atomic::AtomicBool, Arc},
+};
+
+use ffmpeg_next::{ffi, format::context, Dictionary};
+
+/// Opens an FFmpeg protocol URL (e.g. `udp://` or `srt://`) for writing an MPEG-TS
+/// stream. Packetization (PAT/PMT, PCR, continuity counters and PES) is handled by
+/// the FFmpeg `mpegts` muxer. Opening can be interrupted with `should_close`.
+pub(super) fn open_output(
+    url: &str,
+    options: Dictionary,
+    should_close: Arc<AtomicBool>,
+) -> Result<context::Output, ffmpeg_next::Error> {
+    let path = std::ffi::CString::new(url).unwrap();
+    let format = std::ffi::CString::new("mpegts").unwrap();
+
+    unsafe {
+        let mut ctx = ptr::null_mut();
+        let result = ffi::avformat_alloc_output_context2(
+            &mut ctx,
+            ptr::null_mut(),
+            format.as_ptr(),
+            path.as_ptr(),
+        );
+        if result < 0 {
+            return Err(ffmpeg_next::Error::from(result));
+        }
+        (*ctx).interrupt_callback = interrupt_callback(should_close);
+
+        let mut options = options.disown();
+        let result = ffi::avio_open2(
+            &mut (*ctx).pb,
+            path.as_ptr(),
+            ffi::AVIO_FLAG_WRITE as i32,
+            &(*ctx).interrupt_callback,
+            &mut options,
+        );
+        Dictionary::own(options);
+        if result < 0 {
+            ffi::avformat_free_context(ctx);
+            return Err(ffmpeg_next::Error::from(result));
+        }
+
+        Ok(context::Output::wrap(ctx))
+    }
+}
+
+/// Opens an FFmpeg protocol URL for reading and probes the MPEG-TS streams. It blocks
+/// until the first packets are received, it can be interrupted with `should_close`.
+pub(super) fn open_input(
+    url: &str,
+    options: Dictionary,
+    should_close: Arc<AtomicBool>,
+) -> Result<context::Input, ffmpeg_next::Error> {
+    let path = std::ffi::CString::new(url).unwrap();
+    let format = std::ffi::CString::new("mpegts").unwrap();
+
+    unsafe {
+        let mut ctx = ffi::avformat_alloc_context();
+        (*ctx).interrupt_callback = interrupt_callback(should_close);
+
+        let mut options = options.disown();
+        let result = ffi::avformat_open_input(
+            &mut ctx,
+            path.as_ptr(),
+            ffi::av_find_input_format(format.as_ptr()),
+            &mut options,
+        );
+        Dictionary::own(options);
+        if result < 0 {
+            return Err(ffmpeg_next::Error::from(result));
+        }
+
+        let result = ffi::avformat_find_stream_info(ctx, ptr::null_mut());
+        if result < 0 {
+            ffi::avformat_close_input(&mut ctx);
+            return Err(ffmpeg_next::Error::from(result));
+        }
+
+        Ok(context::Input::wrap(ctx))
+    }
+}
+
+fn interrupt_callback(should_close: Arc<AtomicBool>) -> ffi::AVIOInterruptCB {
+    ffmpeg_next::util::interrupt::new(Box::new(move || {
+        should_close.load(std::sync::atomic::Ordering::Relaxed)
+    }))
+    .interrupt
+}
@@ -5,7 +5,15 @@ use crossbeam_channel::{bounded, Receiver, Sender};
 
 use crate::{audio_mixer::OutputSamples, error::RegisterOutputError, queue::PipelineEvent};
 
-use self::rtp::{RtpSender, RtpSenderOptions};
+use self::{
+    hls::{HlsWriter, HlsWriterOptions},
+    mp4::{Mp4FileWriter, Mp4WriterOptions},
+    mpeg_ts::{MpegTsSender, MpegTsSenderOptions},
+    rtmp::{RtmpSender, RtmpSenderOptions},
+    rtp::{RtpSender, RtpSenderOptions},
+    srt::{SrtSender, SrtSenderOptions},
+    whep::{WhepSender, WhepSenderOptions},
+};
 
 use super::{
     encoder::{AudioEncoderOptions, Encoder, EncoderOptions, VideoEncoderOptions},
@@ -13,7 +21,14 @@ use super::{
     PipelineCtx, Port, RawDataReceiver,
 };
 
+pub mod hls;
+pub mod mp4;
+pub mod mpeg_ts;
+mod muxer;
+pub mod rtmp;
 pub mod rtp;
+pub mod srt;
+pub mod whep;
 
 /// Options to configure public outputs that can be constructed via REST API
 #[derive(Debug, Clone)]
@@ -26,6 +41,12 @@ pub struct OutputOptions {
 #[derive(Debug, Clone)]
 pub enum OutputProtocolOptions {
     Rtp(RtpSenderOptions),
+    Rtmp(RtmpSenderOptions),
+    Mp4(Mp4WriterOptions),
+    Hls(HlsWriterOptions),
+    Srt(SrtSenderOptions),
+    MpegTs(MpegTsSenderOptions),
+    Whep(WhepSenderOptions),
 }
 
 /// Options to configure output that sends h264 and opus audio via channel
@@ -61,6 +82,30 @@ pub enum Output {
         sender: RtpSender,
         encoder: Encoder,
     },
+    Rtmp {
+        sender: RtmpSender,
+        encoder: Encoder,
+    },
+    Mp4 {
+        writer: Mp4FileWriter,
+        encoder: Encoder,
+    },
+    Hls {
+        writer: HlsWriter,
+        encoder: Encoder,
+    },
+    Srt {
+        sender: SrtSender,
+        encoder: Encoder,
+    },
+    MpegTs {
+        sender: MpegTsSender,
+        encoder: Encoder,
+    },
+    Whep {
+        sender: WhepSender,
+        encoder: Encoder,
+    },
     EncodedData {
         encoder: Encoder,
     },
@@ -85,9 +130,15 @@ impl OutputOptionsExt<Option<Port>> for OutputOptions {
         output_id: &OutputId,
         ctx: &PipelineCtx,
     ) -> Result<(Output, Option<Port>), RegisterOutputError> {
+        let forced_keyframe_interval = match &self.output_protocol {
+            // Segments are cut at keyframes.
+            OutputProtocolOptions::Hls(hls_options) => Some(hls_options.segment_duration),
+            _ => None,
+        };
         let encoder_opts = EncoderOptions {
             video: self.video.clone(),
             audio: self.audio.clone(),
+            forced_keyframe_interval,
         };
 
         let (encoder, packets) = Encoder::new(output_id, encoder_opts, ctx.output_sample_rate)
@@ -96,11 +147,56 @@ impl OutputOptionsExt<Option<Port>> for OutputOptions {
         match &self.output_protocol {
             OutputProtocolOptions::Rtp(rtp_options) => {
                 let (sender, port) =
-                    rtp::RtpSender::new(output_id, rtp_options.clone(), packets)
+                    rtp::RtpSender::new(output_id, rtp_options.clone(), packets, &encoder)
                         .map_err(|e| RegisterOutputError::OutputError(output_id.clone(), e))?;
 
                 Ok((Output::Rtp { sender, encoder }, port))
             }
+            OutputProtocolOptions::Rtmp(rtmp_options) => {
+                let sender =
+                    rtmp::RtmpSender::new(output_id, rtmp_options.clone(), packets, &encoder)
+                        .map_err(|e| RegisterOutputError::OutputError(output_id.clone(), e))?;
+
+                Ok((Output::Rtmp { sender, encoder }, None))
+            }
+            OutputProtocolOptions::Mp4(mp4_options) => {
+                let writer =
+                    mp4::Mp4FileWriter::new(output_id, mp4_options.clone(), packets, &encoder)
+                        .map_err(|e| RegisterOutputError::OutputError(output_id.clone(), e))?;
+
+                Ok((Output::Mp4 { writer, encoder }, None))
+            }
+            OutputProtocolOptions::Hls(hls_options) => {
+                let writer = hls::HlsWriter::new(output_id, hls_options.clone(), packets, &encoder)
+                    .map_err(|e| RegisterOutputError::OutputError(output_id.clone(), e))?;
+
+                Ok((Output::Hls { writer, encoder }, None))
+            }
+            OutputProtocolOptions::Srt(srt_options) => {
+                let (sender, port) =
+                    srt::SrtSender::new(output_id, srt_options.clone(), packets, &encoder)
+                        .map_err(|e| RegisterOutputError::OutputError(output_id.clone(), e))?;
+
+                Ok((Output::Srt { sender, encoder }, port))
+            }
+            OutputProtocolOptions::MpegTs(mpeg_ts_options) => {
+                let sender = mpeg_ts::MpegTsSender::new(
+                    output_id,
+                    mpeg_ts_options.clone(),
+                    packets,
+                    &encoder,
+                )
+                .map_err(|e| RegisterOutputError::OutputError(output_id.clone(), e))?;
+
+                Ok((Output::MpegTs { sender, encoder }, None))
+            }
+            OutputProtocolOptions::Whep(whep_options) => {
+                let sender =
+                    whep::WhepSender::new(output_id, whep_options.clone(), packets, &encoder)
+                        .map_err(|e| RegisterOutputError::OutputError(output_id.clone(), e))?;
+
+                Ok((Output::Whep { sender, encoder }, None))
+            }
         }
     }
 }
@@ -114,6 +210,7 @@ impl OutputOptionsExt<Receiver<EncoderOutputEvent>> for EncodedDataOutputOptions
         let encoder_opts = EncoderOptions {
             video: self.video.clone(),
             audio: self.audio.clone(),
+            forced_keyframe_interval: None,
         };
 
         let (encoder, packets) = Encoder::new(output_id, encoder_opts, ctx.output_sample_rate)
@@ -161,6 +258,12 @@ impl Output {
     pub fn frame_sender(&self) -> Option<&Sender<PipelineEvent<Frame>>> {
         match &self {
             Output::Rtp { encoder, .. } => encoder.frame_sender(),
+            Output::Rtmp { encoder, .. } => encoder.frame_sender(),
+            Output::Mp4 { encoder, .. } => encoder.frame_sender(),
+            Output::Hls { encoder, .. } => encoder.frame_sender(),
+            Output::Srt { encoder, .. } => encoder.frame_sender(),
+            Output::MpegTs { encoder, .. } => encoder.frame_sender(),
+            Output::Whep { encoder, .. } => encoder.frame_sender(),
             Output::EncodedData { encoder } => encoder.frame_sender(),
             Output::RawData { video, .. } => video.as_ref(),
         }
@@ -169,6 +272,12 @@ impl Output {
     pub fn samples_batch_sender(&self) -> Option<&Sender<PipelineEvent<OutputSamples>>> {
         match &self {
             Output::Rtp { encoder, .. } => encoder.samples_batch_sender(),
+            Output::Rtmp { encoder, .. } => encoder.samples_batch_sender(),
+            Output::Mp4 { encoder, .. } => encoder.samples_batch_sender(),
+            Output::Hls { encoder, .. } => encoder.samples_batch_sender(),
+            Output::Srt { encoder, .. } => encoder.samples_batch_sender(),
+            Output::MpegTs { encoder, .. } => encoder.samples_batch_sender(),
+            Output::Whep { encoder, .. } => encoder.samples_batch_sender(),
             Output::EncodedData { encoder } => encoder.samples_batch_sender(),
             Output::RawData { audio, .. } => audio.as_ref(),
         }
@@ -177,6 +286,12 @@ impl Output {
     pub fn resolution(&self) -> Option<Resolution> {
         match &self {
             Output::Rtp { encoder, .. } => encoder.video.as_ref().map(|v| v.resolution()),
+            Output::Rtmp { encoder, .. } => encoder.video.as_ref().map(|v| v.resolution()),
+            Output::Mp4 { encoder, .. } => encoder.video.as_ref().map(|v| v.resolution()),
+            Output::Hls { encoder, .. } => encoder.video.as_ref().map(|v| v.resolution()),
+            Output::Srt { encoder, .. } => encoder.video.as_ref().map(|v| v.resolution()),
+            Output::MpegTs { encoder, .. } => encoder.video.as_ref().map(|v| v.resolution()),
+            Output::Whep { encoder, .. } => encoder.video.as_ref().map(|v| v.resolution()),
             Output::EncodedData { encoder } => encoder.video.as_ref().map(|v| v.resolution()),
             Output::RawData { resolution, .. } => *resolution,
         }
@@ -185,6 +300,12 @@ impl Output {
     pub fn request_keyframe(&self, output_id: OutputId) -> Result<(), RequestKeyframeError> {
         let encoder = match &self {
             Output::Rtp { encoder, .. } => encoder,
+            Output::Rtmp { encoder, .. } => encoder,
+            Output::Mp4 { encoder, .. } => encoder,
+            Output::Hls { encoder, .. } => encoder,
+            Output::Srt { encoder, .. } => encoder,
+            Output::MpegTs { encoder, .. } => encoder,
+            Output::Whep { encoder, .. } => encoder,
             Output::EncodedData { encoder } => encoder,
             Output::RawData { .. } => return Err(RequestKeyframeError::RawOutput(output_id)),
         };
@@ -200,7 +321,13 @@ impl Output {
 
     pub(super) fn output_frame_format(&self) -> Option<OutputFrameFormat> {
         match &self {
-            Output::Rtp { encoder, .. } => encoder
+            Output::Rtp { encoder, .. }
+            | Output::Rtmp { encoder, .. }
+            | Output::Mp4 { encoder, .. }
+            | Output::Hls { encoder, .. }
+            | Output::Srt { encoder, .. }
+            | Output::MpegTs { encoder, .. }
+            | Output::Whep { encoder, .. } => encoder
                 .video
                 .as_ref()
                 .map(|_| OutputFrameFormat::PlanarYuv420Bytes),
@@ -0,0 +1,190 @@
+use std::{path::PathBuf, time::Duration};
+
+use compositor_render::{OutputId, Resolution};
+use crossbeam_channel::Receiver;
+use ffmpeg_next::{format::context, Dictionary};
+use tracing::{debug, error, info, span, warn, Level};
+
+use crate::{
+    error::OutputInitError,
+    pipeline::{
+        encoder::{AudioEncoderInfo, Encoder},
+        types::EncoderOutputEvent,
+        AudioCodec, VideoCodec,
+    },
+};
+
+use super::muxer::{h264_keyframe_parameter_sets, open_output_file, Muxer, MuxerVideoTrack};
+
+pub const PLAYLIST_FILE_NAME: &str = "playlist.m3u8";
+
+#[derive(Debug, Clone)]
+pub struct HlsWriterOptions {
+    /// Directory where the playlist and segments are written.
+    pub directory: PathBuf,
+    /// Duration of a single segment. Segments are cut at keyframes, so the encoder
+    /// forces a keyframe at every segment boundary.
+    pub segment_duration: Duration,
+    /// Number of segments listed in the playlist. Older segments are deleted.
+    pub playlist_size: usize,
+    pub segment_type: HlsSegmentType,
+    pub video: Option<VideoCodec>,
+    pub audio: Option<AudioCodec>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HlsSegmentType {
+    MpegTs,
+    Fmp4,
+}
+
+#[derive(Debug)]
+pub struct HlsWriter {
+    pub playlist_path: PathBuf,
+}
+
+impl HlsWriter {
+    pub fn new(
+        output_id: &OutputId,
+        options: HlsWriterOptions,
+        packets_receiver: Receiver<EncoderOutputEvent>,
+        encoder: &Encoder,
+    ) -> Result<Self, OutputInitError> {
+        let video = match (options.video, &encoder.video) {
+            (Some(VideoCodec::H264), Some(video_encoder))
+                if video_encoder.codec() == VideoCodec::H264 =>
+            {
+                Some(video_encoder.resolution())
+            }
+            (Some(codec), _) => return Err(OutputInitError::UnsupportedVideoCodec(codec)),
+            (None, _) => None,
+        };
+        let audio = match (options.audio, encoder.audio_info()) {
+            (Some(AudioCodec::Aac), Some(info)) if info.codec == AudioCodec::Aac => Some(info),
+            (Some(codec), _) => return Err(OutputInitError::UnsupportedAudioCodec(codec)),
+            (None, _) => None,
+        };
+
+        std::fs::create_dir_all(&options.directory)?;
+        let playlist_path = options.directory.join(PLAYLIST_FILE_NAME);
+        let context = open_output_file(&playlist_path, "hls")?;
+
+        let output_id = output_id.clone();
+        std::thread::Builder::new()
+            .name(format!("HLS writer for output {}", output_id))
+            .spawn(move || {
+                let _span =
+                    span!(Level::INFO, "HLS writer", output_id = output_id.to_string()).entered();
+                run_hls_writer_thread(context, &options, video, audio, packets_receiver);
+                debug!("Closing HLS writer thread.")
+            })
+            .unwrap();
+
+        Ok(Self { playlist_path })
+    }
+}
+
+fn run_hls_writer_thread(
+    context: context::Output,
+    options: &HlsWriterOptions,
+    video: Option<Resolution>,
+    audio: Option<AudioEncoderInfo>,
+    packets_receiver: Receiver<EncoderOutputEvent>,
+) {
+    let mut context = Some(context);
+    let mut muxer: Option<Muxer> = None;
+
+    let mut video_eos = video.is_none();
+    let mut audio_eos = audio.is_none();
+
+    // Channel is closed when output is unregistered, the playlist is finalized in both cases.
+    for event in packets_receiver {
+        let chunk = match event {
+            EncoderOutputEvent::Data(chunk) => chunk,
+            EncoderOutputEvent::VideoEOS => {
+                video_eos = true;
+                match audio_eos {
+                    true => break,
+                    false => continue,
+                }
+            }
+            EncoderOutputEvent::AudioEOS => {
+                audio_eos = true;
+                match video_eos {
+                    true => break,
+                    false => continue,
+                }
+            }
+        };
+
+        if muxer.is_none() {
+            // The first chunk from the video encoder is a keyframe, audio produced
+            // before it is dropped.
+            let video_track = match video {
+                Some(resolution) => match h264_keyframe_parameter_sets(&chunk) {
+                    Some(parameter_sets) => Some(MuxerVideoTrack {
+                        codec: VideoCodec::H264,
+                        resolution,
+                        parameter_sets,
+                    }),
+                    None => continue,
+                },
+                None => None,
+            };
+            let Some(context) = context.take() else {
+                return;
+            };
+
+            match Muxer::new(context, video_track, audio.clone(), hls_options(options)) {
+                Ok(new_muxer) => muxer = Some(new_muxer),
+                Err(err) => {
+                    error!(%err, "Failed to start HLS muxer.");
+                    return;
+                }
+            }
+        }
+
+        if let Some(muxer) = &mut muxer {
+            if let Err(err) = muxer.write(chunk) {
+                warn!(%err, "Failed to write chunk to HLS segment.");
+            }
+        }
+    }
+
+    match muxer {
+        Some(muxer) => match muxer.finish() {
+            Ok(()) => info!("HLS playlist finalized."),
+            Err(err) => error!(%err, "Failed to finalize HLS playlist."),
+        },
+        None => warn!("No data was written to the HLS output."),
+    }
+}
+
+fn hls_options(options: &HlsWriterOptions) -> Dictionary<'static> {
+    let (segment_type, segment_extension) = match options.segment_type {
+        HlsSegmentType::MpegTs => ("mpegts", "ts"),
+        HlsSegmentType::Fmp4 => ("fmp4", "m4s"),
+    };
+    let segment_path = options
+        .directory
+        .join(format!("segment_%05d.{segment_extension}"));
+
+    let mut muxer_options = Dictionary::new();
+    muxer_options.set(
+        "hls_time",
+        &options.segment_duration.as_secs_f64().to_string(),
+    );
+    muxer_options.set("hls_list_size", &options.playlist_size.to_string());
+    // Segments removed from the playlist are deleted, playlist is replaced atomically.
+    muxer_options.set(
+        "hls_flags",
+        "delete_segments+independent_segments+temp_file",
+    );
+    muxer_options.set("hls_segment_type", segment_type);
+    muxer_options.set("hls_segment_filename", &segment_path.to_string_lossy());
+    if options.segment_type == HlsSegmentType::Fmp4 {
+        // Path is relative to the playlist.
+        muxer_options.set("hls_fmp4_init_filename", "init.mp4");
+    }
+    muxer_options
+}
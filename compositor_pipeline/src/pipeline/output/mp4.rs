@@ -0,0 +1,155 @@
+use std::path::PathBuf;
+
+use compositor_render::{OutputId, Resolution};
+use crossbeam_channel::Receiver;
+use ffmpeg_next::{format::context, Dictionary};
+use tracing::{debug, error, info, span, warn, Level};
+
+use crate::{
+    error::OutputInitError,
+    pipeline::{
+        encoder::{AudioEncoderInfo, Encoder},
+        types::EncoderOutputEvent,
+        AudioCodec, VideoCodec,
+    },
+};
+
+use super::muxer::{open_output_file, Muxer, MuxerVideoTrack};
+
+#[derive(Debug, Clone)]
+pub struct Mp4WriterOptions {
+    pub output_path: PathBuf,
+    /// Write fragmented MP4, the file stays playable even if it was not finalized.
+    pub fragmented: bool,
+    pub video: Option<VideoCodec>,
+    pub audio: Option<AudioCodec>,
+}
+
+#[derive(Debug)]
+pub struct Mp4FileWriter {
+    pub output_path: PathBuf,
+}
+
+impl Mp4FileWriter {
+    pub fn new(
+        output_id: &OutputId,
+        options: Mp4WriterOptions,
+        packets_receiver: Receiver<EncoderOutputEvent>,
+        encoder: &Encoder,
+    ) -> Result<Self, OutputInitError> {
+        let video = match (options.video, &encoder.video) {
+            (Some(codec), Some(video_encoder)) if video_encoder.codec() == codec => {
+                Some(video_encoder.resolution())
+            }
+            (Some(codec), _) => return Err(OutputInitError::UnsupportedVideoCodec(codec)),
+            (None, _) => None,
+        };
+        let audio = match (options.audio, encoder.audio_info()) {
+            (Some(codec), Some(info)) if info.codec == codec => Some(info),
+            (Some(codec), _) => return Err(OutputInitError::UnsupportedAudioCodec(codec)),
+            (None, _) => None,
+        };
+
+        // Opening the file here reports errors like missing directory or lack of
+        // permissions in the register request.
+        let context = open_output_file(&options.output_path, "mp4")?;
+
+        let output_id = output_id.clone();
+        let fragmented = options.fragmented;
+        std::thread::Builder::new()
+            .name(format!("MP4 writer for output {}", output_id))
+            .spawn(move || {
+                let _span =
+                    span!(Level::INFO, "MP4 writer", output_id = output_id.to_string()).entered();
+                run_mp4_writer_thread(context, fragmented, video, audio, packets_receiver);
+                debug!("Closing MP4 writer thread.")
+            })
+            .unwrap();
+
+        Ok(Self {
+            output_path: options.output_path,
+        })
+    }
+}
+
+fn run_mp4_writer_thread(
+    context: context::Output,
+    fragmented: bool,
+    video: Option<Resolution>,
+    audio: Option<AudioEncoderInfo>,
+    packets_receiver: Receiver<EncoderOutputEvent>,
+) {
+    let mut context = Some(context);
+    let mut muxer: Option<Muxer> = None;
+
+    let mut video_eos = video.is_none();
+    let mut audio_eos = audio.is_none();
+
+    // Channel is closed when output is unregistered, file is finalized in both cases.
+    for event in packets_receiver {
+        let chunk = match event {
+            EncoderOutputEvent::Data(chunk) => chunk,
+            EncoderOutputEvent::VideoEOS => {
+                video_eos = true;
+                match audio_eos {
+                    true => break,
+                    false => continue,
+                }
+            }
+            EncoderOutputEvent::AudioEOS => {
+                audio_eos = true;
+                match video_eos {
+                    true => break,
+                    false => continue,
+                }
+            }
+        };
+
+        if muxer.is_none() {
+            // The first chunk from the video encoder is a keyframe, audio produced
+            // before it is dropped.
+            let video_track = match video {
+                Some(resolution) => match MuxerVideoTrack::from_keyframe(&chunk, resolution) {
+                    Some(video_track) => Some(video_track),
+                    None => continue,
+                },
+                None => None,
+            };
+            let Some(context) = context.take() else {
+                return;
+            };
+
+            match Muxer::new(context, video_track, audio.clone(), movflags(fragmented)) {
+                Ok(new_muxer) => muxer = Some(new_muxer),
+                Err(err) => {
+                    error!(%err, "Failed to write MP4 header.");
+                    return;
+                }
+            }
+        }
+
+        if let Some(muxer) = &mut muxer {
+            if let Err(err) = muxer.write(chunk) {
+                warn!(%err, "Failed to write chunk to MP4 file.");
+            }
+        }
+    }
+
+    match muxer {
+        Some(muxer) => match muxer.finish() {
+            Ok(()) => info!("MP4 file finalized."),
+            Err(err) => error!(%err, "Failed to finalize MP4 file."),
+        },
+        None => warn!("No data was written to the MP4 file."),
+    }
+}
+
+fn movflags(fragmented: bool) -> Dictionary<'static> {
+    let mut options = Dictionary::new();
+    if fragmented {
+        // Every keyframe starts a new fragment and moov atom is written upfront,
+        // so the file can be played even if the trailer is never written.
+        options.set("movflags", "frag_keyframe+empty_moov+default_base_moof");
+    }
+    options
+}
@@ -0,0 +1,228 @@
+use std::{
+    net::IpAddr,
+    sync::{atomic::AtomicBool, Arc},
+};
+
+use compositor_render::{OutputId, Resolution};
+use crossbeam_channel::Receiver;
+use ffmpeg_next::{format::context, Dictionary};
+use tracing::{debug, error, info, span, warn, Level};
+
+use crate::{
+    error::OutputInitError,
+    pipeline::{
+        encoder::{AudioEncoderInfo, Encoder},
+        mpeg_ts,
+        rtp::MulticastInterface,
+        types::EncoderOutputEvent,
+        AudioCodec, Port, VideoCodec,
+    },
+};
+
+use super::{
+    muxer::{h264_keyframe_parameter_sets, Muxer, MuxerVideoTrack},
+    rtp::MulticastSenderOptions,
+};
+
+/// Payload of a UDP datagram, 7 TS packets of 188 bytes.
+const UDP_PACKET_SIZE: usize = 7 * 188;
+
+#[derive(Debug)]
+pub struct MpegTsSender {
+    pub ip: Arc<str>,
+    pub port: Port,
+
+    /// Interrupts sending if the output is unregistered before the
+    /// stream is finished.
+    should_close: Arc<AtomicBool>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MpegTsSenderOptions {
+    /// Unicast address of the receiver or a multicast group.
+    pub ip: Arc<str>,
+    pub port: Port,
+    pub multicast: MulticastSenderOptions,
+    pub video: Option<VideoCodec>,
+    pub audio: Option<AudioCodec>,
+}
+
+impl MpegTsSender {
+    pub fn new(
+        output_id: &OutputId,
+        options: MpegTsSenderOptions,
+        packets_receiver: Receiver<EncoderOutputEvent>,
+        encoder: &Encoder,
+    ) -> Result<Self, OutputInitError> {
+        let video = match (options.video, &encoder.video) {
+            (Some(VideoCodec::H264), Some(video_encoder))
+                if video_encoder.codec() == VideoCodec::H264 =>
+            {
+                Some(video_encoder.resolution())
+            }
+            (Some(codec), _) => return Err(OutputInitError::UnsupportedVideoCodec(codec)),
+            (None, _) => None,
+        };
+        let audio = match (options.audio, encoder.audio_info()) {
+            (Some(codec), Some(info)) if info.codec == codec => Some(info),
+            (Some(codec), _) => return Err(OutputInitError::UnsupportedAudioCodec(codec)),
+            (None, _) => None,
+        };
+
+        let should_close = Arc::new(AtomicBool::new(false));
+        // UDP socket is opened immediately, so invalid addresses are reported
+        // in the register request.
+        let context = mpeg_ts::open_output(
+            &udp_url(&options),
+            ffmpeg_options(&options.multicast),
+            should_close.clone(),
+        )?;
+
+        let output_id = output_id.clone();
+        std::thread::Builder::new()
+            .name(format!("MPEG-TS sender for output {}", output_id))
+            .spawn(move || {
+                let _span = span!(
+                    Level::INFO,
+                    "MPEG-TS sender",
+                    output_id = output_id.to_string()
+                )
+                .entered();
+                run_mpeg_ts_sender_thread(context, video, audio, packets_receiver);
+                debug!("Closing MPEG-TS sender thread.")
+            })
+            .unwrap();
+
+        Ok(Self {
+            ip: options.ip,
+            port: options.port,
+            should_close,
+        })
+    }
+}
+
+impl Drop for MpegTsSender {
+    fn drop(&mut self) {
+        self.should_close
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+}
+
+fn udp_url(options: &MpegTsSenderOptions) -> String {
+    match options.ip.parse::<IpAddr>() {
+        Ok(IpAddr::V6(ip)) => format!("udp://[{}]:{}", ip, options.port.0),
+        _ => format!("udp://{}:{}", options.ip, options.port.0),
+    }
+}
+
+/// Options of the FFmpeg `udp` protocol.
+fn ffmpeg_options(multicast: &MulticastSenderOptions) -> Dictionary<'static> {
+    let mut options = Dictionary::new();
+    options.set("pkt_size", &UDP_PACKET_SIZE.to_string());
+    if let Some(ttl) = multicast.ttl {
+        options.set("ttl", &ttl.to_string());
+    }
+    // FFmpeg selects the interface only by its address, so interface indexes
+    // of IPv6 groups are not supported.
+    if let Some(MulticastInterface::V4(address)) = multicast.interface {
+        options.set("localaddr", &address.to_string());
+    }
+    options
+}
+
+fn run_mpeg_ts_sender_thread(
+    context: context::Output,
+    video: Option<Resolution>,
+    audio: Option<AudioEncoderInfo>,
+    packets_receiver: Receiver<EncoderOutputEvent>,
+) {
+    let mut context = Some(context);
+    let mut muxer: Option<Muxer> = None;
+
+    let mut video_eos = video.is_none();
+    let mut audio_eos = audio.is_none();
+
+    for event in packets_receiver {
+        let chunk = match event {
+            EncoderOutputEvent::Data(chunk) => chunk,
+            EncoderOutputEvent::VideoEOS => {
+                video_eos = true;
+                match audio_eos {
+                    true => break,
+                    false => continue,
+                }
+            }
+            EncoderOutputEvent::AudioEOS => {
+                audio_eos = true;
+                match video_eos {
+                    true => break,
+                    false => continue,
+                }
+            }
+        };
+
+        if muxer.is_none() {
+            // Parameter sets of the stream are taken from the first keyframe. The encoder
+            // starts with a keyframe, so only chunks produced before it are dropped.
+            let video_track = match video {
+                Some(resolution) => match h264_keyframe_parameter_sets(&chunk) {
+                    Some(parameter_sets) => Some(MuxerVideoTrack {
+                        codec: VideoCodec::H264,
+                        resolution,
+                        parameter_sets,
+                    }),
+                    None => continue,
+                },
+                None => None,
+            };
+            let Some(context) = context.take() else {
+                return;
+            };
+            match Muxer::new(context, video_track, audio.clone(), Dictionary::new()) {
+                Ok(new_muxer) => muxer = Some(new_muxer),
+                Err(err) => {
+                    error!(%err, "Failed to start MPEG-TS stream.");
+                    return;
+                }
+            }
+        }
+
+        if let Some(muxer) = &mut muxer {
+            if let Err(err) = muxer.write(chunk) {
+                warn!(%err, "Failed to send MPEG-TS packet.");
+            }
+        }
+    }
+
+    if let Some(muxer) = muxer {
+        if video_eos && audio_eos {
+            info!("Output stream finished.");
+        }
+        if let Err(err) = muxer.finish() {
+            warn!(%err, "Failed to finalize MPEG-TS stream.");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn udp_urls() {
+        let options = MpegTsSenderOptions {
+            ip: "ff0e::1".into(),
+            port: Port(5000),
+            multicast: MulticastSenderOptions::default(),
+            video: Some(VideoCodec::H264),
+            audio: None,
+        };
+        assert_eq!(udp_url(&options), "udp://[ff0e::1]:5000");
+
+        let options = MpegTsSenderOptions {
+            ip: "239.0.0.1".into(),
+            ..options
+        };
+        assert_eq!(udp_url(&options), "udp://239.0.0.1:5000");
+    }
+}
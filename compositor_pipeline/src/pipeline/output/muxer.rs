@@ -0,0 +1,307 @@
+use std::{path::Path, ptr, time::Duration};
+
+use bytes::{Bytes, BytesMut};
+use compositor_render::Resolution;
+use ffmpeg_next::{ffi, format::context, packet, Dictionary, Packet, Rational};
+
+use crate::{
+    audio_mixer::AudioChannels,
+    pipeline::{
+        av1,
+        encoder::AudioEncoderInfo,
+        types::{EncodedChunk, EncodedChunkKind},
+        AudioCodec, VideoCodec,
+    },
+};
+
+/// Timescale of `EncodedChunk` timestamps passed to FFmpeg.
+const MICROSECONDS: Rational = Rational(1, 1_000_000);
+
+#[derive(Debug, Clone)]
+pub(super) struct MuxerVideoTrack {
+    pub codec: VideoCodec,
+    pub resolution: Resolution,
+    /// SPS and PPS in Annex B format for H264, sequence header OBU for AV1, empty for
+    /// VP8 and VP9.
+    pub parameter_sets: Bytes,
+}
+
+impl MuxerVideoTrack {
+    /// Describes the video track based on the first chunk of the stream. Returns `None`
+    /// if the chunk is not a keyframe.
+    pub fn from_keyframe(chunk: &EncodedChunk, resolution: Resolution) -> Option<Self> {
+        let EncodedChunkKind::Video(codec) = chunk.kind else {
+            return None;
+        };
+        let parameter_sets = match codec {
+            VideoCodec::H264 => h264_keyframe_parameter_sets(chunk)?,
+            VideoCodec::Vp8 | VideoCodec::Vp9 if chunk.is_keyframe() => Bytes::new(),
+            VideoCodec::Vp8 | VideoCodec::Vp9 => return None,
+            VideoCodec::Av1 if chunk.is_keyframe() => av1::sequence_header(&chunk.data)?,
+            VideoCodec::Av1 => return None,
+        };
+        Some(Self {
+            codec,
+            resolution,
+            parameter_sets,
+        })
+    }
+}
+
+/// Writes encoded chunks into FFmpeg output context (FLV, MP4, ...). Timestamps
+/// are shifted, so the first chunk written is at zero.
+pub(super) struct Muxer {
+    context: context::Output,
+    video_stream: Option<usize>,
+    audio_stream: Option<usize>,
+    start_pts: Option<Duration>,
+}
+
+impl Muxer {
+    /// Adds the streams and writes the container header. If video is present, the first
+    /// chunk written has to be a keyframe.
+    pub fn new(
+        mut context: context::Output,
+        video: Option<MuxerVideoTrack>,
+        audio: Option<AudioEncoderInfo>,
+        options: Dictionary,
+    ) -> Result<Self, ffmpeg_next::Error> {
+        let video_stream = match video {
+            Some(video) => Some(unsafe {
+                let codec_id = match video.codec {
+                    VideoCodec::H264 => ffi::AVCodecID::AV_CODEC_ID_H264,
+                    VideoCodec::Vp8 => ffi::AVCodecID::AV_CODEC_ID_VP8,
+                    VideoCodec::Vp9 => ffi::AVCodecID::AV_CODEC_ID_VP9,
+                    VideoCodec::Av1 => ffi::AVCodecID::AV_CODEC_ID_AV1,
+                };
+                add_stream(
+                    &mut context,
+                    ffi::AVMediaType::AVMEDIA_TYPE_VIDEO,
+                    codec_id,
+                    &video.parameter_sets,
+                    Rational::new(1, 90_000),
+                    |parameters| {
+                        parameters.width = video.resolution.width as i32;
+                        parameters.height = video.resolution.height as i32;
+                    },
+                )?
+            }),
+            None => None,
+        };
+        let audio_stream = match audio {
+            Some(audio) => {
+                let codec_id = match audio.codec {
+                    AudioCodec::Aac => ffi::AVCodecID::AV_CODEC_ID_AAC,
+                    AudioCodec::Opus => ffi::AVCodecID::AV_CODEC_ID_OPUS,
+                };
+                let channels = match audio.channels {
+                    AudioChannels::Mono => 1,
+                    AudioChannels::Stereo => 2,
+                };
+                Some(unsafe {
+                    add_stream(
+                        &mut context,
+                        ffi::AVMediaType::AVMEDIA_TYPE_AUDIO,
+                        codec_id,
+                        &audio.extradata,
+                        Rational::new(1, audio.sample_rate as i32),
+                        |parameters| {
+                            parameters.sample_rate = audio.sample_rate as i32;
+                            ffi::av_channel_layout_default(&mut parameters.ch_layout, channels);
+                        },
+                    )?
+                })
+            }
+            None => None,
+        };
+
+        context.write_header_with(options)?;
+
+        Ok(Self {
+            context,
+            video_stream,
+            audio_stream,
+            start_pts: None,
+        })
+    }
+
+    pub fn write(&mut self, chunk: EncodedChunk) -> Result<(), ffmpeg_next::Error> {
+        let stream_index = match chunk.kind {
+            EncodedChunkKind::Video(_) => self.video_stream,
+            EncodedChunkKind::Audio(_) => self.audio_stream,
+        };
+        let Some(stream_index) = stream_index else {
+            return Ok(());
+        };
+
+        let dts = chunk.dts.unwrap_or(chunk.pts);
+        let start_pts = *self.start_pts.get_or_insert(dts);
+        // Drop audio that was produced before the keyframe the stream started with.
+        let (Some(pts), Some(dts)) = (chunk.pts.checked_sub(start_pts), dts.checked_sub(start_pts))
+        else {
+            return Ok(());
+        };
+
+        let mut packet = Packet::copy(&chunk.data);
+        packet.set_pts(Some(pts.as_micros() as i64));
+        packet.set_dts(Some(dts.as_micros() as i64));
+        packet.set_stream(stream_index);
+        if chunk.is_keyframe() {
+            packet.set_flags(packet::Flags::KEY);
+        }
+        let time_base = self
+            .context
+            .stream(stream_index)
+            .map(|stream| stream.time_base())
+            .unwrap_or(MICROSECONDS);
+        packet.rescale_ts(MICROSECONDS, time_base);
+
+        packet.write_interleaved(&mut self.context)
+    }
+
+    /// Writes the container trailer. Output is closed when muxer is dropped.
+    pub fn finish(mut self) -> Result<(), ffmpeg_next::Error> {
+        self.context.write_trailer()
+    }
+}
+
+/// Creates output context for the file at `path`. Unlike `ffmpeg_next::format::output_as`
+/// it does not open the file for formats that manage files on their own (e.g. HLS).
+pub(super) fn open_output_file(
+    path: &Path,
+    format: &str,
+) -> Result<context::Output, ffmpeg_next::Error> {
+    let path = std::ffi::CString::new(path.to_string_lossy().as_bytes()).unwrap();
+    let format = std::ffi::CString::new(format).unwrap();
+
+    unsafe {
+        let mut ctx = ptr::null_mut();
+        let result = ffi::avformat_alloc_output_context2(
+            &mut ctx,
+            ptr::null_mut(),
+            format.as_ptr(),
+            path.as_ptr(),
+        );
+        if result < 0 {
+            return Err(ffmpeg_next::Error::from(result));
+        }
+
+        if (*(*ctx).oformat).flags & ffi::AVFMT_NOFILE as i32 == 0 {
+            let result = ffi::avio_open(&mut (*ctx).pb, path.as_ptr(), ffi::AVIO_FLAG_WRITE as i32);
+            if result < 0 {
+                ffi::avformat_free_context(ctx);
+                return Err(ffmpeg_next::Error::from(result));
+            }
+        }
+
+        Ok(context::Output::wrap(ctx))
+    }
+}
+
+/// Adds a stream described by the codec parameters to the output and returns its index.
+unsafe fn add_stream(
+    context: &mut context::Output,
+    media_type: ffi::AVMediaType,
+    codec_id: ffi::AVCodecID,
+    extradata: &[u8],
+    time_base: Rational,
+    set_parameters: impl FnOnce(&mut ffi::AVCodecParameters),
+) -> Result<usize, ffmpeg_next::Error> {
+    let stream = ffi::avformat_new_stream(context.as_mut_ptr(), ptr::null());
+    if stream.is_null() {
+        return Err(ffmpeg_next::Error::Unknown);
+    }
+
+    let parameters = &mut *(*stream).codecpar;
+    parameters.codec_type = media_type;
+    parameters.codec_id = codec_id;
+    if !extradata.is_empty() {
+        let size = extradata.len() + ffi::AV_INPUT_BUFFER_PADDING_SIZE as usize;
+        parameters.extradata = ffi::av_mallocz(size) as *mut u8;
+        if parameters.extradata.is_null() {
+            return Err(ffmpeg_next::Error::Unknown);
+        }
+        ptr::copy_nonoverlapping(extradata.as_ptr(), parameters.extradata, extradata.len());
+        parameters.extradata_size = extradata.len() as i32;
+    }
+    set_parameters(parameters);
+    // Muxer can override this value when writing the header.
+    (*stream).time_base = time_base.into();
+
+    Ok((*stream).index as usize)
+}
+
+/// Returns SPS and PPS in Annex B format if the chunk is a H264 keyframe.
+pub(super) fn h264_keyframe_parameter_sets(chunk: &EncodedChunk) -> Option<Bytes> {
+    if chunk.kind != EncodedChunkKind::Video(VideoCodec::H264) || !chunk.is_keyframe() {
+        return None;
+    }
+
+    let mut parameter_sets = BytesMut::new();
+    for nal in annex_b_nal_units(&chunk.data) {
+        // SPS or PPS
+        if matches!(nal[0] & 0x1f, 7 | 8) {
+            parameter_sets.extend_from_slice(&[0, 0, 0, 1]);
+            parameter_sets.extend_from_slice(nal);
+        }
+    }
+
+    match parameter_sets.is_empty() {
+        true => None,
+        false => Some(parameter_sets.freeze()),
+    }
+}
+
+/// Splits H264 Annex B byte stream into NAL units (without start codes).
+fn annex_b_nal_units(data: &[u8]) -> impl Iterator<Item = &[u8]> {
+    let mut start_codes = data
+        .windows(3)
+        .enumerate()
+        .filter(|(_, window)| *window == [0, 0, 1])
+        .map(|(index, _)| index)
+        .peekable();
+
+    std::iter::from_fn(move || {
+        let start = start_codes.next()? + 3;
+        let end = start_codes.peek().copied().unwrap_or(data.len());
+        Some(&data[start..end])
+    })
+    .map(|nal| {
+        // Trailing zero belongs to the 4-byte start code of the next NAL unit.
+        let len = nal.len() - nal.iter().rev().take_while(|byte| **byte == 0).count();
+        &nal[..len]
+    })
+    .filter(|nal| !nal.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn h264_parameter_sets_from_keyframe() {
+        let data = Bytes::from_static(&[
+            0, 0, 0, 1, 0x67, 1, 2, 0, 0, 0, 1, 0x68, 3, 0, 0, 1, 0x06, 4, 0, 0, 1, 0x65, 5, 6,
+        ]);
+        let chunk = EncodedChunk {
+            data,
+            pts: Duration::ZERO,
+            dts: None,
+            discontinuity: false,
+            kind: EncodedChunkKind::Video(VideoCodec::H264),
+        };
+
+        assert_eq!(
+            h264_keyframe_parameter_sets(&chunk),
+            Some(Bytes::from_static(&[
+                0, 0, 0, 1, 0x67, 1, 2, 0, 0, 0, 1, 0x68, 3
+            ]))
+        );
+
+        let chunk = EncodedChunk {
+            data: Bytes::from_static(&[0, 0, 0, 1, 0x41, 1, 2]),
+            ..chunk
+        };
+        assert_eq!(h264_keyframe_parameter_sets(&chunk), None);
+    }
+}
@@ -0,0 +1,293 @@
+use std::{
+    ptr,
+    sync::{atomic::AtomicBool, Arc},
+    time::Duration,
+};
+
+use compositor_render::{OutputId, Resolution};
+use crossbeam_channel::{bounded, Receiver, Sender};
+use ffmpeg_next::{ffi, format::context, Dictionary};
+use tracing::{debug, info, span, warn, Level};
+
+use crate::{
+    error::OutputInitError,
+    pipeline::{
+        encoder::{AudioEncoderInfo, Encoder},
+        types::EncoderOutputEvent,
+        AudioCodec, VideoCodec,
+    },
+};
+
+use super::muxer::{h264_keyframe_parameter_sets, Muxer, MuxerVideoTrack};
+
+const MIN_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// Timeout for a single socket operation. After it expires the connection is
+/// considered broken and the sender tries to reconnect.
+const SOCKET_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug)]
+pub struct RtmpSender {
+    pub url: Arc<str>,
+
+    /// should_close will be set after output is unregistered,
+    /// but the primary way of controlling the shutdown is a channel
+    /// receiver.
+    ///
+    /// RtmpSender should be explicitly closed based on this value
+    /// only to interrupt connection attempts or writes that hang.
+    should_close: Arc<AtomicBool>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RtmpSenderOptions {
+    pub url: Arc<str>,
+    pub video: Option<VideoCodec>,
+    pub audio: Option<AudioCodec>,
+}
+
+#[derive(Debug, Clone)]
+struct VideoTrack {
+    resolution: Resolution,
+    keyframe_request_sender: Sender<()>,
+}
+
+impl RtmpSender {
+    pub fn new(
+        output_id: &OutputId,
+        options: RtmpSenderOptions,
+        packets_receiver: Receiver<EncoderOutputEvent>,
+        encoder: &Encoder,
+    ) -> Result<Self, OutputInitError> {
+        let video = match (options.video, &encoder.video) {
+            (Some(VideoCodec::H264), Some(video_encoder))
+                if video_encoder.codec() == VideoCodec::H264 =>
+            {
+                Some(VideoTrack {
+                    resolution: video_encoder.resolution(),
+                    keyframe_request_sender: video_encoder.keyframe_request_sender(),
+                })
+            }
+            (Some(codec), _) => return Err(OutputInitError::UnsupportedVideoCodec(codec)),
+            (None, _) => None,
+        };
+        let audio = match (options.audio, encoder.audio_info()) {
+            (Some(AudioCodec::Aac), Some(info)) if info.codec == AudioCodec::Aac => Some(info),
+            (Some(codec), _) => return Err(OutputInitError::UnsupportedAudioCodec(codec)),
+            (None, _) => None,
+        };
+
+        let should_close = Arc::new(AtomicBool::new(false));
+        let url = options.url.clone();
+        let output_id = output_id.clone();
+        let should_close2 = should_close.clone();
+        std::thread::Builder::new()
+            .name(format!("RTMP sender for output {}", output_id))
+            .spawn(move || {
+                let _span = span!(
+                    Level::INFO,
+                    "RTMP sender",
+                    output_id = output_id.to_string()
+                )
+                .entered();
+                run_rtmp_sender_thread(url, video, audio, should_close2, packets_receiver);
+                debug!("Closing RTMP sender thread.")
+            })
+            .unwrap();
+
+        Ok(Self {
+            url: options.url,
+            should_close,
+        })
+    }
+}
+
+impl Drop for RtmpSender {
+    fn drop(&mut self) {
+        self.should_close
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+}
+
+/// Chunks produced while there is no connection are dropped, so the encoder is
+/// not blocked while the server is unavailable.
+fn run_rtmp_sender_thread(
+    url: Arc<str>,
+    video: Option<VideoTrack>,
+    audio: Option<AudioEncoderInfo>,
+    should_close: Arc<AtomicBool>,
+    packets_receiver: Receiver<EncoderOutputEvent>,
+) {
+    let mut connector = start_connector(url.clone(), should_close.clone());
+    let mut pending_connection: Option<context::Output> = None;
+    let mut connection: Option<Muxer> = None;
+    let mut keyframe_requested = false;
+
+    let mut video_eos = video.is_none();
+    let mut audio_eos = audio.is_none();
+
+    for event in packets_receiver {
+        if should_close.load(std::sync::atomic::Ordering::Relaxed) {
+            break;
+        }
+
+        let chunk = match event {
+            EncoderOutputEvent::Data(chunk) => chunk,
+            EncoderOutputEvent::VideoEOS => {
+                video_eos = true;
+                match audio_eos {
+                    true => break,
+                    false => continue,
+                }
+            }
+            EncoderOutputEvent::AudioEOS => {
+                audio_eos = true;
+                match video_eos {
+                    true => break,
+                    false => continue,
+                }
+            }
+        };
+
+        if connection.is_none() {
+            let Some(context) = pending_connection
+                .take()
+                .or_else(|| connector.try_recv().ok())
+            else {
+                continue;
+            };
+
+            // FLV stream has to start with a keyframe, that also provides SPS and PPS
+            // for the sequence header.
+            let parameter_sets = match &video {
+                Some(video) => match h264_keyframe_parameter_sets(&chunk) {
+                    Some(parameter_sets) => Some(parameter_sets),
+                    None => {
+                        if !keyframe_requested {
+                            keyframe_requested = true;
+                            if video.keyframe_request_sender.send(()).is_err() {
+                                debug!("Failed to request a keyframe. Channel closed.");
+                            }
+                        }
+                        pending_connection = Some(context);
+                        continue;
+                    }
+                },
+                None => None,
+            };
+            keyframe_requested = false;
+
+            let video_track = video.as_ref().map(|video| MuxerVideoTrack {
+                codec: VideoCodec::H264,
+                resolution: video.resolution,
+                parameter_sets: parameter_sets.unwrap_or_default(),
+            });
+            match Muxer::new(context, video_track, audio.clone(), Dictionary::new()) {
+                Ok(new_connection) => {
+                    info!("Connected to RTMP server.");
+                    connection = Some(new_connection);
+                }
+                Err(err) => {
+                    warn!(%err, "Failed to start FLV stream. Reconnecting.");
+                    connector = start_connector(url.clone(), should_close.clone());
+                    continue;
+                }
+            }
+        }
+
+        if let Some(current) = &mut connection {
+            if let Err(err) = current.write(chunk) {
+                warn!(%err, "Failed to send data to RTMP server. Reconnecting.");
+                connection = None;
+                connector = start_connector(url.clone(), should_close.clone());
+            }
+        }
+    }
+
+    if let Some(connection) = connection {
+        if video_eos && audio_eos {
+            info!("Output stream finished. Closing RTMP connection.");
+        }
+        if let Err(err) = connection.finish() {
+            warn!(%err, "Failed to finalize RTMP stream.");
+        }
+    }
+    // Stops the connector if it still retries the connection.
+    should_close.store(true, std::sync::atomic::Ordering::Relaxed);
+}
+
+/// Connects to the RTMP server on a separate thread, retrying until the server
+/// accepts the connection.
+fn start_connector(url: Arc<str>, should_close: Arc<AtomicBool>) -> Receiver<context::Output> {
+    let (sender, receiver) = bounded(1);
+    let span = tracing::Span::current();
+    std::thread::Builder::new()
+        .name("RTMP connector".to_string())
+        .spawn(move || {
+            let _span = span.entered();
+            let mut reconnect_delay = MIN_RECONNECT_DELAY;
+            loop {
+                match open_flv_output(&url, should_close.clone()) {
+                    Ok(context) => {
+                        let _ = sender.send(context);
+                        return;
+                    }
+                    Err(_) if should_close.load(std::sync::atomic::Ordering::Relaxed) => return,
+                    Err(err) => warn!(
+                        %err,
+                        "Failed to connect to RTMP server. Retrying in {reconnect_delay:?}."
+                    ),
+                }
+                std::thread::sleep(reconnect_delay);
+                reconnect_delay = Duration::min(reconnect_delay * 2, MAX_RECONNECT_DELAY);
+            }
+        })
+        .unwrap();
+    receiver
+}
+
+/// Creates FLV muxer writing to the RTMP URL. Connecting to the server can
+/// be interrupted with `should_close`.
+fn open_flv_output(
+    url: &str,
+    should_close: Arc<AtomicBool>,
+) -> Result<context::Output, ffmpeg_next::Error> {
+    let path = std::ffi::CString::new(url).unwrap();
+    let format = std::ffi::CString::new("flv").unwrap();
+    let mut options = Dictionary::new();
+    options.set("rw_timeout", &SOCKET_TIMEOUT.as_micros().to_string());
+
+    unsafe {
+        let mut ctx = ptr::null_mut();
+        let result = ffi::avformat_alloc_output_context2(
+            &mut ctx,
+            ptr::null_mut(),
+            format.as_ptr(),
+            path.as_ptr(),
+        );
+        if result < 0 {
+            return Err(ffmpeg_next::Error::from(result));
+        }
+        (*ctx).interrupt_callback = ffmpeg_next::util::interrupt::new(Box::new(move || {
+            should_close.load(std::sync::atomic::Ordering::Relaxed)
+        }))
+        .interrupt;
+
+        let mut options = options.disown();
+        let result = ffi::avio_open2(
+            &mut (*ctx).pb,
+            path.as_ptr(),
+            ffi::AVIO_FLAG_WRITE as i32,
+            &(*ctx).interrupt_callback,
+            &mut options,
+        );
+        Dictionary::own(options);
+        if result < 0 {
+            ffi::avformat_free_context(ctx);
+            return Err(ffmpeg_next::Error::from(result));
+        }
+
+        Ok(context::Output::wrap(ctx))
+    }
+}
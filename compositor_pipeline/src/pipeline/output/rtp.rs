@@ -5,16 +5,31 @@ use tracing::{debug, span, Level};
 
 use crate::{
     error::OutputInitError,
-    pipeline::{rtp::RequestedPort, types::EncoderOutputEvent, AudioCodec, Port, VideoCodec},
+    pipeline::{
+        encoder::Encoder,
+        rtp::{
+            sdp::{MediaDirection, SessionDescription, UNSPECIFIED_ADDRESS},
+            srtp::{SrtpContext, SrtpOptions},
+            MulticastInterface, RequestedPort, TransportProtocol,
+        },
+        types::EncoderOutputEvent,
+        AudioCodec, Port, VideoCodec,
+    },
 };
 
-use self::{packet_stream::PacketStream, payloader::Payloader};
+use self::{feedback::RtcpListener, packet_stream::PacketStream};
 
+mod feedback;
 mod packet_stream;
 mod payloader;
+mod tcp_client;
 mod tcp_server;
 mod udp;
 
+pub use udp::UdpDestinations;
+
+pub(super) use payloader::Payloader;
+
 #[derive(Debug)]
 pub struct RtpSender {
     pub connection_options: RtpConnectionOptions,
@@ -27,6 +42,10 @@ pub struct RtpSender {
     /// only if TCP connection is disconnected or writes hang for a
     /// long time.
     should_close: Arc<AtomicBool>,
+
+    sdp: SessionDescription,
+
+    udp_destinations: Option<UdpDestinations>,
 }
 
 #[derive(Debug, Clone)]
@@ -34,12 +53,38 @@ pub struct RtpSenderOptions {
     pub connection_options: RtpConnectionOptions,
     pub video: Option<VideoCodec>,
     pub audio: Option<AudioCodec>,
+    pub srtp: Option<SrtpOptions>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum RtpConnectionOptions {
-    Udp { port: Port, ip: Arc<str> },
-    TcpServer { port: RequestedPort },
+    Udp {
+        port: Port,
+        ip: Arc<str>,
+        multicast: MulticastSenderOptions,
+    },
+    TcpServer {
+        port: RequestedPort,
+    },
+    /// Connects to a remote receiver and reconnects if the connection fails.
+    TcpClient {
+        port: Port,
+        ip: Arc<str>,
+    },
+}
+
+/// Applied only if packets are sent to a multicast group.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MulticastSenderOptions {
+    /// Time to live (IPv4) or hop limit (IPv6) of sent packets.
+    pub ttl: Option<u32>,
+    pub interface: Option<MulticastInterface>,
+}
+
+enum Connection {
+    Udp(socket2::Socket, UdpDestinations),
+    TcpServer(socket2::Socket),
+    TcpClient { ip: Arc<str>, port: Port },
 }
 
 impl RtpSender {
@@ -47,21 +92,75 @@ impl RtpSender {
         output_id: &OutputId,
         options: RtpSenderOptions,
         packets_receiver: Receiver<EncoderOutputEvent>,
+        encoder: &Encoder,
     ) -> Result<(Self, Option<Port>), OutputInitError> {
         let payloader = Payloader::new(options.video, options.audio);
+        let media_formats = payloader.media_formats();
         let mtu = match options.connection_options {
             RtpConnectionOptions::Udp { .. } => 1400,
-            RtpConnectionOptions::TcpServer { .. } => 64000,
+            RtpConnectionOptions::TcpServer { .. } | RtpConnectionOptions::TcpClient { .. } => {
+                64000
+            }
         };
-        let packet_stream = PacketStream::new(packets_receiver, payloader, mtu);
+        let srtp = options.srtp.as_ref().map(SrtpContext::new).transpose()?;
+        let packet_stream = PacketStream::new(packets_receiver, payloader, mtu, srtp);
 
-        let (socket, port) = match &options.connection_options {
-            RtpConnectionOptions::Udp { port, ip } => udp::udp_socket(ip, *port)?,
-            RtpConnectionOptions::TcpServer { port } => tcp_server::tcp_socket(*port)?,
+        let (connection, port) = match &options.connection_options {
+            RtpConnectionOptions::Udp {
+                port,
+                ip,
+                multicast,
+            } => {
+                let (socket, port, destinations) = udp::udp_socket(ip, *port, multicast)?;
+                (Connection::Udp(socket, destinations), port)
+            }
+            RtpConnectionOptions::TcpServer { port } => {
+                let (socket, port) = tcp_server::tcp_socket(*port)?;
+                (Connection::TcpServer(socket), port)
+            }
+            // Connection is established on the sender thread.
+            RtpConnectionOptions::TcpClient { port, ip } => (
+                Connection::TcpClient {
+                    ip: ip.clone(),
+                    port: *port,
+                },
+                *port,
+            ),
+        };
+        let udp_destinations = match &connection {
+            Connection::Udp(_, destinations) => Some(destinations.clone()),
+            _ => None,
+        };
+
+        let (address, transport_protocol) = match &options.connection_options {
+            RtpConnectionOptions::Udp { ip, .. } => (ip.to_string(), TransportProtocol::Udp),
+            RtpConnectionOptions::TcpServer { .. } => (
+                UNSPECIFIED_ADDRESS.to_string(),
+                TransportProtocol::TcpServer,
+            ),
+            RtpConnectionOptions::TcpClient { ip, .. } => {
+                (ip.to_string(), TransportProtocol::TcpClient)
+            }
+        };
+        let sdp = SessionDescription {
+            address,
+            port: port.0,
+            transport_protocol,
+            direction: MediaDirection::SendOnly,
+            media: media_formats,
+            srtp: options.srtp.clone(),
         };
 
         let should_close = Arc::new(AtomicBool::new(false));
-        let connection_options = options.connection_options.clone();
+        let rtcp_listener = RtcpListener::new(
+            output_id.clone(),
+            encoder
+                .video
+                .as_ref()
+                .map(|video| video.keyframe_request_sender()),
+            should_close.clone(),
+            options.srtp.clone(),
+        );
         let output_id = output_id.clone();
         let should_close2 = should_close.clone();
         std::thread::Builder::new()
@@ -69,13 +168,25 @@ impl RtpSender {
             .spawn(move || {
                 let _span =
                     span!(Level::INFO, "RTP sender", output_id = output_id.to_string()).entered();
-                match connection_options {
-                    RtpConnectionOptions::Udp { .. } => {
-                        udp::run_udp_sender_thread(socket, packet_stream)
-                    }
-                    RtpConnectionOptions::TcpServer { .. } => {
-                        tcp_server::run_tcp_sender_thread(socket, should_close2, packet_stream)
+                match connection {
+                    Connection::Udp(socket, destinations) => {
+                        rtcp_listener.start_udp(socket.try_clone(), destinations.clone());
+                        udp::run_udp_sender_thread(socket, packet_stream, destinations)
                     }
+                    Connection::TcpServer(socket) => tcp_server::run_tcp_sender_thread(
+                        socket,
+                        should_close2,
+                        packet_stream,
+                        rtcp_listener,
+                    ),
+                    Connection::TcpClient { ip, port } => tcp_client::run_tcp_client_sender_thread(
+                        &output_id,
+                        ip,
+                        port,
+                        should_close2,
+                        packet_stream,
+                        rtcp_listener,
+                    ),
                 }
                 debug!("Closing RTP sender thread.")
             })
@@ -85,10 +196,22 @@ impl RtpSender {
             Self {
                 connection_options: options.connection_options,
                 should_close,
+                sdp,
+                udp_destinations,
             },
             Some(port),
         ))
     }
+
+    /// Session description of the sent streams.
+    pub fn sdp(&self) -> String {
+        self.sdp.to_string()
+    }
+
+    /// `None` if packets are not sent over UDP.
+    pub fn udp_destinations(&self) -> Option<&UdpDestinations> {
+        self.udp_destinations.as_ref()
+    }
 }
 
 impl Drop for RtpSender {
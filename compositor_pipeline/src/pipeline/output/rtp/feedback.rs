@@ -0,0 +1,229 @@
+use std::{
+    io::{self, Read},
+    net::{TcpStream, UdpSocket},
+    sync::{atomic::AtomicBool, Arc},
+    thread,
+    time::{Duration, Instant},
+};
+
+use bytes::Bytes;
+use compositor_render::OutputId;
+use crossbeam_channel::Sender;
+use rtcp::payload_feedbacks::{
+    full_intra_request::FullIntraRequest, picture_loss_indication::PictureLossIndication,
+};
+use tracing::{debug, span, trace, warn, Level};
+
+use crate::pipeline::rtp::srtp::{SrtpContext, SrtpOptions};
+
+use super::udp::UdpDestinations;
+
+/// Receivers usually repeat the request until the keyframe arrives. Each request
+/// that reaches the encoder produces a new keyframe, so repeated ones are ignored.
+const MIN_KEYFRAME_REQUEST_INTERVAL: Duration = Duration::from_millis(500);
+/// Timeout of blocking reads, so the thread can check if the output was unregistered.
+const READ_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Listens for RTCP packets sent back by the receiver of the RTP stream and
+/// requests a keyframe from the encoder on PLI or FIR.
+#[derive(Debug, Clone)]
+pub(super) struct RtcpListener {
+    output_id: OutputId,
+    keyframe_request_sender: Option<Sender<()>>,
+    should_close: Arc<AtomicBool>,
+    srtp: Option<SrtpOptions>,
+}
+
+impl RtcpListener {
+    pub fn new(
+        output_id: OutputId,
+        keyframe_request_sender: Option<Sender<()>>,
+        should_close: Arc<AtomicBool>,
+        srtp: Option<SrtpOptions>,
+    ) -> Self {
+        Self {
+            output_id,
+            keyframe_request_sender,
+            should_close,
+            srtp,
+        }
+    }
+
+    /// Packets from addresses other than the stream destinations are ignored,
+    /// unless the stream is sent to a multicast group.
+    pub fn start_udp(self, socket: io::Result<socket2::Socket>, destinations: UdpDestinations) {
+        let socket = match socket {
+            Ok(socket) => UdpSocket::from(socket),
+            Err(err) => {
+                warn!(%err, "Failed to clone UDP socket, RTCP feedback is ignored.");
+                return;
+            }
+        };
+        if let Err(err) = socket.set_read_timeout(Some(READ_TIMEOUT)) {
+            warn!(%err, "Failed to set read timeout, RTCP feedback is ignored.");
+            return;
+        }
+        self.spawn(move |listener| {
+            let mut buffer = vec![0; 65536];
+            while !listener.should_close() {
+                match socket.recv_from(&mut buffer) {
+                    Ok((size, address)) if destinations.accepts_rtcp_from(address.ip()) => {
+                        listener.handle_rtcp(&buffer[..size])
+                    }
+                    Ok((_, address)) => {
+                        trace!(%address, "Ignoring RTCP packet from unknown address.")
+                    }
+                    // Timeout or ICMP error caused by a previously sent packet.
+                    Err(_) => continue,
+                }
+            }
+        });
+    }
+
+    /// Packets are framed the same way as sent ones ([RFC 4571](https://datatracker.ietf.org/doc/html/rfc4571)).
+    pub fn start_tcp(self, socket: io::Result<socket2::Socket>) {
+        let mut socket = match socket {
+            Ok(socket) => TcpStream::from(socket),
+            Err(err) => {
+                warn!(%err, "Failed to clone TCP socket, RTCP feedback is ignored.");
+                return;
+            }
+        };
+        if let Err(err) = socket.set_read_timeout(Some(READ_TIMEOUT)) {
+            warn!(%err, "Failed to set read timeout, RTCP feedback is ignored.");
+            return;
+        }
+        self.spawn(move |listener| {
+            let mut buffer = Vec::new();
+            let mut read_buffer = vec![0; 65536];
+            while !listener.should_close() {
+                match socket.read(&mut read_buffer) {
+                    Ok(0) => return,
+                    Ok(size) => buffer.extend_from_slice(&read_buffer[..size]),
+                    Err(err)
+                        if matches!(
+                            err.kind(),
+                            io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                        ) =>
+                    {
+                        continue
+                    }
+                    Err(err) => {
+                        debug!(%err, "Failed to read RTCP packets from TCP connection.");
+                        return;
+                    }
+                }
+
+                while buffer.len() >= 2 {
+                    let len = u16::from_be_bytes([buffer[0], buffer[1]]) as usize;
+                    if buffer.len() < len + 2 {
+                        break;
+                    }
+                    listener.handle_rtcp(&buffer[2..len + 2]);
+                    buffer.drain(..len + 2);
+                }
+            }
+        });
+    }
+
+    /// Used when a new receiver starts listening, so it does not have to wait
+    /// for the next keyframe.
+    pub fn request_keyframe(&self) {
+        if let Some(sender) = &self.keyframe_request_sender {
+            if sender.send(()).is_err() {
+                debug!("Failed to request a keyframe. Channel closed.");
+            }
+        }
+    }
+
+    fn spawn<F: FnOnce(&mut ListenerState) + Send + 'static>(self, run: F) {
+        let output_id = self.output_id.clone();
+        thread::Builder::new()
+            .name(format!("RTCP listener for output {}", output_id))
+            .spawn(move || {
+                let _span = span!(
+                    Level::INFO,
+                    "RTCP listener",
+                    output_id = output_id.to_string()
+                )
+                .entered();
+                // Each connection uses a new context, because a reconnected receiver
+                // starts a new SRTCP index.
+                let srtp = match self.srtp.as_ref().map(SrtpContext::new).transpose() {
+                    Ok(srtp) => srtp,
+                    Err(err) => {
+                        warn!(%err, "Failed to initialize SRTP, RTCP feedback is ignored.");
+                        return;
+                    }
+                };
+                run(&mut ListenerState {
+                    listener: self,
+                    last_keyframe_request: None,
+                    srtp,
+                });
+                debug!("Closing RTCP listener thread.");
+            })
+            .unwrap();
+    }
+}
+
+struct ListenerState {
+    listener: RtcpListener,
+    last_keyframe_request: Option<Instant>,
+    srtp: Option<SrtpContext>,
+}
+
+impl ListenerState {
+    fn should_close(&self) -> bool {
+        self.listener
+            .should_close
+            .load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    fn handle_rtcp(&mut self, data: &[u8]) {
+        let mut data = match &mut self.srtp {
+            Some(srtp) => match srtp.unprotect(data) {
+                Ok(data) => data,
+                Err(err) => {
+                    debug!(%err, "Failed to decrypt SRTCP packet.");
+                    return;
+                }
+            },
+            None => Bytes::copy_from_slice(data),
+        };
+        let packets = match rtcp::packet::unmarshal(&mut data) {
+            Ok(packets) => packets,
+            Err(err) => {
+                debug!(%err, "Received invalid RTCP packet.");
+                return;
+            }
+        };
+
+        for packet in packets {
+            trace!(?packet, "Received RTCP packet.");
+            let packet = packet.as_any();
+            if packet.is::<PictureLossIndication>() || packet.is::<FullIntraRequest>() {
+                self.request_keyframe();
+            }
+        }
+    }
+
+    fn request_keyframe(&mut self) {
+        let Some(sender) = &self.listener.keyframe_request_sender else {
+            return;
+        };
+        let now = Instant::now();
+        if self
+            .last_keyframe_request
+            .is_some_and(|requested_at| now - requested_at < MIN_KEYFRAME_REQUEST_INTERVAL)
+        {
+            return;
+        }
+        self.last_keyframe_request = Some(now);
+
+        debug!("Receiver requested a keyframe.");
+        if sender.send(()).is_err() {
+            debug!("Failed to request a keyframe. Channel closed.");
+        }
+    }
+}
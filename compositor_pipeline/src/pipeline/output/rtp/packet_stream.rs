@@ -1,16 +1,24 @@
-use std::collections::VecDeque;
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
 
 use crossbeam_channel::Receiver;
+use tracing::warn;
 
-use crate::pipeline::types::EncoderOutputEvent;
+use crate::pipeline::{rtp::srtp::SrtpContext, types::EncoderOutputEvent};
 
 use super::payloader::{Payloader, PayloadingError};
 
+const SENDER_REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
 pub(super) struct PacketStream {
     packets_receiver: Receiver<EncoderOutputEvent>,
     state: VecDeque<bytes::Bytes>,
     payloader: Payloader,
     mtu: usize,
+    next_sender_report_at: Instant,
+    srtp: Option<SrtpContext>,
 }
 
 impl PacketStream {
@@ -18,12 +26,22 @@ impl PacketStream {
         packets_receiver: Receiver<EncoderOutputEvent>,
         payloader: Payloader,
         mtu: usize,
+        srtp: Option<SrtpContext>,
     ) -> Self {
         Self {
             packets_receiver,
             payloader,
             mtu,
             state: VecDeque::new(),
+            next_sender_report_at: Instant::now() + SENDER_REPORT_INTERVAL,
+            srtp,
+        }
+    }
+
+    fn next_packet(&mut self) -> Option<Result<bytes::Bytes, PayloadingError>> {
+        match &mut self.state {
+            chunks if chunks.is_empty() => self.next_new_packet(),
+            chunks => chunks.pop_front().map(Ok),
         }
     }
 
@@ -56,17 +74,35 @@ impl PacketStream {
 
         // I'm assuming here that payload will never return empty list
         self.state = rtp_packets;
+        self.maybe_queue_sender_reports();
         self.state.pop_front().map(Ok)
     }
+
+    /// Sender reports are sent together with media, so receivers can map RTP
+    /// timestamps to wall clock time (e.g. to synchronize audio and video).
+    fn maybe_queue_sender_reports(&mut self) {
+        let now = Instant::now();
+        if now < self.next_sender_report_at {
+            return;
+        }
+        self.next_sender_report_at = now + SENDER_REPORT_INTERVAL;
+        match self.payloader.sender_reports() {
+            Ok(Some(report)) => self.state.push_back(report),
+            Ok(None) => (),
+            Err(err) => warn!(%err, "Failed to create RTCP sender report."),
+        }
+    }
 }
 
 impl Iterator for PacketStream {
     type Item = Result<bytes::Bytes, PayloadingError>;
 
+    /// With SRTP, both RTP packets and sender reports are encrypted.
     fn next(&mut self) -> Option<Self::Item> {
-        match &mut self.state {
-            chunks if chunks.is_empty() => self.next_new_packet(),
-            chunks => chunks.pop_front().map(Ok),
+        let packet = self.next_packet()?;
+        match (&mut self.srtp, packet) {
+            (Some(srtp), Ok(packet)) => Some(srtp.protect(&packet).map_err(Into::into)),
+            (_, packet) => Some(packet),
         }
     }
 }
@@ -1,24 +1,39 @@
 use bytes::Bytes;
-use std::{collections::VecDeque, fmt::Debug};
+use std::{
+    collections::VecDeque,
+    fmt::Debug,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
 use tracing::error;
 use webrtc_util::Marshal;
 
 use rand::Rng;
-use rtp::codecs::{h264::H264Payloader, opus::OpusPayloader};
+use rtp::codecs::{h264::H264Payloader, opus::OpusPayloader, vp8::Vp8Payloader, vp9::Vp9Payloader};
 
 use crate::pipeline::{
-    rtp::{AUDIO_PAYLOAD_TYPE, VIDEO_PAYLOAD_TYPE},
+    rtp::{sdp::MediaFormat, srtp::SrtpError, AUDIO_PAYLOAD_TYPE, VIDEO_PAYLOAD_TYPE},
     types::{EncodedChunk, EncodedChunkKind},
     AudioCodec, VideoCodec,
 };
 
-const H264_CLOCK_RATE: u32 = 90000;
+use self::av1::Av1Payloader;
+
+mod av1;
+
+const VIDEO_CLOCK_RATE: u32 = 90000;
 const OPUS_CLOCK_RATE: u32 = 48000;
 
+/// Seconds between 1900 (NTP epoch) and 1970 (UNIX epoch).
+const NTP_UNIX_OFFSET: u64 = 2_208_988_800;
+
 struct RtpStreamContext {
     ssrc: u32,
     next_sequence_number: u16,
     received_eos: bool,
+    packet_count: u32,
+    octet_count: u32,
+    /// RTP timestamp of the last sent packet and the time it was payloaded.
+    last_timestamp: Option<(u32, Instant)>,
 }
 
 impl RtpStreamContext {
@@ -31,8 +46,35 @@ impl RtpStreamContext {
             ssrc,
             next_sequence_number,
             received_eos: false,
+            packet_count: 0,
+            octet_count: 0,
+            last_timestamp: None,
         }
     }
+
+    /// Sender report that maps the current wall clock time to the RTP timestamp
+    /// that a packet sent right now would have.
+    fn sender_report(
+        &self,
+        clock_rate: u32,
+        ntp_time: u64,
+        now: Instant,
+    ) -> Option<rtcp::sender_report::SenderReport> {
+        if self.received_eos {
+            return None;
+        }
+        let (last_timestamp, last_sent_at) = self.last_timestamp?;
+        let elapsed = now.saturating_duration_since(last_sent_at).as_secs_f64();
+        let rtp_time = last_timestamp.wrapping_add((elapsed * clock_rate as f64) as u32);
+        Some(rtcp::sender_report::SenderReport {
+            ssrc: self.ssrc,
+            ntp_time,
+            rtp_time,
+            packet_count: self.packet_count,
+            octet_count: self.octet_count,
+            ..Default::default()
+        })
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -69,6 +111,9 @@ pub enum PayloadingError {
     #[error(transparent)]
     MarshalError(#[from] webrtc_util::Error),
 
+    #[error(transparent)]
+    SrtpError(#[from] SrtpError),
+
     #[error("Audio EOS already sent.")]
     AudioEOSAlreadySent,
 
@@ -86,6 +131,21 @@ enum VideoPayloader {
         payloader: H264Payloader,
         context: RtpStreamContext,
     },
+    /// RFC 7741
+    Vp8 {
+        payloader: Vp8Payloader,
+        context: RtpStreamContext,
+    },
+    /// RFC 9628
+    Vp9 {
+        payloader: Vp9Payloader,
+        context: RtpStreamContext,
+    },
+    /// RTP Payload Format for AV1 (AOMedia)
+    Av1 {
+        payloader: Av1Payloader,
+        context: RtpStreamContext,
+    },
 }
 
 enum AudioPayloader {
@@ -103,7 +163,21 @@ impl Payloader {
         }
     }
 
-    pub(super) fn payload(
+    /// Formats of payloaded streams, used to describe the output in SDP.
+    pub(super) fn media_formats(&self) -> Vec<MediaFormat> {
+        let video = self.video.as_ref().map(|video| match video {
+            VideoPayloader::H264 { .. } => MediaFormat::h264(VIDEO_PAYLOAD_TYPE),
+            VideoPayloader::Vp8 { .. } => MediaFormat::vp8(VIDEO_PAYLOAD_TYPE),
+            VideoPayloader::Vp9 { .. } => MediaFormat::vp9(VIDEO_PAYLOAD_TYPE),
+            VideoPayloader::Av1 { .. } => MediaFormat::av1(VIDEO_PAYLOAD_TYPE),
+        });
+        let audio = self.audio.as_ref().map(|audio| match audio {
+            AudioPayloader::Opus { .. } => MediaFormat::opus(AUDIO_PAYLOAD_TYPE, false),
+        });
+        video.into_iter().chain(audio).collect()
+    }
+
+    pub(crate) fn payload(
         &mut self,
         mtu: usize,
         data: EncodedChunk,
@@ -159,6 +233,33 @@ impl Payloader {
             .unwrap_or(Err(PayloadingError::NoAudioPayloader))
     }
 
+    /// Returns a compound RTCP packet with sender reports for all streams that
+    /// already sent some data.
+    pub(super) fn sender_reports(&mut self) -> Result<Option<Bytes>, PayloadingError> {
+        let now = Instant::now();
+        let ntp_time = ntp_time(SystemTime::now());
+        let video = self.video.as_ref().and_then(|video| {
+            video
+                .context()
+                .sender_report(video.clock_rate(), ntp_time, now)
+        });
+        let audio = self.audio.as_ref().and_then(|audio| {
+            audio
+                .context()
+                .sender_report(audio.clock_rate(), ntp_time, now)
+        });
+
+        let packets: Vec<Box<dyn rtcp::packet::Packet + Send + Sync>> = video
+            .into_iter()
+            .chain(audio)
+            .map(|report| -> Box<dyn rtcp::packet::Packet + Send + Sync> { Box::new(report) })
+            .collect();
+        if packets.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(rtcp::packet::marshal(&packets)?))
+    }
+
     pub(super) fn video_eos(&mut self) -> Result<Bytes, PayloadingError> {
         self.video
             .as_mut()
@@ -186,12 +287,32 @@ impl VideoPayloader {
                 payloader: H264Payloader::default(),
                 context: RtpStreamContext::new(),
             },
+            VideoCodec::Vp8 => {
+                let mut payloader = Vp8Payloader::default();
+                // Picture ID allows receivers to detect lost frames.
+                payloader.enable_picture_id = true;
+                Self::Vp8 {
+                    payloader,
+                    context: RtpStreamContext::new(),
+                }
+            }
+            VideoCodec::Vp9 => Self::Vp9 {
+                payloader: Vp9Payloader::default(),
+                context: RtpStreamContext::new(),
+            },
+            VideoCodec::Av1 => Self::Av1 {
+                payloader: Av1Payloader,
+                context: RtpStreamContext::new(),
+            },
         }
     }
 
     fn codec(&self) -> VideoCodec {
         match self {
             VideoPayloader::H264 { .. } => VideoCodec::H264,
+            VideoPayloader::Vp8 { .. } => VideoCodec::Vp8,
+            VideoPayloader::Vp9 { .. } => VideoCodec::Vp9,
+            VideoPayloader::Av1 { .. } => VideoCodec::Av1,
         }
     }
 
@@ -210,14 +331,68 @@ impl VideoPayloader {
                 chunk,
                 mtu,
                 VIDEO_PAYLOAD_TYPE,
-                H264_CLOCK_RATE,
+                VIDEO_CLOCK_RATE,
+            ),
+            VideoPayloader::Vp8 {
+                ref mut payloader,
+                ref mut context,
+            } => payload(
+                payloader,
+                context,
+                chunk,
+                mtu,
+                VIDEO_PAYLOAD_TYPE,
+                VIDEO_CLOCK_RATE,
+            ),
+            VideoPayloader::Vp9 {
+                ref mut payloader,
+                ref mut context,
+            } => payload(
+                payloader,
+                context,
+                chunk,
+                mtu,
+                VIDEO_PAYLOAD_TYPE,
+                VIDEO_CLOCK_RATE,
+            ),
+            VideoPayloader::Av1 {
+                ref mut payloader,
+                ref mut context,
+            } => payload(
+                payloader,
+                context,
+                chunk,
+                mtu,
+                VIDEO_PAYLOAD_TYPE,
+                VIDEO_CLOCK_RATE,
             ),
         }
     }
 
+    fn context(&self) -> &RtpStreamContext {
+        match self {
+            VideoPayloader::H264 { context, .. }
+            | VideoPayloader::Vp8 { context, .. }
+            | VideoPayloader::Vp9 { context, .. }
+            | VideoPayloader::Av1 { context, .. } => context,
+        }
+    }
+
     fn context_mut(&mut self) -> &mut RtpStreamContext {
         match self {
-            VideoPayloader::H264 { context, .. } => context,
+            VideoPayloader::H264 { context, .. }
+            | VideoPayloader::Vp8 { context, .. }
+            | VideoPayloader::Vp9 { context, .. }
+            | VideoPayloader::Av1 { context, .. } => context,
+        }
+    }
+
+    fn clock_rate(&self) -> u32 {
+        match self {
+            VideoPayloader::H264 { .. }
+            | VideoPayloader::Vp8 { .. }
+            | VideoPayloader::Vp9 { .. }
+            | VideoPayloader::Av1 { .. } => VIDEO_CLOCK_RATE,
         }
     }
 }
@@ -259,11 +434,23 @@ impl AudioPayloader {
         }
     }
 
+    fn context(&self) -> &RtpStreamContext {
+        match self {
+            AudioPayloader::Opus { context, .. } => context,
+        }
+    }
+
     fn context_mut(&mut self) -> &mut RtpStreamContext {
         match self {
             AudioPayloader::Opus { context, .. } => context,
         }
     }
+
+    fn clock_rate(&self) -> u32 {
+        match self {
+            AudioPayloader::Opus { .. } => OPUS_CLOCK_RATE,
+        }
+    }
 }
 
 fn payload<T: rtp::packetizer::Payloader>(
@@ -276,6 +463,8 @@ fn payload<T: rtp::packetizer::Payloader>(
 ) -> Result<VecDeque<Bytes>, PayloadingError> {
     let payloads = payloader.payload(mtu, &chunk.data)?;
     let packets_amount = payloads.len();
+    let timestamp = (chunk.pts.as_secs_f64() * clock_rate as f64) as u32;
+    context.last_timestamp = Some((timestamp, Instant::now()));
 
     payloads
         .into_iter()
@@ -288,13 +477,96 @@ fn payload<T: rtp::packetizer::Payloader>(
                 marker: i == packets_amount - 1, // marker needs to be set on the last packet of each frame
                 payload_type,
                 sequence_number: context.next_sequence_number,
-                timestamp: (chunk.pts.as_secs_f64() * clock_rate as f64) as u32,
+                timestamp,
                 ssrc: context.ssrc,
                 ..Default::default()
             };
             context.next_sequence_number = context.next_sequence_number.wrapping_add(1);
+            context.packet_count = context.packet_count.wrapping_add(1);
+            context.octet_count = context.octet_count.wrapping_add(payload.len() as u32);
 
             Ok(rtp::packet::Packet { header, payload }.marshal()?)
         })
         .collect()
 }
+
+/// 64-bit NTP timestamp, upper 32 bits are seconds and lower 32 bits are a fraction
+/// of a second.
+fn ntp_time(time: SystemTime) -> u64 {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = since_epoch.as_secs() + NTP_UNIX_OFFSET;
+    let fraction = ((since_epoch.subsec_nanos() as u64) << 32) / 1_000_000_000;
+    (seconds << 32) | fraction
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use bytes::Bytes;
+    use webrtc_util::Unmarshal;
+
+    use crate::pipeline::{
+        types::{EncodedChunk, EncodedChunkKind},
+        AudioCodec, VideoCodec,
+    };
+
+    use super::{ntp_time, Payloader};
+
+    #[test]
+    fn ntp_time_conversion() {
+        let time = UNIX_EPOCH + Duration::from_millis(1500);
+        assert_eq!(ntp_time(time), ((2_208_988_800 + 1) << 32) | (1 << 31));
+    }
+
+    #[test]
+    fn sender_report_counts_sent_packets() {
+        let mut payloader = Payloader::new(None, Some(AudioCodec::Opus));
+        assert!(payloader.sender_reports().unwrap().is_none());
+
+        let chunk = EncodedChunk {
+            data: Bytes::from_static(&[1, 2, 3, 4]),
+            pts: Duration::from_secs(1),
+            dts: None,
+            discontinuity: false,
+            kind: EncodedChunkKind::Audio(AudioCodec::Opus),
+        };
+        payloader.payload(1400, chunk).unwrap();
+
+        let report = payloader.sender_reports().unwrap().unwrap();
+        let packets = rtcp::packet::unmarshal(&mut report.clone()).unwrap();
+        let report = packets[0]
+            .as_any()
+            .downcast_ref::<rtcp::sender_report::SenderReport>()
+            .unwrap();
+        assert_eq!(report.packet_count, 1);
+        assert_eq!(report.octet_count, 4);
+        assert!(report.rtp_time >= 48_000);
+    }
+
+    #[test]
+    fn vp8_frame_split_into_packets() {
+        let mut payloader = Payloader::new(Some(VideoCodec::Vp8), None);
+        let chunk = EncodedChunk {
+            data: Bytes::from(vec![0x10; 3000]),
+            pts: Duration::from_secs(1),
+            dts: None,
+            discontinuity: false,
+            kind: EncodedChunkKind::Video(VideoCodec::Vp8),
+        };
+        let packets: Vec<rtp::packet::Packet> = payloader
+            .payload(1200, chunk)
+            .unwrap()
+            .into_iter()
+            .map(|mut packet| rtp::packet::Packet::unmarshal(&mut packet).unwrap())
+            .collect();
+
+        assert_eq!(packets.len(), 3);
+        for (i, packet) in packets.iter().enumerate() {
+            assert_eq!(packet.header.timestamp, 90_000);
+            assert_eq!(packet.header.marker, i == packets.len() - 1);
+            // S bit is set only in the payload descriptor of the first partition packet.
+            assert_eq!(packet.payload[0] & 0x10 != 0, i == 0);
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use bytes::{BufMut, Bytes, BytesMut};
+
+use crate::pipeline::av1::{
+    is_keyframe, leb128_size, parse_obus, write_leb128, OBU_PADDING, OBU_TEMPORAL_DELIMITER,
+    OBU_TILE_LIST,
+};
+
+const AGGREGATION_HEADER_SIZE: usize = 1;
+
+/// Payloader for AV1 ([RTP Payload Format for AV1](https://aomediacodec.github.io/av1-rtp-spec/)).
+/// Chunks have to contain whole temporal units in the low overhead bitstream format.
+/// OBUs are aggregated into packets up to the MTU, OBUs that do not fit are fragmented.
+#[derive(Debug, Default, Clone)]
+pub(super) struct Av1Payloader;
+
+impl rtp::packetizer::Payloader for Av1Payloader {
+    fn payload(&mut self, mtu: usize, payload: &Bytes) -> Result<Vec<Bytes>, rtp::Error> {
+        // Every packet has to fit the aggregation header and at least one byte of an OBU.
+        if mtu <= AGGREGATION_HEADER_SIZE {
+            return Ok(Vec::new());
+        }
+        let obus = parse_obus(payload).map_err(|_| rtp::Error::ErrShortPacket)?;
+        let new_coded_video_sequence = is_keyframe(payload);
+
+        let mut packets = Vec::new();
+        let mut packet = PacketBuilder::default();
+        // Temporal delimiters, tile lists and padding should be removed.
+        let elements = obus.iter().filter(|obu| {
+            !matches!(
+                obu.obu_type(),
+                OBU_TEMPORAL_DELIMITER | OBU_TILE_LIST | OBU_PADDING
+            )
+        });
+        for obu in elements {
+            let mut element = obu.to_bytes();
+            loop {
+                // Size of elements already in the packet includes their lengths, an element
+                // added as the last one is not preceded by its length if there are at most 3.
+                let available = mtu.saturating_sub(packet.size);
+                let length_size = match packet.elements.len() {
+                    0..=2 => 0,
+                    _ => leb128_size(element.len()),
+                };
+                if length_size + element.len() <= available {
+                    packet.push(element);
+                    break;
+                }
+                let fragment_size = match packet.elements.len() {
+                    0..=2 => available,
+                    _ => available.saturating_sub(leb128_size(available)),
+                }
+                .min(element.len());
+                if fragment_size > 0 {
+                    packet.push(element.split_to(fragment_size));
+                    let is_first = packets.is_empty();
+                    packets.push(packet.build(true, is_first && new_coded_video_sequence));
+                    packet = PacketBuilder::continuing_fragment();
+                } else {
+                    let is_first = packets.is_empty();
+                    packets.push(packet.build(false, is_first && new_coded_video_sequence));
+                    packet = PacketBuilder::default();
+                }
+            }
+        }
+        if !packet.elements.is_empty() {
+            let is_first = packets.is_empty();
+            packets.push(packet.build(false, is_first && new_coded_video_sequence));
+        }
+        Ok(packets)
+    }
+
+    fn clone_to(&self) -> Box<dyn rtp::packetizer::Payloader + Send + Sync> {
+        Box::new(self.clone())
+    }
+}
+
+struct PacketBuilder {
+    elements: Vec<Bytes>,
+    /// The first element continues an OBU fragmented in the previous packet.
+    continues_fragment: bool,
+    /// Size of the packet if every element is preceded by its length.
+    size: usize,
+}
+
+impl Default for PacketBuilder {
+    fn default() -> Self {
+        Self {
+            elements: Vec::new(),
+            continues_fragment: false,
+            size: AGGREGATION_HEADER_SIZE,
+        }
+    }
+}
+
+impl PacketBuilder {
+    fn continuing_fragment() -> Self {
+        Self {
+            continues_fragment: true,
+            ..Default::default()
+        }
+    }
+
+    fn push(&mut self, element: Bytes) {
+        self.size += leb128_size(element.len()) + element.len();
+        self.elements.push(element);
+    }
+
+    /// Writes the aggregation header (`|Z|Y| W |N|-|-|-|`) and the elements. If there are
+    /// at most 3 elements, their count is written in the header and the last element is
+    /// not preceded by its length.
+    fn build(self, ends_with_fragment: bool, new_coded_video_sequence: bool) -> Bytes {
+        let element_count = match self.elements.len() {
+            count @ 1..=3 => count,
+            _ => 0,
+        };
+        let aggregation_header = ((self.continues_fragment as u8) << 7)
+            | ((ends_with_fragment as u8) << 6)
+            | ((element_count as u8) << 4)
+            | ((new_coded_video_sequence as u8) << 3);
+
+        let mut packet = BytesMut::with_capacity(self.size);
+        packet.put_u8(aggregation_header);
+        let last_index = self.elements.len() - 1;
+        for (index, element) in self.elements.into_iter().enumerate() {
+            if element_count == 0 || index != last_index {
+                write_leb128(element.len(), &mut packet);
+            }
+            packet.extend_from_slice(&element);
+        }
+        packet.freeze()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::{Bytes, BytesMut};
+    use rtp::packetizer::Payloader;
+
+    use crate::pipeline::av1::write_leb128;
+
+    use super::Av1Payloader;
+
+    /// Temporal unit with a single inter frame OBU, the OBU without its size field
+    /// is `obu_size` bytes long.
+    fn temporal_unit_with_frame(obu_size: usize) -> Bytes {
+        let mut temporal_unit = BytesMut::from(&[0x12, 0x00, 0x32][..]);
+        write_leb128(obu_size - 1, &mut temporal_unit);
+        temporal_unit.extend_from_slice(&vec![0x30; obu_size - 1]);
+        temporal_unit.freeze()
+    }
+
+    #[test]
+    fn obus_are_aggregated() {
+        // temporal delimiter, sequence header and key frame
+        let temporal_unit =
+            Bytes::from_static(&[0x12, 0x00, 0x0a, 0x01, 0xaa, 0x32, 0x02, 0x10, 0xbb]);
+
+        let packets = Av1Payloader.payload(1200, &temporal_unit).unwrap();
+
+        // W=2, N=1, size fields are removed
+        assert_eq!(
+            packets,
+            vec![Bytes::from_static(&[
+                0x28, 0x02, 0x08, 0xaa, 0x30, 0x10, 0xbb
+            ])]
+        );
+    }
+
+    #[test]
+    fn large_obu_is_fragmented() {
+        // inter frame with 10 bytes of payload
+        let temporal_unit =
+            Bytes::from_static(&[0x12, 0x00, 0x32, 0x0a, 0x30, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+
+        let packets = Av1Payloader.payload(6, &temporal_unit).unwrap();
+
+        assert_eq!(
+            packets,
+            vec![
+                // W=1, Y=1
+                Bytes::from_static(&[0x50, 0x30, 0x30, 1, 2, 3]),
+                // W=1, Z=1, Y=1
+                Bytes::from_static(&[0xd0, 4, 5, 6, 7, 8]),
+                // W=1, Z=1
+                Bytes::from_static(&[0x90, 9]),
+            ]
+        );
+    }
+
+    #[test]
+    fn obu_at_mtu_boundary() {
+        // Fits with the aggregation header, the only element is not preceded by its length.
+        for obu_size in [1398, 1399] {
+            let packets = Av1Payloader
+                .payload(1400, &temporal_unit_with_frame(obu_size))
+                .unwrap();
+            assert_eq!(packets.len(), 1);
+            assert_eq!(packets[0].len(), obu_size + 1);
+            // W=1
+            assert_eq!(packets[0][0], 0x10);
+        }
+
+        let packets = Av1Payloader
+            .payload(1400, &temporal_unit_with_frame(1400))
+            .unwrap();
+        assert_eq!(packets.len(), 2);
+        // W=1, Y=1
+        assert_eq!(packets[0][0], 0x50);
+        assert_eq!(packets[0].len(), 1400);
+        // W=1, Z=1
+        assert_eq!(&packets[1][..], &[0x90, 0x30]);
+    }
+
+    #[test]
+    fn last_of_many_obus_at_mtu_boundary() {
+        // 3 OBUs, 2 bytes each with lengths, followed by an OBU without its length.
+        let mut temporal_unit = BytesMut::from(&[0x12, 0x00][..]);
+        for _ in 0..3 {
+            temporal_unit.extend_from_slice(&[0x32, 0x00]);
+        }
+        temporal_unit.extend_from_slice(&temporal_unit_with_frame(10)[2..]);
+
+        // 4 elements, all preceded by their lengths: 1 + 3 * 2 + (1 + 10) bytes.
+        let packets = Av1Payloader
+            .payload(18, &temporal_unit.clone().freeze())
+            .unwrap();
+        assert_eq!(packets.len(), 1);
+        assert_eq!(packets[0].len(), 18);
+        // W=0
+        assert_eq!(packets[0][0], 0x00);
+
+        let packets = Av1Payloader.payload(17, &temporal_unit.freeze()).unwrap();
+        assert_eq!(packets.len(), 2);
+        assert!(packets.iter().all(|packet| packet.len() <= 17));
+        // W=0, Y=1
+        assert_eq!(packets[0][0], 0x40);
+        // W=1, Z=1
+        assert_eq!(packets[1][0], 0x90);
+    }
+}
@@ -0,0 +1,143 @@
+use std::{
+    io::{self, Write},
+    net::{TcpStream, ToSocketAddrs},
+    sync::{atomic::AtomicBool, Arc},
+    thread,
+    time::Duration,
+};
+
+use compositor_render::OutputId;
+use crossbeam_channel::{Receiver, Sender, TryRecvError};
+use tracing::{debug, error, info, span, trace, warn, Level};
+
+use crate::pipeline::Port;
+
+use super::{
+    feedback::RtcpListener, packet_stream::PacketStream, tcp_server::TcpWritePacketStream,
+};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Packets produced while there is no connection are dropped, so the receiver
+/// gets a live stream after reconnecting instead of a backlog.
+pub(super) fn run_tcp_client_sender_thread(
+    output_id: &OutputId,
+    ip: Arc<str>,
+    port: Port,
+    should_close: Arc<AtomicBool>,
+    mut packet_stream: PacketStream,
+    rtcp_listener: RtcpListener,
+) {
+    let (connection_sender, connection_receiver) = crossbeam_channel::bounded(1);
+    let (reconnect_sender, reconnect_receiver) = crossbeam_channel::bounded(1);
+    spawn_connecting_thread(
+        output_id,
+        ip,
+        port,
+        should_close.clone(),
+        connection_sender,
+        reconnect_receiver,
+    );
+
+    let mut connected_socket: Option<TcpWritePacketStream> = None;
+    loop {
+        let chunk = match packet_stream.next() {
+            Some(Ok(chunk)) => chunk,
+            Some(Err(err)) => {
+                error!("Failed to payload a packet: {}", err);
+                continue;
+            }
+            None => {
+                if let Some(mut socket) = connected_socket {
+                    if let Err(err) = socket.socket.flush() {
+                        warn!(%err, "Failed to flush rest of the TCP buffer.");
+                    }
+                }
+                return;
+            }
+        };
+
+        if connected_socket.is_none() {
+            match connection_receiver.try_recv() {
+                Ok(socket) => {
+                    let socket = TcpWritePacketStream::new(socket, should_close.clone());
+                    // RTCP feedback is received over the same connection.
+                    rtcp_listener.clone().start_tcp(socket.socket.try_clone());
+                    // The receiver can not decode anything until the next keyframe.
+                    rtcp_listener.request_keyframe();
+                    connected_socket = Some(socket);
+                }
+                Err(TryRecvError::Empty) => continue,
+                Err(TryRecvError::Disconnected) => return,
+            }
+        }
+        let Some(socket) = &mut connected_socket else {
+            continue;
+        };
+
+        trace!(size_bytes = chunk.len(), "Send RTP TCP packet.");
+        if let Err(err) = socket.write_packet(chunk) {
+            if err.kind() == io::ErrorKind::WouldBlock {
+                // this means that should_close is true
+                return;
+            }
+            warn!(%err, "Failed to send RTP packet, reconnecting.");
+            connected_socket = None;
+            if reconnect_sender.send(()).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Connecting can take a long time, so it is done on a separate thread to avoid
+/// blocking the encoder. The thread waits for a signal from the sender before
+/// it reconnects.
+fn spawn_connecting_thread(
+    output_id: &OutputId,
+    ip: Arc<str>,
+    port: Port,
+    should_close: Arc<AtomicBool>,
+    connection_sender: Sender<socket2::Socket>,
+    reconnect_receiver: Receiver<()>,
+) {
+    let output_id = output_id.clone();
+    thread::Builder::new()
+        .name(format!("TCP client for output {}", output_id))
+        .spawn(move || {
+            let _span = span!(
+                Level::INFO,
+                "RTP TCP client",
+                output_id = output_id.to_string()
+            )
+            .entered();
+            while !should_close.load(std::sync::atomic::Ordering::Relaxed) {
+                let socket = match connect(&ip, port) {
+                    Ok(socket) => socket,
+                    Err(err) => {
+                        debug!(%err, "Failed to connect to {ip}:{}.", port.0);
+                        thread::sleep(RECONNECT_INTERVAL);
+                        continue;
+                    }
+                };
+                info!("Connected to {ip}:{}.", port.0);
+                if connection_sender.send(socket).is_err() || reconnect_receiver.recv().is_err() {
+                    break;
+                }
+            }
+            debug!("Closing TCP client thread.");
+        })
+        .unwrap();
+}
+
+fn connect(ip: &str, port: Port) -> io::Result<socket2::Socket> {
+    let mut last_err = io::Error::from(io::ErrorKind::AddrNotAvailable);
+    for address in (ip, port.0).to_socket_addrs()? {
+        match TcpStream::connect_timeout(&address, CONNECT_TIMEOUT) {
+            Ok(stream) => return Ok(stream.into()),
+            Err(err) => last_err = err,
+        }
+    }
+    Err(last_err)
+}
@@ -10,22 +10,18 @@ use tracing::{debug, error, trace, warn};
 use crate::{
     error::OutputInitError,
     pipeline::{
-        rtp::{bind_to_requested_port, BindToPortError, RequestedPort},
+        rtp::{bind_to_requested_port, dual_stack_socket, BindToPortError, RequestedPort},
         Port,
     },
 };
 
-use super::packet_stream::PacketStream;
+use super::{feedback::RtcpListener, packet_stream::PacketStream};
 
 pub(super) fn tcp_socket(port: RequestedPort) -> Result<(socket2::Socket, Port), OutputInitError> {
-    let socket = socket2::Socket::new(
-        socket2::Domain::IPV4,
-        socket2::Type::STREAM,
-        Some(socket2::Protocol::TCP),
-    )
-    .map_err(OutputInitError::SocketError)?;
+    let (socket, ip) = dual_stack_socket(socket2::Type::STREAM, socket2::Protocol::TCP)
+        .map_err(OutputInitError::SocketError)?;
 
-    let port = bind_to_requested_port(port, &socket)?;
+    let port = bind_to_requested_port(port, ip, &socket)?;
 
     socket.listen(1).map_err(OutputInitError::SocketError)?;
     Ok((socket, port))
@@ -34,6 +30,7 @@ pub(super) fn run_tcp_sender_thread(
     socket: socket2::Socket,
     should_close: Arc<AtomicBool>,
     mut packet_stream: PacketStream,
+    rtcp_listener: RtcpListener,
 ) {
     // make accept non blocking so we have a chance to handle should_close value
     socket
@@ -54,6 +51,8 @@ pub(super) fn run_tcp_sender_thread(
         Some(socket) => TcpWritePacketStream::new(socket, should_close.clone()),
         None => return,
     };
+    // RTCP feedback is received over the same connection.
+    rtcp_listener.start_tcp(socket.socket.try_clone());
 
     loop {
         let chunk = match packet_stream.next() {
@@ -97,13 +96,13 @@ impl From<BindToPortError> for OutputInitError {
     }
 }
 
-struct TcpWritePacketStream {
-    socket: socket2::Socket,
+pub(super) struct TcpWritePacketStream {
+    pub socket: socket2::Socket,
     should_close: Arc<AtomicBool>,
 }
 
 impl TcpWritePacketStream {
-    fn new(socket: socket2::Socket, should_close: Arc<AtomicBool>) -> Self {
+    pub fn new(socket: socket2::Socket, should_close: Arc<AtomicBool>) -> Self {
         // Timeout to make sure we are not left with unregistered
         // connections that are still maintained by a client side.
         socket
@@ -118,7 +117,7 @@ impl TcpWritePacketStream {
         }
     }
 
-    fn write_packet(&mut self, data: bytes::Bytes) -> io::Result<()> {
+    pub fn write_packet(&mut self, data: bytes::Bytes) -> io::Result<()> {
         self.write_bytes(&u16::to_be_bytes(data.len() as u16))?;
         self.write_bytes(&data[..])?;
         io::Result::Ok(())
@@ -1,21 +1,138 @@
+use std::{
+    io,
+    net::{IpAddr, SocketAddr, ToSocketAddrs},
+    sync::{Arc, Mutex},
+};
+
 use tracing::{debug, error, trace};
 
-use crate::{error::OutputInitError, pipeline::Port};
+use crate::{
+    error::OutputInitError,
+    pipeline::{rtp::MulticastInterface, Port},
+};
+
+use super::{packet_stream::PacketStream, MulticastSenderOptions};
+
+/// Addresses RTP packets are sent to. Additional destinations can be attached
+/// and detached while the output is running.
+#[derive(Debug, Clone)]
+pub struct UdpDestinations {
+    primary: SocketAddr,
+    additional: Arc<Mutex<Vec<SocketAddr>>>,
+}
+
+impl UdpDestinations {
+    fn new(primary: SocketAddr) -> Self {
+        Self {
+            primary,
+            additional: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Destination specified when the output was registered.
+    pub fn primary(&self) -> SocketAddr {
+        self.primary
+    }
+
+    /// Returns `false` if the address already receives the stream.
+    pub fn attach(&self, address: SocketAddr) -> bool {
+        let mut additional = self.additional.lock().unwrap();
+        if address == self.primary || additional.contains(&address) {
+            return false;
+        }
+        additional.push(address);
+        true
+    }
+
+    /// Returns `false` if the address was not attached before. The primary
+    /// destination can not be detached.
+    pub fn detach(&self, address: SocketAddr) -> bool {
+        let mut additional = self.additional.lock().unwrap();
+        let len_before = additional.len();
+        additional.retain(|attached| *attached != address);
+        additional.len() != len_before
+    }
+
+    /// Receivers do not always send RTCP from the port they receive RTP on,
+    /// so only the IP address is compared. Receivers of a multicast stream send
+    /// RTCP from their own unicast addresses, so any address is accepted if one
+    /// of the destinations is a multicast group.
+    pub(super) fn accepts_rtcp_from(&self, ip: IpAddr) -> bool {
+        let additional = self.additional.lock().unwrap();
+        std::iter::once(&self.primary)
+            .chain(additional.iter())
+            .any(|address| address.ip() == ip || address.ip().is_multicast())
+    }
+
+    fn all(&self) -> Vec<SocketAddr> {
+        let additional = self.additional.lock().unwrap();
+        std::iter::once(self.primary)
+            .chain(additional.iter().copied())
+            .collect()
+    }
+}
 
-use super::packet_stream::PacketStream;
+pub(super) fn udp_socket(
+    ip: &str,
+    port: Port,
+    multicast: &MulticastSenderOptions,
+) -> Result<(socket2::Socket, Port, UdpDestinations), OutputInitError> {
+    let primary = (ip, port.0)
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::AddrNotAvailable))?;
+    let socket = socket2::Socket::new(
+        socket2::Domain::for_address(primary),
+        socket2::Type::DGRAM,
+        Some(socket2::Protocol::UDP),
+    )?;
+    let unspecified: SocketAddr = match primary {
+        SocketAddr::V4(_) => (std::net::Ipv4Addr::UNSPECIFIED, 0).into(),
+        SocketAddr::V6(_) => (std::net::Ipv6Addr::UNSPECIFIED, 0).into(),
+    };
+    socket.bind(&unspecified.into())?;
+    if primary.ip().is_multicast() {
+        set_multicast_options(&socket, primary.ip(), multicast)?;
+    }
 
-pub(super) fn udp_socket(ip: &str, port: Port) -> Result<(socket2::Socket, Port), OutputInitError> {
-    let socket = std::net::UdpSocket::bind(std::net::SocketAddrV4::new(
-        std::net::Ipv4Addr::UNSPECIFIED,
-        0,
-    ))?;
+    Ok((socket, port, UdpDestinations::new(primary)))
+}
 
-    socket.connect((ip, port.0))?;
-    Ok((socket.into(), port))
+fn set_multicast_options(
+    socket: &socket2::Socket,
+    group: IpAddr,
+    options: &MulticastSenderOptions,
+) -> io::Result<()> {
+    match (group, options.interface) {
+        (IpAddr::V4(_), Some(MulticastInterface::V4(interface))) => {
+            socket.set_multicast_if_v4(&interface)?
+        }
+        (IpAddr::V6(_), Some(MulticastInterface::V6(index))) => {
+            socket.set_multicast_if_v6(index)?
+        }
+        (_, None) => {}
+        (_, Some(_)) => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Multicast interface does not match the IP version of the group.",
+            ))
+        }
+    }
+    if let Some(ttl) = options.ttl {
+        match group {
+            IpAddr::V4(_) => socket.set_multicast_ttl_v4(ttl)?,
+            IpAddr::V6(_) => socket.set_multicast_hops_v6(ttl)?,
+        }
+    }
+    Ok(())
 }
 
 /// this assumes, that a "packet" contains data about a single frame (access unit)
-pub(super) fn run_udp_sender_thread(socket: socket2::Socket, packet_stream: PacketStream) {
+pub(super) fn run_udp_sender_thread(
+    socket: socket2::Socket,
+    packet_stream: PacketStream,
+    destinations: UdpDestinations,
+) {
     for chunk in packet_stream {
         let chunk = match chunk {
             Ok(chunk) => chunk,
@@ -24,9 +141,51 @@ pub(super) fn run_udp_sender_thread(socket: socket2::Socket, packet_stream: Pack
                 continue;
             }
         };
-        trace!(size_bytes = chunk.len(), "Send RTP UDP packet.");
-        if let Err(err) = socket.send(&chunk) {
-            debug!("Failed to send packet: {err}");
-        };
+        for destination in destinations.all() {
+            trace!(size_bytes = chunk.len(), %destination, "Send RTP UDP packet.");
+            if let Err(err) = socket.send_to(&chunk, &destination.into()) {
+                debug!(%destination, "Failed to send packet: {err}");
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attach_and_detach_destinations() {
+        let primary: SocketAddr = "127.0.0.1:5000".parse().unwrap();
+        let additional: SocketAddr = "192.168.1.2:5002".parse().unwrap();
+        let destinations = UdpDestinations::new(primary);
+
+        assert!(!destinations.attach(primary));
+        assert!(destinations.attach(additional));
+        assert!(!destinations.attach(additional));
+        assert_eq!(destinations.all(), vec![primary, additional]);
+        assert!(destinations.accepts_rtcp_from(additional.ip()));
+
+        assert!(!destinations.detach(primary));
+        assert!(destinations.detach(additional));
+        assert!(!destinations.detach(additional));
+        assert!(destinations.accepts_rtcp_from(primary.ip()));
+        assert!(!destinations.accepts_rtcp_from(additional.ip()));
+        assert_eq!(destinations.all(), vec![primary]);
+    }
+
+    #[test]
+    fn rtcp_from_multicast_receivers_is_accepted() {
+        let primary: SocketAddr = "127.0.0.1:5000".parse().unwrap();
+        let multicast: SocketAddr = "239.0.0.1:5002".parse().unwrap();
+        let receiver: IpAddr = "192.168.1.3".parse().unwrap();
+        let destinations = UdpDestinations::new(primary);
+        assert!(!destinations.accepts_rtcp_from(receiver));
+
+        assert!(destinations.attach(multicast));
+        assert!(destinations.accepts_rtcp_from(receiver));
+
+        assert!(destinations.detach(multicast));
+        assert!(!destinations.accepts_rtcp_from(receiver));
     }
 }
@@ -0,0 +1,264 @@
+use std::{
+    sync::{atomic::AtomicBool, Arc},
+    time::Duration,
+};
+
+use compositor_render::{OutputId, Resolution};
+use crossbeam_channel::{bounded, Receiver, Sender};
+use ffmpeg_next::{format::context, Dictionary};
+use tracing::{debug, info, span, warn, Level};
+
+use crate::{
+    error::OutputInitError,
+    pipeline::{
+        encoder::{AudioEncoderInfo, Encoder},
+        mpeg_ts,
+        srt::{self, SrtConnectionOptions, SrtMode},
+        types::EncoderOutputEvent,
+        AudioCodec, Port, VideoCodec,
+    },
+};
+
+use super::muxer::{h264_keyframe_parameter_sets, Muxer, MuxerVideoTrack};
+
+const MIN_RECONNECT_DELAY: Duration = Duration::from_millis(500);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(10);
+
+#[derive(Debug)]
+pub struct SrtSender {
+    pub connection: SrtConnectionOptions,
+
+    /// should_close will be set after output is unregistered,
+    /// but the primary way of controlling the shutdown is a channel
+    /// receiver.
+    ///
+    /// SrtSender should be explicitly closed based on this value
+    /// only to interrupt waiting for a connection.
+    should_close: Arc<AtomicBool>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SrtSenderOptions {
+    pub connection: SrtConnectionOptions,
+    pub video: Option<VideoCodec>,
+    pub audio: Option<AudioCodec>,
+}
+
+#[derive(Debug, Clone)]
+struct VideoTrack {
+    resolution: Resolution,
+    keyframe_request_sender: Sender<()>,
+}
+
+impl SrtSender {
+    pub fn new(
+        output_id: &OutputId,
+        options: SrtSenderOptions,
+        packets_receiver: Receiver<EncoderOutputEvent>,
+        encoder: &Encoder,
+    ) -> Result<(Self, Option<Port>), OutputInitError> {
+        let port = match options.connection.mode {
+            SrtMode::Listener { port } => {
+                srt::check_port_available(port).map_err(|err| match err.kind() {
+                    std::io::ErrorKind::AddrInUse => OutputInitError::PortAlreadyInUse(port.0),
+                    _ => OutputInitError::SocketError(err),
+                })?;
+                Some(port)
+            }
+            SrtMode::Caller { .. } => None,
+        };
+
+        let video = match (options.video, &encoder.video) {
+            (Some(VideoCodec::H264), Some(video_encoder))
+                if video_encoder.codec() == VideoCodec::H264 =>
+            {
+                Some(VideoTrack {
+                    resolution: video_encoder.resolution(),
+                    keyframe_request_sender: video_encoder.keyframe_request_sender(),
+                })
+            }
+            (Some(codec), _) => return Err(OutputInitError::UnsupportedVideoCodec(codec)),
+            (None, _) => None,
+        };
+        let audio = match (options.audio, encoder.audio_info()) {
+            (Some(codec), Some(info)) if info.codec == codec => Some(info),
+            (Some(codec), _) => return Err(OutputInitError::UnsupportedAudioCodec(codec)),
+            (None, _) => None,
+        };
+
+        let should_close = Arc::new(AtomicBool::new(false));
+        let connection = options.connection.clone();
+        let output_id = output_id.clone();
+        let should_close2 = should_close.clone();
+        std::thread::Builder::new()
+            .name(format!("SRT sender for output {}", output_id))
+            .spawn(move || {
+                let _span =
+                    span!(Level::INFO, "SRT sender", output_id = output_id.to_string()).entered();
+                run_srt_sender_thread(connection, video, audio, should_close2, packets_receiver);
+                debug!("Closing SRT sender thread.")
+            })
+            .unwrap();
+
+        Ok((
+            Self {
+                connection: options.connection,
+                should_close,
+            },
+            port,
+        ))
+    }
+}
+
+impl Drop for SrtSender {
+    fn drop(&mut self) {
+        self.should_close
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+}
+
+/// Chunks produced while there is no connection are dropped, so the encoder is
+/// not blocked while waiting for the remote peer.
+fn run_srt_sender_thread(
+    connection_options: SrtConnectionOptions,
+    video: Option<VideoTrack>,
+    audio: Option<AudioEncoderInfo>,
+    should_close: Arc<AtomicBool>,
+    packets_receiver: Receiver<EncoderOutputEvent>,
+) {
+    let mut connector = start_connector(connection_options.clone(), should_close.clone());
+    let mut pending_connection: Option<context::Output> = None;
+    let mut connection: Option<Muxer> = None;
+    let mut keyframe_requested = false;
+
+    let mut video_eos = video.is_none();
+    let mut audio_eos = audio.is_none();
+
+    for event in packets_receiver {
+        if should_close.load(std::sync::atomic::Ordering::Relaxed) {
+            break;
+        }
+
+        let chunk = match event {
+            EncoderOutputEvent::Data(chunk) => chunk,
+            EncoderOutputEvent::VideoEOS => {
+                video_eos = true;
+                match audio_eos {
+                    true => break,
+                    false => continue,
+                }
+            }
+            EncoderOutputEvent::AudioEOS => {
+                audio_eos = true;
+                match video_eos {
+                    true => break,
+                    false => continue,
+                }
+            }
+        };
+
+        if connection.is_none() {
+            let Some(context) = pending_connection
+                .take()
+                .or_else(|| connector.try_recv().ok())
+            else {
+                continue;
+            };
+
+            // MPEG-TS stream should start with a keyframe, so the receiver can
+            // start decoding immediately.
+            let parameter_sets = match &video {
+                Some(video) => match h264_keyframe_parameter_sets(&chunk) {
+                    Some(parameter_sets) => Some(parameter_sets),
+                    None => {
+                        if !keyframe_requested {
+                            keyframe_requested = true;
+                            if video.keyframe_request_sender.send(()).is_err() {
+                                debug!("Failed to request a keyframe. Channel closed.");
+                            }
+                        }
+                        pending_connection = Some(context);
+                        continue;
+                    }
+                },
+                None => None,
+            };
+            keyframe_requested = false;
+
+            let video_track = video.as_ref().map(|video| MuxerVideoTrack {
+                codec: VideoCodec::H264,
+                resolution: video.resolution,
+                parameter_sets: parameter_sets.unwrap_or_default(),
+            });
+            match Muxer::new(context, video_track, audio.clone(), Dictionary::new()) {
+                Ok(new_connection) => {
+                    info!("SRT connection established.");
+                    connection = Some(new_connection);
+                }
+                Err(err) => {
+                    warn!(%err, "Failed to start MPEG-TS stream. Reconnecting.");
+                    connector = start_connector(connection_options.clone(), should_close.clone());
+                    continue;
+                }
+            }
+        }
+
+        if let Some(current) = &mut connection {
+            if let Err(err) = current.write(chunk) {
+                warn!(%err, "Failed to send data over SRT. Reconnecting.");
+                connection = None;
+                connector = start_connector(connection_options.clone(), should_close.clone());
+            }
+        }
+    }
+
+    if let Some(connection) = connection {
+        if video_eos && audio_eos {
+            info!("Output stream finished. Closing SRT connection.");
+        }
+        if let Err(err) = connection.finish() {
+            warn!(%err, "Failed to finalize SRT stream.");
+        }
+    }
+    // Stops the connector if it still waits for the remote peer.
+    should_close.store(true, std::sync::atomic::Ordering::Relaxed);
+}
+
+/// Establishes the connection on a separate thread. In the listener mode it waits
+/// for a caller, in the caller mode it retries until the remote listener accepts
+/// the connection.
+fn start_connector(
+    connection: SrtConnectionOptions,
+    should_close: Arc<AtomicBool>,
+) -> Receiver<context::Output> {
+    let (sender, receiver) = bounded(1);
+    let span = tracing::Span::current();
+    std::thread::Builder::new()
+        .name("SRT connector".to_string())
+        .spawn(move || {
+            let _span = span.entered();
+            let mut reconnect_delay = MIN_RECONNECT_DELAY;
+            loop {
+                match mpeg_ts::open_output(
+                    &connection.url(),
+                    connection.ffmpeg_options(),
+                    should_close.clone(),
+                ) {
+                    Ok(context) => {
+                        let _ = sender.send(context);
+                        return;
+                    }
+                    Err(_) if should_close.load(std::sync::atomic::Ordering::Relaxed) => return,
+                    Err(err) => warn!(
+                        %err,
+                        mode = ?connection.mode,
+                        "Failed to establish SRT connection. Retrying in {reconnect_delay:?}."
+                    ),
+                }
+                std::thread::sleep(reconnect_delay);
+                reconnect_delay = Duration::min(reconnect_delay * 2, MAX_RECONNECT_DELAY);
+            }
+        })
+        .unwrap();
+    receiver
+}
@@ -0,0 +1,549 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, Weak},
+    time::{Duration, Instant},
+};
+
+use compositor_render::OutputId;
+use crossbeam_channel::{Receiver, Sender};
+use rtcp::payload_feedbacks::{
+    full_intra_request::FullIntraRequest, picture_loss_indication::PictureLossIndication,
+};
+use tracing::{debug, info, span, warn, Level};
+use webrtc::{
+    api::interceptor_registry::register_default_interceptors,
+    interceptor::registry::Registry,
+    peer_connection::{
+        configuration::RTCConfiguration, peer_connection_state::RTCPeerConnectionState,
+        RTCPeerConnection,
+    },
+    rtp_transceiver::rtp_sender::RTCRtpSender,
+    track::track_local::{track_local_static_rtp::TrackLocalStaticRTP, TrackLocalWriter},
+};
+
+use crate::{
+    error::{OutputInitError, WhepError},
+    pipeline::{
+        encoder::Encoder,
+        types::{EncodedChunkKind, EncoderOutputEvent},
+        webrtc_utils, AudioCodec, VideoCodec,
+    },
+};
+
+use super::rtp::Payloader;
+
+/// Leaves room for the SRTP authentication tag and TURN or VPN overhead.
+const MTU: usize = 1200;
+
+/// Every viewer requests a keyframe after it connects and when it loses packets,
+/// so requests of many viewers are merged.
+const MIN_KEYFRAME_REQUEST_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone)]
+pub struct WhepSenderOptions {
+    pub video: Option<VideoCodec>,
+    pub audio: Option<AudioCodec>,
+    /// Viewers have to send it in the `Authorization: Bearer <token>` header.
+    pub bearer_token: Option<Arc<str>>,
+}
+
+/// Output that serves the encoded streams to WHEP viewers
+/// ([draft-ietf-wish-whep](https://datatracker.ietf.org/doc/draft-ietf-wish-whep/)).
+/// Each chunk is payloaded once and the same RTP packets are sent to all viewers.
+pub struct WhepSender {
+    endpoint: WhepEndpoint,
+}
+
+/// Handle to a WHEP output used by the HTTP server to start and end sessions of
+/// viewers. It stays valid after the output is unregistered, but it does not
+/// accept new sessions anymore.
+#[derive(Clone)]
+pub struct WhepEndpoint(Arc<EndpointState>);
+
+pub struct WhepSession {
+    pub id: String,
+    pub answer: String,
+}
+
+struct EndpointState {
+    output_id: OutputId,
+    bearer_token: Option<Arc<str>>,
+    video_codec: Option<VideoCodec>,
+    video_track: Option<Arc<TrackLocalStaticRTP>>,
+    audio_track: Option<Arc<TrackLocalStaticRTP>>,
+    keyframe_requests: Option<KeyframeRequests>,
+    /// `None` after the output is unregistered.
+    sessions: Mutex<Option<HashMap<String, Arc<RTCPeerConnection>>>>,
+}
+
+struct KeyframeRequests {
+    sender: Sender<()>,
+    last_request: Mutex<Option<Instant>>,
+}
+
+impl WhepSender {
+    pub fn new(
+        output_id: &OutputId,
+        options: WhepSenderOptions,
+        packets_receiver: Receiver<EncoderOutputEvent>,
+        encoder: &Encoder,
+    ) -> Result<Self, OutputInitError> {
+        let keyframe_request_sender = match (options.video, &encoder.video) {
+            (Some(codec), Some(video_encoder)) if video_encoder.codec() == codec => {
+                Some(video_encoder.keyframe_request_sender())
+            }
+            (Some(codec), _) => return Err(OutputInitError::UnsupportedVideoCodec(codec)),
+            (None, _) => None,
+        };
+        match (options.audio, encoder.audio_info()) {
+            (Some(AudioCodec::Opus), Some(info)) if info.codec == AudioCodec::Opus => (),
+            (Some(codec), _) => return Err(OutputInitError::UnsupportedAudioCodec(codec)),
+            (None, _) => (),
+        };
+
+        let stream_id = output_id.to_string();
+        let video_track = options.video.map(|codec| {
+            Arc::new(TrackLocalStaticRTP::new(
+                webrtc_utils::video_codec_capability(codec),
+                "video".to_string(),
+                stream_id.clone(),
+            ))
+        });
+        let audio_track = options.audio.map(|_| {
+            Arc::new(TrackLocalStaticRTP::new(
+                webrtc_utils::opus_codec_capability(),
+                "audio".to_string(),
+                stream_id.clone(),
+            ))
+        });
+
+        let payloader = Payloader::new(options.video, options.audio);
+        let sender_video_track = video_track.clone();
+        let sender_audio_track = audio_track.clone();
+        let sender_output_id = output_id.clone();
+        std::thread::Builder::new()
+            .name(format!("WHEP sender for output {}", output_id))
+            .spawn(move || {
+                let _span = span!(
+                    Level::INFO,
+                    "WHEP sender",
+                    output_id = sender_output_id.to_string()
+                )
+                .entered();
+                run_sender_thread(
+                    packets_receiver,
+                    payloader,
+                    sender_video_track,
+                    sender_audio_track,
+                );
+                debug!("Closing WHEP sender thread.");
+            })
+            .unwrap();
+
+        let endpoint = WhepEndpoint(Arc::new(EndpointState {
+            output_id: output_id.clone(),
+            bearer_token: options.bearer_token,
+            video_codec: options.video,
+            video_track,
+            audio_track,
+            keyframe_requests: keyframe_request_sender.map(|sender| KeyframeRequests {
+                sender,
+                last_request: Mutex::new(None),
+            }),
+            sessions: Mutex::new(Some(HashMap::new())),
+        }));
+        Ok(Self { endpoint })
+    }
+
+    pub fn endpoint(&self) -> WhepEndpoint {
+        self.endpoint.clone()
+    }
+}
+
+impl Drop for WhepSender {
+    fn drop(&mut self) {
+        self.endpoint.0.close();
+    }
+}
+
+impl WhepEndpoint {
+    /// Accepts an SDP offer of a viewer and returns the answer.
+    pub async fn start_session(
+        &self,
+        bearer_token: Option<&str>,
+        offer: String,
+    ) -> Result<WhepSession, WhepError> {
+        self.authorize(bearer_token)?;
+        if self.0.sessions.lock().unwrap().is_none() {
+            return Err(WhepError::NotFound(self.0.output_id.clone()));
+        }
+
+        let state = self.0.clone();
+        webrtc_utils::run_on_runtime(state.negotiate(offer)).await
+    }
+
+    /// Ends the session of a single viewer.
+    pub fn end_session(
+        &self,
+        bearer_token: Option<&str>,
+        session_id: &str,
+    ) -> Result<(), WhepError> {
+        self.authorize(bearer_token)?;
+        if !self.0.end_session(session_id) {
+            return Err(WhepError::SessionNotFound(
+                self.0.output_id.clone(),
+                session_id.to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn authorize(&self, bearer_token: Option<&str>) -> Result<(), WhepError> {
+        match &self.0.bearer_token {
+            Some(expected) if Some(expected.as_ref()) != bearer_token => {
+                Err(WhepError::Unauthorized(self.0.output_id.clone()))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl EndpointState {
+    async fn negotiate(self: Arc<Self>, offer: String) -> Result<WhepSession, WhepError> {
+        let id = format!("{:016x}", rand::random::<u64>());
+        let (peer_connection, answer) = self
+            .connect(&id, offer)
+            .await
+            .map_err(WhepError::Negotiation)?;
+
+        let mut sessions = self.sessions.lock().unwrap();
+        // The output could have been unregistered during the negotiation.
+        let Some(sessions) = sessions.as_mut() else {
+            webrtc_utils::runtime().spawn(close_peer_connection(peer_connection));
+            return Err(WhepError::NotFound(self.output_id.clone()));
+        };
+        info!(
+            output_id = self.output_id.to_string(),
+            session_id = id,
+            "WHEP viewer connected."
+        );
+        sessions.insert(id.clone(), peer_connection);
+        Ok(WhepSession { id, answer })
+    }
+
+    async fn connect(
+        self: &Arc<Self>,
+        session_id: &str,
+        offer: String,
+    ) -> Result<(Arc<RTCPeerConnection>, String), webrtc::Error> {
+        let mut media_engine =
+            webrtc_utils::media_engine(self.video_codec, self.audio_track.is_some())?;
+        // Default interceptors send sender reports and retransmit packets on NACK.
+        let interceptors = register_default_interceptors(Registry::new(), &mut media_engine)?;
+        let api = webrtc_utils::new_api(media_engine, interceptors);
+        let peer_connection = Arc::new(api.new_peer_connection(RTCConfiguration::default()).await?);
+
+        let state = Arc::downgrade(self);
+        let id = session_id.to_string();
+        peer_connection.on_peer_connection_state_change(Box::new(move |connection_state| {
+            debug!(
+                session_id = id,
+                ?connection_state,
+                "WHEP connection state changed."
+            );
+            if let Some(state) = state.upgrade() {
+                match connection_state {
+                    // New viewer does not have to wait for the next keyframe.
+                    RTCPeerConnectionState::Connected => state.request_keyframe(),
+                    RTCPeerConnectionState::Failed | RTCPeerConnectionState::Closed => {
+                        state.end_session(&id);
+                    }
+                    _ => (),
+                }
+            }
+            Box::pin(async {})
+        }));
+
+        let result = match self.add_tracks(&peer_connection).await {
+            Ok(()) => webrtc_utils::answer_offer(&peer_connection, offer).await,
+            Err(err) => Err(err),
+        };
+        match result {
+            Ok(answer) => Ok((peer_connection, answer)),
+            Err(err) => {
+                close_peer_connection(peer_connection).await;
+                Err(err)
+            }
+        }
+    }
+
+    /// All viewers share the same tracks, so packets written to them are sent to
+    /// every peer connection.
+    async fn add_tracks(
+        self: &Arc<Self>,
+        peer_connection: &RTCPeerConnection,
+    ) -> Result<(), webrtc::Error> {
+        for track in self.video_track.iter().chain(&self.audio_track) {
+            let rtp_sender = peer_connection.add_track(track.clone()).await?;
+            tokio::spawn(handle_feedback(Arc::downgrade(self), rtp_sender));
+        }
+        Ok(())
+    }
+
+    /// Returns `false` if there was no session with this ID.
+    fn end_session(&self, session_id: &str) -> bool {
+        let peer_connection = self
+            .sessions
+            .lock()
+            .unwrap()
+            .as_mut()
+            .and_then(|sessions| sessions.remove(session_id));
+        let Some(peer_connection) = peer_connection else {
+            return false;
+        };
+        info!(
+            output_id = self.output_id.to_string(),
+            session_id, "WHEP session ended."
+        );
+        webrtc_utils::runtime().spawn(close_peer_connection(peer_connection));
+        true
+    }
+
+    /// Ends all sessions and stops accepting new ones.
+    fn close(&self) {
+        let sessions = self.sessions.lock().unwrap().take().unwrap_or_default();
+        for (_, peer_connection) in sessions {
+            webrtc_utils::runtime().spawn(close_peer_connection(peer_connection));
+        }
+    }
+
+    fn request_keyframe(&self) {
+        let Some(requests) = &self.keyframe_requests else {
+            return;
+        };
+        let now = Instant::now();
+        {
+            let mut last_request = requests.last_request.lock().unwrap();
+            if last_request
+                .is_some_and(|requested_at| now - requested_at < MIN_KEYFRAME_REQUEST_INTERVAL)
+            {
+                return;
+            }
+            *last_request = Some(now);
+        }
+
+        debug!("WHEP viewer requested a keyframe.");
+        if requests.sender.send(()).is_err() {
+            debug!("Failed to request a keyframe. Channel closed.");
+        }
+    }
+}
+
+/// Payloads chunks once and writes the packets to the shared tracks. Each track
+/// sends them to all viewers, rewriting the SSRC and the payload type negotiated
+/// with each of them.
+fn run_sender_thread(
+    packets_receiver: Receiver<EncoderOutputEvent>,
+    mut payloader: Payloader,
+    video_track: Option<Arc<TrackLocalStaticRTP>>,
+    audio_track: Option<Arc<TrackLocalStaticRTP>>,
+) {
+    for event in packets_receiver {
+        // Viewers detect the end of the stream when the peer connection is closed.
+        let EncoderOutputEvent::Data(chunk) = event else {
+            continue;
+        };
+        let track = match chunk.kind {
+            EncodedChunkKind::Video(_) => &video_track,
+            EncodedChunkKind::Audio(_) => &audio_track,
+        };
+        let Some(track) = track else {
+            continue;
+        };
+        let packets = match payloader.payload(MTU, chunk) {
+            Ok(packets) => packets,
+            Err(err) => {
+                warn!(%err, "Failed to payload a chunk.");
+                continue;
+            }
+        };
+        for packet in packets {
+            if let Err(err) = webrtc_utils::runtime().block_on(track.write(&packet)) {
+                debug!(%err, "Failed to send RTP packet.");
+            }
+        }
+    }
+}
+
+/// RTCP has to be read for the interceptors to handle NACKs and receiver reports.
+async fn handle_feedback(state: Weak<EndpointState>, rtp_sender: Arc<RTCRtpSender>) {
+    loop {
+        let packets = match rtp_sender.read_rtcp().await {
+            Ok((packets, _)) => packets,
+            Err(err) => {
+                debug!(%err, "Stopped receiving RTCP packets.");
+                return;
+            }
+        };
+        let requests_keyframe = packets.iter().any(|packet| {
+            let packet = packet.as_any();
+            packet.is::<PictureLossIndication>() || packet.is::<FullIntraRequest>()
+        });
+        if requests_keyframe {
+            let Some(state) = state.upgrade() else {
+                return;
+            };
+            state.request_keyframe();
+        }
+    }
+}
+
+async fn close_peer_connection(peer_connection: Arc<RTCPeerConnection>) {
+    if let Err(err) = peer_connection.close().await {
+        debug!(%err, "Failed to close peer connection.");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use webrtc::{
+        api::{media_engine::MediaEngine, APIBuilder},
+        peer_connection::sdp::session_description::RTCSessionDescription,
+        rtp_transceiver::{
+            rtp_codec::RTPCodecType, rtp_transceiver_direction::RTCRtpTransceiverDirection,
+            RTCRtpTransceiverInit,
+        },
+    };
+
+    use crate::pipeline::types::EncodedChunk;
+
+    use super::*;
+
+    /// Connects two local WebRTC viewers and checks that both receive the same
+    /// payloaded Opus chunks.
+    #[test]
+    fn send_opus_to_viewers() {
+        let audio_track = Arc::new(TrackLocalStaticRTP::new(
+            webrtc_utils::opus_codec_capability(),
+            "audio".to_string(),
+            "whep_test".to_string(),
+        ));
+        let endpoint = WhepEndpoint(Arc::new(EndpointState {
+            output_id: OutputId("whep_output".into()),
+            bearer_token: Some("secret".into()),
+            video_codec: None,
+            video_track: None,
+            audio_track: Some(audio_track.clone()),
+            keyframe_requests: None,
+            sessions: Mutex::new(Some(HashMap::new())),
+        }));
+        let (chunk_sender, chunk_receiver) = crossbeam_channel::unbounded();
+        std::thread::spawn(move || {
+            run_sender_thread(
+                chunk_receiver,
+                Payloader::new(None, Some(AudioCodec::Opus)),
+                None,
+                Some(audio_track),
+            )
+        });
+
+        let viewers: Vec<_> = (0..2)
+            .map(|_| webrtc_utils::runtime().block_on(connect_viewer(&endpoint)))
+            .collect();
+
+        let payload = Bytes::from_static(&[0xfc, 0xff, 0xfe]);
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let mut pts = Duration::ZERO;
+        while viewers.iter().any(|(_, _, payloads)| payloads.is_empty()) {
+            assert!(
+                Instant::now() < deadline,
+                "Viewers did not receive packets."
+            );
+            chunk_sender
+                .send(EncoderOutputEvent::Data(EncodedChunk {
+                    data: payload.clone(),
+                    pts,
+                    dts: None,
+                    discontinuity: false,
+                    kind: EncodedChunkKind::Audio(AudioCodec::Opus),
+                }))
+                .unwrap();
+            pts += Duration::from_millis(20);
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        for (_, _, payloads) in &viewers {
+            assert_eq!(payloads.recv().unwrap(), payload);
+        }
+
+        let (_, session_id, _) = &viewers[0];
+        assert!(matches!(
+            endpoint.end_session(None, session_id),
+            Err(WhepError::Unauthorized(_))
+        ));
+        endpoint.end_session(Some("secret"), session_id).unwrap();
+        assert!(matches!(
+            endpoint.end_session(Some("secret"), session_id),
+            Err(WhepError::SessionNotFound(_, _))
+        ));
+        for (client, _, _) in viewers {
+            webrtc_utils::runtime().block_on(close_peer_connection(client));
+        }
+    }
+
+    /// Returns the peer connection of the viewer, the session ID and payloads of
+    /// received RTP packets.
+    async fn connect_viewer(
+        endpoint: &WhepEndpoint,
+    ) -> (Arc<RTCPeerConnection>, String, Receiver<Bytes>) {
+        let mut media_engine = MediaEngine::default();
+        media_engine.register_default_codecs().unwrap();
+        let api = APIBuilder::new().with_media_engine(media_engine).build();
+        let client = Arc::new(
+            api.new_peer_connection(RTCConfiguration::default())
+                .await
+                .unwrap(),
+        );
+        client
+            .add_transceiver_from_kind(
+                RTPCodecType::Audio,
+                Some(RTCRtpTransceiverInit {
+                    direction: RTCRtpTransceiverDirection::Recvonly,
+                    send_encodings: Vec::new(),
+                }),
+            )
+            .await
+            .unwrap();
+
+        let (payload_sender, payload_receiver) = crossbeam_channel::unbounded();
+        client.on_track(Box::new(move |track, _receiver, _transceiver| {
+            let payload_sender = payload_sender.clone();
+            tokio::spawn(async move {
+                while let Ok((packet, _)) = track.read_rtp().await {
+                    if payload_sender.send(packet.payload).is_err() {
+                        return;
+                    }
+                }
+            });
+            Box::pin(async {})
+        }));
+
+        let offer = client.create_offer(None).await.unwrap();
+        let mut gathering_complete = client.gathering_complete_promise().await;
+        client.set_local_description(offer).await.unwrap();
+        let _ = gathering_complete.recv().await;
+        let offer = client.local_description().await.unwrap().sdp;
+
+        assert!(matches!(
+            endpoint.start_session(Some("wrong"), offer.clone()).await,
+            Err(WhepError::Unauthorized(_))
+        ));
+        let WhepSession { id, answer } =
+            endpoint.start_session(Some("secret"), offer).await.unwrap();
+        client
+            .set_remote_description(RTCSessionDescription::answer(answer).unwrap())
+            .await
+            .unwrap();
+        (client, id, payload_receiver)
+    }
+}
@@ -27,6 +27,7 @@ pub(super) fn register_pipeline_input<NewInputResult>(
 
     let (input, receiver, input_result) = input_options.new_input(&input_id, &pipeline_ctx)?;
 
+    let common_timeline = input.has_common_timeline();
     let (audio_eos_received, video_eos_received) = (
         receiver.audio.as_ref().map(|_| false),
         receiver.video.as_ref().map(|_| false),
@@ -57,7 +58,9 @@ pub(super) fn register_pipeline_input<NewInputResult>(
     }
 
     guard.inputs.insert(input_id.clone(), pipeline_input);
-    guard.queue.add_input(&input_id, receiver, queue_options);
+    guard
+        .queue
+        .add_input(&input_id, receiver, queue_options, common_timeline);
     guard.renderer.register_input(input_id);
 
     Ok(input_result)
@@ -1,14 +1,24 @@
-use std::net;
+use std::{
+    io,
+    net::{self, IpAddr},
+};
+
+use tracing::debug;
 
 use super::Port;
 
-pub(crate) const VIDEO_PAYLOAD_TYPE: u8 = 96;
-pub(crate) const AUDIO_PAYLOAD_TYPE: u8 = 97;
+pub mod sdp;
+pub mod srtp;
+
+pub const VIDEO_PAYLOAD_TYPE: u8 = 96;
+pub const AUDIO_PAYLOAD_TYPE: u8 = 97;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TransportProtocol {
     Udp,
     TcpServer,
+    /// Supported only by outputs.
+    TcpClient,
 }
 
 pub(super) enum BindToPortError {
@@ -23,17 +33,43 @@ pub enum RequestedPort {
     Range((u16, u16)),
 }
 
+/// Network interface used to send or receive multicast traffic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MulticastInterface {
+    /// Address of the local interface, used with IPv4 groups.
+    V4(net::Ipv4Addr),
+    /// Index of the local interface, used with IPv6 groups.
+    V6(u32),
+}
+
+/// Creates a socket that accepts both IPv4 and IPv6 traffic and returns it together
+/// with the unspecified address it should be bound to. Falls back to IPv4 if IPv6
+/// is not available on the host.
+pub(super) fn dual_stack_socket(
+    socket_type: socket2::Type,
+    protocol: socket2::Protocol,
+) -> io::Result<(socket2::Socket, IpAddr)> {
+    let ipv6_socket = socket2::Socket::new(socket2::Domain::IPV6, socket_type, Some(protocol))
+        .and_then(|socket| socket.set_only_v6(false).map(|_| socket));
+    match ipv6_socket {
+        Ok(socket) => Ok((socket, net::Ipv6Addr::UNSPECIFIED.into())),
+        Err(err) => {
+            debug!(%err, "IPv6 is not available, falling back to IPv4.");
+            let socket = socket2::Socket::new(socket2::Domain::IPV4, socket_type, Some(protocol))?;
+            Ok((socket, net::Ipv4Addr::UNSPECIFIED.into()))
+        }
+    }
+}
+
 pub(super) fn bind_to_requested_port(
     requested_port: RequestedPort,
+    ip: IpAddr,
     socket: &socket2::Socket,
 ) -> Result<Port, BindToPortError> {
     let port = match requested_port {
         RequestedPort::Exact(port) => {
             socket
-                .bind(
-                    &net::SocketAddr::V4(net::SocketAddrV4::new(net::Ipv4Addr::UNSPECIFIED, port))
-                        .into(),
-                )
+                .bind(&net::SocketAddr::new(ip, port).into())
                 .map_err(|err| match err.kind() {
                     std::io::ErrorKind::AddrInUse => BindToPortError::PortAlreadyInUse(port),
                     _ => BindToPortError::SocketBind(err),
@@ -42,10 +78,7 @@ pub(super) fn bind_to_requested_port(
         }
         RequestedPort::Range((lower_bound, upper_bound)) => {
             let port = (lower_bound..upper_bound).find(|port| {
-                let bind_res = socket.bind(
-                    &net::SocketAddr::V4(net::SocketAddrV4::new(net::Ipv4Addr::UNSPECIFIED, *port))
-                        .into(),
-                );
+                let bind_res = socket.bind(&net::SocketAddr::new(ip, *port).into());
 
                 bind_res.is_ok()
             });
@@ -0,0 +1,557 @@
+use std::{collections::HashMap, fmt, net::Ipv6Addr};
+
+use super::{
+    srtp::{SrtpError, SrtpOptions, SrtpProfile},
+    TransportProtocol,
+};
+
+/// Address used when the compositor listens for a connection or packets. Peers
+/// should replace it with an address under which the compositor is reachable.
+pub(crate) const UNSPECIFIED_ADDRESS: &str = "0.0.0.0";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaKind {
+    Video,
+    Audio,
+}
+
+/// Direction of the stream from the compositor's point of view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaDirection {
+    SendOnly,
+    RecvOnly,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SdpParseError {
+    #[error("Invalid SDP line: \"{0}\".")]
+    InvalidLine(String),
+
+    #[error("Invalid media description: \"{0}\".")]
+    InvalidMediaLine(String),
+
+    #[error("Invalid rtpmap attribute: \"{0}\".")]
+    InvalidRtpMap(String),
+
+    #[error("Unsupported transport protocol \"{0}\". Only RTP/AVP and RTP/SAVP over UDP or TCP are supported.")]
+    UnsupportedTransport(String),
+
+    #[error("SDP does not describe any video or audio stream.")]
+    NoMedia,
+
+    #[error("All streams have to use the same port and transport protocol.")]
+    DifferentPorts,
+
+    #[error("Invalid crypto attribute: \"{0}\".")]
+    InvalidCrypto(String),
+
+    #[error("Every SRTP stream has to be described with a supported crypto attribute.")]
+    MissingCrypto(#[source] Option<SrtpError>),
+
+    #[error("All streams have to use the same SRTP key.")]
+    DifferentSrtpKeys,
+
+    #[error(
+        "None of the video codecs is supported: {0}. Supported codecs are H264, VP8, VP9 and AV1."
+    )]
+    UnsupportedVideoCodecs(String),
+
+    #[error("None of the audio codecs is supported: {0}. Supported codecs are opus and MPEG4-GENERIC (AAC).")]
+    UnsupportedAudioCodecs(String),
+
+    #[error("Invalid format parameters of payload type {payload_type}: {message}")]
+    InvalidFormatParameters {
+        payload_type: u8,
+        message: &'static str,
+    },
+}
+
+/// Codec of a single RTP stream, described by `a=rtpmap` and `a=fmtp` attributes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MediaFormat {
+    pub kind: MediaKind,
+    pub payload_type: u8,
+    pub encoding_name: String,
+    pub clock_rate: u32,
+    pub channels: Option<u8>,
+    pub fmtp: Option<String>,
+}
+
+/// Session description ([RFC 8866](https://datatracker.ietf.org/doc/html/rfc8866)) of
+/// an RTP session. Video and audio are multiplexed together with RTCP on a single port.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionDescription {
+    pub address: String,
+    pub port: u16,
+    pub transport_protocol: TransportProtocol,
+    pub direction: MediaDirection,
+    pub media: Vec<MediaFormat>,
+    /// Key of the SRTP session, described with `a=crypto` attributes ([RFC 4568](https://datatracker.ietf.org/doc/html/rfc4568)).
+    pub srtp: Option<SrtpOptions>,
+}
+
+impl MediaFormat {
+    /// Value of a parameter from the `a=fmtp` attribute. Names are case-insensitive.
+    pub fn fmtp_parameter(&self, name: &str) -> Option<&str> {
+        self.fmtp
+            .as_deref()?
+            .split(';')
+            .filter_map(|parameter| parameter.trim().split_once('='))
+            .find(|(key, _)| key.trim().eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.trim())
+    }
+
+    pub fn h264(payload_type: u8) -> Self {
+        Self {
+            kind: MediaKind::Video,
+            payload_type,
+            encoding_name: "H264".to_string(),
+            clock_rate: 90_000,
+            channels: None,
+            // Payloader fragments NAL units (FU-A), which requires non-interleaved mode.
+            fmtp: Some("packetization-mode=1".to_string()),
+        }
+    }
+
+    pub fn vp8(payload_type: u8) -> Self {
+        Self::video(payload_type, "VP8")
+    }
+
+    pub fn vp9(payload_type: u8) -> Self {
+        Self::video(payload_type, "VP9")
+    }
+
+    pub fn av1(payload_type: u8) -> Self {
+        Self::video(payload_type, "AV1")
+    }
+
+    /// Opus always uses 48kHz clock rate and 2 channels in rtpmap ([RFC 7587](https://datatracker.ietf.org/doc/html/rfc7587#section-7)).
+    pub fn opus(payload_type: u8, forward_error_correction: bool) -> Self {
+        Self {
+            kind: MediaKind::Audio,
+            payload_type,
+            encoding_name: "opus".to_string(),
+            clock_rate: 48_000,
+            channels: Some(2),
+            fmtp: forward_error_correction.then(|| "useinbandfec=1".to_string()),
+        }
+    }
+
+    fn video(payload_type: u8, encoding_name: &str) -> Self {
+        Self {
+            kind: MediaKind::Video,
+            payload_type,
+            encoding_name: encoding_name.to_string(),
+            clock_rate: 90_000,
+            channels: None,
+            fmtp: None,
+        }
+    }
+}
+
+struct MediaSection {
+    kind: Option<MediaKind>,
+    port: u16,
+    transport_protocol: TransportProtocol,
+    /// RTP/SAVP profile.
+    secure: bool,
+    /// First supported `a=crypto` attribute or the error of the last unsupported one.
+    crypto: Option<Result<SrtpOptions, SrtpError>>,
+    payload_types: Vec<u8>,
+    /// Encoding name, clock rate and number of channels.
+    rtpmaps: HashMap<u8, (String, u32, Option<u8>)>,
+    fmtps: HashMap<u8, String>,
+}
+
+impl SessionDescription {
+    /// Parses SDP that describes streams sent to the compositor. Only payload types
+    /// with an `a=rtpmap` attribute are included, media other than video and audio
+    /// are ignored.
+    pub fn parse(sdp: &str) -> Result<Self, SdpParseError> {
+        let mut session_address = None;
+        let mut sections: Vec<MediaSection> = Vec::new();
+
+        for line in sdp.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let Some((line_type, value)) = line.split_once('=') else {
+                return Err(SdpParseError::InvalidLine(line.to_string()));
+            };
+            match (line_type, sections.last_mut()) {
+                ("m", _) => sections.push(parse_media_line(value)?),
+                ("c", _) if session_address.is_none() => {
+                    session_address = parse_connection_line(value)
+                }
+                ("a", Some(section)) => {
+                    if let Some(rtpmap) = value.strip_prefix("rtpmap:") {
+                        let (payload_type, rtpmap) = parse_rtpmap(rtpmap)?;
+                        section.rtpmaps.insert(payload_type, rtpmap);
+                    } else if let Some(crypto) = value.strip_prefix("crypto:") {
+                        if !matches!(section.crypto, Some(Ok(_))) {
+                            section.crypto = Some(parse_crypto(crypto)?);
+                        }
+                    } else if let Some(fmtp) = value.strip_prefix("fmtp:") {
+                        if let Some((payload_type, parameters)) = fmtp.split_once(' ') {
+                            if let Ok(payload_type) = payload_type.parse() {
+                                section
+                                    .fmtps
+                                    .insert(payload_type, parameters.trim().to_string());
+                            }
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        let mut sections: Vec<_> = sections
+            .into_iter()
+            .filter_map(|section| Some((section.kind?, section)))
+            .collect();
+        let Some((_, first_section)) = sections.first() else {
+            return Err(SdpParseError::NoMedia);
+        };
+        let (port, transport_protocol, secure) = (
+            first_section.port,
+            first_section.transport_protocol,
+            first_section.secure,
+        );
+        if sections.iter().any(|(_, section)| {
+            section.port != port
+                || section.transport_protocol != transport_protocol
+                || section.secure != secure
+        }) {
+            return Err(SdpParseError::DifferentPorts);
+        }
+
+        let srtp = if secure {
+            Some(session_srtp(&mut sections)?)
+        } else {
+            None
+        };
+
+        let media = sections
+            .into_iter()
+            .flat_map(|(kind, mut section)| {
+                section
+                    .payload_types
+                    .iter()
+                    .filter_map(|payload_type| {
+                        let (encoding_name, clock_rate, channels) =
+                            section.rtpmaps.remove(payload_type)?;
+                        Some(MediaFormat {
+                            kind,
+                            payload_type: *payload_type,
+                            encoding_name,
+                            clock_rate,
+                            channels,
+                            fmtp: section.fmtps.remove(payload_type),
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        Ok(Self {
+            address: session_address.unwrap_or_else(|| UNSPECIFIED_ADDRESS.to_string()),
+            port,
+            transport_protocol,
+            direction: MediaDirection::RecvOnly,
+            media,
+            srtp,
+        })
+    }
+}
+
+/// All streams are decrypted with a single SRTP context, so they have to share the key.
+fn session_srtp(sections: &mut [(MediaKind, MediaSection)]) -> Result<SrtpOptions, SdpParseError> {
+    let mut keys = sections
+        .iter_mut()
+        .map(|(_, section)| match section.crypto.take() {
+            Some(Ok(srtp)) => Ok(srtp),
+            Some(Err(err)) => Err(SdpParseError::MissingCrypto(Some(err))),
+            None => Err(SdpParseError::MissingCrypto(None)),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let srtp = keys.remove(0);
+    if keys.iter().any(|key| *key != srtp) {
+        return Err(SdpParseError::DifferentSrtpKeys);
+    }
+    Ok(srtp)
+}
+
+/// `a=crypto:<tag> <crypto-suite> inline:<key||salt>[|<lifetime>][|<MKI>:<length>] [<session-params>]`
+///
+/// Unsupported crypto suites are not an error, because the peer can offer more of them.
+fn parse_crypto(value: &str) -> Result<Result<SrtpOptions, SrtpError>, SdpParseError> {
+    let invalid = || SdpParseError::InvalidCrypto(value.to_string());
+    let mut fields = value.split_whitespace();
+    let (Some(_tag), Some(suite), Some(key_params)) = (fields.next(), fields.next(), fields.next())
+    else {
+        return Err(invalid());
+    };
+    let key = key_params
+        .split(';')
+        .next()
+        .and_then(|key_param| key_param.strip_prefix("inline:"))
+        .and_then(|key| key.split('|').next())
+        .ok_or_else(invalid)?;
+    let profile = match SrtpProfile::from_crypto_suite(suite) {
+        Ok(profile) => profile,
+        Err(err) => return Ok(Err(err)),
+    };
+    SrtpOptions::from_inline_key(profile, key)
+        .map(Ok)
+        .map_err(|_| invalid())
+}
+
+/// `m=<media> <port>[/<number of ports>] <proto> <fmt> ...`
+fn parse_media_line(value: &str) -> Result<MediaSection, SdpParseError> {
+    let invalid = || SdpParseError::InvalidMediaLine(value.to_string());
+    let mut fields = value.split_whitespace();
+    let kind = match fields.next().ok_or_else(invalid)? {
+        "video" => Some(MediaKind::Video),
+        "audio" => Some(MediaKind::Audio),
+        _ => None,
+    };
+    let port = fields
+        .next()
+        .and_then(|port| port.split('/').next())
+        .and_then(|port| port.parse().ok())
+        .ok_or_else(invalid)?;
+    let (transport_protocol, secure) = match fields.next().ok_or_else(invalid)? {
+        "RTP/AVP" | "RTP/AVPF" => (TransportProtocol::Udp, false),
+        "RTP/SAVP" | "RTP/SAVPF" => (TransportProtocol::Udp, true),
+        "TCP/RTP/AVP" | "TCP/RTP/AVPF" => (TransportProtocol::TcpServer, false),
+        "TCP/RTP/SAVP" | "TCP/RTP/SAVPF" => (TransportProtocol::TcpServer, true),
+        // Other media (e.g. data channels) are ignored, so their transport does not matter.
+        _ if kind.is_none() => (TransportProtocol::Udp, false),
+        protocol => return Err(SdpParseError::UnsupportedTransport(protocol.to_string())),
+    };
+    let payload_types = match kind {
+        Some(_) => fields
+            .map(|payload_type| payload_type.parse().map_err(|_| invalid()))
+            .collect::<Result<_, _>>()?,
+        None => Vec::new(),
+    };
+    Ok(MediaSection {
+        kind,
+        port,
+        transport_protocol,
+        secure,
+        crypto: None,
+        payload_types,
+        rtpmaps: HashMap::new(),
+        fmtps: HashMap::new(),
+    })
+}
+
+/// `c=IN <IP4|IP6> <address>[/<ttl>]`
+fn parse_connection_line(value: &str) -> Option<String> {
+    let address = value.split_whitespace().nth(2)?;
+    address.split('/').next().map(str::to_string)
+}
+
+/// `a=rtpmap:<payload type> <encoding name>/<clock rate>[/<channels>]`
+fn parse_rtpmap(value: &str) -> Result<(u8, (String, u32, Option<u8>)), SdpParseError> {
+    let invalid = || SdpParseError::InvalidRtpMap(value.to_string());
+    let (payload_type, encoding) = value.split_once(' ').ok_or_else(invalid)?;
+    let payload_type = payload_type.parse().map_err(|_| invalid())?;
+    let mut encoding = encoding.trim().split('/');
+    let encoding_name = encoding.next().ok_or_else(invalid)?.to_string();
+    let clock_rate = encoding
+        .next()
+        .and_then(|clock_rate| clock_rate.parse().ok())
+        .ok_or_else(invalid)?;
+    let channels = encoding
+        .next()
+        .map(|channels| channels.parse().map_err(|_| invalid()))
+        .transpose()?;
+    Ok((payload_type, (encoding_name, clock_rate, channels)))
+}
+
+impl fmt::Display for SessionDescription {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let address_type = match self.address.parse::<Ipv6Addr>() {
+            Ok(_) => "IP6",
+            Err(_) => "IP4",
+        };
+        // RFC 4145, which side of the TCP connection is opened by the compositor.
+        let (protocol, setup) = match (self.transport_protocol, self.srtp.is_some()) {
+            (TransportProtocol::Udp, false) => ("RTP/AVP", None),
+            (TransportProtocol::Udp, true) => ("RTP/SAVP", None),
+            (TransportProtocol::TcpServer, false) => ("TCP/RTP/AVP", Some("passive")),
+            (TransportProtocol::TcpServer, true) => ("TCP/RTP/SAVP", Some("passive")),
+            (TransportProtocol::TcpClient, false) => ("TCP/RTP/AVP", Some("active")),
+            (TransportProtocol::TcpClient, true) => ("TCP/RTP/SAVP", Some("active")),
+        };
+        let direction = match self.direction {
+            MediaDirection::SendOnly => "sendonly",
+            MediaDirection::RecvOnly => "recvonly",
+        };
+
+        write!(f, "v=0\r\n")?;
+        write!(f, "o=- 0 0 IN {address_type} {}\r\n", self.address)?;
+        write!(f, "s=LiveCompositor\r\n")?;
+        write!(f, "c=IN {address_type} {}\r\n", self.address)?;
+        write!(f, "t=0 0\r\n")?;
+        for media in &self.media {
+            let kind = match media.kind {
+                MediaKind::Video => "video",
+                MediaKind::Audio => "audio",
+            };
+            let payload_type = media.payload_type;
+            write!(f, "m={kind} {} {protocol} {payload_type}\r\n", self.port)?;
+            write!(
+                f,
+                "a=rtpmap:{payload_type} {}/{}",
+                media.encoding_name, media.clock_rate
+            )?;
+            if let Some(channels) = media.channels {
+                write!(f, "/{channels}")?;
+            }
+            write!(f, "\r\n")?;
+            if let Some(fmtp) = &media.fmtp {
+                write!(f, "a=fmtp:{payload_type} {fmtp}\r\n")?;
+            }
+            write!(f, "a=rtcp-mux\r\n")?;
+            if let Some(srtp) = &self.srtp {
+                write!(
+                    f,
+                    "a=crypto:1 {} inline:{}\r\n",
+                    srtp.profile.crypto_suite(),
+                    srtp.inline_key()
+                )?;
+            }
+            if let Some(setup) = setup {
+                write!(f, "a=setup:{setup}\r\n")?;
+                write!(f, "a=connection:new\r\n")?;
+            }
+            write!(f, "a={direction}\r\n")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pipeline::rtp::TransportProtocol;
+
+    use crate::pipeline::rtp::srtp::SrtpProfile;
+
+    use super::{MediaDirection, MediaFormat, MediaKind, SdpParseError, SessionDescription};
+
+    #[test]
+    fn udp_session_with_video_and_audio() {
+        let sdp = SessionDescription {
+            address: "127.0.0.1".to_string(),
+            port: 8002,
+            transport_protocol: TransportProtocol::Udp,
+            direction: MediaDirection::SendOnly,
+            media: vec![MediaFormat::h264(96), MediaFormat::opus(97, false)],
+            srtp: None,
+        };
+
+        assert_eq!(
+            sdp.to_string(),
+            [
+                "v=0",
+                "o=- 0 0 IN IP4 127.0.0.1",
+                "s=LiveCompositor",
+                "c=IN IP4 127.0.0.1",
+                "t=0 0",
+                "m=video 8002 RTP/AVP 96",
+                "a=rtpmap:96 H264/90000",
+                "a=fmtp:96 packetization-mode=1",
+                "a=rtcp-mux",
+                "a=sendonly",
+                "m=audio 8002 RTP/AVP 97",
+                "a=rtpmap:97 opus/48000/2",
+                "a=rtcp-mux",
+                "a=sendonly",
+                "",
+            ]
+            .join("\r\n")
+        );
+    }
+
+    #[test]
+    fn parse_ffmpeg_sdp() {
+        let sdp = [
+            "v=0",
+            "o=- 0 0 IN IP4 127.0.0.1",
+            "s=No Name",
+            "c=IN IP4 127.0.0.1",
+            "t=0 0",
+            "a=tool:libavformat 61.1.100",
+            "m=video 8004 RTP/AVP 102",
+            "a=rtpmap:102 H264/90000",
+            "a=fmtp:102 packetization-mode=1; sprop-parameter-sets=Z2QAH6zZQFAFuwEQAAADABAAAAMDIPGDGWA=,aOvjyyLA; profile-level-id=64001F",
+            "m=audio 8004 RTP/AVP 103",
+            "b=AS:128",
+            "a=rtpmap:103 MPEG4-GENERIC/44100/2",
+            "a=fmtp:103 profile-level-id=1;mode=AAC-hbr;sizelength=13;indexlength=3;indexdeltalength=3; config=121056E500",
+        ]
+        .join("\n");
+
+        let sdp = SessionDescription::parse(&sdp).unwrap();
+        assert_eq!(sdp.address, "127.0.0.1");
+        assert_eq!(sdp.port, 8004);
+        assert_eq!(sdp.transport_protocol, TransportProtocol::Udp);
+        assert_eq!(sdp.media.len(), 2);
+
+        let video = &sdp.media[0];
+        assert_eq!(video.kind, MediaKind::Video);
+        assert_eq!(video.payload_type, 102);
+        assert_eq!(video.encoding_name, "H264");
+        assert_eq!(video.fmtp_parameter("packetization-mode"), Some("1"));
+
+        let audio = &sdp.media[1];
+        assert_eq!(audio.kind, MediaKind::Audio);
+        assert_eq!(audio.payload_type, 103);
+        assert_eq!(audio.clock_rate, 44_100);
+        assert_eq!(audio.channels, Some(2));
+        assert_eq!(audio.fmtp_parameter("CONFIG"), Some("121056E500"));
+    }
+
+    #[test]
+    fn parse_rejects_different_ports() {
+        let sdp = [
+            "v=0",
+            "m=video 8004 RTP/AVP 96",
+            "a=rtpmap:96 VP8/90000",
+            "m=audio 8006 RTP/AVP 97",
+            "a=rtpmap:97 opus/48000/2",
+        ]
+        .join("\r\n");
+
+        assert!(SessionDescription::parse(&sdp).is_err());
+    }
+
+    #[test]
+    fn parse_srtp_sdp() {
+        let sdp = [
+            "v=0",
+            "c=IN IP4 127.0.0.1",
+            "m=video 8004 RTP/SAVP 96",
+            "a=rtpmap:96 VP8/90000",
+            "a=crypto:1 AES_CM_256_HMAC_SHA1_80 inline:d0RmdmcmVCspeEc3QGZiNWpVLFJhQX1cfHAwJSoj|2^20|1:32",
+            "a=crypto:2 AES_CM_128_HMAC_SHA1_32 inline:d0RmdmcmVCspeEc3QGZiNWpVLFJhQX1cfHAwJSoj|2^20|1:32",
+            "a=crypto:3 AES_CM_128_HMAC_SHA1_80 inline:WVNfX19zZW1jdGwgKCkgewkyMjA7fQp9CnVubGVz|2^20|1:32",
+        ]
+        .join("\r\n");
+
+        let sdp = SessionDescription::parse(&sdp).unwrap();
+        let srtp = sdp.srtp.as_ref().unwrap();
+        assert_eq!(srtp.profile, SrtpProfile::AesCm128HmacSha1_80);
+        assert_eq!(
+            srtp.inline_key(),
+            "WVNfX19zZW1jdGwgKCkgewkyMjA7fQp9CnVubGVz"
+        );
+        assert!(sdp.to_string().contains("m=video 8004 RTP/SAVP 96"));
+
+        let sdp_without_key = "v=0\r\nm=video 8004 RTP/SAVP 96\r\na=rtpmap:96 VP8/90000";
+        assert!(matches!(
+            SessionDescription::parse(sdp_without_key),
+            Err(SdpParseError::MissingCrypto(None))
+        ));
+    }
+}
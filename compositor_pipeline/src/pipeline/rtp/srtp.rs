@@ -0,0 +1,216 @@
+use std::fmt;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use bytes::Bytes;
+use webrtc_srtp::{context::Context, protection_profile::ProtectionProfile};
+
+pub const MASTER_KEY_LENGTH: usize = 16;
+pub const MASTER_SALT_LENGTH: usize = 14;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SrtpProfile {
+    AesCm128HmacSha1_80,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SrtpError {
+    #[error("SRTP key has to be base64 encoded.")]
+    InvalidKeyEncoding,
+
+    #[error("SRTP key has to contain a {MASTER_KEY_LENGTH} byte master key followed by a {MASTER_SALT_LENGTH} byte master salt, got {0} bytes.")]
+    InvalidKeyLength(usize),
+
+    #[error("Unsupported SRTP crypto suite \"{0}\". The only supported suite is AES_CM_128_HMAC_SHA1_80.")]
+    UnsupportedCryptoSuite(String),
+
+    #[error(transparent)]
+    Srtp(#[from] webrtc_srtp::Error),
+}
+
+/// Master key and salt shared with the peer out of band. The same key protects
+/// packets in both directions of the session.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SrtpOptions {
+    pub profile: SrtpProfile,
+    pub master_key: Bytes,
+    pub master_salt: Bytes,
+}
+
+impl fmt::Debug for SrtpOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SrtpOptions")
+            .field("profile", &self.profile)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SrtpProfile {
+    /// Name of the crypto suite used in SDP ([RFC 4568](https://datatracker.ietf.org/doc/html/rfc4568#section-6.2)).
+    pub fn crypto_suite(&self) -> &'static str {
+        match self {
+            SrtpProfile::AesCm128HmacSha1_80 => "AES_CM_128_HMAC_SHA1_80",
+        }
+    }
+
+    pub fn from_crypto_suite(suite: &str) -> Result<Self, SrtpError> {
+        match suite {
+            "AES_CM_128_HMAC_SHA1_80" => Ok(SrtpProfile::AesCm128HmacSha1_80),
+            suite => Err(SrtpError::UnsupportedCryptoSuite(suite.to_string())),
+        }
+    }
+}
+
+impl SrtpOptions {
+    /// `inline` key format from SDP security descriptions: base64 encoded
+    /// concatenation of the master key and the master salt.
+    pub fn from_inline_key(profile: SrtpProfile, key: &str) -> Result<Self, SrtpError> {
+        let key = BASE64
+            .decode(key.trim())
+            .map_err(|_| SrtpError::InvalidKeyEncoding)?;
+        if key.len() != MASTER_KEY_LENGTH + MASTER_SALT_LENGTH {
+            return Err(SrtpError::InvalidKeyLength(key.len()));
+        }
+        let mut key = Bytes::from(key);
+        let master_salt = key.split_off(MASTER_KEY_LENGTH);
+        Ok(Self {
+            profile,
+            master_key: key,
+            master_salt,
+        })
+    }
+
+    pub fn inline_key(&self) -> String {
+        BASE64.encode([&self.master_key[..], &self.master_salt[..]].concat())
+    }
+}
+
+/// Protects packets sent in one direction or unprotects packets received in one
+/// direction of the session. RTP and RTCP packets are distinguished the same way
+/// as when they are multiplexed on a single port ([RFC 5761](https://datatracker.ietf.org/doc/html/rfc5761#section-4)).
+pub(crate) struct SrtpContext {
+    context: Context,
+}
+
+impl SrtpContext {
+    pub fn new(options: &SrtpOptions) -> Result<Self, SrtpError> {
+        let profile = match options.profile {
+            SrtpProfile::AesCm128HmacSha1_80 => ProtectionProfile::Aes128CmHmacSha1_80,
+        };
+        // Replay protection is disabled, because retransmitted packets reuse
+        // the sequence number of the lost ones.
+        let context = Context::new(
+            &options.master_key,
+            &options.master_salt,
+            profile,
+            None,
+            None,
+        )?;
+        Ok(Self { context })
+    }
+
+    pub fn protect(&mut self, packet: &[u8]) -> Result<Bytes, SrtpError> {
+        if is_rtcp(packet) {
+            Ok(self.context.encrypt_rtcp(packet)?)
+        } else {
+            Ok(self.context.encrypt_rtp(packet)?)
+        }
+    }
+
+    pub fn unprotect(&mut self, packet: &[u8]) -> Result<Bytes, SrtpError> {
+        if is_rtcp(packet) {
+            Ok(self.context.decrypt_rtcp(packet)?)
+        } else {
+            Ok(self.context.decrypt_rtp(packet)?)
+        }
+    }
+}
+
+/// RTCP packet types (192-223) overlap with RTP payload types 64-95 with the marker bit set.
+fn is_rtcp(packet: &[u8]) -> bool {
+    packet
+        .get(1)
+        .is_some_and(|packet_type| (192..=223).contains(packet_type))
+}
+
+#[cfg(test)]
+mod tests {
+    use rtp::packet::Packet;
+    use webrtc_util::Marshal;
+
+    use super::*;
+
+    const KEY: &str = "WVNfX19zZW1jdGwgKCkgewkyMjA7fQp9CnVubGVz";
+
+    #[test]
+    fn inline_key_round_trip() {
+        let options = SrtpOptions::from_inline_key(SrtpProfile::AesCm128HmacSha1_80, KEY).unwrap();
+        assert_eq!(options.master_key.len(), MASTER_KEY_LENGTH);
+        assert_eq!(options.master_salt.len(), MASTER_SALT_LENGTH);
+        assert_eq!(options.inline_key(), KEY);
+
+        assert!(matches!(
+            SrtpOptions::from_inline_key(SrtpProfile::AesCm128HmacSha1_80, "AAAA"),
+            Err(SrtpError::InvalidKeyLength(3))
+        ));
+    }
+
+    fn rtp_packet() -> Bytes {
+        Packet {
+            header: rtp::header::Header {
+                version: 2,
+                payload_type: 96,
+                sequence_number: 1,
+                timestamp: 3000,
+                ssrc: 1234,
+                ..Default::default()
+            },
+            payload: Bytes::from_static(&[1, 2, 3, 4, 5]),
+        }
+        .marshal()
+        .unwrap()
+    }
+
+    fn rtcp_packet() -> Bytes {
+        rtcp::packet::marshal(&[Box::new(rtcp::receiver_report::ReceiverReport {
+            ssrc: 5678,
+            ..Default::default()
+        }) as Box<dyn rtcp::packet::Packet + Send + Sync>])
+        .unwrap()
+    }
+
+    #[test]
+    fn protect_and_unprotect_rtp_and_rtcp() {
+        let options = SrtpOptions::from_inline_key(SrtpProfile::AesCm128HmacSha1_80, KEY).unwrap();
+        let mut sender = SrtpContext::new(&options).unwrap();
+        let mut receiver = SrtpContext::new(&options).unwrap();
+
+        let rtp_packet = rtp_packet();
+        let protected = sender.protect(&rtp_packet).unwrap();
+        assert_ne!(protected[12..17], rtp_packet[12..17]);
+        let unprotected = receiver.unprotect(&protected).unwrap();
+        assert_eq!(unprotected, rtp_packet);
+
+        let rtcp_packet = rtcp_packet();
+        let protected = sender.protect(&rtcp_packet).unwrap();
+        let unprotected = receiver.unprotect(&protected).unwrap();
+        assert_eq!(unprotected, rtcp_packet);
+    }
+
+    #[test]
+    fn every_profile_is_supported() {
+        for profile in [SrtpProfile::AesCm128HmacSha1_80] {
+            assert_eq!(
+                SrtpProfile::from_crypto_suite(profile.crypto_suite()).unwrap(),
+                profile
+            );
+
+            let options = SrtpOptions::from_inline_key(profile, KEY).unwrap();
+            let mut sender = SrtpContext::new(&options).unwrap();
+            let mut receiver = SrtpContext::new(&options).unwrap();
+            for packet in [rtp_packet(), rtcp_packet()] {
+                let protected = sender.protect(&packet).unwrap();
+                assert_eq!(receiver.unprotect(&protected).unwrap(), packet);
+            }
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use std::{
+    io,
+    net::{Ipv4Addr, UdpSocket},
+    sync::Arc,
+    time::Duration,
+};
+
+use ffmpeg_next::Dictionary;
+
+use super::Port;
+
+/// Default latency of the SRT library.
+pub const DEFAULT_LATENCY: Duration = Duration::from_millis(120);
+
+/// Connection of an SRT input or output. Streams are carried in MPEG-TS and
+/// SRT sockets are handled by FFmpeg (libsrt).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SrtConnectionOptions {
+    pub mode: SrtMode,
+    /// Time the receiver buffers packets to retransmit lost ones. The larger of the
+    /// values set by the sender and the receiver is used.
+    pub latency: Duration,
+    /// Encrypts the stream with AES. Both sides have to use the same passphrase.
+    pub passphrase: Option<Arc<str>>,
+    /// Sent by the caller when it connects, e.g. to select a stream on a server.
+    pub stream_id: Option<Arc<str>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SrtMode {
+    /// Waits for a connection from the remote peer on a local UDP port.
+    Listener { port: Port },
+    /// Connects to a remote listener.
+    Caller { ip: Arc<str>, port: Port },
+}
+
+impl SrtConnectionOptions {
+    pub(super) fn url(&self) -> String {
+        match &self.mode {
+            SrtMode::Listener { port } => format!("srt://0.0.0.0:{}", port.0),
+            SrtMode::Caller { ip, port } if ip.contains(':') => {
+                format!("srt://[{}]:{}", ip, port.0)
+            }
+            SrtMode::Caller { ip, port } => format!("srt://{}:{}", ip, port.0),
+        }
+    }
+
+    /// Options of the FFmpeg `srt` protocol.
+    pub(super) fn ffmpeg_options(&self) -> Dictionary {
+        let mut options = Dictionary::new();
+        let mode = match self.mode {
+            SrtMode::Listener { .. } => "listener",
+            SrtMode::Caller { .. } => "caller",
+        };
+        options.set("mode", mode);
+        options.set("latency", &self.latency.as_micros().to_string());
+        if let Some(passphrase) = &self.passphrase {
+            options.set("passphrase", passphrase);
+        }
+        if let Some(stream_id) = &self.stream_id {
+            options.set("streamid", stream_id);
+        }
+        options
+    }
+}
+
+/// FFmpeg binds the socket of a listener only when the connection is opened on
+/// another thread, so the port is checked in advance to report the error in the
+/// register request.
+pub(super) fn check_port_available(port: Port) -> io::Result<()> {
+    UdpSocket::bind((Ipv4Addr::UNSPECIFIED, port.0)).map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn srt_urls() {
+        let options = SrtConnectionOptions {
+            mode: SrtMode::Caller {
+                ip: "::1".into(),
+                port: Port(9000),
+            },
+            latency: DEFAULT_LATENCY,
+            passphrase: None,
+            stream_id: None,
+        };
+        assert_eq!(options.url(), "srt://[::1]:9000");
+
+        let options = SrtConnectionOptions {
+            mode: SrtMode::Listener { port: Port(9001) },
+            ..options
+        };
+        assert_eq!(options.url(), "srt://0.0.0.0:9001");
+    }
+}
@@ -9,6 +9,8 @@ use crate::{
     queue::PipelineEvent,
 };
 
+use super::av1;
+
 /// A struct representing a chunk of encoded data.
 ///
 /// Many codecs specify that encoded data is split into chunks.
@@ -17,9 +19,50 @@ pub struct EncodedChunk {
     pub data: Bytes,
     pub pts: Duration,
     pub dts: Option<Duration>,
+    /// Data preceding this chunk was lost (e.g. RTP packets did not arrive). Decoders
+    /// should conceal the gap instead of treating the stream as continuous.
+    pub discontinuity: bool,
     pub kind: EncodedChunkKind,
 }
 
+impl EncodedChunk {
+    /// Checks if the chunk can be decoded without any previous frames.
+    pub(super) fn is_keyframe(&self) -> bool {
+        match self.kind {
+            EncodedChunkKind::Video(VideoCodec::H264) => h264_contains_idr(&self.data),
+            // Inverse key frame flag in the first byte of the frame tag (RFC 6386, section 9.1)
+            EncodedChunkKind::Video(VideoCodec::Vp8) => {
+                self.data.first().is_some_and(|byte| byte & 0x01 == 0)
+            }
+            EncodedChunkKind::Video(VideoCodec::Vp9) => vp9_is_keyframe(&self.data),
+            EncodedChunkKind::Video(VideoCodec::Av1) => av1::is_keyframe(&self.data),
+            EncodedChunkKind::Audio(_) => true,
+        }
+    }
+}
+
+/// Looks for an IDR slice in an Annex B byte stream.
+fn h264_contains_idr(data: &[u8]) -> bool {
+    const IDR_NAL_UNIT_TYPE: u8 = 5;
+    data.windows(4)
+        .any(|window| window[..3] == [0, 0, 1] && window[3] & 0x1f == IDR_NAL_UNIT_TYPE)
+}
+
+/// Reads `frame_type` from the uncompressed header of a VP9 frame (VP9 bitstream
+/// specification, section 6.2).
+fn vp9_is_keyframe(data: &[u8]) -> bool {
+    let Some(byte) = data.first() else {
+        return false;
+    };
+    // frame_marker (2 bits), profile_low_bit, profile_high_bit
+    let profile = ((byte >> 5) & 1) | (((byte >> 4) & 1) << 1);
+    // Profile 3 has an additional reserved bit.
+    let show_existing_frame_bit = if profile == 3 { 2 } else { 3 };
+    let show_existing_frame = (byte >> show_existing_frame_bit) & 1;
+    let frame_type = (byte >> (show_existing_frame_bit - 1)) & 1;
+    show_existing_frame == 0 && frame_type == 0
+}
+
 pub enum EncoderOutputEvent {
     Data(EncodedChunk),
     AudioEOS,
@@ -57,6 +100,9 @@ pub struct RawDataSender {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum VideoCodec {
     H264,
+    Vp8,
+    Vp9,
+    Av1,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -77,6 +123,9 @@ impl TryFrom<ffmpeg_next::Codec> for VideoCodec {
     fn try_from(value: ffmpeg_next::Codec) -> Result<Self, Self::Error> {
         match value.id() {
             ffmpeg_next::codec::Id::H264 => Ok(Self::H264),
+            ffmpeg_next::codec::Id::VP8 => Ok(Self::Vp8),
+            ffmpeg_next::codec::Id::VP9 => Ok(Self::Vp9),
+            ffmpeg_next::codec::Id::AV1 => Ok(Self::Av1),
             v => Err(CodecFromFfmpegError::UnsupportedCodec(v)),
         }
     }
@@ -114,3 +163,43 @@ impl fmt::Debug for Samples {
             .finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn video_chunk(codec: VideoCodec, data: &'static [u8]) -> EncodedChunk {
+        EncodedChunk {
+            data: Bytes::from_static(data),
+            pts: Duration::ZERO,
+            dts: None,
+            discontinuity: false,
+            kind: EncodedChunkKind::Video(codec),
+        }
+    }
+
+    #[test]
+    fn keyframe_detection() {
+        // SPS, PPS and IDR slice
+        let idr = video_chunk(
+            VideoCodec::H264,
+            &[0, 0, 0, 1, 0x67, 1, 0, 0, 0, 1, 0x68, 1, 0, 0, 1, 0x65, 1],
+        );
+        assert!(idr.is_keyframe());
+        let non_idr = video_chunk(VideoCodec::H264, &[0, 0, 0, 1, 0x41, 1, 2]);
+        assert!(!non_idr.is_keyframe());
+
+        assert!(video_chunk(VideoCodec::Vp8, &[0x10, 0x02]).is_keyframe());
+        assert!(!video_chunk(VideoCodec::Vp8, &[0x11, 0x02]).is_keyframe());
+
+        // frame_marker=2, profile 0, show_existing_frame=0, frame_type=0
+        assert!(video_chunk(VideoCodec::Vp9, &[0b1000_0010]).is_keyframe());
+        // frame_type=1
+        assert!(!video_chunk(VideoCodec::Vp9, &[0b1000_0110]).is_keyframe());
+
+        // temporal delimiter and frame OBU with frame_type=KEY_FRAME
+        assert!(video_chunk(VideoCodec::Av1, &[0x12, 0x00, 0x32, 0x01, 0x10]).is_keyframe());
+        // frame_type=INTER_FRAME
+        assert!(!video_chunk(VideoCodec::Av1, &[0x12, 0x00, 0x32, 0x01, 0x30]).is_keyframe());
+    }
+}
@@ -0,0 +1,189 @@
+use std::{future::Future, sync::OnceLock};
+
+use tokio::runtime::Runtime;
+use webrtc::{
+    api::{
+        media_engine::{MediaEngine, MIME_TYPE_H264, MIME_TYPE_OPUS, MIME_TYPE_VP8, MIME_TYPE_VP9},
+        setting_engine::SettingEngine,
+        APIBuilder, API,
+    },
+    interceptor::registry::Registry,
+    peer_connection::{sdp::session_description::RTCSessionDescription, RTCPeerConnection},
+    rtp_transceiver::{
+        rtp_codec::{RTCRtpCodecCapability, RTCRtpCodecParameters, RTPCodecType},
+        RTCPFeedback,
+    },
+};
+
+use super::{
+    rtp::{AUDIO_PAYLOAD_TYPE, VIDEO_PAYLOAD_TYPE},
+    types::VideoCodec,
+};
+
+/// `profile-level-id` values of H264 profiles accepted from the peer: constrained baseline,
+/// baseline, main and high.
+const H264_PROFILES: [&str; 4] = ["42e01f", "42001f", "4d001f", "64001f"];
+
+/// Media engine of the `webrtc` crate does not define a MIME type for AV1.
+const MIME_TYPE_AV1: &str = "video/AV1";
+
+/// WebRTC sessions are driven by async tasks. They run on a runtime shared by all
+/// inputs and outputs, so they do not depend on the runtime of the caller (e.g. the
+/// HTTP server).
+pub(super) fn runtime() -> &'static Runtime {
+    static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+    RUNTIME.get_or_init(|| {
+        tokio::runtime::Builder::new_multi_thread()
+            .thread_name("WebRTC runtime")
+            .enable_all()
+            .build()
+            .unwrap()
+    })
+}
+
+/// Runs `future` on the WebRTC runtime and waits for its result.
+pub(super) async fn run_on_runtime<F>(future: F) -> F::Output
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    match runtime().spawn(future).await {
+        Ok(output) => output,
+        Err(err) => std::panic::resume_unwind(err.into_panic()),
+    }
+}
+
+/// Media engine that negotiates only the specified codecs.
+pub(super) fn media_engine(
+    video: Option<VideoCodec>,
+    audio: bool,
+) -> Result<MediaEngine, webrtc::Error> {
+    let mut media_engine = MediaEngine::default();
+    if let Some(codec) = video {
+        for codec in video_codecs(codec) {
+            media_engine.register_codec(codec, RTPCodecType::Video)?;
+        }
+    }
+    if audio {
+        media_engine.register_codec(opus_codec(), RTPCodecType::Audio)?;
+    }
+    Ok(media_engine)
+}
+
+/// The compositor has a publicly reachable address, so peer connections run
+/// ICE-lite: they do not gather server reflexive candidates and only respond to
+/// connectivity checks.
+pub(super) fn new_api(media_engine: MediaEngine, interceptors: Registry) -> API {
+    let mut setting_engine = SettingEngine::default();
+    setting_engine.set_lite(true);
+
+    APIBuilder::new()
+        .with_media_engine(media_engine)
+        .with_interceptor_registry(interceptors)
+        .with_setting_engine(setting_engine)
+        .build()
+}
+
+/// Returns the answer once all local candidates are gathered, so it can be sent in
+/// a single HTTP response without trickle ICE.
+pub(super) async fn answer_offer(
+    peer_connection: &RTCPeerConnection,
+    offer: String,
+) -> Result<String, webrtc::Error> {
+    peer_connection
+        .set_remote_description(RTCSessionDescription::offer(offer)?)
+        .await?;
+    let answer = peer_connection.create_answer(None).await?;
+    let mut gathering_complete = peer_connection.gathering_complete_promise().await;
+    peer_connection.set_local_description(answer).await?;
+    let _ = gathering_complete.recv().await;
+
+    peer_connection
+        .local_description()
+        .await
+        .map(|description| description.sdp)
+        .ok_or(webrtc::Error::ErrConnectionClosed)
+}
+
+/// Codec of sent video tracks. For H264 the negotiated profile does not limit what
+/// viewers can decode, it is only used to choose the payload type.
+pub(super) fn video_codec_capability(codec: VideoCodec) -> RTCRtpCodecCapability {
+    video_codecs(codec).remove(0).capability
+}
+
+pub(super) fn opus_codec_capability() -> RTCRtpCodecCapability {
+    opus_codec().capability
+}
+
+/// Payload types used in the answer are the ones chosen by the offerer, local
+/// values only have to be unique.
+fn video_codecs(codec: VideoCodec) -> Vec<RTCRtpCodecParameters> {
+    let rtcp_feedback = vec![
+        RTCPFeedback {
+            typ: "nack".to_string(),
+            parameter: String::new(),
+        },
+        RTCPFeedback {
+            typ: "nack".to_string(),
+            parameter: "pli".to_string(),
+        },
+        RTCPFeedback {
+            typ: "ccm".to_string(),
+            parameter: "fir".to_string(),
+        },
+    ];
+    let codec_parameters =
+        |mime_type: &str, sdp_fmtp_line: String, payload_type: u8| RTCRtpCodecParameters {
+            capability: RTCRtpCodecCapability {
+                mime_type: mime_type.to_string(),
+                clock_rate: 90_000,
+                channels: 0,
+                sdp_fmtp_line,
+                rtcp_feedback: rtcp_feedback.clone(),
+            },
+            payload_type,
+            ..Default::default()
+        };
+
+    match codec {
+        VideoCodec::H264 => H264_PROFILES
+            .iter()
+            .zip(VIDEO_PAYLOAD_TYPE + 2..)
+            .map(|(profile_level_id, payload_type)| {
+                let sdp_fmtp_line = format!(
+                    "level-asymmetry-allowed=1;packetization-mode=1;profile-level-id={profile_level_id}"
+                );
+                codec_parameters(MIME_TYPE_H264, sdp_fmtp_line, payload_type)
+            })
+            .collect(),
+        VideoCodec::Vp8 => vec![codec_parameters(
+            MIME_TYPE_VP8,
+            String::new(),
+            VIDEO_PAYLOAD_TYPE,
+        )],
+        VideoCodec::Vp9 => vec![codec_parameters(
+            MIME_TYPE_VP9,
+            "profile-id=0".to_string(),
+            VIDEO_PAYLOAD_TYPE,
+        )],
+        VideoCodec::Av1 => vec![codec_parameters(
+            MIME_TYPE_AV1,
+            "profile=0".to_string(),
+            VIDEO_PAYLOAD_TYPE,
+        )],
+    }
+}
+
+fn opus_codec() -> RTCRtpCodecParameters {
+    RTCRtpCodecParameters {
+        capability: RTCRtpCodecCapability {
+            mime_type: MIME_TYPE_OPUS.to_string(),
+            clock_rate: 48_000,
+            channels: 2,
+            sdp_fmtp_line: "minptime=10;useinbandfec=1".to_string(),
+            rtcp_feedback: Vec::new(),
+        },
+        payload_type: AUDIO_PAYLOAD_TYPE,
+        ..Default::default()
+    }
+}
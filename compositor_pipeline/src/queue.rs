@@ -21,7 +21,7 @@ use crate::{
 use self::{
     audio_queue::AudioQueue,
     queue_thread::{QueueStartEvent, QueueThread},
-    utils::Clock,
+    utils::{Clock, SharedInputStart},
     video_queue::VideoQueue,
 };
 
@@ -128,11 +128,13 @@ pub struct QueueInputOptions {
     pub buffer_duration: Option<Duration>,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 struct InputOptions {
     required: bool,
     offset: Option<Duration>,
     buffer_duration: Duration,
+    /// Set if audio and video of the input should be started together.
+    shared_start: Option<SharedInputStart>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -195,23 +197,30 @@ impl Queue {
         queue
     }
 
+    /// If `common_timeline` is true, timestamps of audio and video are in the same
+    /// frame of reference and their relative timing is preserved by the queue.
+    /// Otherwise, each of them is synchronized based on the delivery time of its
+    /// initial frames.
     pub fn add_input(
         &self,
         input_id: &InputId,
         receiver: DecodedDataReceiver,
         opts: QueueInputOptions,
+        common_timeline: bool,
     ) {
+        let has_both_tracks = receiver.video.is_some() && receiver.audio.is_some();
         let input_options = InputOptions {
             required: opts.required,
             offset: opts.offset,
             buffer_duration: opts.buffer_duration.unwrap_or(self.default_buffer_duration),
+            shared_start: (common_timeline && has_both_tracks).then(SharedInputStart::default),
         };
 
         if let Some(receiver) = receiver.video {
             self.video_queue.lock().unwrap().add_input(
                 input_id,
                 receiver,
-                input_options,
+                input_options.clone(),
                 self.clock.clone(),
             );
         };
@@ -230,6 +239,22 @@ impl Queue {
         self.audio_queue.lock().unwrap().remove_input(input_id);
     }
 
+    /// Paused input is treated as if it was still delivering its last frame, it is not
+    /// replaced with a fallback and required inputs do not block the queue. Audio is
+    /// silent until the input is resumed.
+    pub fn pause_input(&self, input_id: &InputId) {
+        let now = Instant::now();
+        self.video_queue.lock().unwrap().pause_input(input_id, now);
+        self.audio_queue.lock().unwrap().pause_input(input_id, now);
+    }
+
+    /// Input continues from the position where it was paused.
+    pub fn resume_input(&self, input_id: &InputId) {
+        let now = Instant::now();
+        self.video_queue.lock().unwrap().resume_input(input_id, now);
+        self.audio_queue.lock().unwrap().resume_input(input_id, now);
+    }
+
     pub(super) fn start(
         self: &Arc<Self>,
         video_sender: Sender<QueueVideoOutput>,
@@ -7,7 +7,7 @@ use std::{
 use crate::{audio_mixer::InputSamples, event::Event};
 
 use super::{
-    utils::{Clock, InputProcessor},
+    utils::{Clock, InputPause, InputProcessor},
     InputOptions, PipelineEvent, QueueAudioOutput,
 };
 use compositor_render::{event_handler::emit_event, InputId};
@@ -42,9 +42,11 @@ impl AudioQueue {
                     opts.buffer_duration,
                     clock,
                     input_id.clone(),
+                    opts.shared_start,
                 ),
                 required: opts.required,
                 offset: opts.offset,
+                pause: InputPause::default(),
                 eos_sent: false,
                 first_samples_sent: false,
             },
@@ -55,6 +57,18 @@ impl AudioQueue {
         self.inputs.remove(input_id);
     }
 
+    pub fn pause_input(&mut self, input_id: &InputId, at: Instant) {
+        if let Some(input) = self.inputs.get_mut(input_id) {
+            input.pause.pause(at);
+        }
+    }
+
+    pub fn resume_input(&mut self, input_id: &InputId, at: Instant) {
+        if let Some(input) = self.inputs.get_mut(input_id) {
+            input.pause.resume(at);
+        }
+    }
+
     /// Checks if all inputs are ready to produce frames for specific PTS value (if all inputs have
     /// frames closest to buffer_pts).
     pub(super) fn check_all_inputs_ready_for_pts(
@@ -139,6 +153,8 @@ struct AudioQueueInput {
     /// Offset of the stream relative to the start. If set to `None`
     /// offset will be resolved automatically on the stream start.
     offset: Option<Duration>,
+    /// Paused input produces silence.
+    pause: InputPause,
 
     eos_sent: bool,
     first_samples_sent: bool,
@@ -156,6 +172,10 @@ impl AudioQueueInput {
         // ignore result, we only need to ensure samples are enqueued
         self.check_ready_for_pts(pts_range, queue_start);
 
+        if self.pause.is_paused(pts_range.0, queue_start) {
+            return PipelineEvent::Data(vec![]);
+        }
+
         // range in queue pts time frame
         let (start_pts, end_pts) = pts_range;
 
@@ -198,6 +218,8 @@ impl AudioQueueInput {
                             (input_start_time + batch.end_pts).duration_since(queue_start);
                     }
                 }
+                batch.start_pts = self.pause.queue_pts(batch.start_pts);
+                batch.end_pts = self.pause.queue_pts(batch.end_pts);
                 batch
             })
             .collect::<Vec<InputSamples>>();
@@ -236,6 +258,10 @@ impl AudioQueueInput {
         if self.input_samples_processor.did_receive_eos() {
             return true;
         }
+        // Paused input does not need new samples.
+        if self.pause.is_paused(pts_range.0, queue_start) {
+            return true;
+        }
 
         // range in queue pts time frame
         let end_pts = pts_range.1;
@@ -315,6 +341,7 @@ impl AudioQueueInput {
         queue_pts: Duration,
         queue_start_time: Instant,
     ) -> Option<Duration> {
+        let queue_pts = self.pause.playback_pts(queue_pts, queue_start_time);
         let input_start_time = self.input_start_time();
         match self.offset {
             Some(offset) => queue_pts.checked_sub(offset),
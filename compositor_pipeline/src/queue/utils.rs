@@ -3,7 +3,7 @@ use std::{
     mem,
     sync::{
         atomic::{AtomicI64, Ordering},
-        Arc,
+        Arc, Mutex,
     },
     time::{Duration, Instant},
 };
@@ -42,6 +42,22 @@ pub(super) struct InputProcessor<Payload: InputProcessorMediaExt> {
     state: InputState<Payload>,
 
     clock: Clock,
+
+    shared_start: Option<SharedInputStart>,
+}
+
+/// Start of an input shared by processors of its audio and video. Both use the offset
+/// and the start time of the track that was ready first, so the relative timing of
+/// audio and video is preserved.
+#[derive(Debug, Clone, Default)]
+pub(super) struct SharedInputStart(Arc<Mutex<Option<(Duration, Instant)>>>);
+
+impl SharedInputStart {
+    /// Returns (offset, start_time) of the input. Provided values are used if this
+    /// is the first track that starts.
+    fn get_or_init(&self, offset: Duration, start_time: Instant) -> (Duration, Instant) {
+        *self.0.lock().unwrap().get_or_insert((offset, start_time))
+    }
 }
 
 #[derive(Debug)]
@@ -59,16 +75,35 @@ pub(super) enum InputState<Payload: InputProcessorMediaExt> {
 }
 
 impl<Payload: InputProcessorMediaExt> InputProcessor<Payload> {
-    pub(super) fn new(buffer_duration: Duration, clock: Clock, input_id: InputId) -> Self {
+    pub(super) fn new(
+        buffer_duration: Duration,
+        clock: Clock,
+        input_id: InputId,
+        shared_start: Option<SharedInputStart>,
+    ) -> Self {
         Self {
             buffer_duration,
             start_time: None,
             state: InputState::WaitingForStart,
             clock,
             input_id,
+            shared_start,
         }
     }
 
+    /// Returns (offset, start_time) for a track that is ready now.
+    fn resolve_start(&self, first_pts: Duration, now: Instant) -> (Duration, Instant) {
+        match self.shared_start {
+            Some(ref shared_start) => shared_start.get_or_init(first_pts, now),
+            None => (first_pts, now),
+        }
+    }
+
+    /// Chunks from before the start of the other track are dropped.
+    fn is_before_start(&self, pts: Duration, offset: Duration) -> bool {
+        self.shared_start.is_some() && pts < offset
+    }
+
     pub(super) fn start_time(&self) -> Option<Instant> {
         self.start_time
     }
@@ -94,16 +129,19 @@ impl<Payload: InputProcessorMediaExt> InputProcessor<Payload> {
                 VecDeque::new()
             }
             InputState::Buffering { ref mut buffer } => {
+                let buffer = mem::take(buffer);
                 let first_pts = buffer.first().map(|(_, p)| *p).unwrap_or(Duration::ZERO);
-                let chunks = mem::take(buffer)
+                let (offset, start_time) = self.resolve_start(first_pts, Instant::now());
+                let chunks = buffer
                     .into_iter()
+                    .filter(|(_, pts)| !self.is_before_start(*pts, offset))
                     .map(|(mut buffer, _)| {
-                        buffer.apply_offset(first_pts);
+                        buffer.apply_offset(offset);
                         buffer
                     })
                     .collect();
                 self.state = InputState::Done;
-                self.start_time = Some(Instant::now());
+                self.start_time = Some(start_time);
                 chunks
             }
             InputState::Ready { .. } => {
@@ -135,17 +173,19 @@ impl<Payload: InputProcessorMediaExt> InputProcessor<Payload> {
                 if buffer_duration < self.buffer_duration {
                     VecDeque::new()
                 } else {
-                    let offset = first_pts;
+                    let buffer = mem::take(buffer);
+                    let (offset, start_time) = self.resolve_start(first_pts, self.clock.now());
 
-                    let chunks = mem::take(buffer)
+                    let chunks = buffer
                         .into_iter()
+                        .filter(|(_, pts)| !self.is_before_start(*pts, offset))
                         .map(|(mut buffer, _)| {
                             buffer.apply_offset(offset);
                             buffer
                         })
                         .collect();
                     self.state = InputState::Ready { offset };
-                    self.start_time = Some(self.clock.now());
+                    self.start_time = Some(start_time);
                     self.on_ready();
                     chunks
                 }
@@ -209,6 +249,53 @@ impl InputProcessorMediaExt for InputSamples {
     }
 }
 
+/// Timeline of a paused input does not advance. Video and audio of the same input
+/// receive the same pause and resume instants, so they stay in sync.
+#[derive(Debug, Default)]
+pub(super) struct InputPause {
+    paused_at: Option<Instant>,
+    /// Total duration of all finished pauses.
+    paused_duration: Duration,
+}
+
+impl InputPause {
+    pub(super) fn pause(&mut self, at: Instant) {
+        if self.paused_at.is_none() {
+            self.paused_at = Some(at);
+        }
+    }
+
+    pub(super) fn resume(&mut self, at: Instant) {
+        if let Some(paused_at) = self.paused_at.take() {
+            self.paused_duration += at.saturating_duration_since(paused_at);
+        }
+    }
+
+    /// Returns true if the input is paused at `queue_pts`.
+    pub(super) fn is_paused(&self, queue_pts: Duration, queue_start: Instant) -> bool {
+        self.paused_at
+            .is_some_and(|paused_at| queue_start + queue_pts >= paused_at)
+    }
+
+    /// Converts queue PTS into PTS on a timeline where time stops while the input
+    /// is paused.
+    pub(super) fn playback_pts(&self, queue_pts: Duration, queue_start: Instant) -> Duration {
+        let queue_time = queue_start + queue_pts;
+        let queue_time = match self.paused_at {
+            Some(paused_at) => queue_time.min(paused_at),
+            None => queue_time,
+        };
+        queue_time
+            .saturating_duration_since(queue_start)
+            .saturating_sub(self.paused_duration)
+    }
+
+    /// Reverse of `playback_pts` for inputs that are not paused.
+    pub(super) fn queue_pts(&self, playback_pts: Duration) -> Duration {
+        playback_pts + self.paused_duration
+    }
+}
+
 #[derive(Debug, Clone)]
 pub(super) struct Clock(Arc<AtomicI64>);
 
@@ -12,6 +12,7 @@ use std::time::Instant;
 use crate::event::Event;
 
 use super::utils::Clock;
+use super::utils::InputPause;
 use super::utils::InputProcessor;
 use super::InputOptions;
 use super::PipelineEvent;
@@ -45,9 +46,11 @@ impl VideoQueue {
                     opts.buffer_duration,
                     clock,
                     input_id.clone(),
+                    opts.shared_start,
                 ),
                 required: opts.required,
                 offset: opts.offset,
+                pause: InputPause::default(),
                 eos_sent: false,
                 first_frame_sent: false,
             },
@@ -58,6 +61,18 @@ impl VideoQueue {
         self.inputs.remove(input_id);
     }
 
+    pub fn pause_input(&mut self, input_id: &InputId, at: Instant) {
+        if let Some(input) = self.inputs.get_mut(input_id) {
+            input.pause.pause(at);
+        }
+    }
+
+    pub fn resume_input(&mut self, input_id: &InputId, at: Instant) {
+        if let Some(input) = self.inputs.get_mut(input_id) {
+            input.pause.resume(at);
+        }
+    }
+
     /// Gets frames closest to buffer pts. It does not check whether input is ready
     /// or not. It should not be called before pipeline start.
     pub(super) fn get_frames_batch(
@@ -145,6 +160,8 @@ pub struct VideoQueueInput {
     /// Offset of the stream relative to the start. If set to `None`
     /// offset will be resolved automatically on the stream start.
     offset: Option<Duration>,
+    /// Paused input holds its last frame.
+    pause: InputPause,
 
     eos_sent: bool,
     first_frame_sent: bool,
@@ -163,9 +180,10 @@ impl VideoQueueInput {
 
         self.drop_old_frames(buffer_pts, queue_start);
         let input_start_time = self.input_start_time()?;
+        let playback_pts = self.pause.playback_pts(buffer_pts, queue_start);
         let frame = match self.offset {
             // if stream should not start yet, do not send any frames
-            Some(offset) if offset > buffer_pts => None,
+            Some(offset) if offset > playback_pts => None,
             // if stream is started then take the frames
             Some(offset) => self.queue.front().cloned().map(|mut frame| {
                 frame.pts += offset;
@@ -176,6 +194,15 @@ impl VideoQueueInput {
                 frame
             }),
         };
+        let frame = frame.map(|mut frame| {
+            frame.pts = match self.pause.is_paused(buffer_pts, queue_start) {
+                // Repeated frame is not older than the batch, so it is not
+                // replaced with a fallback after `stream_fallback_timeout`.
+                true => buffer_pts,
+                false => self.pause.queue_pts(frame.pts),
+            };
+            frame
+        });
         // Handle a case where we have last frame and received EOS.
         // "drop_old_frames" is ensuring that there will only be one frame at
         // the end.
@@ -207,6 +234,10 @@ impl VideoQueueInput {
         if self.input_frames_processor.did_receive_eos() {
             return true;
         }
+        // Paused input does not need new frames.
+        if self.pause.is_paused(next_buffer_pts, queue_start) {
+            return true;
+        }
 
         let Some(next_buffer_pts) = self.input_pts_from_queue_pts(next_buffer_pts, queue_start)
         else {
@@ -322,6 +353,7 @@ impl VideoQueueInput {
         queue_pts: Duration,
         queue_start_time: Instant,
     ) -> Option<Duration> {
+        let queue_pts = self.pause.playback_pts(queue_pts, queue_start_time);
         let input_start_time = self.input_start_time();
         match self.offset {
             Some(offset) => queue_pts.checked_sub(offset),
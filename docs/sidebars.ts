@@ -123,14 +123,33 @@ const sidebars: SidebarsConfig = {
           label: 'Outputs',
           collapsible: false,
           description: 'Elements that deliver generated media.',
-          items: ['api/outputs/rtp'],
+          items: [
+            'api/outputs/rtp',
+            'api/outputs/rtmp',
+            'api/outputs/mp4',
+            'api/outputs/hls',
+            'api/outputs/srt',
+            'api/outputs/mpeg_ts',
+            'api/outputs/whep',
+          ],
         },
         {
           type: 'category',
           label: 'Inputs',
           collapsible: false,
           description: 'Elements that deliver media from external sources.',
-          items: ['api/inputs/rtp', 'api/inputs/mp4', 'api/inputs/decklink'],
+          items: [
+            'api/inputs/rtp',
+            'api/inputs/rtmp',
+            'api/inputs/hls',
+            'api/inputs/srt',
+            'api/inputs/mpeg_ts',
+            'api/inputs/mp4',
+            'api/inputs/fragmented_mp4',
+            'api/inputs/test_source',
+            'api/inputs/whip',
+            'api/inputs/decklink',
+          ],
         },
       ],
     },
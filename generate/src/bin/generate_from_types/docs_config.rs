@@ -25,8 +25,16 @@ impl Default for DocsConfig {
             variant_discriminators: [
                 ("AudioEncoderOptions", "type"),
                 ("VideoEncoderOptions", "type"),
+                ("RtmpAudioEncoderOptions", "type"),
+                ("Mp4AudioEncoderOptions", "type"),
+                ("HlsAudioEncoderOptions", "type"),
                 ("InputRtpVideoOptions", "decoder"),
                 ("InputRtpAudioOptions", "decoder"),
+                ("InputRtmpVideoOptions", "decoder"),
+                ("InputRtmpAudioOptions", "decoder"),
+                ("InputFragmentedMp4VideoOptions", "decoder"),
+                ("InputFragmentedMp4AudioOptions", "decoder"),
+                ("TestSourceAudio", "type"),
             ]
             .into(),
             overrides: [
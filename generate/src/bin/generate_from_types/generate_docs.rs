@@ -2,8 +2,11 @@ use super::docs_config::DocsConfig;
 use super::document::generate;
 use super::markdown::overrides;
 use compositor_api::types::{
-    DeckLink, Image, ImageSpec, InputStream, Mp4, Rescaler, RtpInputStream, RtpOutputStream,
-    Shader, ShaderSpec, Text, Tiles, View, WebRendererSpec, WebView,
+    DeckLink, FragmentedMp4InputStream, HlsInputStream, HlsOutputStream, Image, ImageSpec,
+    InputStream, Mp4, Mp4OutputStream, MpegTsInputStream, MpegTsOutputStream, Rescaler,
+    RtmpInputStream, RtmpOutputStream, RtpInputStream, RtpOutputStream, Shader, ShaderSpec,
+    SrtInputStream, SrtOutputStream, TestSourceInput, Text, Tiles, View, WebRendererSpec, WebView,
+    WhepOutputStream, WhipInput,
 };
 use std::{fs, path::PathBuf};
 
@@ -26,7 +29,14 @@ pub fn generate_docs() {
         generate::<ImageSpec>("Image", &config),
         generate::<WebRendererSpec>("WebRenderer", &config),
         generate::<RtpInputStream>("RtpInputStream", &config),
+        generate::<RtmpInputStream>("RtmpInputStream", &config),
+        generate::<HlsInputStream>("HlsInputStream", &config),
+        generate::<SrtInputStream>("SrtInputStream", &config),
+        generate::<MpegTsInputStream>("MpegTsInputStream", &config),
         generate::<Mp4>("Mp4", &config),
+        generate::<FragmentedMp4InputStream>("FragmentedMp4InputStream", &config),
+        generate::<TestSourceInput>("TestSourceInput", &config),
+        generate::<WhipInput>("WhipInput", &config),
         generate::<DeckLink>("DeckLink", &config),
     ];
 
@@ -41,7 +51,15 @@ pub fn generate_docs() {
         generate::<Rescaler>("Rescaler", &config),
     ];
 
-    let output_pages = [generate::<RtpOutputStream>("OutputStream", &config)];
+    let output_pages = [
+        generate::<RtpOutputStream>("OutputStream", &config),
+        generate::<RtmpOutputStream>("RtmpOutputStream", &config),
+        generate::<Mp4OutputStream>("Mp4OutputStream", &config),
+        generate::<HlsOutputStream>("HlsOutputStream", &config),
+        generate::<SrtOutputStream>("SrtOutputStream", &config),
+        generate::<MpegTsOutputStream>("MpegTsOutputStream", &config),
+        generate::<WhepOutputStream>("WhepOutputStream", &config),
+    ];
 
     for page in renderer_pages {
         fs::write(
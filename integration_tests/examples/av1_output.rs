@@ -0,0 +1,97 @@
+use anyhow::Result;
+use compositor_api::types::Resolution;
+use serde_json::json;
+use std::time::Duration;
+
+use integration_tests::{
+    examples::{self, run_example, TestSample},
+    ffmpeg::{start_ffmpeg_send, start_ffplay_output_sdp},
+};
+
+const VIDEO_RESOLUTION: Resolution = Resolution {
+    width: 1280,
+    height: 720,
+};
+
+const IP: &str = "127.0.0.1";
+const INPUT_PORT: u16 = 8002;
+const AV1_OUTPUT_PORT: u16 = 8010;
+
+fn main() {
+    run_example(client_code);
+}
+
+fn client_code() -> Result<()> {
+    examples::post(
+        "input/input_1/register",
+        &json!({
+            "type": "rtp_stream",
+            "port": INPUT_PORT,
+            "video": {
+                "decoder": "ffmpeg_h264"
+            },
+        }),
+    )?;
+
+    let scene = json!({
+        "root": {
+            "type": "input_stream",
+            "input_id": "input_1"
+        }
+    });
+
+    examples::post(
+        "output/output_1/register",
+        &json!({
+            "type": "rtp_stream",
+            "ip": IP,
+            "port": AV1_OUTPUT_PORT,
+            "video": {
+                "resolution": {
+                    "width": VIDEO_RESOLUTION.width,
+                    "height": VIDEO_RESOLUTION.height,
+                },
+                "encoder": {
+                    "type": "ffmpeg_av1",
+                    "bitrate": 1_000_000,
+                    "cpu_used": 8
+                },
+                "initial": scene.clone()
+            }
+        }),
+    )?;
+
+    examples::post(
+        "output/output_2/register",
+        &json!({
+            "type": "mp4",
+            "path": "/tmp/example_av1_output.mp4",
+            "video": {
+                "resolution": {
+                    "width": VIDEO_RESOLUTION.width,
+                    "height": VIDEO_RESOLUTION.height,
+                },
+                "encoder": {
+                    "type": "ffmpeg_av1",
+                    "keyframe_interval": 60
+                },
+                "initial": scene
+            }
+        }),
+    )?;
+
+    start_ffplay_output_sdp("output_1")?;
+
+    std::thread::sleep(Duration::from_millis(500));
+
+    examples::post("start", &json!({}))?;
+
+    start_ffmpeg_send(IP, Some(INPUT_PORT), None, TestSample::BigBuckBunny)?;
+
+    std::thread::sleep(Duration::from_secs(10));
+
+    // Unregistering the output finalizes the MP4 file.
+    examples::post("output/output_2/unregister", &json!({}))?;
+
+    Ok(())
+}
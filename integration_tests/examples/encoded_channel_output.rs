@@ -96,6 +96,9 @@ fn main() {
     let input_options = RegisterInputOptions {
         input_options: InputOptions::Mp4(Mp4Options {
             source: Source::File(root_dir.join(BUNNY_FILE_PATH)),
+            should_loop: false,
+            start_at: None,
+            end_at: None,
         }),
         queue_options: QueueInputOptions {
             required: true,
@@ -129,6 +132,7 @@ fn main() {
         };
         match chunk.kind {
             EncodedChunkKind::Video(VideoCodec::H264) => h264_dump.write_all(&chunk.data).unwrap(),
+            EncodedChunkKind::Video(codec) => panic!("{codec:?} is not supported on output"),
             EncodedChunkKind::Audio(AudioCodec::Opus) => opus_dump.write_all(&chunk.data).unwrap(),
             EncodedChunkKind::Audio(AudioCodec::Aac) => panic!("AAC is not supported on output"),
         }
@@ -0,0 +1,98 @@
+use anyhow::Result;
+use compositor_api::types::Resolution;
+use serde_json::json;
+use std::time::Duration;
+
+use integration_tests::{
+    examples::{self, run_example, TestSample},
+    ffmpeg::{start_ffmpeg_receive, start_ffmpeg_send_fragmented_mp4},
+};
+
+const VIDEO_RESOLUTION: Resolution = Resolution {
+    width: 1280,
+    height: 720,
+};
+
+const IP: &str = "127.0.0.1";
+const INPUT_PORT: u16 = 8010;
+const OUTPUT_VIDEO_PORT: u16 = 8002;
+const OUTPUT_AUDIO_PORT: u16 = 8004;
+
+fn main() {
+    run_example(client_code);
+}
+
+fn client_code() -> Result<()> {
+    start_ffmpeg_receive(Some(OUTPUT_VIDEO_PORT), Some(OUTPUT_AUDIO_PORT))?;
+
+    examples::post(
+        "input/input_1/register",
+        &json!({
+            "type": "fragmented_mp4",
+            "transport_protocol": "tcp_server",
+            "port": INPUT_PORT,
+            "video": {
+                "decoder": "ffmpeg_h264"
+            },
+            "audio": {
+                "decoder": "aac"
+            },
+        }),
+    )?;
+
+    examples::post(
+        "output/output_1/register",
+        &json!({
+            "type": "rtp_stream",
+            "ip": IP,
+            "port": OUTPUT_VIDEO_PORT,
+            "video": {
+                "resolution": {
+                    "width": VIDEO_RESOLUTION.width,
+                    "height": VIDEO_RESOLUTION.height,
+                },
+                "encoder": {
+                    "type": "ffmpeg_h264",
+                    "preset": "fast"
+                },
+                "initial": {
+                    "root": {
+                        "type": "input_stream",
+                        "input_id": "input_1"
+                    }
+                }
+            }
+        }),
+    )?;
+
+    examples::post(
+        "output/output_2/register",
+        &json!({
+            "type": "rtp_stream",
+            "ip": IP,
+            "port": OUTPUT_AUDIO_PORT,
+            "audio": {
+                "initial": {
+                    "inputs": [
+                        {"input_id": "input_1"},
+                    ]
+                },
+                "encoder": {
+                    "type": "opus",
+                    "channels": "stereo",
+                }
+            }
+        }),
+    )?;
+
+    std::thread::sleep(Duration::from_millis(500));
+
+    examples::post("start", &json!({}))?;
+
+    start_ffmpeg_send_fragmented_mp4(
+        &format!("tcp://{IP}:{INPUT_PORT}"),
+        TestSample::BigBuckBunnyAAC,
+    )?;
+
+    Ok(())
+}
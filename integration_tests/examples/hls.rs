@@ -1,7 +1,7 @@
 use anyhow::Result;
 use compositor_api::types::Resolution;
 use serde_json::json;
-use std::{process::Command, time::Duration};
+use std::time::Duration;
 
 use integration_tests::{
     examples::{self, run_example},
@@ -15,7 +15,6 @@ const VIDEO_RESOLUTION: Resolution = Resolution {
 };
 
 const IP: &str = "127.0.0.1";
-const INPUT_PORT: u16 = 8002;
 const OUTPUT_PORT: u16 = 8004;
 
 fn main() {
@@ -26,12 +25,8 @@ fn client_code() -> Result<()> {
     examples::post(
         "input/input_1/register",
         &json!({
-            "type": "rtp_stream",
-            "transport_protocol": "tcp_server",
-            "port": INPUT_PORT,
-            "video": {
-                "decoder": "ffmpeg_h264"
-            }
+            "type": "hls",
+            "url": HLS_URL,
         }),
     )?;
 
@@ -82,11 +77,5 @@ fn client_code() -> Result<()> {
 
     examples::post("start", &json!({}))?;
 
-    let gst_input_command = format!("gst-launch-1.0 -v souphttpsrc location={HLS_URL} ! hlsdemux ! qtdemux ! h264parse ! rtph264pay config-interval=1 pt=96 ! .send_rtp_sink rtpsession .send_rtp_src ! rtpstreampay ! tcpclientsink host=127.0.0.1 port={INPUT_PORT}");
-    Command::new("bash")
-        .arg("-c")
-        .arg(gst_input_command)
-        .spawn()?;
-
     Ok(())
 }
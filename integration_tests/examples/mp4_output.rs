@@ -0,0 +1,99 @@
+use anyhow::Result;
+use compositor_api::types::Resolution;
+use serde_json::json;
+use std::time::Duration;
+
+use integration_tests::{
+    examples::{self, run_example},
+    ffmpeg::start_ffmpeg_send,
+};
+
+const VIDEO_RESOLUTION: Resolution = Resolution {
+    width: 1280,
+    height: 720,
+};
+
+const IP: &str = "127.0.0.1";
+const INPUT_VIDEO_PORT: u16 = 8002;
+const INPUT_AUDIO_PORT: u16 = 8004;
+
+fn main() {
+    run_example(client_code);
+}
+
+fn client_code() -> Result<()> {
+    examples::post(
+        "input/input_1/register",
+        &json!({
+            "type": "rtp_stream",
+            "port": INPUT_VIDEO_PORT,
+            "video": {
+                "decoder": "ffmpeg_h264"
+            },
+        }),
+    )?;
+
+    examples::post(
+        "input/input_2/register",
+        &json!({
+            "type": "rtp_stream",
+            "port": INPUT_AUDIO_PORT,
+            "audio": {
+                "decoder": "opus"
+            },
+        }),
+    )?;
+
+    examples::post(
+        "output/output_1/register",
+        &json!({
+            "type": "mp4",
+            "path": "/tmp/example_output.mp4",
+            "video": {
+                "resolution": {
+                    "width": VIDEO_RESOLUTION.width,
+                    "height": VIDEO_RESOLUTION.height,
+                },
+                "encoder": {
+                    "type": "ffmpeg_h264",
+                    "preset": "fast"
+                },
+                "initial": {
+                    "root": {
+                        "type": "input_stream",
+                        "input_id": "input_1"
+                    }
+                }
+            },
+            "audio": {
+                "encoder": {
+                    "type": "aac",
+                    "channels": "stereo"
+                },
+                "initial": {
+                    "inputs": [
+                        {"input_id": "input_2"},
+                    ]
+                }
+            }
+        }),
+    )?;
+
+    std::thread::sleep(Duration::from_millis(500));
+
+    examples::post("start", &json!({}))?;
+
+    start_ffmpeg_send(
+        IP,
+        Some(INPUT_VIDEO_PORT),
+        Some(INPUT_AUDIO_PORT),
+        examples::TestSample::BigBuckBunny,
+    )?;
+
+    std::thread::sleep(Duration::from_secs(10));
+
+    // Unregistering the output finalizes the MP4 file.
+    examples::post("output/output_1/unregister", &json!({}))?;
+
+    Ok(())
+}
@@ -58,6 +58,7 @@ fn main() {
                 },
                 video: Some(VideoCodec::H264),
                 audio: None,
+                srtp: None,
             }),
             video: Some(VideoEncoderOptions::H264(ffmpeg_h264::Options {
                 preset: EncoderPreset::Ultrafast,
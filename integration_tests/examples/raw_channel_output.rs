@@ -95,6 +95,9 @@ fn main() {
     let input_options = RegisterInputOptions {
         input_options: InputOptions::Mp4(Mp4Options {
             source: Source::File(root_dir().join(BUNNY_FILE_PATH)),
+            should_loop: false,
+            start_at: None,
+            end_at: None,
         }),
         queue_options: QueueInputOptions {
             required: true,
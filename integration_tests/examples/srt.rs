@@ -0,0 +1,87 @@
+use anyhow::Result;
+use compositor_api::types::Resolution;
+use serde_json::json;
+use std::time::Duration;
+
+use integration_tests::{
+    examples::{self, run_example, TestSample},
+    ffmpeg::{start_ffmpeg_send_srt, start_ffplay_srt},
+};
+
+const VIDEO_RESOLUTION: Resolution = Resolution {
+    width: 1280,
+    height: 720,
+};
+
+const IP: &str = "127.0.0.1";
+const INPUT_PORT: u16 = 9000;
+const OUTPUT_PORT: u16 = 9002;
+
+fn main() {
+    run_example(client_code);
+}
+
+fn client_code() -> Result<()> {
+    examples::post(
+        "input/input_1/register",
+        &json!({
+            "type": "srt",
+            "mode": "listener",
+            "port": INPUT_PORT,
+            "video": {
+                "decoder": "ffmpeg_h264"
+            },
+            "audio": {
+                "decoder": "aac"
+            },
+        }),
+    )?;
+
+    examples::post(
+        "output/output_1/register",
+        &json!({
+            "type": "srt",
+            "mode": "listener",
+            "port": OUTPUT_PORT,
+            "video": {
+                "resolution": {
+                    "width": VIDEO_RESOLUTION.width,
+                    "height": VIDEO_RESOLUTION.height,
+                },
+                "encoder": {
+                    "type": "ffmpeg_h264",
+                    "preset": "ultrafast"
+                },
+                "initial": {
+                    "root": {
+                        "type": "input_stream",
+                        "input_id": "input_1"
+                    }
+                }
+            },
+            "audio": {
+                "encoder": {
+                    "type": "aac",
+                    "channels": "stereo"
+                },
+                "initial": {
+                    "inputs": [
+                        {"input_id": "input_1"}
+                    ]
+                }
+            }
+        }),
+    )?;
+
+    std::thread::sleep(Duration::from_millis(500));
+
+    examples::post("start", &json!({}))?;
+
+    start_ffmpeg_send_srt(
+        &format!("srt://{IP}:{INPUT_PORT}?mode=caller"),
+        TestSample::BigBuckBunnyAAC,
+    )?;
+    start_ffplay_srt(&format!("srt://{IP}:{OUTPUT_PORT}?mode=caller"))?;
+
+    Ok(())
+}
@@ -0,0 +1,91 @@
+use anyhow::Result;
+use compositor_api::types::Resolution;
+use serde_json::json;
+use std::time::Duration;
+
+use integration_tests::{
+    examples::{self, run_example, TestSample},
+    ffmpeg::{start_ffmpeg_receive, start_ffmpeg_send_transcoded_video},
+};
+
+const VIDEO_RESOLUTION: Resolution = Resolution {
+    width: 1280,
+    height: 720,
+};
+
+const IP: &str = "127.0.0.1";
+const VP8_INPUT_PORT: u16 = 8006;
+const VP9_INPUT_PORT: u16 = 8008;
+const OUTPUT_PORT: u16 = 8002;
+
+fn main() {
+    run_example(client_code);
+}
+
+fn client_code() -> Result<()> {
+    start_ffmpeg_receive(Some(OUTPUT_PORT), None)?;
+
+    examples::post(
+        "input/input_1/register",
+        &json!({
+            "type": "rtp_stream",
+            "port": VP8_INPUT_PORT,
+            "video": {
+                "decoder": "ffmpeg_vp8"
+            },
+        }),
+    )?;
+
+    examples::post(
+        "input/input_2/register",
+        &json!({
+            "type": "rtp_stream",
+            "port": VP9_INPUT_PORT,
+            "video": {
+                "decoder": "ffmpeg_vp9"
+            },
+        }),
+    )?;
+
+    examples::post(
+        "output/output_1/register",
+        &json!({
+            "type": "rtp_stream",
+            "ip": IP,
+            "port": OUTPUT_PORT,
+            "video": {
+                "resolution": {
+                    "width": VIDEO_RESOLUTION.width,
+                    "height": VIDEO_RESOLUTION.height,
+                },
+                "encoder": {
+                    "type": "ffmpeg_h264",
+                    "preset": "ultrafast"
+                },
+                "initial": {
+                    "root": {
+                        "type": "tiles",
+                        "children": [
+                            { "type": "input_stream", "input_id": "input_1" },
+                            { "type": "input_stream", "input_id": "input_2" },
+                        ]
+                    }
+                }
+            }
+        }),
+    )?;
+
+    std::thread::sleep(Duration::from_millis(500));
+
+    examples::post("start", &json!({}))?;
+
+    start_ffmpeg_send_transcoded_video(IP, VP8_INPUT_PORT, TestSample::BigBuckBunny, "libvpx")?;
+    start_ffmpeg_send_transcoded_video(
+        IP,
+        VP9_INPUT_PORT,
+        TestSample::ElephantsDream,
+        "libvpx-vp9",
+    )?;
+
+    Ok(())
+}
@@ -1,5 +1,6 @@
 use anyhow::{anyhow, Result};
 use compositor_api::types::Resolution;
+use live_compositor::config::read_config;
 use log::info;
 
 use super::examples::{get_asset_path, TestSample};
@@ -45,6 +46,35 @@ pub fn start_ffmpeg_receive(video_port: Option<u16>, audio_port: Option<u16>) ->
     Ok(())
 }
 
+pub fn start_ffmpeg_rtmp_receive(port: u16) -> Result<()> {
+    info!("[example] Start listening RTMP stream on port {port}.");
+
+    Command::new("ffplay")
+        .args([
+            "-listen",
+            "1",
+            &format!("rtmp://0.0.0.0:{port}/live/example"),
+        ])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    thread::sleep(Duration::from_secs(2));
+
+    Ok(())
+}
+
+pub fn start_ffplay_hls(playlist_path: &str) -> Result<()> {
+    info!("[example] Start playing HLS playlist {playlist_path}.");
+
+    Command::new("ffplay")
+        .arg(playlist_path)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    Ok(())
+}
+
 pub fn start_ffmpeg_send(
     ip: &str,
     video_port: Option<u16>,
@@ -85,6 +115,162 @@ pub fn start_ffmpeg_send(
     }
 }
 
+pub fn start_ffmpeg_send_rtmp(url: &str, test_sample: TestSample) -> Result<()> {
+    info!("[example] Start publishing RTMP stream to {url}.");
+
+    Command::new("ffmpeg")
+        .args(["-re", "-i"])
+        .arg(get_asset_path(test_sample)?)
+        .args(["-c:v", "copy", "-c:a", "aac", "-f", "flv", url])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    Ok(())
+}
+
+pub fn start_ffmpeg_send_srt(url: &str, test_sample: TestSample) -> Result<()> {
+    info!("[example] Start sending SRT stream to {url}.");
+
+    Command::new("ffmpeg")
+        .args(["-re", "-i"])
+        .arg(get_asset_path(test_sample)?)
+        .args(["-c:v", "copy", "-c:a", "aac", "-f", "mpegts", url])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    Ok(())
+}
+
+pub fn start_ffplay_srt(url: &str) -> Result<()> {
+    info!("[example] Start playing SRT stream from {url}.");
+
+    Command::new("ffplay")
+        .arg(url)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    Ok(())
+}
+
+pub fn start_ffmpeg_send_mpeg_ts(ip: &str, port: u16, test_sample: TestSample) -> Result<()> {
+    info!("[example] Start sending MPEG-TS stream to {ip}:{port}.");
+
+    Command::new("ffmpeg")
+        .args(["-re", "-i"])
+        .arg(get_asset_path(test_sample)?)
+        .args([
+            "-c:v",
+            "copy",
+            "-c:a",
+            "aac",
+            "-f",
+            "mpegts",
+            &format!("udp://{ip}:{port}?pkt_size=1316"),
+        ])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    Ok(())
+}
+
+pub fn start_ffplay_mpeg_ts(port: u16) -> Result<()> {
+    info!("[example] Start listening MPEG-TS stream on port {port}.");
+
+    Command::new("ffplay")
+        .arg(format!("udp://0.0.0.0:{port}"))
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    thread::sleep(Duration::from_secs(2));
+
+    Ok(())
+}
+
+/// Plays an RTP output described by the SDP served on the `output/:id/sdp` route.
+pub fn start_ffplay_output_sdp(output_id: &str) -> Result<()> {
+    info!("[example] Start playing RTP output {output_id}.");
+
+    let url = format!(
+        "http://127.0.0.1:{}/api/output/{output_id}/sdp",
+        read_config().api_port
+    );
+    let sdp = reqwest::blocking::get(url)?.error_for_status()?.text()?;
+    let sdp_filepath = format!("/tmp/example_sdp_output_{output_id}.sdp");
+    File::create(&sdp_filepath)?.write_all(sdp.as_bytes())?;
+
+    Command::new("ffplay")
+        .args(["-protocol_whitelist", "file,rtp,udp", &sdp_filepath])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    thread::sleep(Duration::from_secs(2));
+
+    Ok(())
+}
+
+pub fn start_ffmpeg_send_fragmented_mp4(url: &str, test_sample: TestSample) -> Result<()> {
+    info!("[example] Start sending fragmented MP4 stream to {url}.");
+
+    Command::new("ffmpeg")
+        .args(["-re", "-i"])
+        .arg(get_asset_path(test_sample)?)
+        .args([
+            "-c:v",
+            "copy",
+            "-c:a",
+            "aac",
+            "-movflags",
+            "frag_keyframe+empty_moov+default_base_moof",
+            "-f",
+            "mp4",
+            url,
+        ])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    Ok(())
+}
+
+/// Transcodes video from the test sample and sends it over RTP. `encoder` is
+/// an FFmpeg encoder name, e.g. `libvpx` for VP8 or `libvpx-vp9` for VP9.
+pub fn start_ffmpeg_send_transcoded_video(
+    ip: &str,
+    port: u16,
+    test_sample: TestSample,
+    encoder: &str,
+) -> Result<()> {
+    info!("[example] Start sending {encoder} video to input port {port}.");
+
+    Command::new("ffmpeg")
+        .args(["-stream_loop", "-1", "-re", "-i"])
+        .arg(get_asset_path(test_sample)?)
+        .args([
+            "-an",
+            "-c:v",
+            encoder,
+            "-deadline",
+            "realtime",
+            "-b:v",
+            "2M",
+            // RTP payloading of VP9 is marked as experimental in FFmpeg.
+            "-strict",
+            "experimental",
+            "-f",
+            "rtp",
+            &format!("rtp://{ip}:{port}?rtcpport={port}"),
+        ])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    Ok(())
+}
+
 fn start_ffmpeg_send_from_file(
     ip: &str,
     video_port: Option<u16>,
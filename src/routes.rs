@@ -1,9 +1,12 @@
 use axum::{
     async_trait,
-    extract::{rejection::JsonRejection, ws::WebSocketUpgrade, FromRequest, Request, State},
+    extract::{
+        rejection::JsonRejection, ws::WebSocketUpgrade, DefaultBodyLimit, FromRequest, Request,
+        State,
+    },
     http::StatusCode,
     response::IntoResponse,
-    routing::{get, post},
+    routing::{delete, get, post},
     Router,
 };
 use compositor_pipeline::Pipeline;
@@ -14,13 +17,20 @@ use crate::state::{ApiState, Response};
 use compositor_api::error::ApiError;
 
 use self::{
-    update_output::handle_keyframe_request, update_output::handle_output_update,
-    ws::handle_ws_upgrade,
+    input_data::handle_input_data, update_output::handle_keyframe_request,
+    update_output::handle_output_update, ws::handle_ws_upgrade,
 };
 
+mod input_data;
+mod input_playback;
+mod input_stats;
+mod output_destinations;
 mod register_request;
+mod sdp;
 mod unregister_request;
 mod update_output;
+mod whep;
+mod whip;
 mod ws;
 
 #[allow(unused_imports)]
@@ -31,13 +41,29 @@ pub use unregister_request::{UnregisterInput, UnregisterOutput};
 pub fn routes(state: ApiState) -> Router {
     let inputs = Router::new()
         .route("/:id/register", post(register_request::handle_input))
-        .route("/:id/unregister", post(unregister_request::handle_input));
+        .route("/:id/unregister", post(unregister_request::handle_input))
+        .route("/:id/pause", post(input_playback::handle_pause))
+        .route("/:id/resume", post(input_playback::handle_resume))
+        .route("/:id/seek", post(input_playback::handle_seek))
+        .route("/:id/stats", get(input_stats::handle_stats))
+        .route("/:id/sdp", get(sdp::handle_input_sdp))
+        // A single request can carry a whole fragment, default limit is 2MB.
+        .route(
+            "/:id/data",
+            // The whole stream is sent in a single request body.
+            post(handle_input_data).layer(DefaultBodyLimit::disable()),
+        );
 
     let outputs = Router::new()
         .route("/:id/register", post(register_request::handle_output))
         .route("/:id/unregister", post(unregister_request::handle_output))
         .route("/:id/update", post(handle_output_update))
-        .route("/:id/request_keyframe", post(handle_keyframe_request));
+        .route("/:id/request_keyframe", post(handle_keyframe_request))
+        .route("/:id/sdp", get(sdp::handle_output_sdp))
+        .route(
+            "/:id/destinations",
+            post(output_destinations::handle_attach).delete(output_destinations::handle_detach),
+        );
 
     let image = Router::new()
         .route("/:id/register", post(register_request::handle_image))
@@ -67,6 +93,11 @@ pub fn routes(state: ApiState) -> Router {
         .nest("/api/shader", shader)
         // Start request
         .route("/api/start", post(handle_start))
+        // WHIP endpoint is not under `/api`, its URL is passed to publishers.
+        .route("/whip/:id", post(whip::handle_offer))
+        .route("/whip/:id/:session_id", delete(whip::handle_delete))
+        .route("/whep/:id", post(whep::handle_offer))
+        .route("/whep/:id/:session_id", delete(whep::handle_delete))
         // WebSocket - events
         .route("/ws", get(ws_handler))
         .route(
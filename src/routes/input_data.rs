@@ -0,0 +1,48 @@
+use axum::{
+    body::Body,
+    extract::{Path, State},
+};
+use compositor_pipeline::Pipeline;
+use futures_util::StreamExt;
+
+use crate::state::{ApiState, Response};
+
+use compositor_api::{error::ApiError, types::InputId};
+
+/// Passes the request body to an input that receives its stream over HTTP
+/// (e.g. fragmented MP4 with `"http"` transport). Data is forwarded as it arrives
+/// and the input stream ends when the request body ends.
+pub(super) async fn handle_input_data(
+    State(api): State<ApiState>,
+    Path(input_id): Path<InputId>,
+    body: Body,
+) -> Result<Response, ApiError> {
+    let input_id: compositor_render::InputId = input_id.into();
+    let mut body = body.into_data_stream();
+    while let Some(data) = body.next().await {
+        let data = match data {
+            Ok(data) => data,
+            Err(err) => {
+                // The stream is ended anyway, the input would wait for more data otherwise.
+                let pipeline = api.pipeline.clone();
+                let _ = tokio::task::spawn_blocking(move || {
+                    Pipeline::end_input_data(&pipeline, &input_id)
+                })
+                .await;
+                return Err(ApiError::malformed_request(&err));
+            }
+        };
+        let pipeline = api.pipeline.clone();
+        let input_id = input_id.clone();
+        // Sending blocks when the input can not keep up with the data.
+        tokio::task::spawn_blocking(move || Pipeline::push_input_data(&pipeline, &input_id, data))
+            .await
+            // `unwrap()` panics only when the task panicked or `response.abort()` was called
+            .unwrap()?;
+    }
+
+    tokio::task::spawn_blocking(move || Pipeline::end_input_data(&api.pipeline, &input_id))
+        .await
+        .unwrap()?;
+    Ok(Response::Ok {})
+}
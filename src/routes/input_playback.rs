@@ -0,0 +1,50 @@
+use std::time::Duration;
+
+use axum::extract::{Path, State};
+use serde::{Deserialize, Serialize};
+
+use crate::state::{ApiState, Response};
+
+use compositor_api::{
+    error::ApiError,
+    types::{InputId, TypeError},
+};
+
+use super::Json;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub(super) struct SeekInput {
+    /// Position in the file in milliseconds. Playback continues from the last
+    /// keyframe before this position.
+    position_ms: f64,
+}
+
+pub(super) async fn handle_pause(
+    State(api): State<ApiState>,
+    Path(input_id): Path<InputId>,
+) -> Result<Response, ApiError> {
+    api.pipeline().pause_input(&input_id.into())?;
+    Ok(Response::Ok {})
+}
+
+pub(super) async fn handle_resume(
+    State(api): State<ApiState>,
+    Path(input_id): Path<InputId>,
+) -> Result<Response, ApiError> {
+    api.pipeline().resume_input(&input_id.into())?;
+    Ok(Response::Ok {})
+}
+
+pub(super) async fn handle_seek(
+    State(api): State<ApiState>,
+    Path(input_id): Path<InputId>,
+    Json(request): Json<SeekInput>,
+) -> Result<Response, ApiError> {
+    if !request.position_ms.is_finite() || request.position_ms < 0.0 {
+        return Err(TypeError::new("`position_ms` has to be a non-negative number.").into());
+    }
+    let position = Duration::from_secs_f64(request.position_ms / 1000.0);
+    api.pipeline().seek_input(&input_id.into(), position)?;
+    Ok(Response::Ok {})
+}
@@ -0,0 +1,51 @@
+use axum::extract::{Path, State};
+use compositor_pipeline::pipeline::input::rtp::RtpStreamStats;
+use serde::Serialize;
+
+use crate::state::ApiState;
+
+use compositor_api::{error::ApiError, types::InputId};
+
+#[derive(Debug, Serialize)]
+pub(super) struct InputStatsResponse {
+    /// One entry per RTP stream (SSRC) received by the input.
+    streams: Vec<RtpStreamStatsResponse>,
+}
+
+#[derive(Debug, Serialize)]
+struct RtpStreamStatsResponse {
+    ssrc: u32,
+    payload_type: u8,
+    received_packets: u64,
+    lost_packets: u64,
+    late_packets: u64,
+    duplicated_packets: u64,
+    reordered_packets: u64,
+}
+
+pub(super) async fn handle_stats(
+    State(api): State<ApiState>,
+    Path(input_id): Path<InputId>,
+) -> Result<axum::Json<InputStatsResponse>, ApiError> {
+    let streams = api
+        .pipeline()
+        .rtp_input_stats(&input_id.into())?
+        .into_iter()
+        .map(RtpStreamStatsResponse::from)
+        .collect();
+    Ok(axum::Json(InputStatsResponse { streams }))
+}
+
+impl From<RtpStreamStats> for RtpStreamStatsResponse {
+    fn from(stats: RtpStreamStats) -> Self {
+        Self {
+            ssrc: stats.ssrc,
+            payload_type: stats.payload_type,
+            received_packets: stats.received,
+            lost_packets: stats.lost,
+            late_packets: stats.late,
+            duplicated_packets: stats.duplicated,
+            reordered_packets: stats.reordered,
+        }
+    }
+}
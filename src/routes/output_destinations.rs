@@ -0,0 +1,54 @@
+use std::net::{IpAddr, SocketAddr};
+
+use axum::extract::{Path, State};
+use serde::{Deserialize, Serialize};
+
+use crate::state::{ApiState, Response};
+
+use compositor_api::{
+    error::ApiError,
+    types::{OutputId, TypeError},
+};
+
+use super::Json;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub(super) struct OutputDestination {
+    /// IP address of the receiver. Hostnames are not supported.
+    ip: String,
+    /// UDP port of the receiver.
+    port: u16,
+}
+
+impl TryFrom<OutputDestination> for SocketAddr {
+    type Error = TypeError;
+
+    fn try_from(value: OutputDestination) -> Result<Self, Self::Error> {
+        let ip: IpAddr = value
+            .ip
+            .parse()
+            .map_err(|_| TypeError::new(format!("\"{}\" is not a valid IP address.", value.ip)))?;
+        Ok(SocketAddr::new(ip, value.port))
+    }
+}
+
+pub(super) async fn handle_attach(
+    State(api): State<ApiState>,
+    Path(output_id): Path<OutputId>,
+    Json(request): Json<OutputDestination>,
+) -> Result<Response, ApiError> {
+    api.pipeline()
+        .attach_output_destination(&output_id.into(), request.try_into()?)?;
+    Ok(Response::Ok {})
+}
+
+pub(super) async fn handle_detach(
+    State(api): State<ApiState>,
+    Path(output_id): Path<OutputId>,
+    Json(request): Json<OutputDestination>,
+) -> Result<Response, ApiError> {
+    api.pipeline()
+        .detach_output_destination(&output_id.into(), request.try_into()?)?;
+    Ok(Response::Ok {})
+}
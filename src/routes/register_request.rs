@@ -10,8 +10,11 @@ use crate::{
 use compositor_api::{
     error::ApiError,
     types::{
-        DeckLink, ImageSpec, InputId, Mp4, OutputId, RendererId, RtpInputStream, RtpOutputStream,
-        ShaderSpec, WebRendererSpec,
+        DeckLink, FragmentedMp4InputStream, HlsInputStream, HlsOutputStream, ImageSpec, InputId,
+        Mp4, Mp4OutputStream, MpegTsInputStream, MpegTsOutputStream, OutputId, RendererId,
+        RtmpInputStream, RtmpOutputStream, RtpInputStream, RtpOutputStream, ShaderSpec,
+        SrtInputStream, SrtOutputStream, TestSourceInput, WebRendererSpec, WhepOutputStream,
+        WhipInput,
     },
 };
 
@@ -21,7 +24,14 @@ use super::ApiState;
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum RegisterInput {
     RtpStream(RtpInputStream),
+    Rtmp(RtmpInputStream),
+    Hls(HlsInputStream),
+    Srt(SrtInputStream),
+    MpegTs(MpegTsInputStream),
     Mp4(Mp4),
+    FragmentedMp4(FragmentedMp4InputStream),
+    TestSource(TestSourceInput),
+    Whip(WhipInput),
     #[serde(rename = "decklink")]
     DeckLink(DeckLink),
 }
@@ -30,6 +40,12 @@ pub enum RegisterInput {
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum RegisterOutput {
     RtpStream(RtpOutputStream),
+    Rtmp(RtmpOutputStream),
+    Mp4(Mp4OutputStream),
+    Hls(HlsOutputStream),
+    Srt(SrtOutputStream),
+    MpegTs(MpegTsOutputStream),
+    Whep(WhepOutputStream),
 }
 
 pub(super) async fn handle_input(
@@ -43,9 +59,30 @@ pub(super) async fn handle_input(
             RegisterInput::RtpStream(rtp) => {
                 Pipeline::register_input(&api.pipeline, input_id.into(), rtp.try_into()?)?
             }
+            RegisterInput::Rtmp(rtmp) => {
+                Pipeline::register_input(&api.pipeline, input_id.into(), rtmp.try_into()?)?
+            }
+            RegisterInput::Hls(hls) => {
+                Pipeline::register_input(&api.pipeline, input_id.into(), hls.try_into()?)?
+            }
+            RegisterInput::Srt(srt) => {
+                Pipeline::register_input(&api.pipeline, input_id.into(), srt.try_into()?)?
+            }
+            RegisterInput::MpegTs(mpeg_ts) => {
+                Pipeline::register_input(&api.pipeline, input_id.into(), mpeg_ts.try_into()?)?
+            }
             RegisterInput::Mp4(mp4) => {
                 Pipeline::register_input(&api.pipeline, input_id.into(), mp4.try_into()?)?
             }
+            RegisterInput::FragmentedMp4(mp4) => {
+                Pipeline::register_input(&api.pipeline, input_id.into(), mp4.try_into()?)?
+            }
+            RegisterInput::TestSource(test_source) => {
+                Pipeline::register_input(&api.pipeline, input_id.into(), test_source.try_into()?)?
+            }
+            RegisterInput::Whip(whip) => {
+                Pipeline::register_input(&api.pipeline, input_id.into(), whip.try_into()?)?
+            }
             RegisterInput::DeckLink(decklink) => {
                 Pipeline::register_input(&api.pipeline, input_id.into(), decklink.try_into()?)?
             }
@@ -71,6 +108,26 @@ pub(super) async fn handle_output(
             RegisterOutput::RtpStream(rtp) => {
                 Pipeline::register_output(&mut api.pipeline(), output_id.into(), rtp.try_into()?)?
             }
+            RegisterOutput::Rtmp(rtmp) => {
+                Pipeline::register_output(&mut api.pipeline(), output_id.into(), rtmp.try_into()?)?
+            }
+            RegisterOutput::Mp4(mp4) => {
+                Pipeline::register_output(&mut api.pipeline(), output_id.into(), mp4.try_into()?)?
+            }
+            RegisterOutput::Hls(hls) => {
+                Pipeline::register_output(&mut api.pipeline(), output_id.into(), hls.try_into()?)?
+            }
+            RegisterOutput::Srt(srt) => {
+                Pipeline::register_output(&mut api.pipeline(), output_id.into(), srt.try_into()?)?
+            }
+            RegisterOutput::MpegTs(mpeg_ts) => Pipeline::register_output(
+                &mut api.pipeline(),
+                output_id.into(),
+                mpeg_ts.try_into()?,
+            )?,
+            RegisterOutput::Whep(whep) => {
+                Pipeline::register_output(&mut api.pipeline(), output_id.into(), whep.try_into()?)?
+            }
         };
         match response {
             Some(Port(port)) => Ok(Response::RegisteredPort { port }),
@@ -0,0 +1,30 @@
+use axum::{
+    extract::{Path, State},
+    http::header,
+    response::IntoResponse,
+};
+
+use crate::state::ApiState;
+
+use compositor_api::{
+    error::ApiError,
+    types::{InputId, OutputId},
+};
+
+pub(super) const SDP_CONTENT_TYPE: &str = "application/sdp";
+
+pub(super) async fn handle_input_sdp(
+    State(api): State<ApiState>,
+    Path(input_id): Path<InputId>,
+) -> Result<impl IntoResponse, ApiError> {
+    let sdp = api.pipeline().input_sdp(&input_id.into())?;
+    Ok(([(header::CONTENT_TYPE, SDP_CONTENT_TYPE)], sdp))
+}
+
+pub(super) async fn handle_output_sdp(
+    State(api): State<ApiState>,
+    Path(output_id): Path<OutputId>,
+) -> Result<impl IntoResponse, ApiError> {
+    let sdp = api.pipeline().output_sdp(&output_id.into())?;
+    Ok(([(header::CONTENT_TYPE, SDP_CONTENT_TYPE)], sdp))
+}
@@ -0,0 +1,43 @@
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+
+use crate::state::{ApiState, Response};
+
+use compositor_api::{error::ApiError, types::OutputId};
+
+use super::{sdp::SDP_CONTENT_TYPE, whip::bearer_token};
+
+/// Handles the SDP offer of a viewer. Every request starts a new session.
+pub(super) async fn handle_offer(
+    State(api): State<ApiState>,
+    Path(output_id): Path<OutputId>,
+    headers: HeaderMap,
+    offer: String,
+) -> Result<impl IntoResponse, ApiError> {
+    let endpoint = api.pipeline().whep_endpoint(&output_id.clone().into())?;
+    let session = endpoint
+        .start_session(bearer_token(&headers), offer)
+        .await?;
+    let location = format!("/whep/{}/{}", output_id, session.id);
+    Ok((
+        StatusCode::CREATED,
+        [
+            (header::CONTENT_TYPE, SDP_CONTENT_TYPE.to_string()),
+            (header::LOCATION, location),
+        ],
+        session.answer,
+    ))
+}
+
+pub(super) async fn handle_delete(
+    State(api): State<ApiState>,
+    Path((output_id, session_id)): Path<(OutputId, String)>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let endpoint = api.pipeline().whep_endpoint(&output_id.into())?;
+    endpoint.end_session(bearer_token(&headers), &session_id)?;
+    Ok(Response::Ok {})
+}
@@ -0,0 +1,51 @@
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+
+use crate::state::{ApiState, Response};
+
+use compositor_api::{error::ApiError, types::InputId};
+
+use super::sdp::SDP_CONTENT_TYPE;
+
+/// Handles the SDP offer of a publisher ([RFC 9725, section 4.2](https://datatracker.ietf.org/doc/html/rfc9725#section-4.2)).
+pub(super) async fn handle_offer(
+    State(api): State<ApiState>,
+    Path(input_id): Path<InputId>,
+    headers: HeaderMap,
+    offer: String,
+) -> Result<impl IntoResponse, ApiError> {
+    let endpoint = api.pipeline().whip_endpoint(&input_id.clone().into())?;
+    let session = endpoint
+        .start_session(bearer_token(&headers), offer)
+        .await?;
+    let location = format!("/whip/{}/{}", input_id, session.id);
+    Ok((
+        StatusCode::CREATED,
+        [
+            (header::CONTENT_TYPE, SDP_CONTENT_TYPE.to_string()),
+            (header::LOCATION, location),
+        ],
+        session.answer,
+    ))
+}
+
+pub(super) async fn handle_delete(
+    State(api): State<ApiState>,
+    Path((input_id, session_id)): Path<(InputId, String)>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let endpoint = api.pipeline().whip_endpoint(&input_id.into())?;
+    endpoint.end_session(bearer_token(&headers), &session_id)?;
+    Ok(Response::Ok {})
+}
+
+pub(super) fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+}